@@ -0,0 +1,576 @@
+//! This module contains functions for working with chords, e.g. for transposing the chord lines of a song.
+//! Chords are stored as chord lines in the `Chords` content of a song part. They can be parsed to `Chord`s and `ChordLine`s
+//! (see `SongPart::parsed_chords`). Transposing and converting to Nashville numbers keep the spelling of the chords,
+//! the converted tokens are laid out by `ChordLine` at their original columns.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::metadata::{Key, KeyMode, Note, NoteNotation};
+
+/// An error which occurs when converting chords
+#[derive(Clone, PartialEq, Debug)]
+pub enum ChordError {
+    /// The text is no chord
+    InvalidChord { chord: String },
+    /// The key can't be parsed
+    InvalidKey { key: String },
+    /// The song has no `key` tag
+    MissingKey,
+    /// The fret of a capo is greater than 11
+    InvalidCapo { fret: u8 },
+}
+
+impl fmt::Display for ChordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChordError::InvalidChord { chord } => write!(f, "{} is no valid chord", chord),
+            ChordError::InvalidKey { key } => write!(f, "{} is no valid key", key),
+            ChordError::MissingKey => write!(f, "The song has no key"),
+            ChordError::InvalidCapo { fret } => write!(f, "A capo can't be placed on fret {}", fret),
+        }
+    }
+}
+
+impl std::error::Error for ChordError {}
+
+/// Returns the regex which matches the quality of a chord after its root (like `m7`, `sus4`, `maj7` or `add9`).
+/// The groups contain the quality, the number after it and the further extensions.
+fn quality_regex() -> &'static Regex {
+    static QUALITY_REGEX: OnceLock<Regex> = OnceLock::new();
+    QUALITY_REGEX.get_or_init(|| {
+        Regex::new(r"^(maj|min|m|M|dim|aug|sus|add|\+|°|ø)?(\d*)((?:(?:sus|add|maj|dim|b|#|\+|-)\d+)*)$").unwrap()
+    })
+}
+
+/// Returns the regex which matches a single extension of a chord after its quality (like `sus4` or `b5`)
+fn extension_regex() -> &'static Regex {
+    static EXTENSION_REGEX: OnceLock<Regex> = OnceLock::new();
+    EXTENSION_REGEX.get_or_init(|| Regex::new(r"(sus|add|maj|dim|b|#|\+|-)(\d+)").unwrap())
+}
+
+/// Formats a note in the given notation, lowercase if the note has been written in lowercase (a minor chord in German notation)
+fn format_note(note: &Note, notation: NoteNotation, lowercase: bool) -> String {
+    let text: String = note.to_notation_string(notation);
+    match lowercase {
+        true => text.to_lowercase(),
+        false => text,
+    }
+}
+
+/// Parses a note which must be the complete text
+fn parse_note(text: &str, notation: NoteNotation) -> Option<Note> {
+    match Note::parse_prefix(text, notation)? {
+        (note, "") => Some(note),
+        _ => None,
+    }
+}
+
+/// A chord which has been parsed from a chord line (see `parse_chord`)
+struct ParsedChord<'a> {
+    root: Note,
+    /// Whether the root is written in lowercase (a minor chord in German notation)
+    lowercase: bool,
+    /// The quality of the chord after the root (like `m7` or `sus4`)
+    quality: &'a str,
+    /// The bass note of a slash chord (like `F#` in `D/F#`) and whether it is written in lowercase
+    bass: Option<(Note, bool)>,
+}
+
+/// Parses a single chord like `Am7`, `Dsus4` or `D/F#`.
+/// # Returns
+/// The parsed chord or None if the text is no chord
+fn parse_chord(chord: &str, notation: NoteNotation) -> Option<ParsedChord<'_>> {
+    let is_lowercase = |text: &str| text.starts_with(|character: char| character.is_lowercase());
+    let lowercase: bool = is_lowercase(chord);
+    // Lowercase chords are only used for minor chords in German notation
+    if lowercase && notation == NoteNotation::English {
+        return None;
+    }
+    let (root, rest) = Note::parse_prefix(chord, notation)?;
+    let (quality, bass): (&str, Option<(Note, bool)>) = match rest.split_once('/') {
+        Some((quality, bass)) => (quality, Some((parse_note(bass, notation)?, is_lowercase(bass)))),
+        None => (rest, None),
+    };
+    if !quality_regex().is_match(quality) {
+        return None;
+    }
+    Some(ParsedChord { root, lowercase, quality, bass })
+}
+
+/// The quality of a chord, which is defined by its third and fifth
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    /// A diminished chord with a minor seventh (`ø7`, also written as `m7b5`)
+    HalfDiminished,
+}
+
+impl fmt::Display for ChordQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChordQuality::Major => write!(f, ""),
+            ChordQuality::Minor => write!(f, "m"),
+            ChordQuality::Diminished => write!(f, "dim"),
+            ChordQuality::Augmented => write!(f, "aug"),
+            ChordQuality::HalfDiminished => write!(f, "ø"),
+        }
+    }
+}
+
+/// An extension of a chord after its quality
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Extension {
+    /// An interval written directly after the quality (like the `7` in `G7` or `Am7`)
+    Interval(u8),
+    /// A major interval (like `maj7`)
+    Major(u8),
+    /// An interval which replaces the third (like `sus4`)
+    Suspended(u8),
+    /// An interval which is added to the chord (like `add9`)
+    Added(u8),
+    /// A lowered interval (like `b5`)
+    Flat(u8),
+    /// A raised interval (like `#5`)
+    Sharp(u8),
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Extension::Interval(interval) => write!(f, "{}", interval),
+            Extension::Major(interval) => write!(f, "maj{}", interval),
+            Extension::Suspended(interval) => write!(f, "sus{}", interval),
+            Extension::Added(interval) => write!(f, "add{}", interval),
+            Extension::Flat(interval) => write!(f, "b{}", interval),
+            Extension::Sharp(interval) => write!(f, "#{}", interval),
+        }
+    }
+}
+
+/// A chord like `Am7`, `Dsus4` or `D/F#`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Chord {
+    pub root: Note,
+    pub quality: ChordQuality,
+    pub extensions: Vec<Extension>,
+    /// The bass note of a slash chord (like `F#` in `D/F#`)
+    pub bass: Option<Note>,
+}
+
+impl Chord {
+    /// Parses a chord in English notation.
+    /// Different spellings of the same chord are parsed to the same chord (e.g. `Cmin7` and `Cm7` or `CM7` and `Cmaj7`),
+    /// a `sus` without a number is a `sus4`.
+    /// # Returns
+    /// The chord or None if the text is no chord
+    /// # Example
+    /// ```
+    /// use cantara_songlib::chords::{Chord, ChordQuality, Extension};
+    /// let chord = Chord::parse("Am7/G").unwrap();
+    /// assert_eq!(chord.quality, ChordQuality::Minor);
+    /// assert_eq!(chord.extensions, vec![Extension::Interval(7)]);
+    /// assert_eq!(chord.bass.unwrap().to_string(), "G");
+    /// assert_eq!(Chord::parse("Dmin7").unwrap().to_string(), "Dm7");
+    /// assert_eq!(Chord::parse("Dsus").unwrap().to_string(), "Dsus4");
+    /// assert_eq!(Chord::parse("Amazing"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Chord> {
+        let chord: ParsedChord = parse_chord(text, NoteNotation::English)?;
+        let captures = quality_regex().captures(chord.quality)?;
+        let number: Option<u8> = match &captures[2] {
+            "" => None,
+            number => Some(number.parse().ok()?),
+        };
+
+        let mut extensions: Vec<Extension> = Vec::new();
+        let quality: ChordQuality = match captures.get(1).map(|quality| quality.as_str()) {
+            Some("m" | "min") => ChordQuality::Minor,
+            Some("dim" | "°") => ChordQuality::Diminished,
+            Some("aug" | "+") => ChordQuality::Augmented,
+            Some("ø") => ChordQuality::HalfDiminished,
+            Some("maj" | "M") => {
+                extensions.extend(number.map(Extension::Major));
+                ChordQuality::Major
+            },
+            Some("sus") => {
+                extensions.push(Extension::Suspended(number.unwrap_or(4)));
+                ChordQuality::Major
+            },
+            Some("add") => {
+                extensions.push(Extension::Added(number?));
+                ChordQuality::Major
+            },
+            _ => ChordQuality::Major,
+        };
+        if extensions.is_empty() {
+            extensions.extend(number.map(Extension::Interval));
+        }
+        for extension in extension_regex().captures_iter(&captures[3]) {
+            let interval: u8 = extension[2].parse().ok()?;
+            extensions.push(match &extension[1] {
+                "sus" => Extension::Suspended(interval),
+                "add" => Extension::Added(interval),
+                "maj" => Extension::Major(interval),
+                "#" | "+" => Extension::Sharp(interval),
+                _ => Extension::Flat(interval),
+            });
+        }
+        Some(Chord { root: chord.root, quality, extensions, bass: chord.bass.map(|(bass, _)| bass) })
+    }
+
+    /// Transposes the chord by the given number of semitones
+    /// # Arguments
+    /// * `semitones` - The number of semitones (negative to transpose down)
+    /// * `prefer_flats` - Whether the black keys are written with flats (`Bb`) instead of sharps (`A#`)
+    pub fn transpose(&self, semitones: i32, prefer_flats: bool) -> Chord {
+        Chord {
+            root: self.root.transpose(semitones, prefer_flats),
+            bass: self.bass.map(|bass| bass.transpose(semitones, prefer_flats)),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.root, self.quality)?;
+        for extension in &self.extensions {
+            write!(f, "{}", extension)?;
+        }
+        if let Some(bass) = &self.bass {
+            write!(f, "/{}", bass)?;
+        }
+        Ok(())
+    }
+}
+
+/// A token of a chord line
+#[derive(Clone, PartialEq, Debug)]
+pub enum ChordToken {
+    Chord(Chord),
+    /// A token which is no chord (like `|`, `x2` or `N.C.`)
+    Text(String),
+}
+
+impl fmt::Display for ChordToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChordToken::Chord(chord) => write!(f, "{}", chord),
+            ChordToken::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// Splits a line at whitespace into its tokens together with their columns (counted in characters)
+fn tokenize(line: &str) -> Vec<(usize, String)> {
+    let mut tokens: Vec<(usize, String)> = Vec::new();
+    let mut token: Option<(usize, String)> = None;
+    for (column, character) in line.chars().enumerate() {
+        match (character.is_whitespace(), &mut token) {
+            (true, _) => tokens.extend(token.take()),
+            (false, Some((_, text))) => text.push(character),
+            (false, None) => token = Some((column, character.to_string())),
+        }
+    }
+    tokens.extend(token);
+    tokens
+}
+
+/// A chord line which has been split into its tokens. Every token is stored with its column (counted in characters),
+/// so that the chords can be placed above the lyrics again.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ChordLine {
+    pub tokens: Vec<(usize, ChordToken)>,
+}
+
+impl ChordLine {
+    /// Parses a chord line in English notation. Tokens which are no chords are kept as `ChordToken::Text`.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::chords::{ChordLine, ChordToken};
+    /// let line = ChordLine::parse("G       D/F#  | x2");
+    /// assert_eq!(line.chords().count(), 2);
+    /// assert_eq!(line.tokens[1].0, 8);
+    /// assert_eq!(line.tokens[3], (16, ChordToken::Text("x2".to_string())));
+    /// assert_eq!(line.to_string(), "G       D/F#  | x2");
+    /// ```
+    pub fn parse(line: &str) -> ChordLine {
+        let tokens: Vec<(usize, ChordToken)> = tokenize(line)
+            .into_iter()
+            .map(|(column, text)| match Chord::parse(&text) {
+                Some(chord) => (column, ChordToken::Chord(chord)),
+                None => (column, ChordToken::Text(text)),
+            })
+            .collect();
+        ChordLine { tokens }
+    }
+
+    /// Returns all chords of the line
+    pub fn chords(&self) -> impl Iterator<Item = &Chord> {
+        self.tokens.iter().filter_map(|(_, token)| match token {
+            ChordToken::Chord(chord) => Some(chord),
+            ChordToken::Text(_) => None,
+        })
+    }
+}
+
+impl fmt::Display for ChordLine {
+    /// Writes the tokens at their columns, a token which would overlap the previous one is moved to the right
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut line_length: usize = 0;
+        for (column, token) in &self.tokens {
+            let token: String = token.to_string();
+            let padding: usize = match line_length {
+                0 => *column,
+                _ => column.saturating_sub(line_length).max(1),
+            };
+            write!(f, "{}{}", " ".repeat(padding), token)?;
+            line_length += padding + token.chars().count();
+        }
+        Ok(())
+    }
+}
+
+/// Transposes a single chord like `Am7`, `Dsus4` or `D/F#`.
+/// # Returns
+/// The transposed chord or None if the text is no chord
+fn transpose_chord(chord: &str, semitones: i32, prefer_flats: bool, notation: NoteNotation) -> Option<String> {
+    let chord: ParsedChord = parse_chord(chord, notation)?;
+    let mut transposed: String = format_note(&chord.root.transpose(semitones, prefer_flats), notation, chord.lowercase);
+    transposed.push_str(chord.quality);
+    if let Some((bass, lowercase)) = chord.bass {
+        transposed.push('/');
+        transposed.push_str(&format_note(&bass.transpose(semitones, prefer_flats), notation, lowercase));
+    }
+    Some(transposed)
+}
+
+/// Converts a token of a chord line with the given function, the token may be enclosed in brackets or parentheses
+/// (like `[G` or `(Em)`). Tokens which are no chords (the function returns None) are kept.
+fn map_token(token: &str, convert: &impl Fn(&str) -> Option<String>) -> String {
+    let chord: &str = token.trim_start_matches(['[', '(']);
+    let prefix: &str = &token[..token.len() - chord.len()];
+    let chord_end: usize = chord.trim_end_matches([']', ')']).len();
+    let (chord, suffix) = chord.split_at(chord_end);
+    match convert(chord) {
+        Some(converted) => format!("{}{}{}", prefix, converted, suffix),
+        None => token.to_string(),
+    }
+}
+
+/// Converts the chords of a line with the given function (see `map_token`). The tokens keep their columns, so that the
+/// chords stay above the same syllables, a token is only moved to the right if a longer chord before it would overlap it.
+fn convert_chords(line: &str, convert: impl Fn(&str) -> Option<String>) -> String {
+    let tokens: Vec<(usize, ChordToken)> = tokenize(line)
+        .into_iter()
+        .map(|(column, token)| (column, ChordToken::Text(map_token(&token, &convert))))
+        .collect();
+    ChordLine { tokens }.to_string()
+}
+
+/// Returns the Nashville number of a note in a key (like `4` or `b7`).
+/// In major keys, the notes outside of the major scale are written as lowered degrees (e.g. `b3` or `b7`), except for the
+/// tritone, which is written as `#4`. Minor keys are numbered from their tonic along the natural minor scale (so `3` is the
+/// minor third), the notes outside of that scale are written as raised degrees (e.g. `#7` for the leading tone).
+fn nashville_degree(note: &Note, key: &Key) -> &'static str {
+    const MAJOR_DEGREES: [&str; 12] = ["1", "b2", "2", "b3", "3", "4", "#4", "5", "b6", "6", "b7", "7"];
+    const MINOR_DEGREES: [&str; 12] = ["1", "b2", "2", "3", "#3", "4", "#4", "5", "6", "#6", "7", "#7"];
+    let interval: usize = (note.pitch_class() + 12 - key.tonic.pitch_class()) as usize % 12;
+    match key.mode {
+        KeyMode::Major => MAJOR_DEGREES[interval],
+        KeyMode::Minor => MINOR_DEGREES[interval],
+    }
+}
+
+/// Converts a parsed chord to the Nashville number system, the quality and the bass note are kept (see `to_nashville`)
+fn chord_to_nashville(chord: &ParsedChord, key: &Key) -> String {
+    let mut number: String = nashville_degree(&chord.root, key).to_string();
+    number.push_str(chord.quality);
+    if let Some((bass, _)) = &chord.bass {
+        number.push('/');
+        number.push_str(nashville_degree(bass, key));
+    }
+    number
+}
+
+/// Converts a chord in English notation to the Nashville number system relative to a key.
+/// The quality of the chord is kept (`Am` in C major is `6m`), a slash chord gets the number of its bass note (`G/B` is `5/7`).
+/// Chords outside of the scale are written as lowered or raised degrees (`Bb` in C major is `b7`).
+/// Minor keys are numbered from their tonic along the natural minor scale, so in A minor `C` is `3` and `E7` is `57`.
+/// # Arguments
+/// * `chord` - The chord (like `Am7` or `D/F#`)
+/// * `key` - The key of the song (like `G` or `Em`)
+/// # Returns
+/// The Nashville number of the chord or an error if the chord or the key can't be parsed
+/// # Example
+/// ```
+/// use cantara_songlib::chords::to_nashville;
+/// assert_eq!(to_nashville("Em7", "G").unwrap(), "6m7");
+/// assert_eq!(to_nashville("D/F#", "G").unwrap(), "5/7");
+/// assert_eq!(to_nashville("F", "G").unwrap(), "b7");
+/// assert_eq!(to_nashville("G", "Am").unwrap(), "7");
+/// assert!(to_nashville("Hm", "G").is_err());
+/// ```
+pub fn to_nashville(chord: &str, key: &str) -> Result<String, ChordError> {
+    let parsed_key: Key = Key::parse(key).ok_or_else(|| ChordError::InvalidKey { key: key.to_string() })?;
+    let parsed_chord: ParsedChord =
+        parse_chord(chord, NoteNotation::English).ok_or_else(|| ChordError::InvalidChord { chord: chord.to_string() })?;
+    Ok(chord_to_nashville(&parsed_chord, &parsed_key))
+}
+
+/// Converts all chords of a line in English notation to the Nashville number system relative to a key (see `to_nashville`).
+/// All other tokens (like `|` or `x2`) are kept and every token keeps its column (see `transpose_line_with_notation`).
+/// # Example
+/// ```
+/// use cantara_songlib::chords::line_to_nashville;
+/// use cantara_songlib::metadata::Key;
+/// assert_eq!(line_to_nashville("C   Am  F  G7  | x2", &Key::parse("C").unwrap()), "1   6m  4  57  | x2");
+/// ```
+pub fn line_to_nashville(line: &str, key: &Key) -> String {
+    convert_chords(line, |chord| parse_chord(chord, NoteNotation::English).map(|chord| chord_to_nashville(&chord, key)))
+}
+
+/// Transposes all chords of a line in English notation by the given number of semitones (see `transpose_line_with_notation`)
+/// # Example
+/// ```
+/// use cantara_songlib::chords::transpose_line;
+/// assert_eq!(transpose_line("G   D/F#  Em7  Csus4", 2, false), "A   E/G#  F#m7 Dsus4");
+/// assert_eq!(transpose_line("C   F   G7  | x2", 1, true), "Db  Gb  Ab7 | x2");
+/// ```
+pub fn transpose_line(line: &str, semitones: i32, prefer_flats: bool) -> String {
+    transpose_line_with_notation(line, semitones, prefer_flats, NoteNotation::English)
+}
+
+/// Transposes all chords of a line by the given number of semitones.
+/// Major, minor, seventh, sus, add and slash chords are recognized, all other tokens (like `|` or `x2`) are kept.
+/// Every token keeps its column, it is only moved to the right if a chord before it has become too long.
+/// In German notation, `H` is B natural, `B` is B flat and lowercase chords are minor chords.
+/// Transposing by 0 semitones returns the line unchanged.
+/// # Arguments
+/// * `line` - The chord line
+/// * `semitones` - The number of semitones (negative to transpose down)
+/// * `prefer_flats` - Whether the black keys are written with flats (`Bb`) instead of sharps (`A#`)
+/// * `notation` - The notation of the note names
+/// # Example
+/// ```
+/// use cantara_songlib::chords::transpose_line_with_notation;
+/// use cantara_songlib::metadata::NoteNotation;
+/// assert_eq!(transpose_line_with_notation("H  e  Fis7", -1, true, NoteNotation::German), "B  es F7");
+/// ```
+pub fn transpose_line_with_notation(line: &str, semitones: i32, prefer_flats: bool, notation: NoteNotation) -> String {
+    if semitones.rem_euclid(12) == 0 {
+        return line.to_string();
+    }
+    convert_chords(line, |chord| transpose_chord(chord, semitones, prefer_flats, notation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chords() {
+        let chord = |text: &str| Chord::parse(text).unwrap();
+        assert_eq!(chord("C").quality, ChordQuality::Major);
+        assert_eq!(chord("C").extensions, vec![]);
+        assert_eq!(chord("Bbm7b5").extensions, vec![Extension::Interval(7), Extension::Flat(5)]);
+        assert_eq!(chord("Bbm7b5").to_string(), "Bbm7b5");
+        assert_eq!(chord("F#ø7").quality, ChordQuality::HalfDiminished);
+        assert_eq!(chord("G7sus4").extensions, vec![Extension::Interval(7), Extension::Suspended(4)]);
+        assert_eq!(chord("Csus2").extensions, vec![Extension::Suspended(2)]);
+        assert_eq!(chord("Ebadd9").extensions, vec![Extension::Added(9)]);
+        assert_eq!(chord("CM9").to_string(), "Cmaj9");
+        assert_eq!(chord("C+").to_string(), "Caug");
+        assert_eq!(chord("Bdim7").to_string(), "Bdim7");
+        assert_eq!(chord("D/F#").transpose(-2, true).to_string(), "C/E");
+
+        // Garbage tokens are no chords
+        for text in ["", "H", "Cadd", "Cm7x", "N.C.", "|", "G/", "am", "C99999"] {
+            assert_eq!(Chord::parse(text), None, "{} has been parsed as chord", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_chord_lines() {
+        let line = ChordLine::parse("  Am   G/B C  N.C.");
+        assert_eq!(line.tokens.iter().map(|(column, _)| *column).collect::<Vec<usize>>(), vec![2, 7, 11, 14]);
+        assert_eq!(line.chords().map(|chord| chord.to_string()).collect::<Vec<String>>(), vec!["Am", "G/B", "C"]);
+        assert_eq!(line.tokens[3].1, ChordToken::Text("N.C.".to_string()));
+        assert_eq!(line.to_string(), "  Am   G/B C  N.C.");
+        assert_eq!(ChordLine::parse(""), ChordLine::default());
+
+        // Tokens which have become longer are moved to the right
+        let mut line = ChordLine::parse("A B");
+        line.tokens[0].1 = ChordToken::Chord(Chord::parse("Amaj7").unwrap());
+        assert_eq!(line.to_string(), "Amaj7 B");
+    }
+
+    #[test]
+    fn test_transpose_chords() {
+        assert_eq!(transpose_line("Am  Dm7  E7sus4  Cadd9  Fmaj7  Bdim", 3, false), "Cm  Fm7  G7sus4  D#add9 G#maj7 Ddim");
+        assert_eq!(transpose_line("[G C] (Em)", -2, true), "[F Bb] (Dm)");
+        assert_eq!(transpose_line("D/F#  A/C#", 5, false), "G/B   D/F#");
+        // Lyrics and other tokens are kept
+        assert_eq!(transpose_line("Amazing grace  N.C.  Add  am", 2, false), "Amazing grace  N.C.  Add  am");
+        assert_eq!(transpose_line("\tG  C ", 12, false), "\tG  C ");
+    }
+
+    #[test]
+    fn test_transpose_keeps_columns() {
+        // A chord which becomes longer or shorter does not move the following chords
+        assert_eq!(transpose_line("G          C", -1, false), "F#         B");
+        assert_eq!(transpose_line("D/F#   A", 2, false), "E/G#   B");
+        assert_eq!(transpose_line("F#m    D/F#   A", 1, false), "Gm     D#/G   A#");
+        let transposed: String = transpose_line("G          C", -1, false);
+        assert_eq!(transposed.find('B'), Some(11));
+        // A chord is only moved if a longer chord before it would overlap it
+        assert_eq!(transpose_line("E/A C", 1, false), "F/A# C#");
+    }
+
+    #[test]
+    fn test_transpose_german_notation() {
+        assert_eq!(transpose_line_with_notation("B  H  Es  As", 2, false, NoteNotation::German), "C  Cis F  Ais");
+        assert_eq!(transpose_line_with_notation("a  d/Fis", 2, false, NoteNotation::German), "h  e/Gis");
+        // In English notation, B is B natural
+        assert_eq!(transpose_line("B  H", 1, false), "C  H");
+    }
+
+    #[test]
+    fn test_transpose_round_trip() {
+        // The gaps are wide enough that no chord has to be moved
+        let line = "C   C#m   D7   D#dim   E/G#   Fmaj7   F#sus4   G/B   G#m7   A   A#add9   B7";
+        assert_eq!(transpose_line(line, 0, true), line);
+        for semitones in -13..=13 {
+            let transposed: String = transpose_line(line, semitones, false);
+            assert_eq!(transpose_line(&transposed, -semitones, false), line, "Transposing by {} changed the line", semitones);
+        }
+
+        let line = "C   Dbm   D7   Ebdim   E/Ab   Fmaj7   Gbsus4   G/B   Abm7   A   Bbadd9   B7";
+        for semitones in -13..=13 {
+            let transposed: String = transpose_line(line, semitones, true);
+            assert_eq!(transpose_line(&transposed, -semitones, true), line, "Transposing by {} changed the line", semitones);
+        }
+    }
+
+    #[test]
+    fn test_to_nashville() {
+        let key: Key = Key::parse("D").unwrap();
+        assert_eq!(line_to_nashville("D  A/C#  Bm7  Gsus2  Em  C  F#m", &key), "1  5/7   6m7  4sus2  2m  b7 3m");
+        // The numbers keep the columns of the chords
+        assert_eq!(line_to_nashville("F#m7       G/B        A", &key), "3m7        4/6        5");
+        assert_eq!(line_to_nashville("[D] (Bb) N.C.", &key), "[1] (b6) N.C.");
+        // Minor keys are numbered from the minor tonic
+        let key: Key = Key::parse("Em").unwrap();
+        assert_eq!(line_to_nashville("Em  C  D  B7  G/B", &key), "1m  6  7  57  3/5");
+        assert_eq!(to_nashville("D#dim", "Em").unwrap(), "#7dim");
+
+        assert_eq!(to_nashville("Xm", "C"), Err(ChordError::InvalidChord { chord: "Xm".to_string() }));
+        assert_eq!(to_nashville("C", "Q"), Err(ChordError::InvalidKey { key: "Q".to_string() }));
+    }
+}
@@ -0,0 +1,214 @@
+//! A song collection is the repertoire of a frontend: the songs of a directory together with the files they come from,
+//! which can be searched and turned into the presentation of a service.
+
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::importer::errors::CantaraImportInvalidSongError;
+use crate::importer::{import_songs_from_directory, SongFile};
+use crate::slides::{slides_from_song, LinkedEntity, PresentationChapter, Slide, SlideSettings};
+use crate::song::Song;
+use crate::text::fold_for_comparison;
+
+/// A song of a collection and the file it has been imported from
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SongEntry {
+    pub song: Song,
+    /// The file of the song, a file with several songs (like a VideoPsalm songbook) is shared by their entries
+    pub song_file: SongFile,
+}
+
+impl SongEntry {
+    /// Returns whether the entry matches a search query (see `SongCollection::search`)
+    fn matches(&self, query_words: &[String]) -> bool {
+        let mut text: Vec<String> = vec![self.song.title.clone()];
+        let mut tags: Vec<(&String, &String)> = self.song.get_tags().iter().collect();
+        tags.sort();
+        text.extend(tags.into_iter().map(|(_, value)| value.clone()));
+        text.push(self.song.plain_lyrics(false));
+        let searched_text: String = fold_for_comparison(&text.join("\n")).split_whitespace().collect::<Vec<&str>>().join(" ");
+        query_words.iter().all(|word| searched_text.contains(word.as_str()))
+    }
+
+    /// Creates the presentation chapter of the song, every slide is linked to the file of the song
+    pub fn to_presentation_chapter(&self, settings: &SlideSettings) -> PresentationChapter {
+        let slides: Vec<Slide> = slides_from_song(&self.song, settings)
+            .into_iter()
+            .map(|slide| slide.with_song_file(self.song_file.clone()))
+            .collect();
+        PresentationChapter::new(slides, LinkedEntity::Song(self.song.clone()))
+    }
+}
+
+/// A collection of songs together with their files
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct SongCollection {
+    pub songs: Vec<SongEntry>,
+}
+
+impl SongCollection {
+    /// Creates a collection from a list of entries
+    pub fn new(songs: Vec<SongEntry>) -> SongCollection {
+        SongCollection { songs }
+    }
+
+    /// Loads all songs from a directory (not recursively) with `import_songs_from_directory`: files which can't be imported
+    /// are skipped, files with several songs add all of their songs and the tags of a defaults file are inherited.
+    /// The songs are sorted by their file name.
+    pub fn load_from_directory(dir: &Path) -> io::Result<SongCollection> {
+        let songs: Vec<SongEntry> = import_songs_from_directory(dir, false)?
+            .into_iter()
+            .filter_map(|(path, song)| Some(SongEntry { song: song.ok()?, song_file: SongFile::new(path.to_str()?).ok()? }))
+            .collect();
+        Ok(SongCollection { songs })
+    }
+
+    /// Searches the songs by their title, their tag values and their lyrics.
+    /// Every word of the query has to occur in the song, the search is case- and diacritic-insensitive.
+    /// # Returns
+    /// The matching entries in the order of the collection (all entries for an empty query)
+    /// # Example
+    /// ```
+    /// use std::path::Path;
+    /// use cantara_songlib::collection::SongCollection;
+    /// let collection = SongCollection::load_from_directory(Path::new("testfiles")).unwrap();
+    /// let results = collection.search("NEWTON wretch");
+    /// assert_eq!(results.len(), 1);
+    /// assert_eq!(results[0].song.title, "Amazing Grace");
+    /// ```
+    pub fn search(&self, query: &str) -> Vec<&SongEntry> {
+        let query_words: Vec<String> = fold_for_comparison(query).split_whitespace().map(str::to_string).collect();
+        self.songs.iter().filter(|entry| entry.matches(&query_words)).collect()
+    }
+
+    /// Returns all entries whose song has the given theme (see `Song::has_theme`).
+    /// The comparison is case- and diacritic-insensitive.
+    pub fn filter_by_theme(&self, theme: &str) -> Vec<&SongEntry> {
+        self.songs.iter().filter(|entry| entry.song.has_theme(theme)).collect()
+    }
+
+    /// Returns the first song with the given title (case- and diacritic-insensitive)
+    pub fn get_by_title(&self, title: &str) -> Option<&SongEntry> {
+        let folded_title: String = fold_for_comparison(title);
+        self.songs.iter().find(|entry| fold_for_comparison(&entry.song.title) == folded_title)
+    }
+
+    /// Creates the presentation of all songs of the collection, one chapter per song (see `SongEntry::to_presentation_chapter`).
+    /// To build a service from some songs, create a collection of the selected entries.
+    /// # Example
+    /// ```
+    /// use std::path::Path;
+    /// use cantara_songlib::collection::SongCollection;
+    /// use cantara_songlib::slides::SlideSettings;
+    /// let collection = SongCollection::load_from_directory(Path::new("testfiles")).unwrap();
+    /// let selection = SongCollection::new(collection.search("grace").into_iter().cloned().collect());
+    /// let chapters = selection.to_presentation(&SlideSettings::default());
+    /// assert_eq!(chapters.len(), selection.songs.len());
+    /// ```
+    pub fn to_presentation(&self, settings: &SlideSettings) -> Vec<PresentationChapter> {
+        self.songs.iter().map(|entry| entry.to_presentation_chapter(settings)).collect()
+    }
+
+    /// Serializes the collection to JSON, e.g. to cache it instead of importing all files again
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Deserializes a collection which has been serialized with `to_json`.
+    /// The songs are validated and their parts are linked like in `import_song_from_json`.
+    /// # Returns
+    /// A Result with the collection or an error if the JSON can't be deserialized or a song is invalid (`CantaraImportInvalidSongError`)
+    pub fn from_json(json: &str) -> Result<SongCollection, Box<dyn Error>> {
+        let mut collection: SongCollection = serde_json::from_str(json)?;
+        for entry in &mut collection.songs {
+            entry.song.link_deserialized_parts()
+                .map_err(|message| CantaraImportInvalidSongError { message })?;
+        }
+        Ok(collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::song::SongPartType;
+
+    #[test]
+    fn test_search() {
+        let collection = SongCollection::load_from_directory(Path::new("testfiles")).unwrap();
+        let titles = |entries: Vec<&SongEntry>| entries.iter().map(|entry| entry.song.title.clone()).collect::<Vec<String>>();
+
+        assert_eq!(titles(collection.search("amazing grace")), vec!["Amazing Grace", "Indented Echo"]);
+        assert_eq!(titles(collection.search("amazing grace newton")), vec!["Amazing Grace"]);
+        // Diacritics and case are ignored, the words may be split across lines
+        assert_eq!(titles(collection.search("naher mein GOTT")), vec!["Näher, mein Gott, zu dir"]);
+        assert_eq!(titles(collection.search("precious did that grace appear")), vec!["Amazing Grace"]);
+        assert!(collection.search("no song contains this").is_empty());
+        assert_eq!(collection.search("").len(), collection.songs.len());
+
+        assert_eq!(collection.get_by_title("AMAZING grace").unwrap().song_file.file_path, Path::new("testfiles/Amazing Grace.song"));
+        assert_eq!(collection.get_by_title("Amazing"), None);
+    }
+
+    #[test]
+    fn test_filter_by_theme() {
+        let mut christmas_song = Song::new("Stille Nacht");
+        christmas_song.add_tag("themes", "Weihnachten");
+        let entry = |song: Song| SongEntry { song, song_file: SongFile::new("testfiles/Amazing Grace.song").unwrap() };
+        let collection = SongCollection::new(vec![entry(christmas_song), entry(Song::new("Amazing Grace"))]);
+
+        let results = collection.filter_by_theme("weihnachten");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].song.title, "Stille Nacht");
+    }
+
+    #[test]
+    fn test_load_songbook_files() {
+        let collection = SongCollection::load_from_directory(Path::new("testfiles/videopsalm")).unwrap();
+        assert!(collection.songs.len() > 1);
+        assert!(collection.get_by_title("Blessed Assurance").is_some());
+        assert!(collection.songs.iter().all(|entry| entry.song_file.file_path.ends_with("Hymns.json")));
+    }
+
+    #[test]
+    fn test_presentation_and_json() {
+        let collection = SongCollection::load_from_directory(Path::new("testfiles")).unwrap();
+        let selection = SongCollection::new(vec![collection.get_by_title("Amazing Grace").unwrap().clone()]);
+
+        let chapters: Vec<PresentationChapter> = selection.to_presentation(&SlideSettings::default());
+        assert_eq!(chapters.len(), 1);
+        assert!(matches!(&chapters[0].linked_entity, LinkedEntity::Song(song) if song.title == "Amazing Grace"));
+        assert!(chapters[0].slides.len() > 3);
+        assert!(chapters[0]
+            .slides
+            .iter()
+            .all(|slide| slide.linked_file.as_ref() == Some(&selection.songs[0].song_file)));
+
+        // The parts of the restored songs are checked to be unique, so the restored collection is compared with a second round trip
+        let json: String = selection.to_json().unwrap();
+        let restored: SongCollection = SongCollection::from_json(&json).unwrap();
+        assert_eq!(restored.songs[0].song_file, selection.songs[0].song_file);
+        assert_eq!(restored.songs[0].song.plain_lyrics(false), selection.songs[0].song.plain_lyrics(false));
+        assert_eq!(SongCollection::from_json(&restored.to_json().unwrap()).unwrap(), restored);
+    }
+
+    #[test]
+    fn test_from_json_links_parts() {
+        let collection = SongCollection::load_from_directory(Path::new("testfiles")).unwrap();
+        let selection = SongCollection::new(vec![collection.get_by_title("O What A Savior That He Died For Me").unwrap().clone()]);
+
+        let mut restored: SongCollection = SongCollection::from_json(&selection.to_json().unwrap()).unwrap();
+        let song: &mut Song = &mut restored.songs[0].song;
+        song.change_part_type("Chorus.1", SongPartType::Bridge).unwrap();
+        // The part order refers to the parts of the song, so it shows the retyped part
+        let ordered_ids: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.get_id()).collect();
+        assert!(ordered_ids.contains(&"Bridge.1".to_string()));
+        assert!(!ordered_ids.contains(&"Chorus.1".to_string()));
+
+        let invalid_json: String = selection.to_json().unwrap().replace("\"Chorus.1\"", "\"Chorus\"");
+        assert!(SongCollection::from_json(&invalid_json).is_err());
+    }
+}
@@ -0,0 +1,269 @@
+//! This module contains functions to export songs to the Cantara classic song format.
+//! See `importer::classic_song` for a description of the format.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::exporter::metadata_tags;
+use crate::song::{Song, SongPart, SongPartContentType, SongPartType};
+
+/// Returns the part which is written to the file for a part: the part itself or the part it is a repetition of
+fn original_part(part: &Rc<RefCell<SongPart>>) -> Rc<RefCell<SongPart>> {
+    match &part.borrow().is_repetition_of {
+        Some(repeated_part) => repeated_part.clone(),
+        None => part.clone(),
+    }
+}
+
+/// Returns the reference to a part as it is written to the `#order` tag (like `verse.1`)
+fn order_reference(part: &SongPart) -> String {
+    format!("{}.{}", part.part_type.to_string().to_lowercase(), part.number)
+}
+
+/// Escapes a lyric line starting with `#`, `//` or `;` and the line `---`, so that it is not read as a tag, a comment or a delimiter
+/// (and a line starting with `\`, so that it is not unescaped). An empty line is written as `\`, because it would end the block.
+fn escape_lyric_line(line: &str) -> String {
+    let content = line.trim_start();
+    match content.is_empty() || content == "---" || ["#", "//", ";", "\\"].iter().any(|prefix| content.starts_with(prefix)) {
+        true => format!("{}\\{}", &line[..line.len() - content.len()], content),
+        false => line.to_string(),
+    }
+}
+
+/// Adds the lines of a content to a block. Empty lines at the beginning and the end of the content are left out.
+fn push_content_lines(lines: &mut Vec<String>, content: &str) {
+    let content_lines: Vec<&str> = content.lines().collect();
+    let first: usize = content_lines.iter().position(|line| !line.trim().is_empty()).unwrap_or(content_lines.len());
+    let last: usize = content_lines.iter().rposition(|line| !line.trim().is_empty()).map_or(first, |last| last + 1);
+    lines.extend(content_lines[first..last].iter().map(|line| escape_lyric_line(line.trim_end())));
+}
+
+/// Returns the part marker (like `(Chorus)` or `(Verse 3)`) of a part or None if the type of the part is guessed correctly
+/// by the importer. Verses are only marked if their numbers are not consecutive in the file, other parts always get a marker,
+/// which contains the number if the parts of this type are not numbered consecutively.
+/// A verse which the importer could take for a bridge (`marked_verses`) is always marked.
+fn part_marker(part: &SongPart, consecutive_types: &[SongPartType], marked_verses: &[String]) -> Option<String> {
+    let is_consecutive: bool = consecutive_types.contains(&part.part_type);
+    match part.part_type {
+        // Parts without a known type are imported as verses
+        SongPartType::Other => None,
+        SongPartType::Verse if is_consecutive && !marked_verses.contains(&order_reference(part)) => None,
+        SongPartType::Verse => Some(format!("({} {})", part.part_type, part.number)),
+        part_type if is_consecutive => Some(format!("({})", part_type)),
+        part_type => Some(format!("({} {})", part_type, part.number)),
+    }
+}
+
+/// Creates the block of a part: the comments, the part marker, the lyrics and the other languages after `---` lines
+fn part_block(part: &SongPart, consecutive_types: &[SongPartType], marked_verses: &[String]) -> String {
+    let mut lines: Vec<String> = part.comments.iter().map(|comment| format!("// {}", comment)).collect();
+    lines.extend(part_marker(part, consecutive_types, marked_verses));
+    let lyrics = part
+        .contents
+        .iter()
+        .filter(|content| matches!(content.voice_type, SongPartContentType::Lyrics { .. }));
+    for (index, content) in lyrics.enumerate() {
+        if index > 0 {
+            lines.push("---".to_string());
+        }
+        push_content_lines(&mut lines, &content.content);
+    }
+    lines.join("\n")
+}
+
+/// Exports a song to the classic song format.
+/// The tags are written to a metadata block (see `metadata_tags`: the title first, the other tags in alphabetical order,
+/// inherited tags are left out and the authors, the key and other typed tags are written in their canonical form).
+/// The parts are written in the order of the part order of the song (or in the order in which they have been added) and
+/// a part which is sung several times (like a chorus) is only written at its first occurrence. If the order of the song
+/// differs from the order of the written parts, it is written as `#order` tag.
+/// Parts which are no verses get a part marker (like `(Chorus)`), so that their types are kept. Verses which the importer
+/// would take for a bridge (a verse sung once between two choruses) are marked as `(Verse N)`.
+/// Lyrics in other languages are written after `---` lines, chords and voices can't be stored and are left out.
+/// Empty lines within the lyrics are written as `\`, because they would end the block.
+/// # Returns
+/// The content of the classic song file
+/// # Example
+/// ```
+/// use cantara_songlib::exporter::classic_song::export_song;
+/// use cantara_songlib::importer::classic_song::import_song;
+/// let chorus = "Sing the chorus\nonce again";
+/// let song = import_song(&format!("#title: Test\n\nFirst verse\n\n{chorus}\n\nSecond verse\n\n{chorus}")).unwrap();
+/// assert_eq!(
+///     export_song(&song),
+///     format!("#title: Test\n#order: verse.1, chorus.1, verse.2, chorus.1\n\nFirst verse\n\n(Chorus)\n{chorus}\n\n(Verse 2)\nSecond verse\n")
+/// );
+/// ```
+pub fn export_song(song: &Song) -> String {
+    let song_parts: Vec<Rc<RefCell<SongPart>>> = (0..song.get_total_part_count())
+        .filter_map(|index| song.get_part_by_index(index))
+        .collect();
+    let sung_parts: Vec<Rc<RefCell<SongPart>>> = match song.get_active_part_order() {
+        Some(part_order) => part_order.to_parts(song),
+        None => song_parts.clone(),
+    }
+    .iter()
+    .map(original_part)
+    .collect();
+
+    // Every part is written once at its first occurrence, parts which are not sung are written at the end
+    let mut written_parts: Vec<Rc<RefCell<SongPart>>> = Vec::new();
+    for part in sung_parts.iter().chain(song_parts.iter().filter(|part| part.borrow().is_repetition_of.is_none())) {
+        let id: String = order_reference(&part.borrow());
+        if !written_parts.iter().any(|written_part| order_reference(&written_part.borrow()) == id) {
+            written_parts.push(part.clone());
+        }
+    }
+
+    let mut metadata: Vec<String> = metadata_tags(song).iter().map(|(key, value)| format!("#{}: {}", key, value)).collect();
+    let sung_order: Vec<String> = sung_parts.iter().map(|part| order_reference(&part.borrow())).collect();
+    let written_order: Vec<String> = written_parts.iter().map(|part| order_reference(&part.borrow())).collect();
+    if sung_order != written_order {
+        metadata.push(format!("#order: {}", sung_order.join(", ")));
+    }
+
+    // The importer numbers the parts of a type consecutively, so the numbers are only written if they differ from that
+    let mut part_types: Vec<SongPartType> = Vec::new();
+    for part in &written_parts {
+        if !part_types.contains(&part.borrow().part_type) {
+            part_types.push(part.borrow().part_type);
+        }
+    }
+    let consecutive_types: Vec<SongPartType> = part_types
+        .into_iter()
+        .filter(|part_type| {
+            written_parts
+                .iter()
+                .filter(|part| part.borrow().part_type == *part_type)
+                .enumerate()
+                .all(|(index, part)| part.borrow().number as usize == index + 1)
+        })
+        .collect();
+
+    // The importer takes an unmarked verse which is sung once between two choruses for a bridge (unless it is the first verse)
+    let is_chorus = |part: &Rc<RefCell<SongPart>>| matches!(part.borrow().part_type, SongPartType::Chorus | SongPartType::Refrain);
+    let first_verse: Option<String> = sung_order.iter().zip(&sung_parts).find(|(_, part)| part.borrow().part_type == SongPartType::Verse).map(|(id, _)| id.clone());
+    let marked_verses: Vec<String> = sung_parts
+        .windows(3)
+        .zip(sung_order.windows(3))
+        .filter(|(parts, ids)| {
+            is_chorus(&parts[0])
+                && is_chorus(&parts[2])
+                && parts[1].borrow().part_type == SongPartType::Verse
+                && Some(&ids[1]) != first_verse.as_ref()
+                && sung_order.iter().filter(|id| **id == ids[1]).count() == 1
+        })
+        .map(|(_, ids)| ids[1].clone())
+        .collect();
+
+    let mut blocks: Vec<String> = Vec::new();
+    if !metadata.is_empty() {
+        blocks.push(metadata.join("\n"));
+    }
+    blocks.extend(written_parts.iter().map(|part| part_block(&part.borrow(), &consecutive_types, &marked_verses)));
+
+    let mut content: String = blocks.join("\n\n");
+    content.push('\n');
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::importer::classic_song::import_song;
+    use crate::importer::import_song_from_file;
+    use crate::song::{LyricLanguage, SongPartContent, SongPartId};
+
+    #[test]
+    fn test_round_trip() {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir("testfiles")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "song"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let song: Song = import_song_from_file(path.to_str().unwrap())
+                .unwrap_or_else(|error| panic!("{} can't be imported: {}", path.display(), error));
+            let exported: String = export_song(&song);
+            let reimported: Song = import_song(&exported).unwrap();
+            assert_eq!(reimported.title, song.title, "The round trip of {} changed the title", path.display());
+            // The title of a file without a title tag is taken from the file name, so it is only written as tag
+            let tags = |song: &Song| {
+                let mut tags = song.get_tags().clone();
+                tags.remove("title");
+                tags
+            };
+            assert_eq!(tags(&reimported), tags(&song), "The round trip of {} changed the tags", path.display());
+            assert_eq!(
+                reimported.get_total_part_count(),
+                song.get_total_part_count(),
+                "The round trip of {} changed the parts",
+                path.display()
+            );
+            let part_ids = |parts: Vec<SongPart>| parts.iter().map(order_reference).collect::<Vec<String>>();
+            let sorted_part_ids = |song: &Song| {
+                let mut part_ids: Vec<String> = part_ids(song.get_unpacked_parts());
+                part_ids.sort();
+                part_ids
+            };
+            assert_eq!(
+                sorted_part_ids(&reimported),
+                sorted_part_ids(&song),
+                "The round trip of {} changed the parts",
+                path.display()
+            );
+            if song.get_active_part_order().is_some() {
+                let ordered_parts = |song: &Song| song.get_ordered_parts().iter().map(|part| part.borrow().clone()).collect();
+                assert_eq!(
+                    part_ids(ordered_parts(&reimported)),
+                    part_ids(ordered_parts(&song)),
+                    "The round trip of {} changed the part order",
+                    path.display()
+                );
+            }
+            assert_eq!(export_song(&reimported), exported);
+        }
+    }
+
+    #[test]
+    fn test_export_lyrics() {
+        let mut song = Song::new("Test");
+        let mut verse = SongPart::new(SongPartId::parse("verse.1").unwrap(), 1);
+        verse.add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "#1 in my heart\n\nyou are the one\n; the only one\n".to_string(),
+        });
+        verse.add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) },
+            content: "Nummer eins in meinem Herzen\n".to_string(),
+        });
+        verse.add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "G C\n".to_string() });
+        verse.comments.push("sing twice".to_string());
+        song.add_part(verse);
+
+        let exported: String = export_song(&song);
+        assert_eq!(
+            exported,
+            "#title: Test\n\n// sing twice\n\\#1 in my heart\n\\\nyou are the one\n\\; the only one\n---\nNummer eins in meinem Herzen\n"
+        );
+        let reimported: Song = import_song(&exported).unwrap();
+        assert_eq!(reimported.get_total_part_count(), 1);
+        assert_eq!(
+            reimported.get_part_by_id("Verse.1").unwrap().borrow().contents[0].content,
+            "#1 in my heart\n\nyou are the one\n; the only one\n"
+        );
+    }
+
+    #[test]
+    fn test_export_part_markers() {
+        let content = "#title: Test\n\n(Verse 2)\nSecond verse\n\n(Chorus)\nThe chorus\n\n(Verse 1)\nFirst verse";
+        let song: Song = import_song(content).unwrap();
+        let exported: String = export_song(&song);
+        assert_eq!(exported, "#title: Test\n\n(Verse 2)\nSecond verse\n\n(Chorus)\nThe chorus\n\n(Verse 1)\nFirst verse\n");
+        assert_eq!(import_song(&exported).unwrap(), song);
+    }
+}
@@ -0,0 +1,160 @@
+//! This module contains functions to export songs to the Cantara structured song format (cssf).
+//! See `importer::cssf` for a description of the format.
+
+use std::collections::HashSet;
+
+use crate::exporter::metadata_tags;
+use crate::song::{LyricLanguage, Song, SongPart, SongPartContentType};
+
+/// Returns the sub-block marker (like `--chords`) for a content type or None if the content type can't be stored in cssf
+fn sub_block_marker(content_type: &SongPartContentType) -> Option<&'static str> {
+    match content_type {
+        SongPartContentType::Chords => Some("--chords"),
+        SongPartContentType::LeadVoice => Some("--lead"),
+        SongPartContentType::SupranoVoice => Some("--soprano"),
+        SongPartContentType::AltoVoice => Some("--alto"),
+        SongPartContentType::TenorVoice => Some("--tenor"),
+        SongPartContentType::BassVoice => Some("--bass"),
+        _ => None,
+    }
+}
+
+/// Returns the ID of a part as it is written in cssf (like `verse.1`)
+fn part_id(part: &SongPart) -> String {
+    part.id.to_string().to_lowercase()
+}
+
+/// Adds the lines of a content to a block. Empty lines are left out, because they would end the block.
+fn push_content_lines(lines: &mut Vec<String>, content: &str) {
+    lines.extend(content.lines().filter(|line| !line.trim().is_empty()).map(str::to_string));
+}
+
+/// Creates the block of a part: the categorization line, the lyrics in the given language and the sub-blocks for chords and voices
+fn part_block(part: &SongPart, language: &LyricLanguage) -> String {
+    let mut lines: Vec<String> = vec![format!("#{}", part_id(part))];
+    if let Some(lyrics) = part.get_lyrics(language) {
+        push_content_lines(&mut lines, &lyrics.content);
+    }
+    for content in &part.contents {
+        if let Some(marker) = sub_block_marker(&content.voice_type) {
+            lines.push(marker.to_string());
+            push_content_lines(&mut lines, &content.content);
+        }
+    }
+    lines.join("\n")
+}
+
+/// Exports a song to the cssf format.
+/// The tags are written to a metadata block (see `metadata_tags`: the title first, the other tags in alphabetical order,
+/// inherited tags are left out and the authors, the key and other typed tags are written in their canonical form).
+/// Every part is written as a categorized block with its lyrics and sub-blocks for its chords and voices.
+/// If the song has a part order, it is written as `#order:` tag to the metadata block and a part which is a repetition
+/// of another part is referred to by the ID of that part. Without a part order, such a part is written as a bare
+/// categorization line at its position.
+/// Only the lyrics in the primary language of the song (see `SongPart::get_lyrics`) are written,
+/// lyrics in other languages can't be stored in cssf and are left out.
+/// # Returns
+/// The content of the cssf file
+/// # Example
+/// ```
+/// use cantara_songlib::exporter::cssf::export_song;
+/// use cantara_songlib::importer::cssf::import_song;
+/// let song = import_song("#title: Test\n#author: Somebody\n\n#verse.1\nFirst verse\n--chords\nG C").unwrap();
+/// assert_eq!(export_song(&song), "#title: Test\n#author: Somebody\n\n#verse.1\nFirst verse\n--chords\nG C\n");
+/// ```
+pub fn export_song(song: &Song) -> String {
+    let mut metadata: Vec<String> = metadata_tags(song).iter().map(|(key, value)| format!("#{}: {}", key, value)).collect();
+    if let Some(part_order) = song.get_active_part_order() {
+        let ids: Vec<String> = part_order
+            .to_parts(song)
+            .iter()
+            .map(|part| {
+                let part = part.borrow();
+                match &part.is_repetition_of {
+                    Some(repeated_part) => part_id(&repeated_part.borrow()),
+                    None => part_id(&part),
+                }
+            })
+            .collect();
+        metadata.push(format!("#order: {}", ids.join(", ")));
+    }
+
+    let mut blocks: Vec<String> = Vec::new();
+    if !metadata.is_empty() {
+        blocks.push(metadata.join("\n"));
+    }
+
+    let mut written_ids: HashSet<String> = HashSet::new();
+    for part in (0..song.get_total_part_count()).filter_map(|index| song.get_part_by_index(index)) {
+        let part = part.borrow();
+        match &part.is_repetition_of {
+            // The repetition is already contained in the part order
+            Some(_) if song.get_active_part_order().is_some() => {}
+            Some(repeated_part) => blocks.push(format!("#{}", part_id(&repeated_part.borrow()))),
+            None if written_ids.insert(part_id(&part)) => blocks.push(part_block(&part, &song.primary_language())),
+            None => {}
+        }
+    }
+
+    let mut content: String = blocks.join("\n\n");
+    content.push('\n');
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::importer::cssf::import_song;
+    use crate::importer::import_song_from_file;
+    use crate::song::{PartOrder, PartOrderName, PartOrderRule, SongPartContent, SongPartId};
+
+    #[test]
+    fn test_round_trip() {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir("testfiles/cssf")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "cssf"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Ok(song) = import_song(&std::fs::read_to_string(&path).unwrap()) else {
+                continue;
+            };
+            let exported: String = export_song(&song);
+            assert_eq!(import_song(&exported).unwrap(), song, "The round trip of {} changed the song", path.display());
+            assert_eq!(export_song(&import_song(&exported).unwrap()), exported);
+        }
+    }
+
+    #[test]
+    fn test_export_repetitions() {
+        let song = import_song_from_file("testfiles/cssf/stanzas.cssf").unwrap();
+        let exported: String = export_song(&song);
+        assert!(exported.contains("#order: verse.1, refrain.1, verse.2, refrain.1\n"));
+        assert!(exported.ends_with("Hirten erst kundgemacht\n"));
+        assert_eq!(exported.matches("Schlaf in himmlischer Ruh,").count(), 1);
+
+        // A part which is a repetition of another part refers to it
+        let mut song = Song::new("Test");
+        let mut chorus = SongPart::new(SongPartId::parse("chorus.1").unwrap(), 1);
+        chorus.add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "The chorus\n".to_string(),
+        });
+        song.add_part(chorus);
+        let mut repetition = SongPart::new(SongPartId::parse("chorus.2").unwrap(), 2);
+        repetition.set_repition(song.get_part_by_id("chorus.1"));
+        song.add_part(repetition);
+        assert_eq!(export_song(&song), "#title: Test\n\n#chorus.1\nThe chorus\n\n#chorus.1\n");
+
+        // With a part order, the repetition is written to the order
+        song.set_part_order(PartOrder::new(
+            PartOrderName::Default,
+            PartOrderRule::Custom(vec![song.get_part_by_id("chorus.1").unwrap(), song.get_part_by_id("chorus.2").unwrap()]),
+        ));
+        assert_eq!(export_song(&song), "#title: Test\n#order: chorus.1, chorus.1\n\n#chorus.1\nThe chorus\n");
+    }
+}
@@ -0,0 +1,451 @@
+//! The exporter module contains functions for exporting songs to different formats.
+//! Specific submodules are used for different file formats.
+
+/// This module contains functions for exporting songs to the classic song format.
+pub mod classic_song;
+/// This module contains functions for exporting songs to the cssf format.
+pub mod cssf;
+#[cfg(test)]
+mod round_trip_tests;
+
+use core::fmt;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::filetypes::FileType;
+use crate::metadata::{format_authors, SongMetadata};
+use crate::song::{LyricLanguage, Song, SongPartContentType, SongPartType, TagSource};
+
+/// A warning which describes which information of a song would get lost during an export to a certain format
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum LossWarning {
+    /// The song contains lyrics, but the target format can't store them
+    Lyrics,
+    /// The song contains lyrics in multiple languages, but the target format can only store one
+    MultipleLanguages { languages: Vec<LyricLanguage> },
+    /// The song contains chords, but the target format can't store them
+    Chords,
+    /// The song contains voices (lead voice or SATB), but the target format can't store them
+    Voices { voices: Vec<SongPartContentType> },
+    /// The song contains typed parts (e.g. chorus or bridge), but the target format can't store the part types
+    PartStructure { part_types: Vec<SongPartType> },
+    /// The song has a presentation order, but the target format can't store it
+    PresentationOrder,
+    /// The song contains tags, but the target format can't store them
+    Metadata { tags: Vec<String> },
+}
+
+impl fmt::Display for LossWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LossWarning::Lyrics => write!(f, "The lyrics will be lost"),
+            LossWarning::MultipleLanguages { languages } => write!(
+                f,
+                "Only one of {} lyric languages will be kept",
+                languages.len()
+            ),
+            LossWarning::Chords => write!(f, "The chords will be lost"),
+            LossWarning::Voices { voices } => write!(
+                f,
+                "The voices will be lost: {}",
+                voices.iter().map(|voice| voice.to_string()).collect::<Vec<String>>().join(", ")
+            ),
+            LossWarning::PartStructure { part_types } => write!(
+                f,
+                "The part types will be lost: {}",
+                part_types.iter().map(|part_type| part_type.to_string()).collect::<Vec<String>>().join(", ")
+            ),
+            LossWarning::PresentationOrder => write!(f, "The presentation order will be lost"),
+            LossWarning::Metadata { tags } => write!(
+                f,
+                "The tags will be lost: {}",
+                tags.join(", ")
+            ),
+        }
+    }
+}
+
+/// Compares the content of a song with the capabilities of a target format
+/// and returns a list of everything which would get lost during the export.
+///
+/// # Arguments
+/// * `song` - The song which is to be exported
+/// * `target` - The format the song is to be exported to
+///
+/// # Returns
+/// A list of `LossWarning`s, which is empty if the export is lossless.
+///
+/// # Example
+/// ```
+/// use cantara_songlib::exporter::lossiness;
+/// use cantara_songlib::filetypes::FileType;
+/// use cantara_songlib::song::Song;
+///
+/// let mut song = Song::new("Amazing Grace");
+/// song.add_tag("author", "John Newton");
+/// assert_eq!(lossiness(&song, FileType::PlainText).len(), 1);
+/// assert!(lossiness(&song, FileType::ClassicSongFile).is_empty());
+/// ```
+pub fn lossiness(song: &Song, target: FileType) -> Vec<LossWarning> {
+    let capabilities = target.capabilities();
+    let content_types = song.get_content_types();
+    let mut warnings: Vec<LossWarning> = Vec::new();
+
+    let mut languages: Vec<LyricLanguage> = Vec::new();
+    for content_type in &content_types {
+        if let SongPartContentType::Lyrics { language } = content_type {
+            if !languages.contains(language) {
+                languages.push(language.clone());
+            }
+        }
+    }
+
+    if !capabilities.lyrics && !languages.is_empty() {
+        warnings.push(LossWarning::Lyrics);
+    }
+
+    if capabilities.lyrics && !capabilities.multiple_languages && languages.len() > 1 {
+        warnings.push(LossWarning::MultipleLanguages { languages });
+    }
+
+    if !capabilities.chords && content_types.contains(&SongPartContentType::Chords) {
+        warnings.push(LossWarning::Chords);
+    }
+
+    let voices: Vec<SongPartContentType> = content_types
+        .iter()
+        .filter(|content_type| matches!(
+            content_type,
+            SongPartContentType::LeadVoice
+                | SongPartContentType::SupranoVoice
+                | SongPartContentType::AltoVoice
+                | SongPartContentType::TenorVoice
+                | SongPartContentType::BassVoice
+        ))
+        .cloned()
+        .collect();
+    if !capabilities.voices && !voices.is_empty() {
+        warnings.push(LossWarning::Voices { voices });
+    }
+
+    let mut part_types: Vec<SongPartType> = Vec::new();
+    for part in song.get_unpacked_parts() {
+        if part.part_type != SongPartType::Verse && !part_types.contains(&part.part_type) {
+            part_types.push(part.part_type);
+        }
+    }
+    if !capabilities.part_structure && !part_types.is_empty() {
+        warnings.push(LossWarning::PartStructure { part_types });
+    }
+
+    if !capabilities.presentation_order && !song.part_orders.is_empty() {
+        warnings.push(LossWarning::PresentationOrder);
+    }
+
+    if !capabilities.metadata && !song.get_tags().is_empty() {
+        let mut tags: Vec<String> = song.get_tags().keys().cloned().collect();
+        tags.sort();
+        warnings.push(LossWarning::Metadata { tags });
+    }
+
+    warnings
+}
+
+/// The error of an export to a format which has no exporter
+#[derive(Debug, Clone, PartialEq)]
+pub struct CantaraExportUnsupportedFormatError {
+    pub file_type: FileType,
+}
+
+impl fmt::Display for CantaraExportUnsupportedFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Songs can't be exported to {:?} files", self.file_type)
+    }
+}
+
+impl Error for CantaraExportUnsupportedFormatError {}
+
+/// Exports a song to the given format. Check `lossiness` first to find out what the export would lose.
+/// Songs can be exported to classic song files, cssf, plain text (the lyrics in the order in which they are sung)
+/// and Cantara JSON.
+/// # Returns
+/// The content of the exported file or a `CantaraExportUnsupportedFormatError` for other formats
+/// # Example
+/// ```
+/// use cantara_songlib::exporter::export_song_to_string;
+/// use cantara_songlib::filetypes::FileType;
+/// use cantara_songlib::song::SongBuilder;
+///
+/// let song = SongBuilder::new("Test").verse("First verse").chorus("The chorus").build();
+/// assert_eq!(export_song_to_string(&song, FileType::PlainText).unwrap(), "First verse\n\nThe chorus");
+/// assert!(export_song_to_string(&song, FileType::ProPresenter).is_err());
+/// ```
+pub fn export_song_to_string(song: &Song, target: FileType) -> Result<String, Box<dyn Error>> {
+    match target {
+        FileType::ClassicSongFile => Ok(classic_song::export_song(song)),
+        FileType::CSSF => Ok(cssf::export_song(song)),
+        FileType::PlainText => Ok(song.plain_lyrics(true)),
+        FileType::CantaraJson => Ok(serde_json::to_string_pretty(song)?),
+        file_type => Err(Box::new(CantaraExportUnsupportedFormatError { file_type })),
+    }
+}
+
+/// Returns the tags which the classic song and the cssf exporter write to their metadata block, read via `SongMetadata`:
+/// the title first, then the other tags in alphabetical order. Inherited tags and the `order` tag are left out,
+/// multi-line values are joined to one line.
+/// The authors (with `format_authors`), the CCLI number, the key, the tempo and the time signature are written in their
+/// canonical form. Values which can't be parsed and all other tags are written as they are, because e.g. the canonical
+/// form of a copyright would drop the `©` sign.
+pub(crate) fn metadata_tags(song: &Song) -> Vec<(String, String)> {
+    let (metadata, _) = SongMetadata::from_tags(song);
+    let mut tags: BTreeMap<String, String> = song
+        .get_tags()
+        .iter()
+        .filter(|(key, _)| key.as_str() != "title" && key.as_str() != "order")
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    let typed_tags: [(&str, Option<String>); 5] = [
+        ("author", (!metadata.authors.is_empty()).then(|| format_authors(&metadata.authors))),
+        ("ccli", metadata.ccli.map(|ccli| ccli.to_string())),
+        ("key", metadata.key.as_ref().map(|key| key.to_string())),
+        ("tempo", metadata.tempo.as_ref().map(|tempo| tempo.to_string())),
+        ("time", metadata.time_signature.map(|time_signature| time_signature.to_string())),
+    ];
+    for (key, value) in typed_tags {
+        if let Some(value) = value {
+            tags.insert(key.to_string(), value);
+        }
+    }
+    tags.retain(|key, _| song.tag_source(key) != Some(TagSource::Inherited));
+
+    let mut result: Vec<(String, String)> = Vec::new();
+    if !metadata.title.is_empty() {
+        result.push(("title".to_string(), metadata.title));
+    }
+    result.extend(tags.into_iter().map(|(key, value)| (key, value.lines().collect::<Vec<&str>>().join(" "))));
+    result
+}
+
+/// The maximum number of characters of a suggested file name (without the extension)
+const MAX_FILE_STEM_LENGTH: usize = 100;
+
+/// Transliterates characters which are problematic in file names on some systems (e.g. umlauts)
+fn transliterate(character: char) -> Option<&'static str> {
+    let replacement = match character {
+        'ä' => "ae", 'ö' => "oe", 'ü' => "ue",
+        'Ä' => "Ae", 'Ö' => "Oe", 'Ü' => "Ue",
+        'ß' => "ss",
+        'à' | 'á' | 'â' | 'ã' | 'å' => "a",
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' => "A",
+        'è' | 'é' | 'ê' | 'ë' => "e",
+        'È' | 'É' | 'Ê' | 'Ë' => "E",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'Ì' | 'Í' | 'Î' | 'Ï' => "I",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ø' => "o",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' => "O",
+        'ù' | 'ú' | 'û' => "u",
+        'Ù' | 'Ú' | 'Û' => "U",
+        'ç' => "c", 'Ç' => "C",
+        'ñ' => "n", 'Ñ' => "N",
+        'æ' => "ae", 'Æ' => "Ae",
+        'œ' => "oe", 'Œ' => "Oe",
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+/// Creates a file stem from a song title which is safe to use on all common file systems
+fn file_stem_from_title(title: &str) -> String {
+    let mut stem = String::new();
+    for character in title.chars() {
+        if let Some(replacement) = transliterate(character) {
+            stem.push_str(replacement);
+        } else if character.is_whitespace() {
+            stem.push(' ');
+        } else if !character.is_control() && !matches!(character, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') {
+            stem.push(character);
+        }
+    }
+
+    let mut stem: String = stem.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if stem.chars().count() > MAX_FILE_STEM_LENGTH {
+        stem = stem.chars().take(MAX_FILE_STEM_LENGTH).collect();
+    }
+    // Windows does not allow file names ending with a dot or a space
+    let mut stem = stem.trim_end_matches(['.', ' ']).to_string();
+
+    if stem.is_empty() {
+        stem = "Untitled".to_string();
+    }
+    // Device names which are reserved on Windows
+    let reserved = ["CON", "PRN", "AUX", "NUL",
+        "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+        "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9"];
+    if reserved.contains(&stem.to_uppercase().as_str()) {
+        stem.push('_');
+    }
+    stem
+}
+
+/// Suggests a file name for exporting a song to the given format.
+/// The title of the song is transliterated (e.g. umlauts), characters which are invalid on Windows are removed,
+/// whitespace is collapsed and the length is bounded. The default extension of the format is appended.
+/// # Example
+/// ```
+/// use cantara_songlib::exporter::suggest_filename;
+/// use cantara_songlib::filetypes::FileType;
+/// use cantara_songlib::song::Song;
+///
+/// let song = Song::new("Großer Gott, wir loben dich");
+/// assert_eq!(suggest_filename(&song, FileType::ClassicSongFile), "Grosser Gott, wir loben dich.song");
+/// ```
+pub fn suggest_filename(song: &Song, target: FileType) -> String {
+    format!("{}.{}", file_stem_from_title(&song.title), target.default_extension())
+}
+
+/// Suggests a file name in a directory which is not used yet and reserves it by creating an empty file.
+/// If the suggested file name already exists, a counter is appended (e.g. `Amazing Grace (2).song`).
+/// As the file is created atomically, two concurrent calls will never return the same path.
+/// # Arguments
+/// * `dir` - The directory in which the file should be created
+/// * `song` - The song which is to be exported
+/// * `target` - The format the song is to be exported to
+/// # Returns
+/// The path of the newly created (empty) file or an IO error
+pub fn suggest_unique_filename(dir: &Path, song: &Song, target: FileType) -> io::Result<PathBuf> {
+    let stem = file_stem_from_title(&song.title);
+    let extension = target.default_extension();
+
+    let mut counter: u32 = 1;
+    loop {
+        let file_name = match counter {
+            1 => format!("{}.{}", stem, extension),
+            _ => format!("{} ({}).{}", stem, counter, extension),
+        };
+        let path = dir.join(file_name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::song::{SongPart, SongPartContent, SongPartId};
+
+    fn bilingual_song_with_chords() -> Song {
+        let mut song = Song::new("Amazing Grace");
+        song.add_tag("author", "John Newton");
+
+        let mut part = SongPart::new(SongPartId::parse("verse.1").unwrap(), 1);
+        part.add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "Amazing grace, how sweet the sound".to_string(),
+        });
+        part.add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) },
+            content: "O Gnade Gottes, wunderbar".to_string(),
+        });
+        part.add_content(SongPartContent {
+            voice_type: SongPartContentType::Chords,
+            content: "G C G".to_string(),
+        });
+        song.add_part(part);
+        song
+    }
+
+    #[test]
+    fn test_lossiness_to_plain_text() {
+        let song = bilingual_song_with_chords();
+        let warnings = lossiness(&song, FileType::PlainText);
+
+        assert_eq!(warnings.len(), 3);
+        assert!(matches!(&warnings[0], LossWarning::MultipleLanguages { languages } if languages.len() == 2));
+        assert_eq!(warnings[1], LossWarning::Chords);
+        assert_eq!(warnings[2], LossWarning::Metadata { tags: vec!["author".to_string()] });
+    }
+
+    #[test]
+    fn test_lossiness_to_cssf() {
+        let song = bilingual_song_with_chords();
+        assert!(lossiness(&song, FileType::CSSF).is_empty());
+    }
+
+    #[test]
+    fn test_export_song_to_string() {
+        let song = bilingual_song_with_chords();
+        assert_eq!(export_song_to_string(&song, FileType::CSSF).unwrap(), cssf::export_song(&song));
+        assert_eq!(export_song_to_string(&song, FileType::ClassicSongFile).unwrap(), classic_song::export_song(&song));
+        let json: String = export_song_to_string(&song, FileType::CantaraJson).unwrap();
+        assert_eq!(crate::importer::import_song_from_json(&json).unwrap().title, "Amazing Grace");
+
+        let error = export_song_to_string(&song, FileType::SongBeamer).unwrap_err();
+        assert_eq!(error.downcast_ref(), Some(&CantaraExportUnsupportedFormatError { file_type: FileType::SongBeamer }));
+    }
+
+    #[test]
+    fn test_metadata_tags() {
+        let mut song = Song::new("Amazing Grace");
+        song.add_tag("title", "Amazing Grace");
+        song.add_tag("author", "John Newton (words) and Traditional | music");
+        song.add_tag("copyright", "© 1779 John Newton");
+        song.add_tag("ccli", "no number");
+        song.add_tag("key", "g-moll");
+        song.add_tag("tempo", "72 bpm");
+        song.add_tag("order", "verse.1");
+        song.add_tag("note", "First line\nsecond line");
+        song.inherit_tags(&HashMap::from([("songbook".to_string(), "Hymns".to_string())]));
+
+        let tags: Vec<(String, String)> = metadata_tags(&song);
+        let expected: Vec<(&str, &str)> = vec![
+            ("title", "Amazing Grace"),
+            ("author", "John Newton (words), Traditional (music)"),
+            ("ccli", "no number"),
+            ("copyright", "© 1779 John Newton"),
+            ("key", "Gm"),
+            ("note", "First line second line"),
+            ("tempo", "72"),
+        ];
+        assert_eq!(tags, expected.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect::<Vec<(String, String)>>());
+    }
+
+    #[test]
+    fn test_suggest_filename() {
+        assert_eq!(suggest_filename(&Song::new("Näher, mein Gott, zu dir"), FileType::CSSF), "Naeher, mein Gott, zu dir.cssf");
+        assert_eq!(suggest_filename(&Song::new("What?  A <friend>: \"Jesus\" / Lord*"), FileType::PlainText), "What A friend Jesus Lord.txt");
+        assert_eq!(suggest_filename(&Song::new("Amen..."), FileType::ClassicSongFile), "Amen.song");
+        assert_eq!(suggest_filename(&Song::new("???"), FileType::ClassicSongFile), "Untitled.song");
+        assert_eq!(suggest_filename(&Song::new("con"), FileType::ClassicSongFile), "con_.song");
+
+        let long_title = "a".repeat(300);
+        assert_eq!(suggest_filename(&Song::new(&long_title), FileType::ClassicSongFile).len(), MAX_FILE_STEM_LENGTH + 5);
+    }
+
+    #[test]
+    fn test_suggest_unique_filename() {
+        let dir = std::env::temp_dir().join(format!("cantara-songlib-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let song = Song::new("Amazing Grace");
+
+        let first = suggest_unique_filename(&dir, &song, FileType::ClassicSongFile).unwrap();
+        let second = suggest_unique_filename(&dir, &song, FileType::ClassicSongFile).unwrap();
+        let third = suggest_unique_filename(&dir, &song, FileType::ClassicSongFile).unwrap();
+
+        assert_eq!(first.file_name().unwrap(), "Amazing Grace.song");
+        assert_eq!(second.file_name().unwrap(), "Amazing Grace (2).song");
+        assert_eq!(third.file_name().unwrap(), "Amazing Grace (3).song");
+        assert!(third.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+//! Property-style round trip tests for the exporters: random songs are created with the `SongBuilder`,
+//! exported to the classic song format and to cssf and imported again. The imported song has to be structurally equal
+//! to the generated one (same title, tags, part types and numbers, part order and lyrics).
+//! The comparison ignores what the formats document as normalization or loss: the `title` tag which the importers add,
+//! the canonical form of the authors, the line break at the end of the lyrics, empty lines in cssf, chords in classic song files
+//! and translations in cssf.
+//! A failing case prints its seed and the exported source.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::exporter::{classic_song, cssf};
+use crate::importer;
+use crate::metadata::{format_authors, parse_authors};
+use crate::song::{LyricLanguage, Song, SongBuilder, SongPart, SongPartContent, SongPartContentType, SongPartType};
+
+/// The number of random songs which are tested
+const CASES: u64 = 300;
+
+/// A small deterministic random number generator (SplitMix64), so that every failure can be reproduced from its seed
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value: u64 = self.0;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /// Returns a number in the range `from..=to`
+    fn range(&mut self, from: usize, to: usize) -> usize {
+        from + (self.next() % (to - from + 1) as u64) as usize
+    }
+
+    /// Returns true with the given probability in percent
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0, items.len() - 1)]
+    }
+}
+
+const WORDS: [&str; 16] = [
+    "grace", "amazing", "sound", "glory", "Lord", "heart", "sing", "praise", "light", "über", "Gnade", "joy", "Zion", "peace",
+    "forever", "Halleluja",
+];
+
+/// Lyric lines which need to be escaped in at least one of the formats
+const TRICKY_LINES: [&str; 7] = ["#1 in my heart", "; capo 2", "// not a comment", "\\o/ rejoice", "---", "  indented echo", "Verse: one"];
+
+/// Tag values with characters which have a meaning in the formats
+const TRICKY_TAG_VALUES: [&str; 8] = [
+    "#1 hit",
+    "Psalm 23:1",
+    "---",
+    "// not a comment",
+    "C#: sharp",
+    "a # b",
+    "; semicolon",
+    "John Newton",
+];
+
+const TAG_KEYS: [&str; 6] = ["author", "copyright", "songbook", "melody", "note", "scripture"];
+
+const LANGUAGES: [&str; 3] = ["en", "de", "fr"];
+
+/// Creates the lyrics of a part. Every line contains the unique number of the part,
+/// so that the importers don't take different parts for repetitions.
+fn random_lyrics(random: &mut Random, part_number: usize, language: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line_number in 0..random.range(1, 4) {
+        if line_number > 0 && random.chance(10) {
+            lines.push(String::new());
+        }
+        if random.chance(15) {
+            lines.push(random.pick(&TRICKY_LINES).to_string());
+        }
+        let words: Vec<&str> = (0..random.range(2, 5)).map(|_| *random.pick(&WORDS)).collect();
+        lines.push(format!("{} {} {}{}", language, words.join(" "), part_number, line_number));
+    }
+    lines.join("\n")
+}
+
+/// Creates a random song with one to three lyrics languages, random parts (some of them repeated) and random chords
+fn random_song(random: &mut Random) -> Song {
+    let title: String = format!("{} {}", random.pick(&WORDS), random.pick(&TRICKY_TAG_VALUES));
+    let mut builder: SongBuilder = SongBuilder::new(&title);
+
+    let mut keys: Vec<&str> = TAG_KEYS.to_vec();
+    for _ in 0..random.range(0, 4) {
+        let key: &str = keys.remove(random.range(0, keys.len() - 1));
+        let value = *random.pick(&TRICKY_TAG_VALUES);
+        builder = builder.tag(key, value);
+    }
+
+    let mut languages: Vec<String> = LANGUAGES.iter().map(|language| language.to_string()).collect();
+    languages.truncate(random.range(1, 3));
+    if languages.len() > 1 || random.chance(50) {
+        builder = builder.tag("language", &languages[0]).lyrics_language(LyricLanguage::Specific(languages[0].clone()));
+    }
+    if languages.len() > 1 {
+        builder = builder.tag("translations", &languages[1..].join(", "));
+    }
+
+    let part_types: [SongPartType; 10] = [
+        SongPartType::Verse,
+        SongPartType::Verse,
+        SongPartType::Verse,
+        SongPartType::Chorus,
+        SongPartType::Chorus,
+        SongPartType::Refrain,
+        SongPartType::Bridge,
+        SongPartType::PreChorus,
+        SongPartType::Intro,
+        SongPartType::Outro,
+    ];
+    let mut repeatable_parts: Vec<(SongPartType, String)> = Vec::new();
+    for part_number in 0..random.range(1, 7) {
+        if !repeatable_parts.is_empty() && random.chance(25) {
+            let (part_type, lyrics) = random.pick(&repeatable_parts).clone();
+            builder = builder.part(part_type, &lyrics);
+            continue;
+        }
+        let part_type: SongPartType = *random.pick(&part_types);
+        let lyrics: String = random_lyrics(random, part_number, &languages[0]);
+        if part_type.is_repeatable() {
+            repeatable_parts.push((part_type, lyrics.clone()));
+        }
+        builder = builder.part(part_type, &lyrics);
+    }
+    let song: Song = builder.build();
+
+    for part in song.get_file_ordered_parts() {
+        let mut part = part.borrow_mut();
+        let part_number: usize = part.number as usize;
+        for language in &languages[1..] {
+            let lyrics: String = random_lyrics(random, part_number, language);
+            part.add_content(SongPartContent {
+                voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific(language.clone()) },
+                content: format!("{}\n", lyrics),
+            });
+        }
+        if random.chance(40) {
+            let chords: Vec<String> = (0..random.range(1, 3))
+                .map(|_| (0..random.range(1, 4)).map(|_| *random.pick(&["G", "C", "D7", "Em", "F#m", "Bb/D"])).collect::<Vec<&str>>().join("  "))
+                .collect();
+            part.add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: chords.join("\n") });
+        }
+    }
+    song
+}
+
+/// Removes the whitespace at the end of a text and, if requested, its empty lines
+fn normalized(text: &str, without_empty_lines: bool) -> String {
+    text.trim_end()
+        .lines()
+        .filter(|line| !without_empty_lines || !line.trim().is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Returns the type and the number of a part (the part itself or the part it repeats)
+fn part_key(part: &Rc<RefCell<SongPart>>) -> (SongPartType, u32) {
+    let part = part.borrow();
+    match &part.is_repetition_of {
+        Some(repeated_part) => (repeated_part.borrow().part_type, repeated_part.borrow().number),
+        None => (part.part_type, part.number),
+    }
+}
+
+/// Compares the structure of a song with the song which has been imported from its export
+/// # Arguments
+/// * `keep_languages` - The format stores all languages (classic song files), otherwise only the primary lyrics are compared
+/// * `keep_chords` - The format stores chords (cssf)
+/// # Returns
+/// Ok or a description of the first difference
+fn compare(song: &Song, imported: &Song, keep_languages: bool, keep_chords: bool) -> Result<(), String> {
+    if imported.title != song.title {
+        return Err(format!("The title {:?} became {:?}", song.title, imported.title));
+    }
+    // The importers add the title as tag, the exporters write the authors in their canonical form
+    let tags = |song: &Song| {
+        let mut tags = song.get_tags().clone();
+        tags.remove("title");
+        if let Some(author) = tags.get_mut("author") {
+            *author = format_authors(&parse_authors(author));
+        }
+        tags
+    };
+    if tags(imported) != tags(song) {
+        return Err(format!("The tags {:?} became {:?}", tags(song), tags(imported)));
+    }
+    let parts = |song: &Song| song.get_file_ordered_parts().iter().map(part_key).collect::<Vec<(SongPartType, u32)>>();
+    if parts(imported) != parts(song) {
+        return Err(format!("The parts {:?} became {:?}", parts(song), parts(imported)));
+    }
+    let ordered_parts = |song: &Song| song.get_ordered_parts().iter().map(part_key).collect::<Vec<(SongPartType, u32)>>();
+    if ordered_parts(imported) != ordered_parts(song) {
+        return Err(format!("The part order {:?} became {:?}", ordered_parts(song), ordered_parts(imported)));
+    }
+
+    for (part, imported_part) in song.get_file_ordered_parts().iter().zip(imported.get_file_ordered_parts()) {
+        let (part, imported_part) = (part.borrow(), imported_part.borrow());
+        let contents = |part: &SongPart, song: &Song| -> Vec<(String, String)> {
+            let mut contents: Vec<(String, String)> = match keep_languages {
+                true => part
+                    .contents_of_type(SongPartContentType::is_lyrics)
+                    .iter()
+                    .map(|content| (content.voice_type.to_string(), normalized(&content.content, false)))
+                    .collect(),
+                false => part
+                    .get_lyrics(&song.primary_language())
+                    .map(|content| ("Lyrics".to_string(), normalized(&content.content, true)))
+                    .into_iter()
+                    .collect(),
+            };
+            if keep_chords {
+                contents.extend(
+                    part.contents_of_type(|voice_type| voice_type == &SongPartContentType::Chords)
+                        .iter()
+                        .map(|content| ("Chords".to_string(), normalized(&content.content, true))),
+                );
+            }
+            contents
+        };
+        if contents(&imported_part, imported) != contents(&part, song) {
+            return Err(format!(
+                "The contents of {} {:?} became {:?}",
+                part.id,
+                contents(&part, song),
+                contents(&imported_part, imported)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Exports the song with the exporter, imports it again and compares the songs. Panics with the seed and the source on a difference.
+fn assert_round_trip(
+    seed: u64,
+    song: &Song,
+    format: &str,
+    export: fn(&Song) -> String,
+    import: fn(&str) -> Result<Song, Box<dyn std::error::Error>>,
+    keep_languages: bool,
+    keep_chords: bool,
+) {
+    let source: String = export(song);
+    let result: Result<(), String> = match import(&source) {
+        Ok(imported) => compare(song, &imported, keep_languages, keep_chords),
+        Err(error) => Err(format!("The import failed: {}", error)),
+    };
+    if let Err(difference) = result {
+        panic!("The {} round trip of the song with seed {} failed: {}\nSource:\n{}", format, seed, difference, source);
+    }
+}
+
+#[test]
+fn test_classic_song_round_trip() {
+    for seed in 0..CASES {
+        let song: Song = random_song(&mut Random(seed));
+        assert_round_trip(seed, &song, "classic song", classic_song::export_song, importer::classic_song::import_song, true, false);
+    }
+}
+
+#[test]
+fn test_cssf_round_trip() {
+    for seed in 0..CASES {
+        let song: Song = random_song(&mut Random(seed));
+        assert_round_trip(seed, &song, "cssf", cssf::export_song, importer::cssf::import_song, false, true);
+    }
+}
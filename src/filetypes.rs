@@ -1,24 +1,167 @@
+use serde::{Deserialize, Serialize};
+
 /// This enum contains entries for all supported file formats (as input and output)
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
 pub enum FileType {
     ClassicSongFile,
     CSSF,
     CCLISongselectFile,
+    /// ChordPro files with inline chords and directives like `{title: ...}`
+    ChordPro,
+    /// SongBeamer files with header lines like `#Title=...` and parts separated by `---`
+    SongBeamer,
+    /// UltraStar karaoke files (`.txt` files starting with `#TITLE:`)
+    UltraStar,
+    /// Plain text with lyrics only (no tags, no structure)
+    PlainText,
+    /// VideoPsalm songbooks (`.json` files which contain many songs)
+    VideoPsalm,
+    /// ProPresenter 6 documents (XML with the text of the slides as RTF)
+    ProPresenter,
+    /// Songs which have been serialized as JSON (`.cantara.json`)
+    CantaraJson,
 }
 
-pub fn contains_song_structure(file_type: FileType) -> bool {
-    match file_type {
-        FileType::ClassicSongFile => false,
-        FileType::CSSF => true,
-        FileType::CCLISongselectFile => true,
+/// Describes which kinds of song information a file format is able to express.
+/// This can be used to warn a user before exporting a song to a format which would lose data.
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct FormatCapabilities {
+    /// The format can store lyrics
+    pub lyrics: bool,
+    /// The format can store lyrics in more than one language (e.g. translations)
+    pub multiple_languages: bool,
+    /// The format can store chords
+    pub chords: bool,
+    /// The format can store voices (lead voice, soprano, alto, tenor, bass)
+    pub voices: bool,
+    /// The format can store the type of each part (verse, chorus, bridge, ...)
+    pub part_structure: bool,
+    /// The format can store a presentation order
+    pub presentation_order: bool,
+    /// The format can store metadata (tags like author, copyright, ...)
+    pub metadata: bool,
+}
+
+impl FileType {
+    /// Returns the default file extension (without a leading dot) which is used when exporting to this format
+    pub fn default_extension(&self) -> &'static str {
+        match self {
+            FileType::ClassicSongFile => "song",
+            FileType::CSSF => "cssf",
+            FileType::CCLISongselectFile => "ccli",
+            FileType::ChordPro => "cho",
+            FileType::SongBeamer => "sng",
+            FileType::UltraStar => "txt",
+            FileType::PlainText => "txt",
+            FileType::VideoPsalm => "json",
+            FileType::ProPresenter => "pro6",
+            FileType::CantaraJson => "cantara.json",
+        }
+    }
+
+    /// Returns the capabilities of the file format
+    pub fn capabilities(&self) -> FormatCapabilities {
+        match self {
+            FileType::ClassicSongFile => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: true,
+                chords: false,
+                voices: false,
+                part_structure: false,
+                presentation_order: true,
+                metadata: true,
+            },
+            FileType::CSSF => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: true,
+                chords: true,
+                voices: true,
+                part_structure: true,
+                presentation_order: true,
+                metadata: true,
+            },
+            FileType::CCLISongselectFile => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: false,
+                chords: false,
+                voices: false,
+                part_structure: true,
+                presentation_order: false,
+                metadata: true,
+            },
+            FileType::ChordPro => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: false,
+                chords: true,
+                voices: false,
+                part_structure: true,
+                presentation_order: true,
+                metadata: true,
+            },
+            FileType::SongBeamer => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: true,
+                chords: true,
+                voices: false,
+                part_structure: true,
+                presentation_order: true,
+                metadata: true,
+            },
+            FileType::UltraStar => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: false,
+                chords: false,
+                voices: true,
+                part_structure: false,
+                presentation_order: false,
+                metadata: true,
+            },
+            FileType::PlainText => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: false,
+                chords: false,
+                voices: false,
+                part_structure: false,
+                presentation_order: false,
+                metadata: false,
+            },
+            FileType::VideoPsalm => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: false,
+                chords: true,
+                voices: false,
+                part_structure: false,
+                presentation_order: false,
+                metadata: true,
+            },
+            FileType::ProPresenter => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: false,
+                chords: false,
+                voices: false,
+                part_structure: true,
+                presentation_order: true,
+                metadata: true,
+            },
+            FileType::CantaraJson => FormatCapabilities {
+                lyrics: true,
+                multiple_languages: true,
+                chords: true,
+                voices: true,
+                part_structure: true,
+                presentation_order: true,
+                metadata: true,
+            },
+        }
     }
 }
 
+pub fn contains_song_structure(file_type: FileType) -> bool {
+    file_type.capabilities().part_structure
+}
+
 pub fn conatains_presentation_order(file_type: FileType) -> bool {
-    match file_type {
-        FileType::ClassicSongFile => true,
-        FileType::CSSF => true,
-        FileType::CCLISongselectFile => false,
-    }
+    file_type.capabilities().presentation_order
 }
 
 pub fn get_file_type_by_file_ending(ending: &str) -> Option<FileType> {
@@ -26,18 +169,86 @@ pub fn get_file_type_by_file_ending(ending: &str) -> Option<FileType> {
         ".cssf" => Some(FileType::CSSF),
         ".song" => Some(FileType::ClassicSongFile),
         ".ccli" => Some(FileType::CCLISongselectFile),
+        ".cho" | ".chopro" => Some(FileType::ChordPro),
+        ".sng" => Some(FileType::SongBeamer),
+        ".txt" => Some(FileType::PlainText),
+        // VideoPsalm songbooks are recognized by their content (see `importer::import_song_from_file`)
+        ".json" => Some(FileType::CantaraJson),
+        ".pro6" => Some(FileType::ProPresenter),
         _ => None,
     }
 }
 
+/// Guesses the file type by looking at the content of a file.
+/// This is used if the file extension is missing or turned out to be wrong.
+/// # Returns
+/// The detected file type or None if the content is empty or looks like an unsupported format (e.g. XML).
+pub fn get_file_type_by_content(content: &str) -> Option<FileType> {
+    let trimmed_content = content.trim_start();
+    if trimmed_content.is_empty() || trimmed_content.starts_with('<') {
+        return None;
+    }
+    if trimmed_content.starts_with('{') && content.contains("\"part_orders\"") {
+        return Some(FileType::CantaraJson);
+    }
+    if crate::importer::videopsalm::is_videopsalm_songbook(content) {
+        return Some(FileType::VideoPsalm);
+    }
+    if content.lines().any(crate::importer::cssf::is_categorization_line) {
+        return Some(FileType::CSSF);
+    }
+    if crate::importer::ultrastar::is_ultrastar_file(content) {
+        return Some(FileType::UltraStar);
+    }
+    if content.lines().any(crate::importer::songbeamer::is_header_line) {
+        return Some(FileType::SongBeamer);
+    }
+    if content.lines().any(crate::importer::chordpro::is_chordpro_directive) {
+        return Some(FileType::ChordPro);
+    }
+    if content.lines().any(crate::importer::ccli_song::is_footer_line) {
+        return Some(FileType::CCLISongselectFile);
+    }
+    Some(FileType::ClassicSongFile)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{FileType, contains_song_structure};
+    use super::{FileType, contains_song_structure, conatains_presentation_order, get_file_type_by_content};
 
     #[test]
     fn test_contains_song_structure() {
         assert!(contains_song_structure(FileType::CCLISongselectFile));
         assert!(!contains_song_structure(FileType::ClassicSongFile));
         assert!(contains_song_structure(FileType::CSSF));
+        assert!(!contains_song_structure(FileType::PlainText));
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert!(conatains_presentation_order(FileType::ClassicSongFile));
+        assert!(!conatains_presentation_order(FileType::CCLISongselectFile));
+
+        let plain_text = FileType::PlainText.capabilities();
+        assert!(plain_text.lyrics);
+        assert!(!plain_text.chords);
+        assert!(!plain_text.metadata);
+
+        let cssf = FileType::CSSF.capabilities();
+        assert!(cssf.chords && cssf.voices && cssf.multiple_languages);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_file_type_by_content() {
+        assert_eq!(get_file_type_by_content("#title: Test\n\n#verse.1\nLyrics"), Some(FileType::CSSF));
+        assert_eq!(get_file_type_by_content("#title: Test\n\nLyrics"), Some(FileType::ClassicSongFile));
+        assert_eq!(get_file_type_by_content("Test\n\nVerse 1\nLyrics\n\nCCLI Song # 1234"), Some(FileType::CCLISongselectFile));
+        assert_eq!(get_file_type_by_content("{title: Test}\n\n[G]Lyrics"), Some(FileType::ChordPro));
+        assert_eq!(get_file_type_by_content("#LangCount=1\n#Title=Test\n---\nLyrics"), Some(FileType::SongBeamer));
+        assert_eq!(get_file_type_by_content("#TITLE:Test\n: 0 4 60 La\nE"), Some(FileType::UltraStar));
+        assert_eq!(get_file_type_by_content("{Text:\"Book\",Songs:[{Text:\"Test\",Verses:[]}]}"), Some(FileType::VideoPsalm));
+        assert_eq!(get_file_type_by_content("{\"title\": \"Test\", \"parts\": [], \"part_orders\": []}"), Some(FileType::CantaraJson));
+        assert_eq!(get_file_type_by_content("<?xml version=\"1.0\"?><song/>"), None);
+        assert_eq!(get_file_type_by_content("   "), None);
+    }
+}
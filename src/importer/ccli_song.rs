@@ -0,0 +1,217 @@
+//! This module contains functions to import songs from the plain text export of CCLI SongSelect.
+//! The first line of such a file is the title, followed by the parts of the song which start with a label
+//! like `Verse 1` or `Chorus`. The file ends with a footer containing the CCLI song number, the authors and the copyright.
+//!
+//! # Example
+//! ```text
+//! Amazing Grace
+//!
+//! Verse 1
+//! Amazing grace how sweet the sound
+//! That saved a wretch like me
+//!
+//! CCLI Song # 22025
+//! John Newton
+//! © Words: Public Domain
+//! For use solely with the SongSelect® Terms of Use. All rights reserved. www.ccli.com
+//! CCLI License # 1234567
+//! ```
+
+use std::error::Error;
+use std::sync::OnceLock;
+
+extern crate regex;
+use regex::Regex;
+
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::importer::{normalize_input, parse_part_label};
+use crate::song::{LyricLanguage, Song, SongPartContent, SongPartContentType, SongPartType};
+
+fn song_number_regex() -> &'static Regex {
+    static SONG_NUMBER_REGEX: OnceLock<Regex> = OnceLock::new();
+    SONG_NUMBER_REGEX.get_or_init(|| Regex::new(r"(?i)^\s*CCLI[- ]?(?:Song|Liednummer)\s*(?:#|Nr\.?)?\s*(\d+)\s*$").unwrap())
+}
+
+fn license_regex() -> &'static Regex {
+    static LICENSE_REGEX: OnceLock<Regex> = OnceLock::new();
+    LICENSE_REGEX.get_or_init(|| Regex::new(r"(?i)^\s*CCLI[- ]?(?:License|Lizenz)").unwrap())
+}
+
+/// Labels which are only used by SongSelect (see `importer::parse_part_label`)
+const SONGSELECT_LABELS: [(&str, SongPartType); 5] = [
+    ("ending", SongPartType::Outro),
+    ("tag", SongPartType::Other),
+    ("misc", SongPartType::Other),
+    ("vamp", SongPartType::Other),
+    ("channel", SongPartType::Other),
+];
+
+/// Returns whether a line is the first line of the CCLI footer (the line with the song number like `CCLI Song # 22025`)
+pub(crate) fn is_footer_line(line: &str) -> bool {
+    song_number_regex().is_match(line)
+}
+
+/// A part of the song while the file is read
+struct Section<'a> {
+    part_type: SongPartType,
+    number: Option<u32>,
+    /// Whether the section has been started by a label (otherwise it ends at the next blank line)
+    labeled: bool,
+    lines: Vec<&'a str>,
+}
+
+fn add_section(song: &mut Song, section: Section) {
+    let lines: Vec<&str> = section.lines;
+    // Blank lines are only kept between the lines of a part
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    let (Some(first), Some(last)) = (first, last) else {
+        return;
+    };
+    song.add_part_of_type(section.part_type, section.number).borrow_mut().add_content(SongPartContent {
+        voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+        content: lines[first..=last].join("\n"),
+    });
+}
+
+/// Adds the information of the CCLI footer to the song: the song number as `ccli`, the copyright as `copyright`
+/// and all other lines (except the license and terms of use) as `author`.
+fn parse_footer(song: &mut Song, footer: &[&str]) {
+    let mut authors: Vec<String> = Vec::new();
+    for line in footer.iter().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        if let Some(captures) = song_number_regex().captures(line) {
+            song.add_tag("ccli", &captures[1]);
+        } else if ["©", "(c)", "(C)", "Copyright", "copyright"].iter().any(|prefix| line.starts_with(prefix)) {
+            song.add_tag("copyright", line);
+        } else if license_regex().is_match(line) || line.contains("SongSelect") || line.contains("www.ccli.com") {
+            continue;
+        } else {
+            // SongSelect separates the authors with pipes
+            authors.extend(line.split('|').map(|author| author.trim().to_string()).filter(|author| !author.is_empty()));
+        }
+    }
+    if !authors.is_empty() {
+        song.add_tag("author", &authors.join(", "));
+    }
+}
+
+/// Imports a song from a str which contains the plain text export of CCLI SongSelect.
+/// Lyrics before the first label are imported as verses (separated by blank lines).
+/// The footer is only used for the tags, it never becomes part of the lyrics.
+/// # Returns
+/// A Result with the Song or an error if there is no content
+/// # Example
+/// ```
+/// use cantara_songlib::importer::ccli_song::import_song;
+/// use cantara_songlib::song::SongPartType;
+/// let song = import_song("Test\n\nVerse 1\nFirst verse\n\nChorus\nThe chorus\n\nCCLI Song # 1234\nSomebody").unwrap();
+/// assert_eq!(song.title, "Test");
+/// assert_eq!(song.get_part_count(SongPartType::Verse), 1);
+/// assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+/// assert_eq!(song.get_tag("ccli").unwrap(), "1234");
+/// ```
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    let content = normalize_input(content, true);
+    if content.trim().is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let footer_start: usize = lines.iter().position(|line| is_footer_line(line)).unwrap_or(lines.len());
+    let (body, footer) = lines.split_at(footer_start);
+
+    let mut body = body.iter().skip_while(|line| line.trim().is_empty());
+    let mut song = Song::new(body.next().map(|title| title.trim()).unwrap_or_default());
+    let mut current: Option<Section> = None;
+
+    for line in body {
+        if let Some((part_type, number)) = parse_part_label(line, &SONGSELECT_LABELS) {
+            if let Some(section) = current.take() {
+                add_section(&mut song, section);
+            }
+            current = Some(Section { part_type, number, labeled: true, lines: Vec::new() });
+        } else if line.trim().is_empty() {
+            match current.as_mut() {
+                Some(section) if section.labeled => section.lines.push(""),
+                _ => if let Some(section) = current.take() {
+                    add_section(&mut song, section);
+                },
+            }
+        } else {
+            current
+                .get_or_insert(Section { part_type: SongPartType::Verse, number: None, labeled: false, lines: Vec::new() })
+                .lines
+                .push(line);
+        }
+    }
+    if let Some(section) = current {
+        add_section(&mut song, section);
+    }
+
+    parse_footer(&mut song, footer);
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::import_song_from_file;
+
+    #[test]
+    fn test_import_ccli_song() {
+        let song = import_song_from_file("testfiles/ccli/Blessed Assurance.ccli").unwrap();
+        assert_eq!(song.title, "Blessed Assurance");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 3);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+        assert_eq!(song.get_total_part_count(), 4);
+        assert_eq!(song.get_tag("ccli").unwrap(), "22324");
+        assert_eq!(song.get_tag("author").unwrap(), "Fanny Crosby, Phoebe Palmer Knapp");
+        assert_eq!(song.get_tag("copyright").unwrap(), "© Words: Public Domain");
+
+        let chorus = song.get_parts_by_type(SongPartType::Chorus)[0].clone();
+        assert!(chorus.borrow().contents[0].content.starts_with("This is my story, this is my song\n"));
+
+        // Neither the title nor the footer end up in the lyrics
+        for part in song.get_unpacked_parts() {
+            let lyrics = &part.contents[0].content;
+            assert!(!lyrics.contains("Blessed Assurance"));
+            assert!(!lyrics.contains("CCLI"));
+            assert!(!lyrics.contains("SongSelect"));
+        }
+    }
+
+    #[test]
+    fn test_import_ccli_song_with_txt_extension() {
+        let song = import_song_from_file("testfiles/ccli/Amazing Grace.txt").unwrap();
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 4);
+        assert_eq!(song.get_tag("ccli").unwrap(), "22025");
+        assert_eq!(song.get_tag("author").unwrap(), "John Newton");
+    }
+
+    #[test]
+    fn test_labels() {
+        let parse_label = |line: &str| parse_part_label(line, &SONGSELECT_LABELS);
+        assert_eq!(parse_label("Verse 2"), Some((SongPartType::Verse, Some(2))));
+        assert_eq!(parse_label("Chorus"), Some((SongPartType::Chorus, None)));
+        assert_eq!(parse_label("Pre-Chorus 1"), Some((SongPartType::PreChorus, Some(1))));
+        assert_eq!(parse_label("Ending"), Some((SongPartType::Outro, None)));
+        assert_eq!(parse_label("Misc 1"), Some((SongPartType::Other, Some(1))));
+        assert_eq!(parse_label("Amazing grace how sweet the sound"), None);
+
+        let song = import_song("Test\n\nPre-Chorus\nLa la\n\nChorus\nLu lu\n\nChorus\nLi li").unwrap();
+        assert_eq!(song.get_part_count(SongPartType::PreChorus), 1);
+        // A repeated label without a number gets the next free number
+        assert!(song.get_part_by_id("Chorus.2").is_some());
+    }
+
+    #[test]
+    fn test_lyrics_without_labels() {
+        let song = import_song("Test\n\nFirst verse\nLine two\n\nSecond verse\n\nCCLI-Liednummer 1234\n© 2000 Somebody").unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_tag("ccli").unwrap(), "1234");
+        assert_eq!(song.get_tag("copyright").unwrap(), "© 2000 Somebody");
+        assert_eq!(song.get_tag("author"), None);
+        assert!(import_song("  \n").is_err());
+    }
+}
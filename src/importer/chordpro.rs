@@ -0,0 +1,323 @@
+//! This module contains functions to import songs from ChordPro files (`.cho`, `.chopro`).
+//! Directives in curly braces like `{title: Amazing Grace}` are mapped to the tags of the song,
+//! the section directives like `{start_of_chorus}` create the parts of the song.
+//! Chords are written inline in square brackets (`[G]Amazing [C]grace`). They are stored as separate `Chords` content
+//! with the chords placed above the lyrics (one chord line per lyric line), while the lyrics are stored without chords.
+//!
+//! # Example
+//! ```text
+//! {title: Amazing Grace}
+//! {artist: John Newton}
+//!
+//! {start_of_verse}
+//! A[G]mazing [G7]grace how [C]sweet the [G]sound
+//! {end_of_verse}
+//! ```
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+extern crate regex;
+use regex::Regex;
+
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::importer::normalize_input;
+use crate::song::{
+    LyricLanguage, PartOrder, PartOrderName, PartOrderRule, Song, SongPart, SongPartContent, SongPartContentType, SongPartType,
+};
+
+type PartReference = Rc<RefCell<SongPart>>;
+
+fn directive_regex() -> &'static Regex {
+    static DIRECTIVE_REGEX: OnceLock<Regex> = OnceLock::new();
+    DIRECTIVE_REGEX.get_or_init(|| Regex::new(r"^\s*\{\s*([A-Za-z_-]+)\s*(?::\s*(.*?))?\s*\}\s*$").unwrap())
+}
+
+/// Returns whether a line is a directive which is typical for ChordPro files (the title or the start of a section)
+pub(crate) fn is_chordpro_directive(line: &str) -> bool {
+    directive_regex().captures(line).is_some_and(|captures| {
+        matches!(&captures[1], "title" | "t" | "start_of_chorus" | "soc" | "start_of_verse" | "sov")
+    })
+}
+
+/// Splits a line with inline chords into the lyrics and a chord line with the chords placed above the lyrics.
+/// Chords which would overlap the previous chord are moved to the right.
+/// # Returns
+/// The lyrics without chords and the chord line (empty if the line contains no chords)
+pub(crate) fn split_chords(line: &str) -> (String, String) {
+    let mut lyrics = String::new();
+    let mut chords = String::new();
+    let mut rest: &str = line;
+
+    while let Some(start) = rest.find('[') {
+        let Some(length) = rest[start..].find(']') else {
+            break;
+        };
+        lyrics.push_str(&rest[..start]);
+        // Annotations like `[*Rit.]` are handled like chords
+        let chord: &str = rest[start + 1..start + length].trim_start_matches('*');
+        let column: usize = lyrics.chars().count();
+        let chord_column: usize = chords.chars().count();
+        if chord_column > 0 || column > 0 {
+            // At least one space separates two chords
+            let padding = column.max(chord_column + usize::from(chord_column > 0)) - chord_column;
+            chords.push_str(&" ".repeat(padding));
+        }
+        chords.push_str(chord);
+        rest = &rest[start + length + 1..];
+    }
+    lyrics.push_str(rest);
+    (lyrics.trim_end().to_string(), chords)
+}
+
+/// A part of the song while the file is read
+struct Section {
+    part_type: SongPartType,
+    /// Whether the section has been started by a directive (otherwise it ends at the next blank line)
+    explicit: bool,
+    lines: Vec<String>,
+    chords: Vec<String>,
+    comments: Vec<String>,
+}
+
+impl Section {
+    fn new(part_type: SongPartType, explicit: bool) -> Section {
+        Section { part_type, explicit, lines: Vec::new(), chords: Vec::new(), comments: Vec::new() }
+    }
+}
+
+/// Adds a section as new part to the song
+/// # Returns
+/// The new part or None if the section has no lyrics
+fn add_section(song: &mut Song, section: Section) -> Option<PartReference> {
+    // Blank lines are only kept between the lines of a part
+    let first = section.lines.iter().position(|line| !line.trim().is_empty())?;
+    let last = section.lines.iter().rposition(|line| !line.trim().is_empty())?;
+
+    let part = song.add_part_of_type(section.part_type, None);
+    {
+        let mut part = part.borrow_mut();
+        part.add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: section.lines[first..=last].join("\n"),
+        });
+        let chords = &section.chords[first..=last];
+        if chords.iter().any(|chord_line| !chord_line.is_empty()) {
+            part.add_content(SongPartContent {
+                voice_type: SongPartContentType::Chords,
+                content: chords.join("\n"),
+            });
+        }
+        part.comments = section.comments;
+    }
+    Some(part)
+}
+
+/// Adds the finished section (if any) to the song and to the order of the parts
+fn finish_section(song: &mut Song, section: Option<Section>, part_order: &mut Vec<PartReference>) {
+    if let Some(part) = section.and_then(|section| add_section(song, section)) {
+        part_order.push(part);
+    }
+}
+
+/// Returns the tag for a directive with a value or None if the directive is no metadata
+fn tag_name(directive: &str) -> Option<&str> {
+    match directive {
+        "title" | "t" => Some("title"),
+        "subtitle" | "st" => Some("subtitle"),
+        "artist" | "composer" | "lyricist" => Some("author"),
+        "copyright" | "album" | "year" | "key" | "time" | "tempo" | "capo" | "duration" | "ccli" => Some(directive),
+        _ => None,
+    }
+}
+
+/// Adds the value of a metadata directive to the tags of the song. The values of `artist`, `composer` and `lyricist`
+/// are collected in the `author` tag (composers and lyricists with their role, see `metadata::parse_authors`).
+fn apply_directive(song: &mut Song, directive: &str, value: &str) {
+    if directive == "meta" {
+        // `{meta: name value}` is the same as `{name: value}`
+        if let Some((name, value)) = value.split_once(char::is_whitespace) {
+            apply_directive(song, &name.to_lowercase(), value.trim());
+        }
+        return;
+    }
+    let Some(tag) = tag_name(directive) else {
+        return;
+    };
+    let value: String = match directive {
+        "composer" => format!("{} (music)", value),
+        "lyricist" => format!("{} (words)", value),
+        _ => value.to_string(),
+    };
+    if tag == "title" {
+        song.title = value.clone();
+    }
+    let value: String = match (tag, song.get_tag(tag)) {
+        ("author", Some(authors)) => format!("{}, {}", authors, value),
+        _ => value,
+    };
+    song.add_tag(tag, &value);
+}
+
+/// Imports a song from a str which contains the song in the ChordPro format.
+/// Lyrics outside of sections are imported as verses (separated by blank lines). `{chorus}` repeats the last chorus.
+/// Comments (`{comment: ...}`) are stored in the comments of the part they belong to, lines starting with `#` are ignored.
+/// # Returns
+/// A Result with the Song or an error if there is no content
+/// # Example
+/// ```
+/// use cantara_songlib::importer::chordpro::import_song;
+/// use cantara_songlib::song::{SongPartContentType, SongPartType};
+/// let song = import_song("{title: Test}\n\n{soc}\n[G]This is the [C]chorus\n{eoc}").unwrap();
+/// assert_eq!(song.title, "Test");
+/// let chorus = song.get_parts_by_type(SongPartType::Chorus)[0].clone();
+/// assert_eq!(chorus.borrow().contents[0].content, "This is the chorus");
+/// assert_eq!(chorus.borrow().get_content(SongPartContentType::Chords).unwrap().content, "G           C");
+/// ```
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    let content = normalize_input(content, false);
+    if content.trim().is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+
+    let mut song = Song::new("");
+    // The parts in the order of their occurence in the file (repeated choruses occur several times)
+    let mut part_order: Vec<PartReference> = Vec::new();
+    let mut current: Option<Section> = None;
+    // Comments which have been found outside of a section belong to the next part
+    let mut pending_comments: Vec<String> = Vec::new();
+    // Tabs and grids are skipped, because they contain no lyrics
+    let mut skip_until: Option<&str> = None;
+
+    for line in content.lines() {
+        if let Some(end) = skip_until {
+            if directive_regex().captures(line).is_some_and(|captures| &captures[1] == end) {
+                skip_until = None;
+            }
+            continue;
+        }
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let Some(captures) = directive_regex().captures(line) else {
+            if line.trim().is_empty() {
+                match current.as_mut() {
+                    Some(section) if section.explicit => {
+                        section.lines.push(String::new());
+                        section.chords.push(String::new());
+                    },
+                    _ => finish_section(&mut song, current.take(), &mut part_order),
+                }
+                continue;
+            }
+            let section = current.get_or_insert_with(|| {
+                let mut section = Section::new(SongPartType::Verse, false);
+                section.comments.append(&mut pending_comments);
+                section
+            });
+            let (lyrics, chords) = split_chords(line);
+            section.lines.push(lyrics);
+            section.chords.push(chords);
+            continue;
+        };
+
+        let directive: String = captures[1].to_lowercase();
+        let value: &str = captures.get(2).map_or("", |value| value.as_str());
+        let section_type: Option<SongPartType> = match directive.as_str() {
+            "start_of_chorus" | "soc" => Some(SongPartType::Chorus),
+            "start_of_verse" | "sov" => Some(SongPartType::Verse),
+            "start_of_bridge" | "sob" => Some(SongPartType::Bridge),
+            _ => None,
+        };
+        if let Some(part_type) = section_type {
+            finish_section(&mut song, current.take(), &mut part_order);
+            let mut section = Section::new(part_type, true);
+            section.comments.append(&mut pending_comments);
+            current = Some(section);
+            continue;
+        }
+        match directive.as_str() {
+            "end_of_chorus" | "eoc" | "end_of_verse" | "eov" | "end_of_bridge" | "eob" => {
+                finish_section(&mut song, current.take(), &mut part_order);
+            },
+            "start_of_tab" | "sot" => skip_until = Some("end_of_tab"),
+            "start_of_grid" | "sog" => skip_until = Some("end_of_grid"),
+            "chorus" => {
+                finish_section(&mut song, current.take(), &mut part_order);
+                if let Some(chorus) = song.get_parts_by_type(SongPartType::Chorus).last() {
+                    part_order.push(chorus.clone());
+                }
+            },
+            "comment" | "c" | "comment_italic" | "ci" | "comment_box" | "cb" | "highlight" => match current.as_mut() {
+                Some(section) => section.comments.push(value.to_string()),
+                None => pending_comments.push(value.to_string()),
+            },
+            _ => apply_directive(&mut song, &directive, value),
+        }
+    }
+    finish_section(&mut song, current.take(), &mut part_order);
+    // Comments at the end of the file belong to the last part
+    if let Some(last_part) = part_order.last() {
+        last_part.borrow_mut().comments.append(&mut pending_comments);
+    }
+
+    if !part_order.is_empty() {
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(part_order)));
+    }
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::import_song_from_file;
+
+    #[test]
+    fn test_split_chords() {
+        assert_eq!(split_chords("[G]Amazing [C]grace"), ("Amazing grace".to_string(), "G       C".to_string()));
+        // Chords in the middle of a word don't change the lyrics
+        assert_eq!(split_chords("A[G]ma[D]zing"), ("Amazing".to_string(), " G D".to_string()));
+        // Chords which are too close to each other are separated by a space
+        assert_eq!(split_chords("[Gsus4][G]Grace"), ("Grace".to_string(), "Gsus4 G".to_string()));
+        assert_eq!(split_chords("No chords"), ("No chords".to_string(), String::new()));
+        assert_eq!(split_chords("Open [bracket"), ("Open [bracket".to_string(), String::new()));
+    }
+
+    #[test]
+    fn test_import_chordpro_file() {
+        let song = import_song_from_file("testfiles/chordpro/Amazing Grace.cho").unwrap();
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.get_tag("subtitle").unwrap(), "Traditional");
+        assert_eq!(song.get_tag("author").unwrap(), "John Newton (words), Traditional (music)");
+        assert_eq!(song.get_tag("key").unwrap(), "G");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+
+        let first_verse = song.get_parts_by_type(SongPartType::Verse)[0].clone();
+        let first_verse = first_verse.borrow();
+        assert_eq!(first_verse.contents[0].content.lines().next().unwrap(), "Amazing grace how sweet the sound");
+        assert_eq!(first_verse.get_content(SongPartContentType::Chords).unwrap().content.lines().count(), 4);
+        assert_eq!(first_verse.comments, vec!["Slowly".to_string()]);
+
+        // The chorus is repeated by `{chorus}`
+        let order: Vec<SongPartType> = song.get_ordered_parts().iter().map(|part| part.borrow().part_type).collect();
+        assert_eq!(order, vec![SongPartType::Verse, SongPartType::Chorus, SongPartType::Verse, SongPartType::Chorus]);
+
+        // The song can be presented without chords and directives on the slides
+        let chorus = song.get_parts_by_type(SongPartType::Chorus)[0].clone();
+        assert!(!chorus.borrow().contents[0].content.contains('['));
+        assert!(song.get_unpacked_parts().iter().all(|part| !part.contents[0].content.contains('{')));
+    }
+
+    #[test]
+    fn test_lyrics_without_sections() {
+        let song = import_song_from_file("testfiles/chordpro/Plain Verses.chopro").unwrap();
+        assert_eq!(song.title, "Plain Verses");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_parts_by_type(SongPartType::Verse)[1].borrow().get_content(SongPartContentType::Chords), None);
+        assert!(import_song(" \n").is_err());
+    }
+}
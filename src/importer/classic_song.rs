@@ -2,17 +2,23 @@
 //! The Cantara song format is a simple text format that is used to write songs in plain text files.
 //! You can find a documentation here: <https://www.cantara.app/tutorial/where-to-get-the-songs/index.html#the-song-file-format>
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::{cell::RefCell, rc::Rc};
 use std::sync::OnceLock;
 
 extern crate regex;
-use regex::{Regex,RegexBuilder};
+use regex::Regex;
 
-use crate::importer::errors::CantaraImportNoContentError;
+use crate::importer::errors::{CantaraImportNoContentError, CantaraImportParsingError, ParsingErrorType};
+use crate::importer::{
+    normalize_input, parse_part_label, ChorusMatching, DuplicateTagPolicy, ImportOptions, ImportReport, ImportWarning, SongSeparator, StanzaSeparator,
+};
 use crate::song::{
     LyricLanguage, 
+    PartOrder,
+    PartOrderName,
+    PartOrderRule,
     Song, 
     SongPart, 
     SongPartContent, 
@@ -21,106 +27,762 @@ use crate::song::{
 };
 
 use crate::slides::*;
-use crate::templating::render_metadata;
+use crate::text::{normalize_for_matching, normalize_value, similarity};
 
 
-fn parse_metadata_block(block: &str) -> HashMap<String, String> {
-    let mut metadata: HashMap<String, String> = HashMap::new();
+/// The similarity (see `ChorusMatching::Similar`) from which a stanza which is no repetition of an earlier part
+/// is reported as similar to it (`ImportWarning::SimilarStanza`)
+const SIMILAR_STANZA_THRESHOLD: f32 = 0.8;
 
+/// Returns the regex which matches a single tag line like `#title: Amazing Grace`
+fn tag_line_regex() -> &'static Regex {
     // With that we make sure that the regex is only compiled once.
-    let tags_regex = { 
-        static TAGS_REGEX: OnceLock<Regex> = OnceLock::new();
-        TAGS_REGEX.get_or_init(|| {
-            RegexBuilder::new(r"^\s*#(\w+):\s*(.+)$")
-                .multi_line(true)
-                .build()
-                .unwrap()
-        })
-    };        
-
-    tags_regex
-        .captures_iter(block)
-        .for_each(|capture: regex::Captures| {
-            let tag: &str = capture.get(1).unwrap().as_str().trim();
-            let value = capture.get(2).unwrap().as_str().trim().to_string();
-            let tag_lowercase = tag.to_lowercase();
-            
-            metadata.insert(tag_lowercase, value);
-        });
+    static TAG_LINE_REGEX: OnceLock<Regex> = OnceLock::new();
+    TAG_LINE_REGEX.get_or_init(|| Regex::new(r"^\s*#(\w+):\s*(.+)$").unwrap())
+}
 
-    metadata
+/// Checks whether a line belongs to the metadata: a tag line (`#title: Amazing Grace`, also without a value)
+/// or a line consisting of a single `#` keyword (e.g. `#verse.1`), which is reported as invalid tag.
+/// Other lines starting with `#` (e.g. `#1 in my heart`) are lyrics.
+fn is_metadata_line(line: &str) -> bool {
+    static METADATA_LINE_REGEX: OnceLock<Regex> = OnceLock::new();
+    METADATA_LINE_REGEX.get_or_init(|| Regex::new(r"^\s*#[\w.]+(:|\s*$)").unwrap()).is_match(line)
 }
 
-fn parse_block(block: &str, song: Song) -> Result<Song, Box<dyn Error>> {
-    if block.is_empty() {
-        return Ok(song);
+/// Removes the escape of a lyric line starting with `\#`, `\//` or `\;` (which would be a tag or a comment otherwise),
+/// of the line `\---` (which would be a delimiter) or of a line starting with `\\`, keeping its indentation.
+/// A line which only consists of `\` is an empty line within the stanza.
+fn unescape_lyric_line(line: &str) -> String {
+    let content = line.trim_start();
+    match content.strip_prefix('\\') {
+        Some(rest) if rest.is_empty() || rest == "---" || ["#", "//", ";", "\\"].iter().any(|prefix| rest.starts_with(prefix)) => {
+            format!("{}{}", &line[..line.len() - content.len()], rest)
+        },
+        _ => line.to_string(),
     }
+}
 
-    let mut cloned_song: Song = song.clone();
+/// Resolves a tag which has already been set according to the duplicate tag policy.
+/// # Returns
+/// The value which is to be kept and a warning describing the conflict, or an error if the policy forbids duplicates.
+fn resolve_duplicate_tag(
+    key: &str,
+    existing_value: &str,
+    new_value: &str,
+    line: usize,
+    policy: DuplicateTagPolicy,
+) -> Result<(String, ImportWarning), CantaraImportParsingError> {
+    let (kept, discarded) = match policy {
+        DuplicateTagPolicy::KeepFirst => (existing_value.to_string(), Some(new_value.to_string())),
+        DuplicateTagPolicy::KeepLast => (new_value.to_string(), Some(existing_value.to_string())),
+        DuplicateTagPolicy::Append => (format!("{}; {}", existing_value, new_value), None),
+        DuplicateTagPolicy::Error => return Err(CantaraImportParsingError {
+            line,
+            error_type: ParsingErrorType::DuplicateTag { key: key.to_string() },
+        }),
+    };
+    let warning = ImportWarning::DuplicateTag {
+        key: key.to_string(),
+        kept: kept.clone(),
+        discarded,
+        line,
+    };
+    Ok((kept, warning))
+}
 
-    // If first letter is a #, then parse the tags
-    if block.starts_with('#') {
-        
-        // With that we make sure that the regex is only compiled once.
-        let tags_regex = { 
-            static TAGS_REGEX: OnceLock<Regex> = OnceLock::new();
-            TAGS_REGEX.get_or_init(|| {
-                RegexBuilder::new(r"\s*#(\w+):\s*(.+)$")
-                    .multi_line(true)
-                    .build()
-                    .unwrap()
-            })
-        };        
-
-        tags_regex
-            .captures_iter(block)
-            .for_each(|capture: regex::Captures| {
-                let tag: &str = capture.get(1).unwrap().as_str();
-                let value: &str = capture.get(2).unwrap().as_str();
-                let tag_lowercase = tag.to_lowercase();
-                cloned_song.add_tag(tag_lowercase.as_str(), value);
-                if tag_lowercase == "title" {
-                    cloned_song.title = value.to_string();
+/// Collects the tags of a metadata block into a map.
+/// Tags which are set more than once are handled according to the given policy and reported as warnings.
+fn collect_metadata(
+    tags: &[ClassicTag],
+    policy: DuplicateTagPolicy,
+) -> Result<(HashMap<String, String>, Vec<ImportWarning>), CantaraImportParsingError> {
+    let mut metadata: HashMap<String, String> = HashMap::new();
+    let mut warnings: Vec<ImportWarning> = Vec::new();
+
+    for tag in tags {
+        let value = match metadata.get(&tag.key) {
+            Some(existing_value) => {
+                let (kept, warning) = resolve_duplicate_tag(&tag.key, existing_value, &tag.value, tag.line, policy)?;
+                warnings.push(warning);
+                kept
+            },
+            None => tag.value.clone(),
+        };
+        metadata.insert(tag.key.clone(), value);
+    }
+
+    Ok((metadata, warnings))
+}
+
+/// The type and the number (if given) of a part, as stated by a label or a marker in the file
+type PartLabel = (SongPartType, Option<u32>);
+
+/// Parses an explicit part marker like `(Chorus)`, `(Verse 3)` or `(Strophe 2)`.
+/// Other than labels (see `importer::parse_part_label`), markers are always recognized, because the parentheses make them unambiguous.
+/// # Returns
+/// The type of the part and the number (if given) or None if the line is no marker
+fn parse_part_marker(line: &str) -> Option<PartLabel> {
+    let marker_regex = {
+        static MARKER_REGEX: OnceLock<Regex> = OnceLock::new();
+        MARKER_REGEX.get_or_init(|| Regex::new(r"^\s*\(\s*([^\W\d_][\w\- ]*?)(?:\s+(\d+))?\s*\)\s*$").unwrap())
+    };
+    let captures = marker_regex.captures(line)?;
+    let part_type = SongPartType::from_label(captures.get(1)?.as_str())?;
+    let number: Option<u32> = captures.get(2).and_then(|number| number.as_str().parse().ok());
+    Some((part_type, number))
+}
+
+/// Splits a first line which is recognized by `parse_line` (like `importer::parse_part_label` or `parse_part_marker`) from a block.
+/// A block which only consists of such a line is not split.
+fn split_part_label(block: &str, parse_line: fn(&str) -> Option<PartLabel>) -> (Option<PartLabel>, &str) {
+    if let Some((first_line, rest)) = block.trim_start().split_once('\n') {
+        if !rest.trim().is_empty() {
+            if let Some(label) = parse_line(first_line) {
+                return (Some(label), rest);
+            }
+        }
+    }
+    (None, block)
+}
+
+/// A tag of a metadata block like `#title: Amazing Grace`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassicTag {
+    /// The tag name in lowercase
+    pub key: String,
+    /// The normalized value (trimmed, without carriage returns and zero-width characters)
+    pub value: String,
+    /// The line number (starting with 1) of the tag
+    pub line: usize,
+}
+
+/// The lines of a classic song file which a block spans (line numbers start with 1, the last line is included)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockSpan {
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+/// A block of a classic song file, as returned by `scan_blocks`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClassicBlock {
+    /// A block which starts with a tag. Lines of the block which are no tags are ignored.
+    Metadata {
+        tags: Vec<ClassicTag>,
+        /// The lines of the block which start with `#`, but are no valid tag (like `#author:` without a value),
+        /// with their line number
+        invalid_tags: Vec<(usize, String)>,
+        span: BlockSpan,
+    },
+    /// A stanza with the lines of its primary text and of every secondary (`---`) section.
+    /// The lines keep their indentation, trailing whitespace is removed.
+    Stanza {
+        primary: Vec<String>,
+        secondary: Vec<Vec<String>>,
+        span: BlockSpan,
+    },
+    /// A comment line (without the comment marker). Comment lines don't end a block,
+    /// a comment within a block is returned before the block itself.
+    Comment {
+        text: String,
+        line: usize,
+    },
+}
+
+/// Splits the content of a classic song file into its blocks. This is the tokenizer which is used by the importer and
+/// by the slide generation, so tools (like editors with a live preview) can use it to get the same blocks without a full import.
+///
+/// Blocks are separated by empty (or whitespace-only) lines. A block whose first line starts with `#` is a metadata block,
+/// every other block is a stanza whose `---` lines start its secondary sections. Lines starting with one of the
+/// `comment_prefixes` (after optional whitespace, usually `DEFAULT_COMMENT_PREFIXES`) are comments.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::classic_song::{scan_blocks, ClassicBlock};
+/// use cantara_songlib::importer::DEFAULT_COMMENT_PREFIXES;
+/// let blocks = scan_blocks("#title: Test\n\nLine 1\n---\nZeile 1\n// A comment", &DEFAULT_COMMENT_PREFIXES);
+/// assert!(matches!(&blocks[0], ClassicBlock::Metadata { tags, .. } if tags[0].value == "Test"));
+/// assert!(matches!(&blocks[1], ClassicBlock::Comment { text, line: 6 } if text == "A comment"));
+/// assert!(matches!(&blocks[2], ClassicBlock::Stanza { primary, secondary, span }
+///     if primary == &["Line 1"] && secondary == &[vec!["Zeile 1"]] && span.first_line == 3 && span.last_line == 5));
+/// ```
+pub fn scan_blocks<S: AsRef<str>>(content: &str, comment_prefixes: &[S]) -> Vec<ClassicBlock> {
+    scan_blocks_with_separator(content, comment_prefixes, StanzaSeparator::SingleBlankLine)
+}
+
+/// Splits the content of a classic song file into its blocks like `scan_blocks`, but with the given stanza separator.
+/// With `StanzaSeparator::MultipleBlankLines`, single blank lines are kept as empty lines within the stanzas.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::StanzaSeparator;
+/// use cantara_songlib::importer::classic_song::{scan_blocks_with_separator, ClassicBlock};
+/// let blocks = scan_blocks_with_separator("Line 1\n\nLine 2\n\n\nLine 3", &["//"], StanzaSeparator::MultipleBlankLines);
+/// assert_eq!(blocks.len(), 2);
+/// assert!(matches!(&blocks[0], ClassicBlock::Stanza { primary, .. } if primary == &["Line 1", "", "Line 2"]));
+/// ```
+pub fn scan_blocks_with_separator<S: AsRef<str>>(
+    content: &str,
+    comment_prefixes: &[S],
+    stanza_separator: StanzaSeparator,
+) -> Vec<ClassicBlock> {
+    let content = normalize_input(content, false);
+    let minimum_blank_lines: usize = match stanza_separator {
+        StanzaSeparator::SingleBlankLine => 1,
+        StanzaSeparator::MultipleBlankLines => 2,
+    };
+    let mut blocks: Vec<ClassicBlock> = Vec::new();
+    // The line numbers and lines of the current block
+    let mut block_lines: Vec<(usize, &str)> = Vec::new();
+    // The line numbers of the blank lines since the last line of the current block
+    let mut blank_lines: Vec<usize> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        // Only trailing whitespace is removed, leading whitespace is kept for indented lines (e.g. echoes)
+        let line: &str = line.trim_end();
+        if let Some(text) = comment_text(line, comment_prefixes) {
+            blocks.push(ClassicBlock::Comment { text, line: index + 1 });
+        } else if line.trim().is_empty() {
+            blank_lines.push(index + 1);
+            // Metadata blocks always end at a blank line
+            let is_metadata_block = block_lines.first().is_some_and(|(_, first_line)| is_metadata_line(first_line));
+            if blank_lines.len() == minimum_blank_lines || is_metadata_block {
+                blocks.extend(finish_block(&block_lines));
+                block_lines.clear();
+            }
+        } else {
+            // A tag after a blank line always starts a new (metadata) block
+            if !blank_lines.is_empty() && is_metadata_line(line) {
+                blocks.extend(finish_block(&block_lines));
+                block_lines.clear();
+            }
+            // Blank lines which don't separate stanzas belong to the stanza
+            if !block_lines.is_empty() {
+                block_lines.extend(blank_lines.iter().map(|line| (*line, "")));
+            }
+            blank_lines.clear();
+            block_lines.push((index + 1, line));
+        }
+    }
+    blocks.extend(finish_block(&block_lines));
+    blocks
+}
+
+/// Splits the content of a file into the contents of its songs at the given separator
+fn split_songs<'a>(content: &'a str, separator: &SongSeparator) -> Vec<&'a str> {
+    match separator {
+        SongSeparator::FormFeed => content.split('\u{000C}').collect(),
+        SongSeparator::Line(marker) => {
+            let mut songs: Vec<&str> = Vec::new();
+            let mut start: usize = 0;
+            let mut position: usize = 0;
+            for line in content.split_inclusive('\n') {
+                if line.trim() == marker.trim() {
+                    songs.push(&content[start..position]);
+                    start = position + line.len();
                 }
+                position += line.len();
+            }
+            songs.push(&content[start..]);
+            songs
+        },
+    }
+}
+
+/// Creates a block from its lines (see `scan_blocks`)
+fn finish_block(block_lines: &[(usize, &str)]) -> Option<ClassicBlock> {
+    let (first_line, first_text) = block_lines.first()?;
+    let span = BlockSpan { first_line: *first_line, last_line: block_lines.last()?.0 };
+
+    if is_metadata_line(first_text) {
+        let tags: Vec<ClassicTag> = block_lines
+            .iter()
+            .filter_map(|(line, text)| {
+                tag_line_regex().captures(text).map(|capture| ClassicTag {
+                    key: capture.get(1).unwrap().as_str().trim().to_lowercase(),
+                    value: normalize_value(capture.get(2).unwrap().as_str(), false),
+                    line: *line,
+                })
+            })
+            .collect();
+        let invalid_tags: Vec<(usize, String)> = block_lines
+            .iter()
+            .filter(|(_, text)| text.trim_start().starts_with('#') && !tag_line_regex().is_match(text))
+            .map(|(line, text)| (*line, text.trim().to_string()))
+            .collect();
+        return Some(ClassicBlock::Metadata { tags, invalid_tags, span });
+    }
+
+    let mut primary: Vec<String> = Vec::new();
+    let mut secondary: Vec<Vec<String>> = Vec::new();
+    for (_, text) in block_lines {
+        // Every --- delimiter starts another secondary section (e.g. one for each translation)
+        if text.trim() == "---" {
+            secondary.push(Vec::new());
+            continue;
+        }
+        secondary.last_mut().unwrap_or(&mut primary).push(unescape_lyric_line(text));
+    }
+    Some(ClassicBlock::Stanza { primary, secondary, span })
+}
+
+/// Returns the text of a comment line (starting with one of the comment prefixes after optional whitespace)
+/// without the comment prefix. Comment lines are skipped everywhere in classic song files.
+/// # Returns
+/// The trimmed text of the comment or None if the line is no comment
+fn comment_text<S: AsRef<str>>(line: &str, comment_prefixes: &[S]) -> Option<String> {
+    let line = line.trim();
+    comment_prefixes
+        .iter()
+        .map(|prefix| prefix.as_ref())
+        .filter(|prefix| !prefix.is_empty())
+        .find_map(|prefix| line.strip_prefix(prefix))
+        .map(|text| text.trim().to_string())
+}
+
+/// Attaches the collected comments to a part
+fn attach_comments(part: &PartReference, comments: &mut Vec<String>) {
+    part.borrow_mut().comments.append(comments);
+}
+
+/// The language of secondary (`---`) blocks if the song has no `#translation` or `#translations` tag.
+/// Further secondary sections of a stanza get their position appended (`secondary2`, `secondary3`, ...).
+pub const SECONDARY_LANGUAGE: &str = "secondary";
+
+/// Returns the placeholder language of the secondary section with the given index (starting with 0)
+fn secondary_language(index: usize) -> String {
+    match index {
+        0 => SECONDARY_LANGUAGE.to_string(),
+        _ => format!("{}{}", SECONDARY_LANGUAGE, index + 1),
+    }
+}
+
+/// Returns the languages of the secondary sections, given by `#translations: de, fr`, a single `#translation: de` tag
+/// or numbered tags like `#language2: de` and `#language3: fr`
+fn translation_languages(song: &Song) -> Vec<String> {
+    match song.get_tag("translations").or_else(|| song.get_tag("translation")) {
+        Some(languages) => languages
+            .split(',')
+            .map(|language| language.trim().to_string())
+            .filter(|language| !language.is_empty())
+            .collect(),
+        None => (2..)
+            .map_while(|number| song.get_tag(&format!("language{}", number)))
+            .map(|language| language.trim().to_string())
+            .collect(),
+    }
+}
+
+/// Makes sure that every stanza with secondary sections has as many of them as expected
+/// (the number of languages in the `#translations` tag or the highest number of sections in the song).
+/// Missing sections are added as empty lyrics and reported. Stanzas without any secondary section are left untouched.
+fn complete_secondary_sections(song: &Song, warnings: &mut Vec<ImportWarning>) {
+    let default_lyrics = SongPartContentType::Lyrics { language: LyricLanguage::Default };
+    let section_count = |part: &SongPart| part.contents.iter().filter(|content| content.voice_type != default_lyrics).count();
+
+    let parts: Vec<PartReference> = (0..song.get_total_part_count())
+        .filter_map(|index| song.get_part_by_index(index))
+        .collect();
+    let expected = parts
+        .iter()
+        .map(|part| section_count(&part.borrow()))
+        .chain(std::iter::once(translation_languages(song).len()))
+        .max()
+        .unwrap_or(0);
+
+    for part_reference in parts {
+        let mut part = part_reference.borrow_mut();
+        let found = section_count(&part);
+        if found == 0 || found >= expected {
+            continue;
+        }
+        for index in found..expected {
+            part.add_content(SongPartContent {
+                voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific(secondary_language(index)) },
+                content: String::new(),
             });
-        return Ok(cloned_song);
+        }
+        warnings.push(ImportWarning::MissingSecondarySections { part: part.id.to_string(), expected, found });
+    }
+}
+
+/// Splits the printed number of a stanza (like `3.` or `3)` at the beginning of its first line) from a block.
+/// Leading line breaks of the block are kept.
+fn split_verse_number(block: &str) -> (Option<u32>, String) {
+    let verse_number_regex = {
+        static VERSE_NUMBER_REGEX: OnceLock<Regex> = OnceLock::new();
+        VERSE_NUMBER_REGEX.get_or_init(|| Regex::new(r"^[ \t]*(\d{1,3})[.)][ \t]+").unwrap())
+    };
+    let text = block.trim_start_matches('\n');
+    let leading_line_breaks = &block[..block.len() - text.len()];
+    match verse_number_regex.captures(text) {
+        Some(captures) => (
+            captures.get(1).unwrap().as_str().parse().ok(),
+            format!("{}{}", leading_line_breaks, &text[captures.get(0).unwrap().end()..]),
+        ),
+        None => (None, block.to_string()),
+    }
+}
+
+/// A shared reference to a part of a song
+type PartReference = Rc<RefCell<SongPart>>;
+
+/// Adds the tags of a metadata block to the song. Tags which have already been set (in this or an earlier block)
+/// are handled according to the duplicate tag policy.
+fn apply_tags(
+    song: &mut Song,
+    tags: &[ClassicTag],
+    options: &ImportOptions,
+    warnings: &mut Vec<ImportWarning>,
+) -> Result<(), CantaraImportParsingError> {
+    for tag in tags {
+        let key: String = match &options.tag_aliases {
+            Some(aliases) => aliases.resolve(&tag.key),
+            None => tag.key.clone(),
+        };
+        let value: String = normalize_value(&tag.value, options.normalize_unicode);
+        let value = match song.get_tag(&key) {
+            Some(existing_value) => {
+                let (kept, warning) = resolve_duplicate_tag(
+                    &key, existing_value, &value, tag.line, options.duplicate_tag_policy
+                )?;
+                warnings.push(warning);
+                kept
+            },
+            None => value,
+        };
+        song.add_tag(&key, &value);
+        if key == "title" {
+            song.title = value;
+        }
+    }
+    Ok(())
+}
+
+/// Creates the warning for a line which starts with `#`, but is no valid tag
+fn invalid_tag_warning(line: usize, text: String) -> ImportWarning {
+    let empty_tag_regex = {
+        static EMPTY_TAG_REGEX: OnceLock<Regex> = OnceLock::new();
+        EMPTY_TAG_REGEX.get_or_init(|| Regex::new(r"^\s*#(\w+):\s*$").unwrap())
+    };
+    match empty_tag_regex.captures(&text) {
+        Some(captures) => ImportWarning::EmptyTag { key: captures[1].to_lowercase(), line },
+        None => ImportWarning::MalformedTag { text, line },
+    }
+}
+
+/// Returns whether a line only consists of chord symbols (like `G  C  D`, `Am7 | F/A` or `[G C D]`)
+pub(crate) fn is_chord_line(line: &str) -> bool {
+    let chord_regex = {
+        static CHORD_REGEX: OnceLock<Regex> = OnceLock::new();
+        CHORD_REGEX.get_or_init(|| {
+            Regex::new(r"^[A-H](?:#|b|is|es)?(?:m|min|maj|dim|aug|sus|add|\+)?\d*(?:(?:sus|add|maj|b|#)\d+)*(?:/[A-H](?:#|b|is|es)?)?$").unwrap()
+        })
+    };
+    let line: &str = line.trim();
+    let line: &str = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')).unwrap_or(line);
+    let mut symbols = line.split_whitespace().filter(|symbol| *symbol != "|").peekable();
+    symbols.peek().is_some() && symbols.all(|symbol| chord_regex.is_match(symbol))
+}
+
+/// Splits the chord lines (see `is_chord_line`) from the lyric lines of a stanza.
+/// # Returns
+/// The lyric lines and the chords with one line for every lyric line (the chord line above it or an empty line).
+/// Chord lines at the end of the stanza are added as further lines. The chords are None if the stanza has no chord lines.
+fn split_chord_lines(lines: &[String]) -> (Vec<String>, Option<Vec<String>>) {
+    let mut lyric_lines: Vec<String> = Vec::new();
+    let mut chord_lines: Vec<String> = Vec::new();
+    let mut pending_chords: Option<String> = None;
+    for line in lines {
+        if is_chord_line(line) {
+            let trimmed: &str = line.trim();
+            let chords: &str = match trimmed.strip_prefix('[').and_then(|chords| chords.strip_suffix(']')) {
+                Some(chords) => chords.trim(),
+                None => line.trim_end(),
+            };
+            // Two chord lines in a row: the first one has no lyrics
+            if let Some(chords) = pending_chords.replace(chords.to_string()) {
+                chord_lines.push(chords);
+            }
+        } else {
+            lyric_lines.push(line.clone());
+            chord_lines.push(pending_chords.take().unwrap_or_default());
+        }
+    }
+    chord_lines.extend(pending_chords);
+
+    match chord_lines.iter().any(|chords| !chords.is_empty()) {
+        true => (lyric_lines, Some(chord_lines)),
+        false => (lyric_lines, None),
+    }
+}
+
+/// Returns the text of the given lines in the format of the song contents (every line ends with a line break)
+fn lines_to_text(lines: &[String]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Parses a stanza of a classic song file and adds it to the song.
+/// # Returns
+/// The part which occurs at the position of the stanza (a new part or the earlier part which is repeated by the stanza)
+/// or None if the stanza has no text.
+fn parse_stanza(
+    song: &mut Song,
+    primary: &[String],
+    secondary: &[Vec<String>],
+    first_line: usize,
+    options: &ImportOptions,
+    warnings: &mut Vec<ImportWarning>,
+    numbered_parts: &mut Vec<PartReference>,
+) -> Option<PartReference> {
+    let (primary, mut chords): (Vec<String>, Option<Vec<String>>) = match options.detect_chord_lines {
+        true => split_chord_lines(primary),
+        false => (primary.to_vec(), None),
+    };
+    let primary_text: String = lines_to_text(&primary);
+    let block: &str = &primary_text;
+
+    let (label, block) = match split_part_label(block, parse_part_marker) {
+        (Some(marker), rest) => (Some(marker), rest),
+        _ if options.recognize_part_labels => split_part_label(block, |line| parse_part_label(line, &[])),
+        _ => (None, block),
+    };
+    // The chords stay aligned with the lyric lines if a label line is removed
+    if let (Some(_), Some(chords)) = (&label, &mut chords) {
+        chords.remove(0);
     }
+    // The printed number of a stanza is removed before the block is compared with the other parts
+    let (verse_number, numberless_block): (Option<u32>, String) = match options.recognize_verse_numbers {
+        true => split_verse_number(block),
+        false => (None, block.to_string()),
+    };
+    let primary_text: String = numberless_block;
 
     // We will find first whether the content is already in the song, if yes, we have most likely a chorus.
     // If not, we will add a new verse.
     // If the content is already in the song, we will change the part type to chorus and add the content as a new chorus part.
-    
-    let content_vector = song.find_content_in_part(block);
-    let (part_type, part_reference) = match content_vector.len() {
-        0 => (SongPartType::Verse, None),
-        _ => (SongPartType::Chorus, Some(content_vector.last().unwrap().clone())),
-    };
 
-    let lyric_language: LyricLanguage = LyricLanguage::Default;
-    let lyrics_content: SongPartContent = SongPartContent {
-        voice_type: SongPartContentType::Lyrics {
-            language: lyric_language,
+    // Secondary sections (after a `---` line) are stored as own lyrics, only the primary text is used for the chorus detection
+    let secondary_texts: Vec<String> = secondary
+        .iter()
+        .map(|section| match options.detect_chord_lines {
+            true => lines_to_text(&split_chord_lines(section).0),
+            false => lines_to_text(section),
+        })
+        .collect();
+    let default_lyrics = SongPartContentType::Lyrics { language: LyricLanguage::Default };
+
+    // A block without any text (e.g. only a stripped verse number) never becomes a part
+    if primary_text.trim().is_empty() && secondary_texts.iter().all(|text| text.trim().is_empty()) {
+        return None;
+    }
+
+    // Very short blocks are never regarded as repetition, so that short responses stay independent verses
+    let line_count: usize = primary_text.lines().filter(|line| !line.trim().is_empty()).count();
+    let is_repetition_candidate: bool = line_count >= options.min_repetition_lines
+        && primary_text.trim().chars().count() >= options.min_repetition_chars;
+
+    let lowercase_text = primary_text.to_lowercase();
+    let normalized_block = normalize_for_matching(&primary_text);
+    let content_vector = match options.chorus_matching {
+        _ if !is_repetition_candidate => Vec::new(),
+        ChorusMatching::Exact => {
+            song.find_matching_content_in_part(|content| content.voice_type == default_lyrics && content.content.to_lowercase() == lowercase_text)
+        },
+        ChorusMatching::Normalized => {
+            song.find_matching_content_in_part(|content| content.voice_type == default_lyrics && normalize_for_matching(&content.content) == normalized_block)
+        },
+        ChorusMatching::Similar(threshold) => {
+            song.find_matching_content_in_part(|content| {
+                content.voice_type == default_lyrics && similarity(&normalize_for_matching(&content.content), &normalized_block) >= threshold
+            })
         },
-        content: block.to_string(),
     };
-    
-    if part_reference.is_none() {    
-        let song_part_reference: Rc<RefCell<SongPart>> = cloned_song.add_part_of_type(part_type, None);
-
-        let mut song_part: std::cell::RefMut<SongPart> = song_part_reference.borrow_mut();
-        let _ = &mut song_part.add_content(lyrics_content);
-        song_part.set_repition(part_reference);
-    } else {
-        let unwrapped_reference = part_reference.unwrap();
-        let mut previous_song_part: std::cell::RefMut<SongPart> = unwrapped_reference.borrow_mut();
+    let part_reference = content_vector.last().cloned();
+
+    // A repetition which is not exactly the same text (e.g. other punctuation) might be a mistake in the file,
+    // as well as a stanza which is nearly, but not enough the same as an earlier part to be its repetition
+    let similar_part: Option<(PartReference, bool)> = match &part_reference {
+        Some(reference) => reference
+            .borrow()
+            .contents
+            .iter()
+            .any(|content| content.voice_type == default_lyrics && content.content.to_lowercase() != lowercase_text)
+            .then(|| (reference.clone(), true)),
+        None if is_repetition_candidate => song
+            .find_matching_content_in_part(|content| {
+                content.voice_type == default_lyrics
+                    && similarity(&normalize_for_matching(&content.content), &normalized_block) >= SIMILAR_STANZA_THRESHOLD
+            })
+            .last()
+            .map(|reference| (reference.clone(), false)),
+        None => None,
+    };
+
+    if let Some(unwrapped_reference) = part_reference.clone() {
+        // Labeled parts keep their type, guessed verses become a chorus
+        let previous_type: SongPartType = unwrapped_reference.borrow().get_type();
+        if previous_type == SongPartType::Verse {
+            song.change_type_of_part(&unwrapped_reference, SongPartType::Chorus);
+            renumber_verses(song, numbered_parts);
+        }
+    }
+    // The warning is created after a repeated part has become a chorus, so that it names the new ID of the part
+    if let Some((similar_part, repetition)) = similar_part {
+        warnings.push(ImportWarning::SimilarStanza { part: similar_part.borrow().id.to_string(), line: first_line, repetition });
+    }
+
+    if part_reference.is_none() {
+        let (part_type, label_number) = label.unwrap_or((SongPartType::Verse, None));
+        let number: Option<u32> = label_number.or(verse_number);
+        if let Some(number) = number {
+            let is_taken = numbered_parts.iter().any(|part| {
+                let part = part.borrow();
+                part.part_type == part_type && part.number == number
+            });
+            if is_taken {
+                warnings.push(ImportWarning::DuplicatePartNumber { part: format!("{}.{}", part_type, number), line: first_line });
+            }
+        }
+        let song_part_reference: Rc<RefCell<SongPart>> = song.add_part_of_type(part_type, number);
+        if number.is_some() {
+            numbered_parts.push(song_part_reference.clone());
+        }
+        // Unnumbered verses must not take the numbers which are given in the file
+        if part_type == SongPartType::Verse {
+            renumber_verses(song, numbered_parts);
+        }
+
         {
-            let _ = &mut previous_song_part.set_type(SongPartType::Chorus);
+            let mut song_part: std::cell::RefMut<SongPart> = song_part_reference.borrow_mut();
+            let _ = &mut song_part.add_content(SongPartContent { voice_type: default_lyrics, content: primary_text });
+            for (index, secondary_text) in secondary_texts.into_iter().enumerate() {
+                song_part.add_content(SongPartContent {
+                    voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific(secondary_language(index)) },
+                    content: secondary_text,
+                });
+            }
+            if let Some(chords) = chords {
+                song_part.add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: lines_to_text(&chords) });
+            }
+            song_part.set_repition(None);
+        }
+        return Some(song_part_reference);
+    }
+
+    part_reference
+}
+
+/// Numbers the verses of a song consecutively (e.g. after a verse has become a chorus).
+/// Verses with an explicit number from the file (`numbered_parts`) keep it and their numbers are skipped.
+fn renumber_verses(song: &Song, numbered_parts: &[PartReference]) {
+    let verses: Vec<PartReference> = song.get_parts_by_type(SongPartType::Verse);
+    let is_numbered = |verse: &PartReference| numbered_parts.iter().any(|part| Rc::ptr_eq(part, verse));
+    let taken_numbers: HashSet<u32> = verses
+        .iter()
+        .filter(|verse| is_numbered(verse))
+        .map(|verse| verse.borrow().number)
+        .collect();
+
+    let mut number: u32 = 1;
+    for verse in verses.iter().filter(|verse| !is_numbered(verse)) {
+        while taken_numbers.contains(&number) {
+            number += 1;
         }
-        previous_song_part.number = 1;
-        let _ = &mut previous_song_part.update_id();
+        let mut verse = verse.borrow_mut();
+        verse.number = number;
+        verse.update_id();
+        number += 1;
+    }
+}
+
+/// Returns the number of non-empty lines of the default lyrics of a part
+fn lyric_line_count(part: &SongPart) -> usize {
+    part.get_content(SongPartContentType::Lyrics { language: LyricLanguage::Default })
+        .map(|content| content.content.lines().filter(|line| !line.trim().is_empty()).count())
+        .unwrap_or_default()
+}
+
+/// Retypes a guessed verse to a bridge if it is sung only once between two chorus occurrences.
+/// Because the later verses of a song are often sung between two choruses as well, only a verse with another
+/// number of lines than the first verse is regarded as bridge, and only if there is exactly one such verse.
+fn detect_bridge(song: &Song, part_order: &[PartReference], numbered_parts: &[PartReference]) {
+    let is_chorus = |part: &PartReference| matches!(part.borrow().part_type, SongPartType::Chorus | SongPartType::Refrain);
+    let Some(first_verse) = part_order.iter().find(|part| part.borrow().part_type == SongPartType::Verse) else {
+        return;
+    };
+    let verse_line_count: usize = lyric_line_count(&first_verse.borrow());
+
+    let candidates: Vec<&PartReference> = part_order
+        .windows(3)
+        .filter(|window| is_chorus(&window[0]) && is_chorus(&window[2]))
+        .map(|window| &window[1])
+        .filter(|part| {
+            !Rc::ptr_eq(part, first_verse)
+                && part.borrow().part_type == SongPartType::Verse
+                && !numbered_parts.iter().any(|numbered_part| Rc::ptr_eq(numbered_part, part))
+                && part_order.iter().filter(|occurrence| Rc::ptr_eq(occurrence, part)).count() == 1
+                && lyric_line_count(&part.borrow()) != verse_line_count
+        })
+        .collect();
+
+    if let [bridge] = candidates.as_slice() {
+        song.change_type_of_part(bridge, SongPartType::Bridge);
+        renumber_verses(song, numbered_parts);
     }
+}
+
+/// Returns the tags of all metadata blocks of a classic song file
+fn metadata_blocks<S: AsRef<str>>(content: &str, comment_prefixes: &[S]) -> Vec<Vec<ClassicTag>> {
+    scan_blocks(content, comment_prefixes)
+        .into_iter()
+        .filter_map(|block| match block {
+            ClassicBlock::Metadata { tags, .. } => Some(tags),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the metadata of the first metadata block of a classic song file.
+/// Tags in lyrics blocks or in later metadata blocks are ignored, values are trimmed.
+/// If a tag occurs twice in the block, the first value is used.
+/// Lines starting with one of the `DEFAULT_COMMENT_PREFIXES` are comments, use
+/// `get_metadata_from_file_content_with_options` for other comment prefixes.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::classic_song::get_metadata_from_file_content;
+/// let metadata = get_metadata_from_file_content("#title: Amazing Grace \r\n#author: John Newton\r\n\r\nAmazing grace");
+/// assert_eq!(metadata.get("title").unwrap(), "Amazing Grace");
+/// assert_eq!(metadata.get("author").unwrap(), "John Newton");
+/// ```
+pub fn get_metadata_from_file_content(content: &str) -> HashMap<String, String> {
+    get_metadata_from_file_content_with_options(content, &ImportOptions::default())
+}
+
+/// Returns the metadata of the first metadata block of a classic song file like `get_metadata_from_file_content`,
+/// using the comment prefixes of the given import options.
+pub fn get_metadata_from_file_content_with_options(content: &str, options: &ImportOptions) -> HashMap<String, String> {
+    metadata_blocks(content, &options.comment_prefixes)
+        .first()
+        .and_then(|tags| collect_metadata(tags, DuplicateTagPolicy::KeepFirst).ok())
+        .map(|(metadata, _)| metadata)
+        .unwrap_or_default()
+}
 
-    Ok(cloned_song)
+/// Returns the title of a classic song file, taken from the `#title` tag of the first metadata block which contains one.
+/// Lines starting with one of the `DEFAULT_COMMENT_PREFIXES` are comments, use
+/// `get_title_from_file_content_with_options` for other comment prefixes.
+/// # Returns
+/// The trimmed title or None if there is no title tag in a metadata block
+pub fn get_title_from_file_content(content: &str) -> Option<String> {
+    get_title_from_file_content_with_options(content, &ImportOptions::default())
+}
+
+/// Returns the title of a classic song file like `get_title_from_file_content`,
+/// using the comment prefixes of the given import options.
+pub fn get_title_from_file_content_with_options(content: &str, options: &ImportOptions) -> Option<String> {
+    metadata_blocks(content, &options.comment_prefixes)
+        .iter()
+        .filter_map(|tags| collect_metadata(tags, DuplicateTagPolicy::KeepFirst).ok())
+        .find_map(|(mut metadata, _)| metadata.remove("title"))
 }
 
 /// Imports a song from a str which contains the song in the Cantara classic song format.
@@ -128,55 +790,236 @@ fn parse_block(block: &str, song: Song) -> Result<Song, Box<dyn Error>> {
 /// The function guesses the part types (Refrain/Chorus, Verse, Bridge, etc.) based on the content and
 /// keeps the song order which is provided.
 pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    import_song_with_options(content, &ImportOptions::default())
+        .map(|report| report.song)
+}
+
+/// Imports a song from a str in the Cantara classic song format with the given import options.
+/// # Returns
+/// A Result with an `ImportReport` containing the song and all warnings (e.g. duplicate tags), or an error.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::{ImportOptions, ImportWarning};
+/// use cantara_songlib::importer::classic_song::import_song_with_options;
+/// let report = import_song_with_options("#title: First\n#title: Second", &ImportOptions::default()).unwrap();
+/// assert_eq!(report.song.title, "Second");
+/// assert!(matches!(report.warnings[0], ImportWarning::DuplicateTag { line: 2, .. }));
+/// ```
+pub fn import_song_with_options(content: &str, options: &ImportOptions) -> Result<ImportReport, Box<dyn Error>> {
+    parse_song(content, options).map(|parsed_song| parsed_song.report)
+}
+
+/// A song parsed from a classic song file together with the order of its parts in the file
+struct ParsedSong {
+    report: ImportReport,
+    /// The parts in the order of their occurence in the file (repeated parts occur several times)
+    file_order: Vec<PartReference>,
+    /// The index of the stanza block in the file (counted from 0) of every entry of `file_order`
+    file_order_stanzas: Vec<usize>,
+}
+
+/// Parses a song from a str in the Cantara classic song format (see `import_song_with_options`)
+fn parse_song(content: &str, options: &ImportOptions) -> Result<ParsedSong, Box<dyn Error>> {
+    let normalized_content = normalize_input(content, options.strip_trailing_whitespace);
+    // Only the first song of a file with several songs is imported (see `import_songs_with_options`)
+    let content: &str = match &options.song_separator {
+        Some(separator) => split_songs(&normalized_content, separator)
+            .into_iter()
+            .find(|song| !song.trim().is_empty())
+            .unwrap_or_default(),
+        None => &normalized_content,
+    };
     if content.is_empty() {
         return Err(Box::new(CantaraImportNoContentError {}));
     }
 
-    // Make sure that the regex is only compiled once.
-    let title_regex: &Regex = {
-        static TITLE_REGEX: OnceLock<Regex> = OnceLock::new();
-        TITLE_REGEX.get_or_init(|| {
-            RegexBuilder::new(r"\s*#title:\s*(.+?)$")
-                .multi_line(true)
-                .build()
-                .unwrap()
-        })
+    // The title is set from the metadata blocks (see apply_tags), otherwise it is taken from the filename later
+    let mut song: Song = Song::new("");
+    let mut warnings: Vec<ImportWarning> = Vec::new();
+    // The parts in the order of their occurence in the file (repeated parts occur several times)
+    let mut part_order: Vec<PartReference> = Vec::new();
+    // The index of the stanza of every entry of the part order and the number of stanzas so far
+    let mut part_order_stanzas: Vec<usize> = Vec::new();
+    let mut stanza_count: usize = 0;
+
+    // Comments which have not been attached to a part yet (only collected if they are to be preserved)
+    let mut pending_comments: Vec<String> = Vec::new();
+    // The parts which got their number from the file (by a label or a printed verse number)
+    let mut numbered_parts: Vec<PartReference> = Vec::new();
+    // The value and the line of the `#order` tag, it is resolved after all parts are known
+    let mut order_tag: Option<ClassicTag> = None;
+    // Parse the blocks
+    for block in scan_blocks_with_separator(content, &options.comment_prefixes, options.stanza_separator) {
+        match block {
+            ClassicBlock::Metadata { tags, invalid_tags, .. } => {
+                let (order_tags, tags): (Vec<ClassicTag>, Vec<ClassicTag>) = tags.into_iter().partition(|tag| tag.key == "order");
+                order_tag = order_tags.into_iter().last().or(order_tag);
+                apply_tags(&mut song, &tags, options, &mut warnings)?;
+                warnings.extend(invalid_tags.into_iter().map(|(line, text)| invalid_tag_warning(line, text)));
+            },
+            ClassicBlock::Stanza { primary, secondary, span } => {
+                let occurrence = parse_stanza(
+                    &mut song, &primary, &secondary, span.first_line, options, &mut warnings, &mut numbered_parts
+                );
+                if let Some(occurrence) = occurrence {
+                    attach_comments(&occurrence, &mut pending_comments);
+                    part_order.push(occurrence);
+                    part_order_stanzas.push(stanza_count);
+                }
+                stanza_count += 1;
+            },
+            ClassicBlock::Comment { text, .. } => {
+                if options.preserve_comments {
+                    pending_comments.push(text);
+                }
+            },
+        }
+    }
+    // Comments at the end of the file belong to the last part
+    if let Some(last_part) = part_order.last() {
+        attach_comments(last_part, &mut pending_comments);
+    }
+
+    complete_secondary_sections(&song, &mut warnings);
+    detect_bridge(&song, &part_order, &numbered_parts);
+
+    let tag_order: Option<Vec<PartReference>> = order_tag.and_then(|tag| match resolve_order(&song, &tag.value) {
+        Ok(tag_order) => Some(tag_order),
+        Err(reference) => {
+            warnings.push(ImportWarning::UnknownPartInOrder { reference, line: tag.line });
+            None
+        },
+    });
+    // The order of the tag or of the file is kept, so that repeated parts (like the chorus) are known at every occurrence
+    let song_order: Vec<PartReference> = tag_order.unwrap_or_else(|| part_order.clone());
+    if !song_order.is_empty() {
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(song_order)));
+    }
+
+    // This is done after parsing, because the tags may be located anywhere in the file
+    if options.language_from_tags {
+        apply_language_tags(&song);
+    }
+    
+    Ok(ParsedSong { report: ImportReport { song, warnings }, file_order: part_order, file_order_stanzas: part_order_stanzas })
+}
+
+/// Parses a reference to a part in the `#order` tag, either an ID like `verse.1` or `chorus`
+/// or a shorthand like `V1` or `C` (V/S: verse, C: chorus, R: refrain, B: bridge, PC: pre-chorus, I: intro, O: outro).
+/// # Returns
+/// The type and the number of the part (1 if no number is given) or None if the reference can't be parsed
+fn parse_order_reference(reference: &str) -> Option<(SongPartType, u32)> {
+    let reference_regex = {
+        static REFERENCE_REGEX: OnceLock<Regex> = OnceLock::new();
+        REFERENCE_REGEX.get_or_init(|| Regex::new(r"^([^\W\d_][\w\-]*?)\.?(\d*)$").unwrap())
+    };
+    let captures = reference_regex.captures(reference)?;
+    let part_type: SongPartType = match captures[1].to_lowercase().as_str() {
+        "v" | "s" => SongPartType::Verse,
+        "c" => SongPartType::Chorus,
+        "r" => SongPartType::Refrain,
+        "b" => SongPartType::Bridge,
+        "pc" => SongPartType::PreChorus,
+        "i" => SongPartType::Intro,
+        "o" => SongPartType::Outro,
+        name => SongPartType::from_label(name)?,
     };
+    let number: u32 = match &captures[2] {
+        "" => 1,
+        number => number.parse().ok()?,
+    };
+    Some((part_type, number))
+}
 
-    // Get the title either from the content or the filename
-    let title: &str = match title_regex.captures(content) {
-        Some(title_captures) => title_captures.get(1).unwrap().as_str(),
-        None => "",
+/// Resolves the references of an `#order` tag (separated by commas or spaces) to the parts of the song
+/// # Returns
+/// The parts in the given order or the first reference which is no part of the song
+fn resolve_order(song: &Song, order: &str) -> Result<Vec<PartReference>, String> {
+    order
+        .split(|character: char| character == ',' || character.is_whitespace())
+        .filter(|reference| !reference.is_empty())
+        .map(|reference| {
+            parse_order_reference(reference)
+                .and_then(|(part_type, number)| {
+                    song.get_parts_by_type(part_type).into_iter().find(|part| part.borrow().number == number)
+                })
+                .ok_or_else(|| reference.to_string())
+        })
+        .collect()
+}
+
+/// Imports all songs of a str in the Cantara classic song format, which are separated by `ImportOptions::song_separator`.
+/// Without a separator, the whole content is one song. Empty songs (e.g. after a trailing separator) are skipped.
+/// The line numbers in the warnings are counted from the beginning of each song.
+/// # Returns
+/// A Result with an `ImportReport` for every song, or an error if a song cannot be imported or there is no song at all
+/// # Example
+/// ```
+/// use cantara_songlib::importer::{ImportOptions, SongSeparator};
+/// use cantara_songlib::importer::classic_song::import_songs_with_options;
+/// let options = ImportOptions { song_separator: Some(SongSeparator::Line("***".to_string())), ..Default::default() };
+/// let reports = import_songs_with_options("#title: First\n\nLa la la\n\n***\n#title: Second\n\nLu lu lu", &options).unwrap();
+/// assert_eq!(reports.len(), 2);
+/// assert_eq!(reports[1].song.title, "Second");
+/// ```
+pub fn import_songs_with_options(content: &str, options: &ImportOptions) -> Result<Vec<ImportReport>, Box<dyn Error>> {
+    let content = normalize_input(content, options.strip_trailing_whitespace);
+    let songs: Vec<&str> = match &options.song_separator {
+        Some(separator) => split_songs(&content, separator),
+        None => vec![&content],
     };
+    let song_options = ImportOptions { song_separator: None, ..options.clone() };
+
+    let reports: Vec<ImportReport> = songs
+        .into_iter()
+        .filter(|song| !song.trim().is_empty())
+        .map(|song| import_song_with_options(song, &song_options))
+        .collect::<Result<Vec<ImportReport>, Box<dyn Error>>>()?;
+    if reports.is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+    Ok(reports)
+}
 
-    let mut song: Song = Song::new(title);
+/// Applies the `#language` and `#translation`/`#translations` tags to the lyrics of all parts.
+/// The lyrics get the primary language of the song, the secondary (`---`) sections get the translation languages in their order.
+fn apply_language_tags(song: &Song) {
+    let primary_language = song.primary_language();
+    let translation_languages: Vec<String> = translation_languages(song);
 
-    let mut part: String = String::new();
-    // Parse the blocks
-    for line in content.trim().lines(){
-        match line.trim() {
-            "" => {
-                if part.is_empty() {
-                    continue;
-                }
-                song = parse_block(&part, song.clone()).unwrap();
-                part.clear();
-            }
-            _ => {
-                part.push_str(line.trim());
-                part.push('\n');
+    for index in 0..song.get_total_part_count() {
+        let part_reference = song.get_part_by_index(index).unwrap();
+        let mut part = part_reference.borrow_mut();
+        for content in part.contents.iter_mut() {
+            let SongPartContentType::Lyrics { language } = &mut content.voice_type else {
+                continue;
+            };
+            match language {
+                LyricLanguage::Default => *language = primary_language.clone(),
+                LyricLanguage::Specific(placeholder) => {
+                    let translation = translation_languages
+                        .iter()
+                        .enumerate()
+                        .find(|(index, _)| secondary_language(*index) == *placeholder);
+                    if let Some((_, translation)) = translation {
+                        *language = LyricLanguage::Specific(translation.clone());
+                    }
+                },
             }
         }
     }
-    if !(part.is_empty()) {
-        song = parse_block(&part, song.clone()).unwrap();
-        part.clear();
-    }
-    
-    Ok(song)
+}
+
+/// Removes the chord lines (see `is_chord_line`) from the lines of a block together with their file lines
+fn without_chord_lines(lines: Vec<String>, file_lines: Vec<Option<usize>>) -> (Vec<String>, Vec<Option<usize>>) {
+    lines.into_iter().zip(file_lines).filter(|(line, _)| !is_chord_line(line)).unzip()
 }
 
 /// Generates slides from a classic song content which is provided as &str
+/// The content is imported with the same parser as `import_song` and every block is assigned to the part of the song
+/// which has been imported from it. The blocks are shown in the part order of the song, so that e.g. an `#order` tag
+/// is honored (a block can be shown several times), and the chorus is repeated if `SlideSettings::repeat_chorus` is set.
+/// The slides keep the text of the blocks as it is written in the file and their sources contain the IDs of the parts.
 /// 
 /// # Arguments
 /// - `content`: The content of the classic song file given as a &str
@@ -189,223 +1032,151 @@ pub fn slides_from_classic_song(
     content: &str,
     slide_settings: &SlideSettings,
     backup_title: String) -> Vec<Slide> {
-    
-    /// Defines the current parsing state (which area is to be parsed)
-    enum WritingArea {
-        // The main block
-        MainBlock,
-        // The SecondarybBlock
-        SecondaryBlock
-    }
-    
-    // The emptyness of the line before (in the loop)
-    let mut empty_line = false;
-    // A new block has been started (in the iteration before)
-    let mut start_block_flag = true;
-    // The current block is a meta block
-    let mut meta_block_flag = false;
+    let content = normalize_input(content, false);
+
     // All (main) blocks
     let mut blocks: Vec<Vec<String>> = vec![];
-    // All secondary blocks. There will be always as many secondary blocks as there are primary blocks. 
-    // Empty String equals None
-    let mut secondary_blocks: Vec<Vec<String>> = vec![];
-    
-    // The current string of the block (used in the algorithm below)
-    let mut cur_block_string: String = "".to_string();
-    // The current string of the second block (used in the algorithm below)
-    let mut cur_secundary_block_string: String = "".to_string();
-    
-    // The metadata of the song
+    // The secondary sections of all (main) blocks. There will be always as many entries as there are primary blocks.
+    let mut secondary_sections: Vec<Vec<Vec<String>>> = vec![];
+    // The metadata of the song, collected from all meta blocks regardless of their position
     let mut metadata: HashMap<String, String> = HashMap::new();
-    // Which block is currently written to (Main Block/Secondary Block)
-    let mut writing_area: WritingArea = WritingArea::MainBlock;
-    
-    // A sub function for handling a block (putting it at the right position)
-    // As this code is used twice in the code, it is outsourced into this function
-    fn handle_block(metadata: &mut HashMap<String, String>, 
-        meta_block_flag: &bool, 
-        backup_title: &String,
-        cur_block_string: &String, 
-        cur_secundary_block_string: &String, 
-        blocks: &mut Vec<Vec<String>>, 
-        secondary_blocks: &mut Vec<Vec<String>>
-        ) {
-        match meta_block_flag {
-                true => { 
-                    parse_metadata_block(cur_block_string)
+
+    // The sources of all (main) blocks (the lines of the file)
+    let mut sources: Vec<BlockSource> = vec![];
+    // The index of the stanza in the file of every (main) block
+    let mut block_stanzas: Vec<usize> = vec![];
+    let mut stanza_count: usize = 0;
+    // The line numbers of all comment lines (they are skipped within the blocks)
+    let mut comment_lines: HashSet<usize> = HashSet::new();
+
+    // The lines of a block get a leading line break each (so that the block text starts with one),
+    // labeled and unlabeled blocks look the same
+    let block_text = |lines: &[String]| -> String { lines.iter().map(|line| format!("\n{}", line)).collect() };
+
+    for block in scan_blocks(&content, &slide_settings.comment_prefixes) {
+        match block {
+            ClassicBlock::Metadata { tags, .. } => {
+                // Duplicates are not reported for slides, the last value wins
+                if let Ok((block_metadata, _)) = collect_metadata(&tags, DuplicateTagPolicy::KeepLast) {
+                    metadata.extend(block_metadata);
+                }
+            },
+            ClassicBlock::Stanza { mut primary, mut secondary, span } => {
+                // The file lines of the primary lines, the --- delimiters and the secondary lines in the order of the file
+                let mut file_lines = (span.first_line..=span.last_line).filter(|line| !comment_lines.contains(line));
+                let mut primary_lines: Vec<Option<usize>> = file_lines.by_ref().take(primary.len()).map(Some).collect();
+                let mut section_lines: Vec<Vec<Option<usize>>> = secondary
                     .iter()
-                    .for_each(|(key, value)| {
-                        metadata.insert(key.clone(), value.clone());
-                    }); 
-                    if metadata.get("title").is_none() {
-                        metadata.insert("title".to_string(), backup_title.clone());
-                    }
-                },
-                false => { 
-                    if !cur_block_string.trim().is_empty() {
-                        blocks.push(
-                            cur_block_string.lines()
-                            .map(|str| str.to_string()).collect()
-                        );
-                        secondary_blocks.push(
-                            cur_secundary_block_string.lines()
-                            .map(|str| str.to_string()).collect()
-                        );
+                    .map(|section| {
+                        let lines = file_lines.by_ref().skip(1).take(section.len()).map(Some);
+                        std::iter::once(None).chain(lines).collect()
+                    })
+                    .collect();
+
+                if slide_settings.strip_chord_lines {
+                    (primary, primary_lines) = without_chord_lines(primary, primary_lines);
+                    for (section, lines) in secondary.iter_mut().zip(section_lines.iter_mut()) {
+                        // The first file line belongs to the --- delimiter
+                        let (section_text, mut text_lines) = without_chord_lines(std::mem::take(section), lines.split_off(1));
+                        *section = section_text;
+                        lines.append(&mut text_lines);
                     }
-                },
-            }
-    }
-                
-    for line in content.trim().lines() {
-        if empty_line { start_block_flag = true };
-        
-        if start_block_flag && !line.is_empty() {
-            meta_block_flag = match line.chars().next().unwrap() {
-                '#' => true,
-                _   => false,
-            };
-            start_block_flag = false;
-        }
-        
-        if line.trim().is_empty() {
-            empty_line = true;
-            writing_area = WritingArea::MainBlock;
-            
-            // Skip anything below if the line is empty as well
-            if cur_block_string.is_empty() {
-                continue;
-            }
+                }
 
-            handle_block(&mut metadata, 
-                &meta_block_flag, 
-                &backup_title, 
-                &cur_block_string, 
-                &cur_secundary_block_string, 
-                &mut blocks, 
-                &mut secondary_blocks
-            );
-            
-            cur_block_string = "".to_string();
-            cur_secundary_block_string = "".to_string();
-            
-        }
-        // The --- delimiter starts a secondary block in a stanza
-        else if line.trim() == "---" {
-            writing_area = WritingArea::SecondaryBlock;
-        }
-        else {
-            match writing_area {
-                WritingArea::MainBlock => {
-                    cur_block_string.push('\n');
-                    cur_block_string.push_str(line);
-                },
-                WritingArea::SecondaryBlock => {
-                    cur_secundary_block_string.push('\n');
-                    cur_secundary_block_string.push_str(line);
+                let mut cur_block_string: String = block_text(&primary);
+                // Part markers are never shown, labels only if they are not stripped
+                let (label, rest) = match split_part_label(&cur_block_string, parse_part_marker) {
+                    (Some(marker), rest) => (Some(marker), rest),
+                    _ if slide_settings.strip_part_labels => split_part_label(&cur_block_string, |line| parse_part_label(line, &[])),
+                    _ => (None, cur_block_string.as_str()),
+                };
+                if label.is_some() {
+                    cur_block_string = format!("\n{}", rest);
+                    primary_lines.remove(0);
                 }
-            }
-            
+                if slide_settings.strip_verse_numbers {
+                    cur_block_string = split_verse_number(&cur_block_string).1;
+                }
+                if !cur_block_string.trim().is_empty() {
+                    blocks.push(cur_block_string.lines().map(|str| str.to_string()).collect());
+                    secondary_sections.push(
+                        secondary.iter()
+                        .map(|section| block_text(section).lines().map(|str| str.to_string()).collect())
+                        .collect()
+                    );
+                    // Every block starts with an empty line which is not in the file
+                    sources.push(BlockSource {
+                        part_id: None,
+                        lines: std::iter::once(None).chain(primary_lines).collect(),
+                        sections: section_lines,
+                    });
+                    block_stanzas.push(stanza_count);
+                }
+                stanza_count += 1;
+            },
+            ClassicBlock::Comment { line, .. } => {
+                comment_lines.insert(line);
+            },
         }
     }
-    handle_block(&mut metadata, 
-        &meta_block_flag, 
-        &backup_title, 
-        &cur_block_string, 
-        &cur_secundary_block_string, 
-        &mut blocks, 
-        &mut secondary_blocks
-    );
 
-    if slide_settings.max_lines.is_some() {
-        let wrapped_blocks_output: Vec<Vec<Vec<String>>> = wrap_blocks(&vec![blocks, secondary_blocks], slide_settings.max_lines.unwrap(), true);
-        blocks = wrapped_blocks_output.get(0).unwrap().clone();
-        secondary_blocks = wrapped_blocks_output.get(1).unwrap().clone();
+    // The blocks are assigned to the parts of the imported song by their stanzas, so that the slides follow its part order
+    // (e.g. given by an `#order` tag). The text of the blocks is used for the slides, because it keeps the lines of the file.
+    let options = ImportOptions {
+        comment_prefixes: slide_settings.comment_prefixes.clone(),
+        recognize_part_labels: slide_settings.strip_part_labels,
+        recognize_verse_numbers: slide_settings.strip_verse_numbers,
+        detect_chord_lines: slide_settings.strip_chord_lines,
+        // Like the metadata of the slides, the last value of a duplicate tag is kept
+        duplicate_tag_policy: DuplicateTagPolicy::KeepLast,
+        ..Default::default()
+    };
+    // With these options only an empty content can't be parsed and an empty content has no blocks
+    let Ok(ParsedSong { report, file_order, file_order_stanzas }) = parse_song(&content, &options) else {
+        return slides_from_blocks(blocks, secondary_sections, sources, metadata, slide_settings, backup_title);
+    };
+    let block_parts: Vec<Option<PartReference>> = block_stanzas
+        .iter()
+        .map(|stanza| file_order_stanzas.iter().position(|part_stanza| part_stanza == stanza).map(|index| file_order[index].clone()))
+        .collect();
+    for (source, part) in sources.iter_mut().zip(&block_parts) {
+        source.part_id = part.as_ref().map(|part| part.borrow().id.to_string());
     }
 
-    // Create the Presentation
-    
-    let mut slides: Vec<Slide> = vec![];
-
-    let meta_text_rendering_result = render_metadata(
-        &slide_settings.meta_syntax,
-        &metadata
-    );
-    let mut meta_text: String = "".to_string();
-
-    let meta_text_showable: bool = match meta_text_rendering_result {
-        Ok(str) => {
-            meta_text = str.clone();
-            !str.is_empty()
-        },
-        Err(_) => false,
-    };
-
-    // Make sure that the meta tag title is available (bugfix...)
-    if metadata.get("title").is_none() {
-        metadata.insert("title".to_string(), backup_title.clone());
-    }
-
-    if slide_settings.title_slide {
-        let displayed_meta_text = match meta_text_showable {
-            true => Some(meta_text.clone()),
-            false => None,
+    if slide_settings.follow_part_order || slide_settings.repeat_chorus {
+        // The n-th occurrence of a part shows the n-th block of the part in the file (or the first one if there are less blocks),
+        // so that repeated blocks keep their own text and file lines. Blocks which are no part of the song are left out.
+        let ordered_parts: Vec<PartReference> = match slide_settings.follow_part_order {
+            true => sung_parts(&report.song, slide_settings),
+            false => file_order,
         };
-        
-        slides.push(
-            Slide::new_title_slide(
-                metadata.get("title").unwrap().into(),                                          
-                displayed_meta_text
-            )
-        )
-    }
-    
-    let count = blocks.len();
-    for (index, block) in blocks.iter().enumerate() {
-        let displayed_meta_text = match meta_text_showable && (slide_settings.show_meta_information.on_first_slide() && index == 1) || (slide_settings.show_meta_information.on_last_slide() && index == count -1) {
-            true => Some(meta_text.clone()),
-            false => None,
+        let ordered_parts: Vec<PartReference> = match slide_settings.repeat_chorus {
+            true => with_repeated_chorus(&report.song, ordered_parts),
+            false => ordered_parts,
         };
-        
-        let secondary_block = secondary_blocks.get(index).unwrap();
-        if secondary_block.is_empty() {
-            match blocks.get(index+1) {
-                Some(next_block) => {
-                    slides.push(
-                        Slide::new_content_slide(block.join("\n"), Some(next_block.join("\n")), displayed_meta_text)
-                    )       
-                },
-                None => {
-                    slides.push(
-                        Slide::new_content_slide(
-                            block.join("\n"), None, 
-                            displayed_meta_text
-                        )
-                    )
-                }
-            }
-        } else {
-            slides.push(
-                Slide::new_content_slide(block.join("\n"),
-                    Some(secondary_block.join("\n")), 
-                    displayed_meta_text
-                )
-            );
-        }
-    }
-    
-    if slide_settings.empty_last_slide {
-        slides.push(
-            Slide::new_empty_slide(false)    
-        );
+        let block_indices: Vec<usize> = ordered_parts
+            .iter()
+            .enumerate()
+            .filter_map(|(index, part)| {
+                let occurrence: usize = ordered_parts[..index].iter().filter(|earlier| Rc::ptr_eq(earlier, part)).count();
+                let part_blocks: Vec<usize> = (0..block_parts.len())
+                    .filter(|block| block_parts[*block].as_ref().is_some_and(|block_part| Rc::ptr_eq(block_part, part)))
+                    .collect();
+                part_blocks.get(occurrence).or(part_blocks.first()).copied()
+            })
+            .collect();
+        blocks = block_indices.iter().map(|index| blocks[*index].clone()).collect();
+        secondary_sections = block_indices.iter().map(|index| secondary_sections[*index].clone()).collect();
+        sources = block_indices.iter().map(|index| sources[*index].clone()).collect();
     }
-    
-    slides
+
+    slides_from_blocks(blocks, secondary_sections, sources, metadata, slide_settings, backup_title)
 
 }
 
 #[cfg(test)]
 mod test {
-    use crate::importer::import_song_from_file;
+    use crate::importer::{import_song_from_file, TagAliasMap, DEFAULT_COMMENT_PREFIXES};
 
     use super::*;
 
@@ -437,10 +1208,12 @@ mod test {
             This is a verse
             
             And a refrain
+            with two lines
             
             The second verse
             
-            And a refrain"
+            And a refrain
+            with two lines"
             .to_string();
         let song = import_song(&content).unwrap();
         assert_eq!(song.get_part_count(SongPartType::Verse), 2);
@@ -467,7 +1240,11 @@ mod test {
     fn test_metadata_parsing() {
         let metadata_block: &str = "#title: Test \n\
             #author: J.S. Bach";
-        let metadata = parse_metadata_block(metadata_block);
+        let ClassicBlock::Metadata { tags, .. } = &scan_blocks(metadata_block, &DEFAULT_COMMENT_PREFIXES)[0] else {
+            panic!("The block is no metadata block");
+        };
+        let (metadata, warnings) = collect_metadata(tags, DuplicateTagPolicy::KeepLast).unwrap();
+        assert!(warnings.is_empty());
         
         assert_eq!(metadata.len(), 2);
         assert_eq!(metadata.get("title").unwrap(), "Test");
@@ -485,6 +1262,19 @@ mod test {
             empty_last_slide: true, 
             show_spoiler: true ,
             max_lines: Some(10),
+            strip_part_labels: false,
+            comment_prefixes: crate::importer::default_comment_prefixes(),
+            strip_verse_numbers: false,
+            strip_chord_lines: false,
+            title_slide_spoiler: false,
+            spoiler_max_lines: None,
+            legacy_classic_slides: true,
+            repeat_markers: RepeatMarkers::Keep,
+            follow_part_order: true,
+            part_order_name: None,
+            repeat_chorus: false,
+            secondary_block_role: SecondaryBlockRole::Spoiler,
+            post_processors: Vec::new(),
         };
         
         let slides: Vec<Slide> = slides_from_classic_song(
@@ -509,6 +1299,19 @@ mod test {
             empty_last_slide: true, 
             show_spoiler: true,
             max_lines: None,
+            strip_part_labels: false,
+            comment_prefixes: crate::importer::default_comment_prefixes(),
+            strip_verse_numbers: false,
+            strip_chord_lines: false,
+            title_slide_spoiler: false,
+            spoiler_max_lines: None,
+            legacy_classic_slides: true,
+            repeat_markers: RepeatMarkers::Keep,
+            follow_part_order: true,
+            part_order_name: None,
+            repeat_chorus: false,
+            secondary_block_role: SecondaryBlockRole::Spoiler,
+            post_processors: Vec::new(),
         };
 
         let slides: Vec<Slide> = slides_from_classic_song(
@@ -527,7 +1330,1239 @@ mod test {
             "Verily, Verily".to_string()
         );
 
-        assert!(slides.get(1).unwrap().has_meta_text());
+        assert!(slides.first().unwrap().has_meta_text());
+        assert_eq!(slides.iter().filter(|slide| slide.has_meta_text()).count(), 1);
+
+        presentation_settings.show_meta_information = ShowMetaInformation::LastSlide;
+
+        let slides: Vec<Slide> = slides_from_classic_song(
+            &testfile,
+            &presentation_settings,
+            "Verily, Verily".to_string()
+        );
+
+        // The empty last slide is added after the content slides
+        assert!(slides[slides.len() - 2].has_meta_text());
+        assert_eq!(slides.iter().filter(|slide| slide.has_meta_text()).count(), 1);
+
+        // Without a meta text, the last slide doesn't get one either
+        presentation_settings.meta_syntax = "".to_string();
+
+        let slides: Vec<Slide> = slides_from_classic_song(
+            &testfile,
+            &presentation_settings,
+            "Verily, Verily".to_string()
+        );
+
+        slides.iter().for_each(|slide| assert!(!slide.has_meta_text()));
+
+        // If the first block is wrapped, only its first slide with text shows the meta text
+        presentation_settings.meta_syntax = "{{title}} ({{author}})".to_string();
+        presentation_settings.show_meta_information = ShowMetaInformation::FirstSlide;
+        presentation_settings.max_lines = Some(2);
+
+        let slides: Vec<Slide> = slides_from_classic_song(
+            &testfile,
+            &presentation_settings,
+            "Verily, Verily".to_string()
+        );
+
+        let meta_slides: Vec<&Slide> = slides.iter().filter(|slide| slide.has_meta_text()).collect();
+        assert_eq!(meta_slides.len(), 1);
+        let SlideContent::SingleLanguageMainContent(content) = &meta_slides[0].slide_content else {
+            panic!("The slide should be a content slide");
+        };
+        assert_eq!(content.clone().main_text(), "Oh, what a Saviour that He died for me!");
+    }
+
+    #[test]
+    fn test_spoiler_displayed_correctly() {
+        let testfile = std::fs::read_to_string("testfiles/O What A Savior That He Died For Me.song").unwrap();
+
+        let mut presentation_settings = SlideSettings { title_slide: false, show_spoiler: true, ..Default::default() };
+
+        let slides: Vec<Slide> = slides_from_classic_song(
+            &testfile,
+            &presentation_settings,
+            "Verily, Verily".to_string()
+        );
+
+        assert!(slides.first().unwrap().has_spoiler());
+
+        presentation_settings.show_spoiler = false;
+
+        let slides: Vec<Slide> = slides_from_classic_song(
+            &testfile,
+            &presentation_settings,
+            "Verily, Verily".to_string()
+        );
+
+        slides.iter().for_each(|slide| assert!(!slide.has_spoiler()));
+
+        // Secondary sections are no spoilers either
+        let testfile = std::fs::read_to_string("testfiles/Bilingual Chorus.song").unwrap();
+        let slides: Vec<Slide> = slides_from_classic_song(&testfile, &presentation_settings, "Bilingual".to_string());
+        slides.iter().for_each(|slide| assert!(!slide.has_spoiler()));
+
+        let presentation_settings = SlideSettings { secondary_block_role: SecondaryBlockRole::Translation, ..presentation_settings };
+        let slides: Vec<Slide> = slides_from_classic_song(&testfile, &presentation_settings, "Bilingual".to_string());
+        assert!(slides.iter().any(|slide| matches!(slide.slide_content, SlideContent::MultiLanguageMainContent(_))));
+        slides.iter().for_each(|slide| assert!(!slide.has_spoiler()));
+    }
+
+
+    #[test]
+    fn test_trailing_metadata_block() {
+        let testfile = std::fs::read_to_string("testfiles/Trailing Metadata.song").unwrap();
+
+        let slide_settings = SlideSettings {
+            title_slide: true,
+            meta_syntax: "{{title}} (CCLI {{ccli}})".to_string(),
+            show_meta_information: ShowMetaInformation::FirstSlideAndLastSlide,
+            empty_last_slide: false,
+            show_spoiler: true,
+            max_lines: None,
+            strip_part_labels: false,
+            comment_prefixes: crate::importer::default_comment_prefixes(),
+            strip_verse_numbers: false,
+            strip_chord_lines: false,
+            title_slide_spoiler: false,
+            spoiler_max_lines: None,
+            legacy_classic_slides: true,
+            repeat_markers: RepeatMarkers::Keep,
+            follow_part_order: true,
+            part_order_name: None,
+            repeat_chorus: false,
+            secondary_block_role: SecondaryBlockRole::Spoiler,
+            post_processors: Vec::new(),
+        };
+
+        let slides: Vec<Slide> = slides_from_classic_song(
+            &testfile,
+            &slide_settings,
+            "Backup Title".to_string()
+        );
+
+        // Title slide and two verses, the meta block does not become a slide
+        assert_eq!(slides.len(), 3);
+        match &slides.first().unwrap().slide_content {
+            SlideContent::Title(title_slide) => {
+                assert_eq!(title_slide.title_text, "Trailing Metadata");
+                assert_eq!(title_slide.meta_text, Some("Trailing Metadata (CCLI 12345)".to_string()));
+            },
+            _ => panic!("The first slide should be a title slide"),
+        }
+        assert!(slides.last().unwrap().has_meta_text());
+        slides.iter().for_each(|slide| {
+            if let SlideContent::SingleLanguageMainContent(content) = &slide.slide_content {
+                assert!(!content.clone().main_text().contains("#ccli"));
+            }
+        });
+    }
+
+    #[test]
+    fn test_split_metadata_blocks() {
+        let song: Song = import_song_from_file("testfiles/Split Metadata.song").unwrap();
+        assert_eq!(song.title, "Split Metadata");
+        assert_eq!(song.get_tag("ccli").unwrap(), "12345");
+        assert_eq!(song.get_tag("copyright").unwrap(), "1990 Somebody");
+        assert_eq!(song.get_total_part_count(), 2);
+        assert!(song.get_ordered_parts().iter().all(|part| part.borrow().contents.iter().all(|content| !content.content.contains('#'))));
+
+        let testfile = std::fs::read_to_string("testfiles/Split Metadata.song").unwrap();
+        let slide_settings = SlideSettings { meta_syntax: "{{title}} (CCLI {{ccli}})".to_string(), ..Default::default() };
+        let slides: Vec<Slide> = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string());
+        // Title slide, two verses and the empty last slide
+        assert_eq!(slides.len(), 4);
+        for slide in &slides {
+            match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => {
+                    assert!(!content.clone().main_text().contains('#'));
+                    assert!(!content.clone().spoiler_text().unwrap_or_default().contains('#'));
+                },
+                SlideContent::Title(title_slide) => assert_eq!(title_slide.meta_text, Some("Split Metadata (CCLI 12345)".to_string())),
+                _ => {},
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_line_inside_stanza_is_lyrics() {
+        let content = "First verse\n#not a tag\n\nSecond verse";
+
+        let slides: Vec<Slide> = slides_from_classic_song(
+            content,
+            &SlideSettings::default(),
+            "Backup Title".to_string()
+        );
+
+        let main_texts: Vec<String> = slides.iter().filter_map(|slide| match &slide.slide_content {
+            SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+            _ => None,
+        }).collect();
+        assert_eq!(main_texts.len(), 2);
+        assert!(main_texts.first().unwrap().contains("#not a tag"));
+    }
+
+    #[test]
+    /// Stanzas may start with `#` if the line is no tag line, a leading `\#` is unescaped
+    fn test_hash_at_start_of_stanza() {
+        let song: Song = import_song_from_file("testfiles/Hash Lyrics.song").unwrap();
+        assert_eq!(song.title, "Hash Lyrics");
+        assert_eq!(song.get_total_part_count(), 2);
+        assert!(song.get_tag("1").is_none());
+
+        let testfile = std::fs::read_to_string("testfiles/Hash Lyrics.song").unwrap();
+        let slides: Vec<Slide> = slides_from_classic_song(&testfile, &SlideSettings::default(), "Backup Title".to_string());
+        let main_texts: Vec<String> = slides.iter().filter_map(|slide| match &slide.slide_content {
+            SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text().trim().to_string()),
+            _ => None,
+        }).collect();
+        assert_eq!(main_texts, vec![
+            "#1 in my heart,\nyou are the one.".to_string(),
+            "#hashtag blessed,\nthe song is done.".to_string(),
+        ]);
+
+        // Escaped comments, backslashes and empty lines within a stanza
+        let song: Song = import_song("\\; not a comment\n\\\n\\\\o/\n  \\// indented").unwrap();
+        assert_eq!(song.get_part_by_index(0).unwrap().borrow().contents[0].content, "; not a comment\n\n\\o/\n  // indented\n");
+    }
+
+    #[test]
+    fn test_duplicate_tag_policies() {
+        let content = "#title: First Title\n#author: John Newton\n\nAmazing grace\n\n#title: Second Title";
+        let import_with_policy = |policy: DuplicateTagPolicy| import_song_with_options(
+            content,
+            &ImportOptions { duplicate_tag_policy: policy, ..Default::default() }
+        );
+
+        let report = import_with_policy(DuplicateTagPolicy::KeepLast).unwrap();
+        assert_eq!(report.song.title, "Second Title");
+        assert_eq!(report.warnings, vec![ImportWarning::DuplicateTag {
+            key: "title".to_string(),
+            kept: "Second Title".to_string(),
+            discarded: Some("First Title".to_string()),
+            line: 6,
+        }]);
+        assert_eq!(import_song(content).unwrap().title, "Second Title");
+
+        let report = import_with_policy(DuplicateTagPolicy::KeepFirst).unwrap();
+        assert_eq!(report.song.title, "First Title");
+        assert_eq!(report.song.get_tag("title").unwrap(), "First Title");
+        assert_eq!(report.warnings.len(), 1);
+
+        let report = import_with_policy(DuplicateTagPolicy::Append).unwrap();
+        assert_eq!(report.song.get_tag("title").unwrap(), "First Title; Second Title");
+        assert!(matches!(&report.warnings[0], ImportWarning::DuplicateTag { discarded: None, .. }));
+
+        let error = import_with_policy(DuplicateTagPolicy::Error).unwrap_err();
+        assert_eq!(error.to_string(), "Parsing error in line 6: the tag #title has already been set");
+    }
+
+    #[test]
+    fn test_duplicate_tag_in_metadata_block() {
+        let ClassicBlock::Metadata { tags, .. } = &scan_blocks("\n\n#author: A\n#author: B", &DEFAULT_COMMENT_PREFIXES)[0] else {
+            panic!("The block is no metadata block");
+        };
+        let (metadata, warnings) = collect_metadata(tags, DuplicateTagPolicy::KeepFirst).unwrap();
+        assert_eq!(metadata.get("author").unwrap(), "A");
+        assert!(matches!(&warnings[0], ImportWarning::DuplicateTag { line: 4, .. }));
+    }
+
+    #[test]
+    fn test_crlf_and_nbsp_in_tags() {
+        let content = "#title: Amazing Grace\r\n#author:\u{00A0}John Newton\u{00A0}\u{200B}\r\n\r\nAmazing grace\r\nhow sweet the sound\r\n";
+        let song = import_song(content).unwrap();
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.get_tag("title").unwrap(), "Amazing Grace");
+        assert_eq!(song.get_tag("author").unwrap(), "John Newton");
+
+        let slide_settings = SlideSettings {
+            meta_syntax: "{{title}} ({{author}})".to_string(),
+            ..Default::default()
+        };
+        let slides = slides_from_classic_song(content, &slide_settings, "Backup Title".to_string());
+        match &slides.first().unwrap().slide_content {
+            SlideContent::Title(title_slide) => assert_eq!(title_slide.meta_text, Some("Amazing Grace (John Newton)".to_string())),
+            _ => panic!("The first slide should be a title slide"),
+        }
+    }
+
+    #[test]
+    fn test_nfc_normalization_of_tags() {
+        let content = "#title: Noe\u{0308}l";
+        let options = ImportOptions { normalize_unicode: true, ..Default::default() };
+        assert_eq!(import_song_with_options(content, &options).unwrap().song.title, "Noël");
+        assert_eq!(import_song(content).unwrap().title, "Noe\u{0308}l");
+    }
+
+    #[test]
+    fn test_tag_aliases() {
+        let content = "#Titel: Lobe den Herren\n#Autor: Joachim Neander\n#Jahr: 1680\n\nLobe den Herren";
+        let options = ImportOptions { tag_aliases: Some(TagAliasMap::default()), ..Default::default() };
+        let song = import_song_with_options(content, &options).unwrap().song;
+        assert_eq!(song.title, "Lobe den Herren");
+        assert_eq!(song.get_tag("author").unwrap(), "Joachim Neander");
+        assert_eq!(song.get_tag("year").unwrap(), "1680");
+        assert_eq!(song.get_tag("autor"), None);
+
+        let slide_settings = SlideSettings { meta_syntax: "{{title}} ({{author}})".to_string(), ..Default::default() };
+        match &slides_from_song(&song, &slide_settings)[0].slide_content {
+            SlideContent::Title(title_slide) => assert_eq!(title_slide.meta_text, Some("Lobe den Herren (Joachim Neander)".to_string())),
+            _ => panic!("The first slide should be a title slide"),
+        }
+
+        // An alias and the tag itself are the same tag
+        let options = ImportOptions { tag_aliases: Some(TagAliasMap::new().with_alias("writer", "author")), ..Default::default() };
+        let report = import_song_with_options("#author: A\n#writer: B", &options).unwrap();
+        assert!(matches!(&report.warnings[0], ImportWarning::DuplicateTag { key, line: 2, .. } if key == "author"));
+
+        // Without aliases the tags keep their names
+        assert_eq!(import_song(content).unwrap().get_tag("autor").unwrap(), "Joachim Neander");
+    }
+
+    #[test]
+    fn test_language_tags() {
+        let lyrics = |song: &Song| song.get_part_by_index(0).unwrap().borrow().contents.clone();
+
+        let untagged = import_song("#title: Amazing Grace\n\nAmazing grace").unwrap();
+        assert_eq!(lyrics(&untagged)[0].voice_type, SongPartContentType::Lyrics { language: LyricLanguage::Default });
+
+        let tagged = import_song("Stille Nacht\n\n#title: Stille Nacht\n#language: de").unwrap();
+        assert_eq!(tagged.primary_language(), LyricLanguage::Specific("de".to_string()));
+        assert_eq!(lyrics(&tagged)[0].voice_type, SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) });
+
+        let options = ImportOptions { language_from_tags: false, ..Default::default() };
+        let ignored = import_song_with_options("#language: de\n\nStille Nacht", &options).unwrap().song;
+        assert_eq!(lyrics(&ignored)[0].voice_type, SongPartContentType::Lyrics { language: LyricLanguage::Default });
+    }
+
+    #[test]
+    fn test_translation_tag_splits_secondary_blocks() {
+        let song = import_song(
+            "#title: Stille Nacht\n#language: de\n#translation: en\n\nStille Nacht, heilige Nacht\n---\nSilent night, holy night"
+        ).unwrap();
+        let contents = song.get_part_by_index(0).unwrap().borrow().contents.clone();
+
+        assert_eq!(contents.len(), 2);
+        assert_eq!(contents[0].voice_type, SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) });
+        assert_eq!(contents[0].content, "Stille Nacht, heilige Nacht\n");
+        assert_eq!(contents[1].voice_type, SongPartContentType::Lyrics { language: LyricLanguage::Specific("en".to_string()) });
+        assert_eq!(contents[1].content, "Silent night, holy night\n");
+    }
+
+    #[test]
+    fn test_secondary_blocks_in_song() {
+        let song: Song = import_song_from_file("testfiles/Bilingual Chorus.song").unwrap();
+        let german = SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) };
+        let english = SongPartContentType::Lyrics { language: LyricLanguage::Specific("en".to_string()) };
+
+        // The chorus is recognized by its primary text, even though the repetition has no translation
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+        let chorus = song.get_parts_by_type(SongPartType::Chorus)[0].borrow().contents.clone();
+        assert_eq!(chorus, vec![
+            SongPartContent { voice_type: german.clone(), content: "Lobt den Herrn,\nalle Welt!\n".to_string() },
+            SongPartContent { voice_type: english.clone(), content: "Praise the Lord,\nall the world!\n".to_string() },
+        ]);
+        for index in 0..song.get_total_part_count() {
+            let part = song.get_part_by_index(index).unwrap();
+            assert!(part.borrow().contents.iter().all(|content| !content.content.contains("---")));
+        }
+
+        // Without a translation tag the secondary language is used
+        let untagged = import_song("Erste Zeile\n---\nFirst line").unwrap();
+        assert_eq!(untagged.get_part_by_index(0).unwrap().borrow().contents[1], SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific(SECONDARY_LANGUAGE.to_string()) },
+            content: "First line\n".to_string(),
+        });
+
+        // The slides keep the secondary blocks as spoilers
+        let testfile = std::fs::read_to_string("testfiles/Bilingual Chorus.song").unwrap();
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        let slides = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string());
+        let texts: Vec<(String, Option<String>)> = slides
+            .iter()
+            .filter_map(|slide| match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => Some((content.clone().main_text(), content.clone().spoiler_text())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts.len(), 4);
+        assert_eq!(texts[0].0, "\nDer Morgen bricht an,\ndie Nacht ist vorbei.");
+        assert_eq!(texts[1].1, Some("\nPraise the Lord,\nall the world!".to_string()));
+    }
+
+    #[test]
+    fn test_scripture_in_meta_text() {
+        let content = "#title: Amazing Grace\n#scripture: Eph 2:8\n\nAmazing grace";
+        assert_eq!(import_song(content).unwrap().scripture_refs()[0].book, "Ephesians");
+
+        let slide_settings = SlideSettings {
+            meta_syntax: "{{title}} ({{scripture}})".to_string(),
+            ..Default::default()
+        };
+        let slides = slides_from_classic_song(content, &slide_settings, "Backup Title".to_string());
+        match &slides.first().unwrap().slide_content {
+            SlideContent::Title(title_slide) => assert_eq!(title_slide.meta_text, Some("Amazing Grace (Eph 2:8)".to_string())),
+            _ => panic!("The first slide should be a title slide"),
+        }
+    }
+
+    #[test]
+    fn test_chorus_differing_by_punctuation_and_wrapping() {
+        let content = "#title: Test Song\n\n\
+            First verse\n\n\
+            Glory, glory, hallelujah,\nHis truth is marching on.\n\n\
+            Second verse\n\n\
+            Glory glory hallelujah\nHis truth\nis marching on";
+
+        let song = import_song(content).unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+
+        let options = ImportOptions { chorus_matching: ChorusMatching::Exact, ..Default::default() };
+        let song = import_song_with_options(content, &options).unwrap().song;
+        assert_eq!(song.get_part_count(SongPartType::Verse), 4);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 0);
+    }
+
+    #[test]
+    fn test_chorus_with_similarity_threshold() {
+        let content = "First verse\n\n\
+            Glory, glory, hallelujah\nHis truth is marching on\n\n\
+            Second verse\n\n\
+            Glory, glory, halleluja\nHis truth is marching on";
+
+        assert_eq!(import_song(content).unwrap().get_part_count(SongPartType::Chorus), 0);
+
+        let options = ImportOptions { chorus_matching: ChorusMatching::Similar(0.9), ..Default::default() };
+        let song = import_song_with_options(content, &options).unwrap().song;
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+    }
+
+    #[test]
+    fn test_part_labels() {
+        let content = "#title: Test Song\n\n\
+            Verse 1\nFirst verse line\n\n\
+            Chorus:\nGlory, glory, hallelujah\nHis truth is marching on\n\n\
+            Second verse line\n\n\
+            Glory, glory, hallelujah\nHis truth is marching on\n\n\
+            [Bridge]\nBridge line\n\n\
+            Chorus\nGlory, glory, hallelujah\nHis truth is marching on";
+
+        let options = ImportOptions { recognize_part_labels: true, ..Default::default() };
+        let song = import_song_with_options(content, &options).unwrap().song;
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+        assert_eq!(song.get_part_count(SongPartType::Bridge), 1);
+        let chorus = song.get_parts_by_type(SongPartType::Chorus).first().unwrap().clone();
+        assert_eq!(chorus.borrow().contents[0].content.trim(), "Glory, glory, hallelujah\nHis truth is marching on");
+
+        // Without the setting, labels are lyrics
+        let song = import_song(content).unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Bridge), 0);
+        assert_eq!(song.get_part_count(SongPartType::Verse), 4);
+    }
+
+    #[test]
+    fn test_part_labels_in_slides() {
+        let content = "Strophe 1\nErste Zeile\n\nRefrain:\nHalleluja";
+        let main_texts = |strip_part_labels: bool| -> Vec<String> {
+            let slide_settings = SlideSettings { strip_part_labels, title_slide: false, empty_last_slide: false, ..Default::default() };
+            slides_from_classic_song(content, &slide_settings, "Test".to_string())
+                .iter()
+                .filter_map(|slide| match &slide.slide_content {
+                    SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert_eq!(main_texts(true), vec!["\nErste Zeile", "\nHalleluja"]);
+        assert_eq!(main_texts(false), vec!["\nStrophe 1\nErste Zeile", "\nRefrain:\nHalleluja"]);
+    }
+
+    #[test]
+    fn test_chord_lines() {
+        assert!(is_chord_line("G          C         G"));
+        assert!(is_chord_line("[G  C  D]"));
+        assert!(is_chord_line("Em  Am7 | D/F#  Hsus4"));
+        assert!(!is_chord_line("A mighty fortress is our God"));
+        assert!(!is_chord_line("[Bridge]"));
+
+        let content = std::fs::read_to_string("testfiles/Chord Lines.song").unwrap();
+        let options = ImportOptions { detect_chord_lines: true, ..Default::default() };
+        let song = import_song_with_options(&content, &options).unwrap().song;
+        assert!(song.get_content_types().contains(&SongPartContentType::Chords));
+        let verse = song.get_part_by_id("Verse.1").unwrap();
+        let verse = verse.borrow();
+        assert_eq!(
+            verse.get_content(SongPartContentType::Lyrics { language: LyricLanguage::Default }).unwrap().content,
+            "Morning has come, the night is gone,\nthe light is shining on everyone.\n"
+        );
+        assert_eq!(verse.get_content(SongPartContentType::Chords).unwrap().content, "G          C         G\nEm          Am7     D/F#\n");
+        let chords = song.get_part_by_id("Verse.2").unwrap().borrow().get_content(SongPartContentType::Chords).unwrap().content.clone();
+        assert_eq!(chords, "G  C  D\n\n");
+
+        // Without the setting, chord lines are lyrics
+        assert!(!import_song(&content).unwrap().get_content_types().contains(&SongPartContentType::Chords));
+
+        let slide_settings = SlideSettings { strip_chord_lines: true, title_slide: false, empty_last_slide: false, ..Default::default() };
+        let main_texts: Vec<String> = slides_from_classic_song(&content, &slide_settings, "Test".to_string())
+            .iter()
+            .filter_map(|slide| match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(main_texts.len(), 2);
+        assert!(main_texts.iter().all(|text| text.lines().all(|line| !is_chord_line(line))));
+        assert!(main_texts.iter().all(|text| !text.contains("Am7") && !text.contains("[G")));
+        assert_eq!(main_texts[0], "\nMorning has come, the night is gone,\nthe light is shining on everyone.");
+
+        // Slides which are created from the imported song don't show the chords either
+        let slide_settings = SlideSettings { legacy_classic_slides: false, ..slide_settings };
+        let chapter = crate::importer::create_presentation_from_file("testfiles/Chord Lines.song", &slide_settings).unwrap();
+        assert_eq!(chapter.slides.len(), 2);
+        assert!(chapter.slides.iter().all(|slide| match &slide.slide_content {
+            SlideContent::SingleLanguageMainContent(content) => !content.clone().main_text().contains("Am7"),
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn test_order_tag() {
+        let report = import_song_with_options(&std::fs::read_to_string("testfiles/Ordered Song.song").unwrap(), &ImportOptions::default()).unwrap();
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.song.get_tag("order"), None);
+        let ordered_ids: Vec<String> = report.song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(ordered_ids, vec!["Chorus.1", "Verse.1", "Chorus.1", "Verse.2", "Chorus.1"]);
+
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        let content = std::fs::read_to_string("testfiles/Ordered Song.song").unwrap();
+        let main_texts: Vec<String> = slides_from_classic_song(&content, &slide_settings, "Test".to_string())
+            .iter()
+            .filter_map(|slide| match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(main_texts.len(), 5);
+        assert_eq!(main_texts.iter().filter(|text| text.contains("Sing to the Lord")).count(), 3);
+        assert!(main_texts[1].contains("Morning has come"));
+
+        // Unknown references fall back to the order of the file
+        let content = "#title: Test\n#order: verse.1, verse.5, verse.2\n\nFirst verse\n\nSecond verse";
+        let report = import_song_with_options(content, &ImportOptions::default()).unwrap();
+        assert_eq!(report.warnings, vec![ImportWarning::UnknownPartInOrder { reference: "verse.5".to_string(), line: 2 }]);
+        let ordered_ids: Vec<String> = report.song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(ordered_ids, vec!["Verse.1", "Verse.2"]);
+        assert_eq!(slides_from_classic_song(content, &slide_settings, "Test".to_string()).len(), 2);
+    }
+
+    #[test]
+    fn test_part_markers() {
+        let song: Song = import_song_from_file("testfiles/Marked Parts.song").unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Verse), 3);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+        assert_eq!(song.get_part_count(SongPartType::Bridge), 1);
+        let ids: Vec<String> = (0..song.get_total_part_count())
+            .map(|index| song.get_part_by_index(index).unwrap().borrow().id.to_string())
+            .collect();
+        assert_eq!(ids, vec!["Verse.1", "Chorus.1", "Verse.2", "Bridge.1", "Verse.3"]);
+        let chorus = song.get_part_by_id("Chorus.1").unwrap();
+        assert!(chorus.borrow().contents[0].content.starts_with("Sing to the Lord"));
+
+        // Markers are never shown on slides, also not if the labels are kept
+        let content = "(Refrain)\nHalleluja\n\n(Verse 2)\nZweite Zeile";
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        let main_texts: Vec<String> = slides_from_classic_song(content, &slide_settings, "Test".to_string())
+            .iter()
+            .filter_map(|slide| match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(main_texts, vec!["\nHalleluja", "\nZweite Zeile"]);
+    }
+
+    #[test]
+    fn test_bridge_detection() {
+        let song: Song = import_song_from_file("testfiles/Bridge Song.song").unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Bridge), 1);
+        assert_eq!(song.get_part_count(SongPartType::Verse), 1);
+        let ordered_ids: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(ordered_ids, vec!["Verse.1", "Chorus.1", "Bridge.1", "Chorus.1"]);
+
+        // Later verses between two choruses with as many lines as the first verse stay verses
+        let content = "First line\nSecond line\n\nChorus line\nChorus line 2\n\nThird line\nFourth line\n\nChorus line\nChorus line 2";
+        let song: Song = import_song(content).unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Bridge), 0);
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+    }
+
+    #[test]
+    fn test_repeat_chorus() {
+        let main_texts = |content: &str, slide_settings: &SlideSettings| -> Vec<String> {
+            slides_from_classic_song(content, slide_settings, "Test".to_string())
+                .iter()
+                .filter_map(|slide| match &slide.slide_content {
+                    SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, repeat_chorus: true, max_lines: Some(2), ..Default::default() };
+
+        // The chorus is sung after all four verses, every wrapped chorus slide is shown each time
+        let content = std::fs::read_to_string("testfiles/O What A Savior That He Died For Me.song").unwrap();
+        let texts = main_texts(&content, &slide_settings);
+        let chorus_starts: Vec<usize> = texts
+            .iter()
+            .enumerate()
+            .filter(|(_, text)| text.contains("Verily, verily, I say unto you"))
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(chorus_starts.len(), 4);
+        for chorus_start in chorus_starts {
+            assert!(texts[chorus_start + 1].contains("message ever new"));
+            assert!(texts[chorus_start + 2].contains("'tis true"));
+        }
+        assert!(texts.last().unwrap().contains("'tis true"));
+
+        // A chorus which is written once is inserted after every verse and at the end
+        let content = std::fs::read_to_string("testfiles/Marked Parts.song").unwrap();
+        let slide_settings = SlideSettings { max_lines: None, ..slide_settings };
+        let first_lines: Vec<String> = main_texts(&content, &slide_settings)
+            .iter()
+            .map(|text| text.trim().lines().next().unwrap().to_string())
+            .collect();
+        assert_eq!(first_lines, vec![
+            "Morning has come, the night is gone,",
+            "Sing to the Lord, sing a new song,",
+            "Noon has come, the sun is high,",
+            "Sing to the Lord, sing a new song,",
+            "All the earth shall praise his name,",
+            "Evening has come, the day is done,",
+            "Sing to the Lord, sing a new song,",
+        ]);
+        let slide_settings = SlideSettings { repeat_chorus: false, ..slide_settings };
+        assert_eq!(main_texts(&content, &slide_settings).len(), 5);
+    }
+
+    #[test]
+    /// A block which has no slide (here an outro with a translation only) doesn't prevent the part order from being followed
+    fn test_slides_follow_part_order_with_hidden_blocks() {
+        let content = "#title: Test\n#order: V1, C, V2, C\n\nFirst verse\n\n(Chorus)\nThe chorus\n\nSecond verse\n\n(Outro)\n---\nNachspiel";
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        let main_texts: Vec<String> = slides_from_classic_song(content, &slide_settings, "Test".to_string())
+            .iter()
+            .filter_map(|slide| match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text().trim().to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(main_texts, vec!["First verse", "The chorus", "Second verse", "The chorus"]);
+    }
+
+    #[test]
+    fn test_part_order_keeps_every_chorus_occurrence() {
+        let song: Song = import_song_from_file("testfiles/O What A Savior That He Died For Me.song").unwrap();
+        let ordered_ids: Vec<String> = song
+            .get_ordered_parts()
+            .iter()
+            .map(|part| part.borrow().id.get_id())
+            .collect();
+
+        assert_eq!(ordered_ids, vec![
+            "Verse.1", "Chorus.1", "Verse.2", "Chorus.1", "Verse.3", "Chorus.1", "Verse.4", "Chorus.1"
+        ]);
+        // The chorus is shared between its occurrences
+        assert_eq!(song.get_total_part_count(), 5);
+    }
+
+    #[test]
+    fn test_indentation_and_crlf() {
+        let song: Song = import_song_from_file("testfiles/Indented Echo.song").unwrap();
+        assert_eq!(song.title, "Indented Echo");
+        assert_eq!(song.get_tag("author").unwrap(), "Traditional");
+        // The tab-only line separates the stanzas
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        let first_verse = song.get_part_by_index(0).unwrap().borrow().contents[0].content.clone();
+        assert_eq!(first_verse, "Amazing grace\n  (echo: amazing grace)\nHow sweet the sound\n\t(echo: sweet the sound)\n");
+        let second_verse = song.get_part_by_index(1).unwrap().borrow().contents[0].content.clone();
+        assert_eq!(second_verse, "That saved a wretch like me\n    like me\n");
+
+        let testfile = std::fs::read_to_string("testfiles/Indented Echo.song").unwrap();
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        let main_texts: Vec<String> = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string())
+            .iter()
+            .filter_map(|slide| match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(main_texts, vec![
+            "\nAmazing grace\n  (echo: amazing grace)\nHow sweet the sound\n\t(echo: sweet the sound)",
+            "\nThat saved a wretch like me\n    like me",
+        ]);
+    }
+
+    #[test]
+    fn test_comment_lines() {
+        let content = "// Arrangement for the evening service
+#title: Comments
+// #author: Nobody
+
+First line
+// sing softly
+Second line
+
+// only a comment
+; capo 2
+
+Third line
+  // fade out";
+
+        // Lines starting with `//` and `;` are comments by default, comment-only blocks don't create parts
+        let song = import_song(content).unwrap();
+        assert_eq!(song.title, "Comments");
+        assert!(song.get_tag("author").is_none());
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        let first_verse = song.get_part_by_index(0).unwrap();
+        assert_eq!(first_verse.borrow().contents[0].content, "First line\nSecond line\n");
+        assert!(first_verse.borrow().comments.is_empty());
+
+        let options = ImportOptions { preserve_comments: true, ..Default::default() };
+        let song = import_song_with_options(content, &options).unwrap().song;
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        let first_verse = song.get_part_by_index(0).unwrap();
+        assert_eq!(first_verse.borrow().comments, vec!["Arrangement for the evening service", "#author: Nobody", "sing softly"]);
+        let second_verse = song.get_part_by_index(1).unwrap();
+        assert_eq!(second_verse.borrow().contents[0].content, "Third line\n");
+        assert_eq!(second_verse.borrow().comments, vec!["only a comment", "capo 2", "fade out"]);
+
+        // Groups whose lyrics start with `;` can opt out
+        let options = ImportOptions { comment_prefixes: vec!["//".to_string()], ..Default::default() };
+        let song = import_song_with_options(content, &options).unwrap().song;
+        assert_eq!(song.get_part_count(SongPartType::Verse), 3);
+        let second_verse = song.get_part_by_index(1).unwrap().borrow().contents[0].content.clone();
+        assert_eq!(second_verse, "; capo 2\n");
+
+        let main_texts = |slide_settings: &SlideSettings| -> Vec<String> {
+            slides_from_classic_song(content, slide_settings, "Backup Title".to_string())
+                .iter()
+                .filter_map(|slide| match &slide.slide_content {
+                    SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        assert_eq!(main_texts(&slide_settings), vec!["\nFirst line\nSecond line", "\nThird line"]);
+        let slide_settings = SlideSettings { comment_prefixes: vec!["//".to_string()], ..slide_settings };
+        assert_eq!(main_texts(&slide_settings), vec!["\nFirst line\nSecond line", "\n; capo 2", "\nThird line"]);
+    }
+
+    #[test]
+    fn test_multiple_secondary_sections() {
+        let report = import_song_with_options(
+            &std::fs::read_to_string("testfiles/Trilingual Song.song").unwrap(),
+            &ImportOptions::default()
+        ).unwrap();
+        let lyrics = |language: &str, content: &str| SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific(language.to_string()) },
+            content: content.to_string(),
+        };
+
+        let first_verse = report.song.get_part_by_index(0).unwrap().borrow().contents.clone();
+        assert_eq!(first_verse, vec![
+            lyrics("en", "Peace be with you,\npeace in every land.\n"),
+            lyrics("de", "Friede sei mit dir,\nFriede in jedem Land.\n"),
+            lyrics("fr", "La paix soit avec toi,\nla paix en tout pays.\n"),
+        ]);
+        // The missing French section is added empty and reported
+        let second_verse = report.song.get_part_by_index(1).unwrap().borrow().contents.clone();
+        assert_eq!(second_verse[2], lyrics("fr", ""));
+        assert_eq!(report.warnings, vec![ImportWarning::MissingSecondarySections { part: "Verse.2".to_string(), expected: 2, found: 1 }]);
+
+        // Without a #translations tag the sections keep their placeholder languages
+        let untagged = import_song("One\n---\nEins\n---\nUn").unwrap();
+        let contents = untagged.get_part_by_index(0).unwrap().borrow().contents.clone();
+        assert_eq!(contents[1], lyrics(SECONDARY_LANGUAGE, "Eins\n"));
+        assert_eq!(contents[2], lyrics("secondary2", "Un\n"));
+
+        let testfile = std::fs::read_to_string("testfiles/Trilingual Song.song").unwrap();
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, max_lines: Some(4), ..Default::default() };
+        let slides: Vec<MultiLanguageMainContentSlide> = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string())
+            .into_iter()
+            .filter_map(|slide| match slide.slide_content {
+                SlideContent::MultiLanguageMainContent(content) => Some(content),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].main_text_list, vec![
+            "\nPeace be with you,\npeace in every land.",
+            "\nFriede sei mit dir,\nFriede in jedem Land.",
+            "\nLa paix soit avec toi,\nla paix en tout pays.",
+        ]);
+        assert_eq!(slides[0].spoiler_text_vector[0], "\nJoy be with you,\njoy in every heart.");
+        assert_eq!(slides[1].main_text_list[2], "");
+        assert!(slides[1].spoiler_text_vector.is_empty());
+    }
+
+    #[test]
+    fn test_secondary_block_role() {
+        let song: Song = import_song_from_file("testfiles/Bilingual Song.song").unwrap();
+        let second_verse = song.get_part_by_id("Verse.2").unwrap().borrow().contents.clone();
+        assert_eq!(second_verse[0].voice_type, SongPartContentType::Lyrics { language: LyricLanguage::Specific("en".to_string()) });
+        assert_eq!(second_verse[1], SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) },
+            content: "Der Abend ist da,\nder Tag ist vollbracht.\n".to_string(),
+        });
+
+        let testfile = std::fs::read_to_string("testfiles/Bilingual Song.song").unwrap();
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+
+        // As spoiler, the secondary section is shown below the main text
+        let slides = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string());
+        assert_eq!(slides.len(), 2);
+        let SlideContent::SingleLanguageMainContent(content) = &slides[0].slide_content else {
+            panic!("The slide should be a single language slide");
+        };
+        assert_eq!(content.clone().spoiler_text().unwrap(), "\nDer Morgen ist da,\ndie Nacht ist vorbei.");
+
+        // As translation, both languages are the main text and the next block is the spoiler
+        let slide_settings = SlideSettings { secondary_block_role: SecondaryBlockRole::Translation, ..slide_settings };
+        let slides: Vec<MultiLanguageMainContentSlide> = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string())
+            .into_iter()
+            .filter_map(|slide| match slide.slide_content {
+                SlideContent::MultiLanguageMainContent(content) => Some(content),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].main_text_list, vec!["\nMorning has come,\nthe night is gone.", "\nDer Morgen ist da,\ndie Nacht ist vorbei."]);
+        assert_eq!(slides[0].spoiler_text_vector, vec!["\nEvening has come,\nthe day is done.", "\nDer Abend ist da,\nder Tag ist vollbracht."]);
+        assert!(slides[1].spoiler_text_vector.is_empty());
+
+        // Songs without secondary sections keep single language slides
+        let slides = slides_from_classic_song("First verse\n\nSecond verse", &slide_settings, "Test".to_string());
+        assert!(slides.iter().all(|slide| !matches!(slide.slide_content, SlideContent::MultiLanguageMainContent(_))));
+    }
+
+    #[test]
+    fn test_verse_numbers() {
+        let content = "#title: Numbered\n\n\
+            2. Second stanza\nfirst line\n\n\
+            Chorus line\nsecond chorus line\n\n\
+            4) Fourth stanza\n\n\
+            Chorus line\nsecond chorus line\n\n\
+            3. Third stanza\n\n\
+            Unnumbered stanza";
+        let options = ImportOptions { recognize_verse_numbers: true, ..Default::default() };
+        let report = import_song_with_options(content, &options).unwrap();
+        let song = report.song;
+
+        let verse = |id: &str| song.get_part_by_id(id).map(|part| part.borrow().contents[0].content.clone());
+        assert_eq!(verse("Verse.2").unwrap(), "Second stanza\nfirst line\n");
+        assert_eq!(verse("Verse.4").unwrap(), "Fourth stanza\n");
+        assert_eq!(verse("Verse.3").unwrap(), "Third stanza\n");
+        // The promoted chorus does not shift the printed numbers, unnumbered verses take the free numbers
+        assert_eq!(verse("Chorus.1").unwrap(), "Chorus line\nsecond chorus line\n");
+        assert_eq!(verse("Verse.1").unwrap(), "Unnumbered stanza\n");
+        assert!(report.warnings.is_empty());
+
+        // Without the option the numbers stay in the lyrics
+        let song = import_song(content).unwrap();
+        assert_eq!(song.get_part_by_index(0).unwrap().borrow().contents[0].content, "2. Second stanza\nfirst line\n");
+
+        // Two stanzas with the same number are reported
+        let report = import_song_with_options("1. First\n\n1. Again", &options).unwrap();
+        assert_eq!(report.warnings, vec![ImportWarning::DuplicatePartNumber { part: "Verse.1".to_string(), line: 3 }]);
+    }
+
+    #[test]
+    fn test_tag_and_stanza_warnings() {
+        let report = import_song_with_options("#title: Test\n#author:\n#year 1779\n\nLine", &ImportOptions::default()).unwrap();
+        assert_eq!(report.song.get_tag("author"), None);
+        assert_eq!(report.warnings, vec![
+            ImportWarning::EmptyTag { key: "author".to_string(), line: 2 },
+            ImportWarning::MalformedTag { text: "#year 1779".to_string(), line: 3 },
+        ]);
+
+        // A repetition which only differs in punctuation is the same chorus, but it is reported
+        let content = "First verse\nline two\n\nThe chorus,\nline two!\n\nSecond verse\nline two\n\nThe chorus\nline two";
+        let report = import_song_with_options(content, &ImportOptions::default()).unwrap();
+        assert_eq!(report.song.get_part_count(SongPartType::Chorus), 1);
+        assert_eq!(report.warnings, vec![ImportWarning::SimilarStanza { part: "Chorus.1".to_string(), line: 10, repetition: true }]);
+
+        // A nearly equal stanza which is no repetition is reported as well
+        let content = "The chorus of the song\nline two\n\nThe chorus of this song\nline two";
+        let report = import_song_with_options(content, &ImportOptions::default()).unwrap();
+        assert_eq!(report.song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(report.warnings, vec![ImportWarning::SimilarStanza { part: "Verse.1".to_string(), line: 4, repetition: false }]);
+
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, strip_verse_numbers: true, ..Default::default() };
+        let slides = slides_from_classic_song("1. First\nline\n\n2) Second", &slide_settings, "Backup Title".to_string());
+        let main_texts: Vec<String> = slides
+            .iter()
+            .filter_map(|slide| match &slide.slide_content {
+                SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(main_texts, vec!["\nFirst\nline", "\nSecond"]);
+    }
+
+    #[test]
+    fn test_title_slide_spoiler() {
+        let content = "#title: Amazing Grace\n\nAmazing grace, how sweet the sound\nThat saved a wretch like me\nI once was lost";
+        let title_spoiler = |slide_settings: &SlideSettings| match &slides_from_classic_song(content, slide_settings, "Backup Title".to_string())[0].slide_content {
+            SlideContent::Title(title_slide) => title_slide.spoiler_text.clone(),
+            _ => panic!("The first slide should be a title slide"),
+        };
+
+        assert_eq!(title_spoiler(&SlideSettings::default()), None);
+        let slide_settings = SlideSettings { title_slide_spoiler: true, ..Default::default() };
+        assert_eq!(
+            title_spoiler(&slide_settings),
+            Some("Amazing grace, how sweet the sound\nThat saved a wretch like me\nI once was lost".to_string())
+        );
+        let slide_settings = SlideSettings { title_slide_spoiler: true, spoiler_max_lines: Some(1), ..Default::default() };
+        assert_eq!(title_spoiler(&slide_settings), Some("Amazing grace, how sweet the sound".to_string()));
+    }
+
+    #[test]
+    fn test_repeat_markers() {
+        let content = "#title: Repeats\n\n||: Glory to God :||\nin the highest (2x)\n\nAmen\n(3x)";
+        // The markers are kept in the song for the musicians
+        let song = import_song(content).unwrap();
+        assert_eq!(song.get_part_by_index(0).unwrap().borrow().contents[0].content, "||: Glory to God :||\nin the highest (2x)\n");
+
+        let main_texts = |repeat_markers: RepeatMarkers| -> Vec<String> {
+            let slide_settings = SlideSettings { repeat_markers, title_slide: false, empty_last_slide: false, ..Default::default() };
+            slides_from_classic_song(content, &slide_settings, "Test".to_string())
+                .iter()
+                .filter_map(|slide| match &slide.slide_content {
+                    SlideContent::SingleLanguageMainContent(content) => Some(content.clone().main_text()),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        assert_eq!(main_texts(RepeatMarkers::Keep), vec!["\n||: Glory to God :||\nin the highest (2x)", "\nAmen\n(3x)"]);
+        assert_eq!(main_texts(RepeatMarkers::Strip), vec!["\nGlory to God\nin the highest", "\nAmen"]);
+        assert_eq!(main_texts(RepeatMarkers::Expand), vec![
+            "\nGlory to God\nGlory to God\nin the highest\nin the highest",
+            "\nAmen",
+            "\nAmen",
+            "\nAmen",
+        ]);
+    }
+
+    #[test]
+    fn test_two_promoted_choruses() {
+        let content = "First verse\n\nFirst chorus\nline two\n\nSecond verse\n\nSecond chorus\nline two\n\nThird verse\n\nFirst chorus\nline two\n\nSecond chorus\nline two";
+        let song = import_song(content).unwrap();
+
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 2);
+        assert_eq!(song.get_part_count(SongPartType::Verse), 3);
+        let chorus = |id: &str| song.get_part_by_id(id).unwrap().borrow().contents[0].content.clone();
+        assert_eq!(chorus("Chorus.1"), "First chorus\nline two\n");
+        assert_eq!(chorus("Chorus.2"), "Second chorus\nline two\n");
+        assert!(song.get_part_by_id("Chorus.2").unwrap().borrow().id.get_checked_unique());
+        assert_eq!(song.get_part_by_id("Verse.3").unwrap().borrow().contents[0].content, "Third verse\n");
+    }
+
+    #[test]
+    fn test_minimum_repetition_size() {
+        let content = "#title: Responsive Song\n\n\
+            The Lord be with you\n\n\
+            Hallelujah\n\n\
+            Lift up your hearts\n\n\
+            Hallelujah";
+
+        let song = import_song(content).unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Verse), 4);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 0);
+
+        let options = ImportOptions { min_repetition_lines: 1, ..Default::default() };
+        let song = import_song_with_options(content, &options).unwrap().song;
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+
+        // The character count can be used as well
+        let options = ImportOptions { min_repetition_lines: 1, min_repetition_chars: 20, ..Default::default() };
+        assert_eq!(import_song_with_options(content, &options).unwrap().song.get_part_count(SongPartType::Chorus), 0);
+
+        // A block without any text (like a stray --- delimiter) is no part
+        let song = import_song("First verse\n\n---\n\nSecond verse").unwrap();
+        assert_eq!(song.get_total_part_count(), 2);
+    }
+
+    #[test]
+    fn test_title_from_file_content() {
+        // A stanza which quotes the header of the song does not set the title
+        let content = "#title: Amazing Grace\r\n\r\nThe song begins with\r\n#title: Something else\r\n\r\n#title: Trailing Title";
+        assert_eq!(get_title_from_file_content(content), Some("Amazing Grace".to_string()));
+        assert_eq!(get_metadata_from_file_content(content).len(), 1);
+
+        let song = import_song("Quoted header\n#title: Something else\n\n#title: Amazing Grace \r\n").unwrap();
+        assert_eq!(song.title, "Amazing Grace");
+        assert!(song.get_part_by_index(0).unwrap().borrow().contents[0].content.contains("#title: Something else"));
+
+        // Commented tags and files without metadata
+        assert_eq!(get_title_from_file_content("// #title: Old\n#title: New"), Some("New".to_string()));
+        assert_eq!(get_title_from_file_content("Only lyrics\n#title: Not a title"), None);
+
+        // Without ; as comment prefix, the ; line makes the first block a lyrics block
+        let content = "; #title: Old\n#title: Commented\n\n#title: New";
+        let options = ImportOptions { comment_prefixes: vec!["//".to_string()], ..Default::default() };
+        assert_eq!(get_title_from_file_content(content), Some("Commented".to_string()));
+        assert_eq!(get_title_from_file_content_with_options(content, &options), Some("New".to_string()));
+        assert_eq!(get_metadata_from_file_content_with_options(content, &options).get("title").unwrap(), "New");
+    }
+
+    #[test]
+    fn test_slide_snapshots() {
+        let update_fixtures = std::env::var_os("UPDATE_FIXTURES").is_some();
+        let files = [
+            "Amazing Grace",
+            "O What A Savior That He Died For Me",
+            "What a friend we have in Jesus",
+            "Bilingual Chorus",
+            "Single Stanza",
+        ];
+
+        for file in files {
+            let content = std::fs::read_to_string(format!("testfiles/{}.song", file)).unwrap();
+            let mut actual = serde_json::Map::new();
+            for title_slide in [true, false] {
+                for (meta_name, show_meta_information) in [
+                    ("none", ShowMetaInformation::None),
+                    ("first", ShowMetaInformation::FirstSlide),
+                    ("last", ShowMetaInformation::LastSlide),
+                    ("first_and_last", ShowMetaInformation::FirstSlideAndLastSlide),
+                ] {
+                    for max_lines in [None, Some(2), Some(6)] {
+                        for show_spoiler in [true, false] {
+                            let key = format!(
+                                "title_slide={} meta={} max_lines={} spoiler={}",
+                                title_slide,
+                                meta_name,
+                                max_lines.map_or("none".to_string(), |lines: usize| lines.to_string()),
+                                show_spoiler,
+                            );
+                            let settings = SlideSettings {
+                                title_slide,
+                                show_spoiler,
+                                show_meta_information,
+                                meta_syntax: "{{author}}".to_string(),
+                                max_lines,
+                                ..Default::default()
+                            };
+                            let slides = slides_from_classic_song(&content, &settings, file.to_string());
+                            actual.insert(key, serde_json::to_value(slides).unwrap());
+                        }
+                    }
+                }
+            }
+            let actual = serde_json::Value::Object(actual);
+
+            let expected_path = format!("testfiles/slides/{}.json", file);
+            if update_fixtures {
+                std::fs::create_dir_all("testfiles/slides").unwrap();
+                std::fs::write(&expected_path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+                continue;
+            }
+            let expected: serde_json::Value = serde_json::from_str(
+                &std::fs::read_to_string(&expected_path)
+                    .unwrap_or_else(|_| panic!("{} is missing, run the tests with UPDATE_FIXTURES=1 to create it", expected_path))
+            ).unwrap();
+            for (key, value) in actual.as_object().unwrap() {
+                assert_eq!(Some(value), expected.get(key), "The slides of {} with {} differ from the snapshot", file, key);
+            }
+            assert_eq!(actual.as_object().unwrap().len(), expected.as_object().unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let testfile = std::fs::read_to_string("testfiles/O What A Savior That He Died For Me.song").unwrap();
+        let slide_settings = SlideSettings { max_lines: Some(2), ..Default::default() };
+        let expected_song = import_song(&testfile).unwrap();
+        let expected_slides = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string());
+
+        // Old Mac files (`\r` only), Windows files with a byte order mark and files with mixed line endings
+        for fixture in ["testfiles/line_endings/cr.song", "testfiles/line_endings/crlf_bom.song", "testfiles/line_endings/mixed.song"] {
+            let content = std::fs::read_to_string(fixture).unwrap();
+            assert_eq!(import_song(&content).unwrap(), expected_song, "{}", fixture);
+            assert_eq!(slides_from_classic_song(&content, &slide_settings, "Backup Title".to_string()), expected_slides, "{}", fixture);
+            assert_eq!(get_title_from_file_content(&content), Some(expected_song.title.clone()));
+        }
+
+        // The byte order mark doesn't turn the tags into a verse and no `\r` ends up in the slides
+        let content = std::fs::read_to_string("testfiles/line_endings/crlf_bom.song").unwrap();
+        let song = import_song(&content).unwrap();
+        assert_eq!(song.get_tag("author").unwrap(), "James McGranahan");
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+        let slides = serde_json::to_string(&slides_from_classic_song(&content, &slide_settings, "Backup Title".to_string())).unwrap();
+        assert!(!slides.contains("\\r"));
+
+        // Trailing whitespace only differs in the content if it is not stripped
+        let padded: String = testfile.lines().map(|line| format!("{} \t\r\n", line)).collect();
+        let options = ImportOptions { strip_trailing_whitespace: true, ..Default::default() };
+        assert_eq!(import_song_with_options(&padded, &options).unwrap().song, expected_song);
+    }
+
+    #[test]
+    fn test_import_snapshots() {
+        let update_fixtures = std::env::var_os("UPDATE_FIXTURES").is_some();
+        let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir("testfiles")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "song"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let content = crate::importer::read_song_file(path.to_str().unwrap()).unwrap();
+            let report = import_song_with_options(&content, &ImportOptions::default()).unwrap();
+            let actual = serde_json::json!({
+                "song": report.song,
+                "warnings": report.warnings.iter().map(|warning| warning.to_string()).collect::<Vec<String>>(),
+            });
+
+            let expected_path = std::path::Path::new("testfiles/import").join(path.with_extension("json").file_name().unwrap());
+            if update_fixtures {
+                std::fs::create_dir_all("testfiles/import").unwrap();
+                std::fs::write(&expected_path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+                continue;
+            }
+            let expected: serde_json::Value = serde_json::from_str(
+                &std::fs::read_to_string(&expected_path)
+                    .unwrap_or_else(|_| panic!("{} is missing, run the tests with UPDATE_FIXTURES=1 to create it", expected_path.display()))
+            ).unwrap();
+            assert_eq!(actual, expected, "The import of {} differs from the snapshot", path.display());
+        }
+    }
+
+    #[test]
+    fn test_scan_blocks_consistency() {
+        // A stanza without primary text doesn't pass its secondary section on to the next stanza
+        let content = "#title: Test\n#author: A\n---\n#author: B\n\n---\nOnly a translation\n\nFirst line\nSecond line";
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        let slides = slides_from_classic_song(content, &slide_settings, "Backup Title".to_string());
+        assert_eq!(slides.len(), 1);
+        assert!(matches!(&slides[0].slide_content, SlideContent::SingleLanguageMainContent(_)));
+
+        // The tags after a --- line of a metadata block are used by the importer and the slides alike
+        let report = import_song_with_options(content, &ImportOptions::default()).unwrap();
+        assert_eq!(report.song.get_tag("author").unwrap(), "B");
+        let slide_settings = SlideSettings { meta_syntax: "{{author}}".to_string(), ..slide_settings };
+        let slides = slides_from_classic_song(content, &slide_settings, "Backup Title".to_string());
+        assert_eq!(serde_json::to_value(&slides[0]).unwrap()["slide_content"]["SingleLanguageMainContent"]["meta_text"], "B");
+
+        // Line numbers of tags count comment lines as well
+        let report = import_song_with_options("#title: A\n// A comment\n#title: B", &ImportOptions::default()).unwrap();
+        assert!(matches!(report.warnings[0], ImportWarning::DuplicateTag { line: 3, .. }));
+    }
+
+    #[test]
+    fn test_stanza_separator() {
+        let content = std::fs::read_to_string("testfiles/separators/double_blank_lines.song").unwrap();
+        let first_verse = |song: &Song| song.get_part_by_index(0).unwrap().borrow().contents[0].content.clone();
+
+        // By default, every blank line separates two stanzas
+        let song = import_song_with_options(&content, &ImportOptions::default()).unwrap().song;
+        assert_eq!(song.get_part_count(SongPartType::Verse), 4);
+        assert_eq!(first_verse(&song), "First verse, first line\nFirst verse, second line\n");
+
+        // Single blank lines are kept within a stanza if stanzas are separated by multiple blank lines
+        let options = ImportOptions { stanza_separator: StanzaSeparator::MultipleBlankLines, ..Default::default() };
+        let song = import_song_with_options(&content, &options).unwrap().song;
+        assert_eq!(song.title, "Double Blank Lines");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 3);
+        assert_eq!(first_verse(&song), "First verse, first line\nFirst verse, second line\n\nFirst verse, after a single blank line\n");
     }
 
+    #[test]
+    fn test_song_separator() {
+        let options = ImportOptions { song_separator: Some(SongSeparator::Line("***".to_string())), ..Default::default() };
+        let content = std::fs::read_to_string("testfiles/separators/two_songs.song").unwrap();
+        let reports = import_songs_with_options(&content, &options).unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].song.title, "First Song");
+        assert_eq!(reports[0].song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(reports[1].song.title, "Second Song");
+        assert_eq!(reports[1].song.get_tag("author"), None);
+        assert_eq!(reports[1].song.get_part_count(SongPartType::Verse), 1);
+
+        // The single song import only takes the first song
+        let report = import_song_with_options(&content, &options).unwrap();
+        assert_eq!(report.song.title, "First Song");
+        assert_eq!(report.song.get_part_count(SongPartType::Verse), 2);
+
+        // Form feeds separate songs, empty songs are skipped
+        let options = ImportOptions { song_separator: Some(SongSeparator::FormFeed), ..Default::default() };
+        let reports = import_songs_with_options("\u{000C}#title: A\n\nLa la\n\u{000C}\n#title: B\n\nLu lu\n\u{000C}\n", &options).unwrap();
+        let titles: Vec<&str> = reports.iter().map(|report| report.song.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B"]);
+
+        // Without a separator, the whole content is one song
+        let reports = import_songs_with_options(&content, &ImportOptions::default()).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(import_songs_with_options("\u{000C}\n", &ImportOptions { song_separator: Some(SongSeparator::FormFeed), ..Default::default() }).is_err());
+    }
+
+    #[test]
+    fn test_slide_sources() {
+        let testfile = std::fs::read_to_string("testfiles/Amazing Grace.song").unwrap();
+        let slide_settings = SlideSettings { max_lines: Some(4), title_slide: true, ..Default::default() };
+        let ranges: Vec<Option<(usize, usize)>> = slides_from_classic_song(&testfile, &slide_settings, "Backup Title".to_string())
+            .iter()
+            .map(|slide| slide.source().and_then(|source| source.file_line_range))
+            .collect();
+        // The wrapped stanzas only point to the lines which are shown, title and empty slides have no source
+        assert_eq!(ranges, vec![
+            None,
+            Some((4, 5)), Some((6, 9)),
+            Some((11, 12)), Some((13, 16)),
+            Some((18, 19)), Some((20, 23)),
+            None,
+        ]);
+
+        // Comments and secondary sections are taken into account
+        let content = "#title: Test\n\nFirst line\n// A comment\nSecond line\n---\nErste Zeile\nZweite Zeile";
+        let slides = slides_from_classic_song(content, &SlideSettings { title_slide: false, ..Default::default() }, "Test".to_string());
+        assert_eq!(slides[0].source().unwrap().file_line_range, Some((3, 8)));
+        let slide_settings = SlideSettings { title_slide: false, max_lines: Some(2), ..Default::default() };
+        let slides = slides_from_classic_song(content, &slide_settings, "Test".to_string());
+        assert_eq!(slides[0].source().unwrap().file_line_range, None);
+        assert_eq!(slides[1].source().unwrap().file_line_range, Some((3, 8)));
+    }
+
+    #[test]
+    /// The slides know the parts of the imported song which they show, also if they follow the part order
+    fn test_slide_part_ids() {
+        let content = "#title: Test\n#order: V1, C, V2, C\n\nFirst verse\n\n(Chorus)\nThe chorus\n\nSecond verse";
+        let slide_settings = || SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        let part_ids = |slide_settings: &SlideSettings| -> Vec<Option<String>> {
+            slides_from_classic_song(content, slide_settings, "Test".to_string())
+                .iter()
+                .map(|slide| slide.source().and_then(|source| source.part_id.clone()))
+                .collect()
+        };
+        let expected = |ids: &[&str]| ids.iter().map(|id| Some(id.to_string())).collect::<Vec<Option<String>>>();
+
+        assert_eq!(part_ids(&slide_settings()), expected(&["Verse.1", "Chorus.1", "Verse.2", "Chorus.1"]));
+        let file_order = SlideSettings { follow_part_order: false, ..slide_settings() };
+        assert_eq!(part_ids(&file_order), expected(&["Verse.1", "Chorus.1", "Verse.2"]));
+        let repeated_chorus = SlideSettings { follow_part_order: false, repeat_chorus: true, ..slide_settings() };
+        assert_eq!(part_ids(&repeated_chorus), expected(&["Verse.1", "Chorus.1", "Verse.2", "Chorus.1"]));
+    }
 }
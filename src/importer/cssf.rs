@@ -0,0 +1,395 @@
+//! This module contains functions to import songs from the Cantara structured song format (cssf).
+//! A cssf file consists of blocks which are separated by empty lines.
+//! Blocks which only contain tags (`#key: value`) are metadata blocks.
+//! Every other block has to start with a categorization line like `#verse.1` or `#refrain.1`,
+//! the following lines are the lyrics of that part. Another block with the categorization line of an existing part
+//! (e.g. a second `#refrain.1`) repeats that part.
+//! The presentation order can be given in the metadata block with the `#order:` tag, which lists the IDs of the parts
+//! (e.g. `#order: verse.1, chorus.1, verse.2, chorus.1`). Without it, the parts are presented in the order of the file.
+//! After the lyrics, a part can contain sub-blocks with further contents, which start with a marker line:
+//! `--chords` starts the chords of the part, `--lead`, `--soprano`, `--alto`, `--tenor` and `--bass` start the notes of a voice
+//! (e.g. `c4 d4 e4`). Other markers are an error.
+//!
+//! # Example
+//! ```text
+//! #title: Amazing Grace
+//! #author: John Newton
+//!
+//! #verse.1
+//! Amazing grace, how sweet the sound
+//! that saved a wretch like me.
+//! --chords
+//! G G7 C G
+//! G Em D
+//! ```
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+extern crate regex;
+use regex::Regex;
+
+use crate::importer::{normalize_input, ImportOptions};
+use crate::importer::errors::{CantaraImportNoContentError, CantaraImportParsingError, ParsingErrorType};
+use crate::song::{
+    LyricLanguage, PartOrder, PartOrderName, PartOrderRule, Song, SongPart, SongPartContent, SongPartContentType, SongPartId,
+    SongPartType,
+};
+
+fn tag_regex() -> &'static Regex {
+    static TAG_REGEX: OnceLock<Regex> = OnceLock::new();
+    TAG_REGEX.get_or_init(|| Regex::new(r"^\s*#(\w+):\s*(.*?)\s*$").unwrap())
+}
+
+fn categorization_regex() -> &'static Regex {
+    static CATEGORIZATION_REGEX: OnceLock<Regex> = OnceLock::new();
+    CATEGORIZATION_REGEX.get_or_init(|| Regex::new(r"^\s*#([a-zA-Z]+)\.(\d+)\s*$").unwrap())
+}
+
+fn sub_block_regex() -> &'static Regex {
+    static SUB_BLOCK_REGEX: OnceLock<Regex> = OnceLock::new();
+    SUB_BLOCK_REGEX.get_or_init(|| Regex::new(r"^\s*--(\w+)\s*$").unwrap())
+}
+
+/// Returns the content type of a sub-block marker like `--chords` or `--alto`
+/// # Returns
+/// None if the line is no sub-block marker, otherwise the content type or the `ParsingErrorType` of an unknown marker
+fn sub_block_content_type(line: &str) -> Option<Result<SongPartContentType, ParsingErrorType>> {
+    let captures = sub_block_regex().captures(line)?;
+    let marker: String = captures[1].to_lowercase();
+    Some(match marker.as_str() {
+        "chords" => Ok(SongPartContentType::Chords),
+        "lead" => Ok(SongPartContentType::LeadVoice),
+        "soprano" => Ok(SongPartContentType::SupranoVoice),
+        "alto" => Ok(SongPartContentType::AltoVoice),
+        "tenor" => Ok(SongPartContentType::TenorVoice),
+        "bass" => Ok(SongPartContentType::BassVoice),
+        _ => Err(ParsingErrorType::UnknownSubBlock { marker }),
+    })
+}
+
+/// Returns the ID of a part from the name and number of a categorization line (e.g. `stanza` and `2` become `verse.2`).
+/// Alternative names like `stanza` are mapped to the part type, unknown names are kept as they are.
+fn part_id(name: &str, number: &str) -> String {
+    let part_name: String = match SongPartType::from_label(name) {
+        Some(part_type) => part_type.to_string().to_lowercase(),
+        None => name.to_lowercase(),
+    };
+    format!("{}.{}", part_name, number)
+}
+
+/// Returns whether a line is a categorization line like `#verse.1`
+pub(crate) fn is_categorization_line(line: &str) -> bool {
+    categorization_regex().is_match(line)
+}
+
+/// A block of the file together with the line number (starting with 1) of its first line
+struct Block<'a> {
+    first_line: usize,
+    lines: Vec<&'a str>,
+}
+
+fn split_blocks(content: &str) -> Vec<Block<'_>> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut current: Option<Block> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            if let Some(block) = current.take() {
+                blocks.push(block);
+            }
+            continue;
+        }
+        current
+            .get_or_insert(Block { first_line: index + 1, lines: Vec::new() })
+            .lines
+            .push(line.trim_end());
+    }
+    if let Some(block) = current {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Parses a block of the file and adds it to the song: the tags of a metadata block or a new part.
+/// A block which refers to an existing part (e.g. a second `#refrain.1`) repeats that part, its lines are ignored.
+/// The `#order:` tag is not added to the song, but stored in `presentation_order` with its line number.
+/// # Returns
+/// The part which occurs at the position of the block or None for a metadata block
+fn parse_block(
+    block: &Block,
+    song: &mut Song,
+    options: &ImportOptions,
+    presentation_order: &mut Option<(usize, String)>,
+) -> Result<Option<Rc<RefCell<SongPart>>>, CantaraImportParsingError> {
+    // Blocks only consisting of tags are metadata blocks
+    if block.lines.iter().all(|line| tag_regex().is_match(line)) {
+        for (index, line) in block.lines.iter().enumerate() {
+            let captures = tag_regex().captures(line).unwrap();
+            let key: String = match &options.tag_aliases {
+                Some(aliases) => aliases.resolve(&captures[1]),
+                None => captures[1].to_lowercase(),
+            };
+            let value = &captures[2];
+            if key == "order" {
+                *presentation_order = Some((block.first_line + index, value.to_string()));
+                continue;
+            }
+            if key == "title" {
+                song.title = value.to_string();
+            }
+            song.add_tag(&key, value);
+        }
+        return Ok(None);
+    }
+
+    let captures = match categorization_regex().captures(block.lines[0]) {
+        Some(captures) => captures,
+        None => return Err(CantaraImportParsingError {
+            line: block.first_line,
+            error_type: ParsingErrorType::BlockNeedsToStartWithCategorization,
+        }),
+    };
+    let id_string = part_id(&captures[1], &captures[2]);
+    if let Some(repeated_part) = song.get_part_by_id(&id_string) {
+        return Ok(Some(repeated_part));
+    }
+    // The regex guarantees that both the id and the number can be parsed
+    let id = SongPartId::parse(&id_string).unwrap();
+    let number: u32 = captures[2].parse().unwrap_or(1);
+
+    // The lyrics and every sub-block with their lines
+    let mut sections: Vec<(SongPartContentType, Vec<&str>)> =
+        vec![(SongPartContentType::Lyrics { language: LyricLanguage::Default }, Vec::new())];
+    for (index, line) in block.lines.iter().enumerate().skip(1) {
+        let content_type = match sub_block_content_type(line) {
+            Some(Ok(_)) if index == 1 => Err(ParsingErrorType::SubBlockBeforeLyrics),
+            Some(content_type) => content_type,
+            None => {
+                sections.last_mut().unwrap().1.push(line);
+                continue;
+            },
+        };
+        let content_type = content_type.map_err(|error_type| CantaraImportParsingError {
+            line: block.first_line + index,
+            error_type,
+        })?;
+        sections.push((content_type, Vec::new()));
+    }
+
+    let mut part = SongPart::new(id, number);
+    for (voice_type, lines) in sections {
+        if !lines.is_empty() {
+            part.add_content(SongPartContent { voice_type, content: lines.join("\n") });
+        }
+    }
+    song.add_part(part);
+    Ok(song.get_part_by_id(&id_string))
+}
+
+/// Resolves the part IDs of an `#order:` tag (separated by commas) to the parts of the song
+/// # Returns
+/// The parts in the given order or a `ParsingErrorType::UnknownPartInOrder` for the first ID which is no part of the song
+fn resolve_presentation_order(song: &Song, presentation_order: &str) -> Result<Vec<Rc<RefCell<SongPart>>>, ParsingErrorType> {
+    let order_entry_regex = {
+        static ORDER_ENTRY_REGEX: OnceLock<Regex> = OnceLock::new();
+        ORDER_ENTRY_REGEX.get_or_init(|| Regex::new(r"^#?([a-zA-Z]+)\.(\d+)$").unwrap())
+    };
+    presentation_order
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            order_entry_regex
+                .captures(entry)
+                .and_then(|captures| song.get_part_by_id(&part_id(&captures[1], &captures[2])))
+                .ok_or_else(|| ParsingErrorType::UnknownPartInOrder { id: entry.to_string() })
+        })
+        .collect()
+}
+
+/// Imports a song from a str which contains the song in the cssf format.
+/// # Returns
+/// A Result with the Song or an error. A `CantaraImportParsingError` is returned
+/// if a content block does not start with a categorization line.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::cssf::import_song;
+/// use cantara_songlib::song::SongPartType;
+/// let song = import_song("#title: Test\n\n#verse.1\nFirst verse\n\n#verse.2\nSecond verse").unwrap();
+/// assert_eq!(song.title, "Test");
+/// assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+/// ```
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    import_song_with_options(content, &ImportOptions::default())
+}
+
+/// Imports a song from a str which contains the song in the cssf format with the given import options
+/// (the tag names are resolved with `ImportOptions::tag_aliases`).
+/// # Returns
+/// A Result with the Song or an error (see `import_song`)
+/// # Example
+/// ```
+/// use cantara_songlib::importer::{ImportOptions, TagAliasMap};
+/// use cantara_songlib::importer::cssf::import_song_with_options;
+/// let options = ImportOptions { tag_aliases: Some(TagAliasMap::default()), ..Default::default() };
+/// let song = import_song_with_options("#titel: Test\n#autor: Somebody\n\n#verse.1\nFirst verse", &options).unwrap();
+/// assert_eq!(song.title, "Test");
+/// assert_eq!(song.get_tag("author").unwrap(), "Somebody");
+/// ```
+pub fn import_song_with_options(content: &str, options: &ImportOptions) -> Result<Song, Box<dyn Error>> {
+    let content = normalize_input(content, false);
+    if content.trim().is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+
+    let mut song = Song::new("");
+    // The parts in the order of the blocks (repeated parts occur several times)
+    let mut part_order: Vec<Rc<RefCell<SongPart>>> = Vec::new();
+    let mut presentation_order: Option<(usize, String)> = None;
+    for block in split_blocks(&content) {
+        part_order.extend(parse_block(&block, &mut song, options, &mut presentation_order)?);
+    }
+
+    if let Some((line, presentation_order)) = presentation_order {
+        let part_order: Vec<Rc<RefCell<SongPart>>> = resolve_presentation_order(&song, &presentation_order)
+            .map_err(|error_type| CantaraImportParsingError { line, error_type })?;
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(part_order)));
+    }
+    // The order of the file is only kept if it repeats parts itself, otherwise the order is guessed from the part types
+    else if part_order.len() > song.get_total_part_count() {
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(part_order)));
+    }
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn test_import_cssf_song() {
+        let content = "#title: Test Song\n#author: Somebody\n\n#verse.1\nLine one\nLine two\n\n#refrain.1\nRefrain line";
+        let song = import_song(content).unwrap();
+        assert_eq!(song.title, "Test Song");
+        assert_eq!(song.get_tag("author").unwrap(), "Somebody");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 1);
+        assert_eq!(song.get_part_count(SongPartType::Refrain), 1);
+        let verse = song.get_part_by_id("verse.1").unwrap();
+        assert_eq!(verse.borrow().contents[0].content, "Line one\nLine two");
+    }
+
+    #[test]
+    fn test_stanzas_and_repeated_refrain() {
+        let song = import_song(&std::fs::read_to_string("testfiles/cssf/stanzas.cssf").unwrap()).unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Refrain), 1);
+        assert_eq!(song.get_total_part_count(), 3);
+        assert_eq!(song.get_part_by_id("verse.2").unwrap().borrow().contents[0].content, "Stille Nacht, heilige Nacht!\nHirten erst kundgemacht");
+
+        // The repeated refrain is the same part
+        let order: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(order, vec!["verse.1", "refrain.1", "verse.2", "refrain.1"]);
+    }
+
+    #[test]
+    fn test_chords() {
+        let song = import_song(&std::fs::read_to_string("testfiles/cssf/chords.cssf").unwrap()).unwrap();
+        let verse = song.get_part_by_id("verse.1").unwrap();
+        assert_eq!(verse.borrow().get_content(SongPartContentType::Chords).unwrap().content, "G G7 C G\nG Em D");
+        assert_eq!(verse.borrow().contents[0].content, "Amazing grace, how sweet the sound\nthat saved a wretch like me.");
+        assert_eq!(song.get_content_types(), vec![
+            SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            SongPartContentType::Chords,
+        ]);
+
+        // The chords need to come after the lyrics
+        let error = import_song("#title: Test\n\n#verse.1\n--chords\nG C\nFirst line").unwrap_err();
+        let parsing_error = error.downcast_ref::<CantaraImportParsingError>().unwrap();
+        assert_eq!(parsing_error.line, 4);
+        assert_eq!(parsing_error.error_type, ParsingErrorType::SubBlockBeforeLyrics);
+    }
+
+    #[test]
+    fn test_voices() {
+        let song = import_song(&std::fs::read_to_string("testfiles/cssf/voices.cssf").unwrap()).unwrap();
+        let verse = song.get_part_by_id("verse.1").unwrap();
+        let verse = verse.borrow();
+        assert_eq!(verse.get_content(SongPartContentType::LeadVoice).unwrap().content, "c4 d4 e4");
+        assert_eq!(verse.get_content(SongPartContentType::SupranoVoice).unwrap().content, "e4 f4 g4");
+        assert_eq!(verse.get_content(SongPartContentType::AltoVoice).unwrap().content, "c4 c4 c4");
+        assert_eq!(verse.get_content(SongPartContentType::TenorVoice).unwrap().content, "g3 a3 g3");
+        assert_eq!(verse.get_content(SongPartContentType::BassVoice).unwrap().content, "c3 f3 c3");
+        assert_eq!(verse.contents.len(), 6);
+
+        let error = import_song("#title: Test\n\n#verse.1\nFirst line\n--descant\ng5 a5").unwrap_err();
+        let parsing_error = error.downcast_ref::<CantaraImportParsingError>().unwrap();
+        assert_eq!(parsing_error.line, 5);
+        assert_eq!(parsing_error.error_type, ParsingErrorType::UnknownSubBlock { marker: "descant".to_string() });
+    }
+
+    #[test]
+    fn test_presentation_order() {
+        let song = import_song(&std::fs::read_to_string("testfiles/cssf/order.cssf").unwrap()).unwrap();
+        assert_eq!(song.get_tag("order"), None);
+        assert!(song.get_active_part_order().is_some());
+        let order: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(order, vec!["chorus.1", "verse.1", "chorus.1", "verse.2", "chorus.1"]);
+
+        let error = import_song("#title: Test\n#order: verse.1, chorus.1\n\n#verse.1\nFirst verse").unwrap_err();
+        let parsing_error = error.downcast_ref::<CantaraImportParsingError>().unwrap();
+        assert_eq!(parsing_error.line, 2);
+        assert_eq!(parsing_error.error_type, ParsingErrorType::UnknownPartInOrder { id: "chorus.1".to_string() });
+    }
+
+    #[test]
+    fn test_block_without_categorization() {
+        let content = "#title: Test Song\n\nJust some lyrics";
+        let error = import_song(content).unwrap_err();
+        let parsing_error = error.downcast_ref::<CantaraImportParsingError>().unwrap();
+        assert_eq!(parsing_error.line, 3);
+        assert_eq!(parsing_error.error_type, ParsingErrorType::BlockNeedsToStartWithCategorization);
+    }
+
+    /// Imports every `*.cssf` file in `testfiles/cssf` and compares the result with the `*.json` file of the same name.
+    /// Set the `UPDATE_FIXTURES` environment variable to regenerate the expected files.
+    #[test]
+    fn test_fixtures() {
+        let update_fixtures = std::env::var_os("UPDATE_FIXTURES").is_some();
+        let mut paths: Vec<PathBuf> = std::fs::read_dir("testfiles/cssf")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "cssf"))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+
+        for path in paths {
+            let content = std::fs::read_to_string(&path).unwrap();
+            let actual: Value = match import_song(&content) {
+                Ok(song) => json!({ "song": song }),
+                Err(error) => match error.downcast_ref::<CantaraImportParsingError>() {
+                    Some(parsing_error) => json!({ "error": parsing_error }),
+                    None => json!({ "error": error.to_string() }),
+                },
+            };
+
+            let expected_path = path.with_extension("json");
+            if update_fixtures {
+                std::fs::write(&expected_path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+                continue;
+            }
+            let expected: Value = serde_json::from_str(
+                &std::fs::read_to_string(&expected_path)
+                    .unwrap_or_else(|_| panic!("{} is missing, run the tests with UPDATE_FIXTURES=1 to create it", expected_path.display()))
+            ).unwrap();
+            assert_eq!(actual, expected, "The import of {} differs from the fixture", path.display());
+        }
+    }
+}
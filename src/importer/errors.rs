@@ -1,6 +1,11 @@
+use std::error::Error;
 use std::fmt;
 
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+use crate::song::SongEditError;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CantaraImportNoContentError;
 
 impl fmt::Display for CantaraImportNoContentError {
@@ -15,7 +20,7 @@ impl std::error::Error for CantaraImportNoContentError {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CantaraImportUnknownFileExtensionError {
     pub file_extension: String,
 }
@@ -32,7 +37,8 @@ impl std::error::Error for CantaraImportUnknownFileExtensionError {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
 pub struct CantaraImportUnknownBlockError {
     pub block: String,
 }
@@ -63,4 +69,198 @@ impl std::error::Error for CantaraFileDoesNotExistError {
     fn description(&self) -> &str {
         "There file does not exist"
     }
-}
\ No newline at end of file
+}
+/// The file name of a song is needed (e.g. as title), but the path has no usable file name
+#[derive(Debug, Clone, PartialEq)]
+pub struct CantaraImportInvalidFileNameError {
+    pub file_path: String,
+}
+
+impl fmt::Display for CantaraImportInvalidFileNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The path {} has no valid file name", self.file_path)
+    }
+}
+
+impl std::error::Error for CantaraImportInvalidFileNameError {
+    fn description(&self) -> &str {
+        "Invalid file name"
+    }
+}
+
+/// A service file (e.g. of OpenLP) can't be read, because it has an unexpected structure
+#[derive(Debug, Clone, PartialEq)]
+pub struct CantaraImportInvalidServiceError {
+    pub message: String,
+}
+
+impl fmt::Display for CantaraImportInvalidServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid service file: {}", self.message)
+    }
+}
+
+impl std::error::Error for CantaraImportInvalidServiceError {
+    fn description(&self) -> &str {
+        "Invalid service file"
+    }
+}
+
+/// A serialized song (e.g. JSON) can be read, but its structure is invalid (like duplicate part IDs)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CantaraImportInvalidSongError {
+    pub message: String,
+}
+
+impl fmt::Display for CantaraImportInvalidSongError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid song: {}", self.message)
+    }
+}
+
+impl std::error::Error for CantaraImportInvalidSongError {
+    fn description(&self) -> &str {
+        "Invalid song"
+    }
+}
+
+/// The content of a file can't be decoded with the encoding which has been detected (e.g. a broken UTF-16 file)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CantaraImportEncodingError {
+    pub encoding: String,
+}
+
+impl fmt::Display for CantaraImportEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The file can't be decoded as {}", self.encoding)
+    }
+}
+
+impl std::error::Error for CantaraImportEncodingError {
+    fn description(&self) -> &str {
+        "Invalid encoding"
+    }
+}
+
+/// The kinds of errors which can occur while parsing a structured file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ParsingErrorType {
+    /// A content block does not start with a categorization line like `#verse.1`
+    BlockNeedsToStartWithCategorization,
+    /// A tag has been set more than once (and the duplicate tag policy does not allow that)
+    DuplicateTag { key: String },
+    /// A sub-block like `--chords` comes before the lyrics of a part
+    SubBlockBeforeLyrics,
+    /// A sub-block marker (like `--chords`) is unknown
+    UnknownSubBlock { marker: String },
+    /// The presentation order (`#order:`) refers to a part which does not exist
+    UnknownPartInOrder { id: String },
+}
+
+impl fmt::Display for ParsingErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsingErrorType::BlockNeedsToStartWithCategorization => 
+                write!(f, "the block needs to start with a categorization like #verse.1"),
+            ParsingErrorType::DuplicateTag { key } =>
+                write!(f, "the tag #{} has already been set", key),
+            ParsingErrorType::SubBlockBeforeLyrics =>
+                write!(f, "the lyrics of a part need to come before its sub-blocks like --chords"),
+            ParsingErrorType::UnknownSubBlock { marker } =>
+                write!(f, "the sub-block --{} is unknown", marker),
+            ParsingErrorType::UnknownPartInOrder { id } =>
+                write!(f, "the order refers to the part {}, which does not exist", id),
+        }
+    }
+}
+
+/// An error which occured while parsing a file at a specific line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CantaraImportParsingError {
+    /// The line (starting with 1) where the error occured
+    pub line: usize,
+    pub error_type: ParsingErrorType,
+}
+
+impl fmt::Display for CantaraImportParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Parsing error in line {}: {}", self.line, self.error_type)
+    }
+}
+
+impl std::error::Error for CantaraImportParsingError {
+    fn description(&self) -> &str {
+        "Parsing error"
+    }
+}
+
+/// All errors which are defined by this library.
+/// The functions of the library return a `Box<dyn Error>`, which can be turned into a `CantaraError` with `CantaraError::from_error` to match on it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CantaraError {
+    NoContent(CantaraImportNoContentError),
+    UnknownFileExtension(CantaraImportUnknownFileExtensionError),
+    UnknownBlock(CantaraImportUnknownBlockError),
+    FileDoesNotExist(CantaraFileDoesNotExistError),
+    InvalidFileName(CantaraImportInvalidFileNameError),
+    InvalidService(CantaraImportInvalidServiceError),
+    InvalidSong(CantaraImportInvalidSongError),
+    Encoding(CantaraImportEncodingError),
+    Parsing(CantaraImportParsingError),
+    SongEdit(SongEditError),
+}
+
+impl CantaraError {
+    /// Returns the library error behind an error
+    /// # Returns
+    /// The `CantaraError` or None if the error does not come from this library (e.g. an I/O error)
+    /// # Example
+    /// ```
+    /// use cantara_songlib::importer::errors::CantaraError;
+    /// use cantara_songlib::importer::classic_song::import_song;
+    /// let error = import_song("").unwrap_err();
+    /// assert!(matches!(CantaraError::from_error(error.as_ref()), Some(CantaraError::NoContent(_))));
+    /// ```
+    pub fn from_error(error: &(dyn Error + 'static)) -> Option<CantaraError> {
+        if let Some(error) = error.downcast_ref::<CantaraImportNoContentError>() {
+            Some(CantaraError::NoContent(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraImportUnknownFileExtensionError>() {
+            Some(CantaraError::UnknownFileExtension(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraImportUnknownBlockError>() {
+            Some(CantaraError::UnknownBlock(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraFileDoesNotExistError>() {
+            Some(CantaraError::FileDoesNotExist(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraImportInvalidFileNameError>() {
+            Some(CantaraError::InvalidFileName(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraImportInvalidServiceError>() {
+            Some(CantaraError::InvalidService(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraImportInvalidSongError>() {
+            Some(CantaraError::InvalidSong(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraImportEncodingError>() {
+            Some(CantaraError::Encoding(error.clone()))
+        } else if let Some(error) = error.downcast_ref::<CantaraImportParsingError>() {
+            Some(CantaraError::Parsing(error.clone()))
+        } else {
+            error.downcast_ref::<SongEditError>().map(|error| CantaraError::SongEdit(error.clone()))
+        }
+    }
+}
+
+impl fmt::Display for CantaraError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CantaraError::NoContent(error) => error.fmt(f),
+            CantaraError::UnknownFileExtension(error) => error.fmt(f),
+            CantaraError::UnknownBlock(error) => error.fmt(f),
+            CantaraError::FileDoesNotExist(error) => error.fmt(f),
+            CantaraError::InvalidFileName(error) => error.fmt(f),
+            CantaraError::InvalidService(error) => error.fmt(f),
+            CantaraError::InvalidSong(error) => error.fmt(f),
+            CantaraError::Encoding(error) => error.fmt(f),
+            CantaraError::Parsing(error) => error.fmt(f),
+            CantaraError::SongEdit(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CantaraError {}
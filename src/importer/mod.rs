@@ -2,18 +2,71 @@
 //! Specific submodules are used for different file formats.
 
 /// This module contains defined errors which may occur during the import process.
-mod errors;
+pub mod errors;
 
 /// This module contains functions for importing classic song files.
 pub mod classic_song;
 
-use errors::CantaraFileDoesNotExistError;
+/// This module contains functions for importing cssf song files.
+pub mod cssf;
+
+/// This module contains functions for importing the plain text export of CCLI SongSelect.
+pub mod ccli_song;
+
+/// This module contains functions for importing ChordPro files.
+pub mod chordpro;
+
+/// This module contains functions for importing SongBeamer files.
+pub mod songbeamer;
+
+/// This module contains functions for importing songs in the OpenLyrics XML format.
+pub mod openlyrics;
+
+/// This module contains functions for importing the songs of an OpenLP service file.
+pub mod openlp_service;
+
+/// This module contains functions for extracting the lyrics of UltraStar karaoke files.
+pub mod ultrastar;
+
+/// This module contains functions for importing lyrics from plain text files without any markup.
+pub mod plaintext;
+
+/// This module contains functions for importing the songs of a VideoPsalm songbook.
+pub mod videopsalm;
+
+/// This module contains functions for importing ProPresenter 6 files.
+pub mod propresenter;
+
+/// This module contains the options which control the import process.
+mod options;
+
+/// This module contains the report of an import (the song and non-fatal warnings).
+mod report;
+
+/// This module contains the trait for importers and the registry which chooses the importer for a file.
+mod registry;
+
+use errors::{CantaraFileDoesNotExistError, CantaraImportEncodingError};
+pub use options::{
+    default_comment_prefixes, ChorusMatching, DuplicateTagPolicy, ImportOptions, SongSeparator, StanzaSeparator, TagAliasMap,
+    DEFAULT_COMMENT_PREFIXES,
+};
+pub use registry::{ImporterRegistry, SongImporter};
+pub use report::{ImportReport, ImportWarning};
 use serde::{Deserialize, Serialize};
 
-use crate::song::Song;
+use crate::filetypes::{get_file_type_by_content, FileType};
+use crate::metadata::resolve_tag_templates;
+use crate::slides::{PresentationChapter, SlideSettings};
+use crate::song::{Song, SongPartContentType, SongPartType};
+use crate::text::decode_windows_1252;
+use std::borrow::Cow;
 use std::error::Error;
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use regex::Regex;
 
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -48,6 +101,31 @@ impl SongFile {
         }
   
     }
+
+    /// Parses the file with the default import options and stores the result in the parsing state.
+    /// A file with a single song becomes `ParsedCantaraSong`, a file with several songs `ParsedMultipleSongs`.
+    /// # Returns
+    /// Ok or the error of the import (the parsing state is kept in that case)
+    /// # Example
+    /// ```
+    /// use cantara_songlib::importer::{SongFile, SongFileParsingState};
+    /// let mut song_file = SongFile::new("testfiles/videopsalm/Hymns.json").unwrap();
+    /// song_file.parse().unwrap();
+    /// assert!(matches!(song_file.parsing_state, SongFileParsingState::ParsedMultipleSongs(ref songs) if songs.len() == 2));
+    /// ```
+    pub fn parse(&mut self) -> Result<(), Box<dyn Error>> {
+        let file_path: &str = self.file_path.to_str()
+            .ok_or_else(|| errors::CantaraImportInvalidFileNameError { file_path: self.file_path.to_string_lossy().to_string() })?;
+        let mut songs: Vec<Song> = import_songs_from_file_with_options(file_path, &ImportOptions::default())?
+            .into_iter()
+            .map(|report| report.song)
+            .collect();
+        self.parsing_state = match songs.len() {
+            1 => SongFileParsingState::ParsedCantaraSong(songs.remove(0)),
+            _ => SongFileParsingState::ParsedMultipleSongs(songs),
+        };
+        Ok(())
+    }
 }
 
 
@@ -59,6 +137,8 @@ pub enum SongFileParsingState {
     FormatDetermined,
     ClassicSongNotParsed,
     ParsedCantaraSong(Song),
+    /// The file contains several songs (e.g. a VideoPsalm songbook), which have all been parsed
+    ParsedMultipleSongs(Vec<Song>),
 }
 
 /// Imports a song from a file.
@@ -79,36 +159,347 @@ pub enum SongFileParsingState {
 /// assert_eq!(song.title, "Amazing Grace");
 /// ```
 pub fn import_song_from_file(file_path: &str) -> Result<Song, Box<dyn Error>> {
-    let content_wraped = std::fs::read_to_string(file_path);
-    if content_wraped.is_err() {
-        return Err(Box::new(content_wraped.err().unwrap()));
+    import_song_from_file_with_options(file_path, &ImportOptions::default())
+        .map(|report| report.song)
+}
+
+/// Decodes the bytes of a song file. A byte order mark decides between UTF-8 and UTF-16 (little and big endian),
+/// files without a byte order mark are read as UTF-8. If they are no valid UTF-8 they are decoded as Windows-1252,
+/// which is the encoding of many song files written by older Windows programs (e.g. SongBeamer).
+/// # Returns
+/// The decoded content or a `CantaraImportEncodingError` if the bytes are invalid for the encoding of their byte order mark
+fn decode_song_file(bytes: &[u8]) -> Result<String, CantaraImportEncodingError> {
+    let Some((encoding, bom_length)) = encoding_rs::Encoding::for_bom(bytes) else {
+        return Ok(match std::str::from_utf8(bytes) {
+            Ok(content) => content.to_string(),
+            Err(_) => decode_windows_1252(bytes),
+        });
+    };
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])
+        .map(|content| content.into_owned())
+        .ok_or_else(|| CantaraImportEncodingError { encoding: encoding.name().to_string() })
+}
+
+/// Reads the content of a song file and decodes it (see `decode_song_file`)
+fn read_song_file(file_path: &str) -> Result<String, Box<dyn Error>> {
+    let bytes: Vec<u8> = std::fs::read(file_path)?;
+    Ok(decode_song_file(&bytes)?)
+}
+
+/// Normalizes the content of a song file before it is parsed. Every importer calls this first, so that all parts of the
+/// import work on the same text: all line endings (`\r\n` and lone `\r` of old Mac files) are converted to `\n` and a
+/// leading byte order mark is removed. If `strip_trailing_whitespace` is true, whitespace at the end of each line is removed as well.
+/// The original content is not changed, so it can still be used where the formatting has to be preserved.
+/// # Returns
+/// The normalized content (borrowed if nothing had to be changed)
+/// # Example
+/// ```
+/// use cantara_songlib::importer::normalize_input;
+/// assert_eq!(normalize_input("\u{FEFF}#title: Test\r\rLine \r\n", false), "#title: Test\n\nLine \n");
+/// assert_eq!(normalize_input("Line \r\nLine\t", true), "Line\nLine");
+/// ```
+pub fn normalize_input(content: &str, strip_trailing_whitespace: bool) -> Cow<'_, str> {
+    let content = content.strip_prefix('\u{FEFF}').unwrap_or(content);
+    let needs_change = content.contains('\r')
+        || (strip_trailing_whitespace && content.split('\n').any(|line| line.len() != line.trim_end().len()));
+    if !needs_change {
+        return Cow::Borrowed(content);
     }
-    let content: String = content_wraped.unwrap();
 
-    let file_extension: &str = Path::new(file_path)
-        .extension()
-        .and_then(OsStr::to_str)
-        .unwrap();
-    match file_extension {
-        "song" => {
-            let wraped_song: Result<Song, Box<dyn Error>> = classic_song::import_song(&content);
-            if wraped_song.is_err() {
-                return Result::Err(wraped_song.err().unwrap());
-            }
-            let mut song: Song = wraped_song.unwrap();
-            if song.title.is_empty() {
-                let title: &str = Path::new(file_path)
-                    .file_stem()
-                    .and_then(OsStr::to_str)
-                    .unwrap();
-                song.title = title.to_string();
+    let content = content.replace("\r\n", "\n").replace('\r', "\n");
+    match strip_trailing_whitespace {
+        true => Cow::Owned(content.split('\n').map(str::trim_end).collect::<Vec<&str>>().join("\n")),
+        false => Cow::Owned(content),
+    }
+}
+
+/// Parses a label-only line like `Chorus:`, `[Bridge]`, `Verse 1` or `Strophe 2`, as it is used by most of the formats.
+/// The label is recognized by `SongPartType::from_label` or, if that fails, looked up (in lowercase) in the labels which are
+/// only used by the format.
+/// # Arguments
+/// * `line` - The line which may be a label
+/// * `format_labels` - The labels of the format in lowercase with their part types (like `("misc", SongPartType::Other)`)
+/// # Returns
+/// The type of the part and the number (if given) or None if the line is no label
+pub(crate) fn parse_part_label(line: &str, format_labels: &[(&str, SongPartType)]) -> Option<(SongPartType, Option<u32>)> {
+    let label_regex = {
+        static LABEL_REGEX: OnceLock<Regex> = OnceLock::new();
+        LABEL_REGEX.get_or_init(|| Regex::new(r"^\s*\[?\s*([^\W\d_][\w\- ]*?)(?:\s*(\d+))?\s*\]?\s*:?\s*$").unwrap())
+    };
+    let captures = label_regex.captures(line)?;
+    let label: &str = captures.get(1)?.as_str();
+    let part_type: SongPartType = SongPartType::from_label(label).or_else(|| {
+        let label: String = label.trim().to_lowercase();
+        format_labels
+            .iter()
+            .find(|(format_label, _)| *format_label == label)
+            .map(|(_, part_type)| *part_type)
+    })?;
+    let number: Option<u32> = captures.get(2).and_then(|number| number.as_str().parse().ok());
+    Some((part_type, number))
+}
+
+/// Imports a song from a file with the given import options.
+/// Unless a format is forced with `ImportOptions::format_override`, the importer is chosen by the file extension
+/// (see `ImporterRegistry`) and the content is imported with `import_song_from_string_with_options`.
+/// The file name is used as title of songs without a title.
+/// # Arguments
+/// * `file_path` - A string slice that holds the path to the file.
+/// * `options` - The options for the import
+/// # Returns
+/// A Result with an `ImportReport` containing the song and all warnings, or an error.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::{import_song_from_file_with_options, ImportOptions};
+/// let report = import_song_from_file_with_options("testfiles/Amazing Grace.song", &ImportOptions::default()).unwrap();
+/// assert_eq!(report.song.title, "Amazing Grace");
+/// assert!(report.warnings.is_empty());
+/// ```
+pub fn import_song_from_file_with_options(file_path: &str, options: &ImportOptions) -> Result<ImportReport, Box<dyn Error>> {
+    ImporterRegistry::default().import_song_from_file_with_options(file_path, options)
+}
+
+/// Imports a song from a str in the given format, e.g. content which has been uploaded to a server.
+/// # Arguments
+/// * `content` - The content of the song file
+/// * `file_type` - The format of the content
+/// * `fallback_title` - The title of the song if the content contains no title (most likely the file name)
+/// # Returns
+/// A Result with the Song or an error
+/// # Example
+/// ```
+/// use cantara_songlib::filetypes::FileType;
+/// use cantara_songlib::importer::import_song_from_string;
+/// let song = import_song_from_string("Amazing grace how sweet the sound", FileType::ClassicSongFile, "Amazing Grace").unwrap();
+/// assert_eq!(song.title, "Amazing Grace");
+/// let song = import_song_from_string("{title: Test}\n\nLa la la", FileType::ChordPro, "Fallback").unwrap();
+/// assert_eq!(song.title, "Test");
+/// ```
+pub fn import_song_from_string(content: &str, file_type: FileType, fallback_title: &str) -> Result<Song, Box<dyn Error>> {
+    import_song_from_string_with_options(content, file_type, fallback_title, &ImportOptions::default())
+        .map(|report| report.song)
+}
+
+/// Imports a song from a str in the given format with the default import options and returns it with the warnings of the import
+/// (e.g. malformed tags or stanzas which are nearly, but not exactly repetitions). Use `import_song_from_string` to only get the song.
+/// # Returns
+/// A Result with an `ImportReport` containing the song and all warnings, or an error.
+/// # Example
+/// ```
+/// use cantara_songlib::filetypes::FileType;
+/// use cantara_songlib::importer::{import_song_with_report, ImportWarning};
+/// let report = import_song_with_report("#title: Amazing Grace\n#author:\n\nAmazing grace", FileType::ClassicSongFile).unwrap();
+/// assert_eq!(report.song.title, "Amazing Grace");
+/// assert_eq!(report.warnings, vec![ImportWarning::EmptyTag { key: "author".to_string(), line: 2 }]);
+/// assert_eq!(report.warnings[0].line(), Some(2));
+/// ```
+pub fn import_song_with_report(content: &str, file_type: FileType) -> Result<ImportReport, Box<dyn Error>> {
+    import_song_from_string_with_options(content, file_type, "", &ImportOptions::default())
+}
+
+/// Imports a song from a str in the given format with the given import options.
+/// If the result of the import looks broken (e.g. cssf content which has been given as classic song),
+/// the format is detected from the content and the import is retried (unless the format is forced with `ImportOptions::format_override`).
+/// In that case, an `ImportWarning::FormatMismatch` is added to the report.
+/// # Returns
+/// A Result with an `ImportReport` containing the song and all warnings, or an error.
+pub fn import_song_from_string_with_options(
+    content: &str,
+    file_type: FileType,
+    fallback_title: &str,
+    options: &ImportOptions,
+) -> Result<ImportReport, Box<dyn Error>> {
+    let content = normalize_input(content, options.strip_trailing_whitespace);
+    let mut result = import_song_by_file_type(&content, file_type, options);
+
+    // Only retry if the format has not been forced by the user
+    let needs_retry = options.format_override.is_none() && match &result {
+        Ok(report) => looks_misparsed(&report.song, &content),
+        Err(_) => true,
+    };
+    if needs_retry {
+        if let Some(detected_type) = get_file_type_by_content(&content) {
+            if detected_type != file_type && is_importable(detected_type) {
+                if let Ok(mut report) = import_song_by_file_type(&content, detected_type, options) {
+                    report.warnings.insert(0, ImportWarning::FormatMismatch {
+                        extension_type: file_type,
+                        detected_type,
+                    });
+                    result = Ok(report);
+                }
             }
-            Ok(song)
         }
-        _ => Err(Box::new(errors::CantaraImportUnknownFileExtensionError {
-            file_extension: file_extension.to_string(),
-        })),
     }
+
+    Ok(complete_import(result?, fallback_title, options))
+}
+
+/// Imports all songs of a file, e.g. a classic song file which contains several songs separated by
+/// `ImportOptions::song_separator` or a VideoPsalm songbook. Files in other formats always contain exactly one song.
+/// Songs without a title get the title from the file name.
+/// # Returns
+/// A Result with an `ImportReport` for every song, or an error.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::{import_songs_from_file_with_options, ImportOptions, SongSeparator};
+/// let options = ImportOptions { song_separator: Some(SongSeparator::Line("***".to_string())), ..Default::default() };
+/// let reports = import_songs_from_file_with_options("testfiles/separators/two_songs.song", &options).unwrap();
+/// assert_eq!(reports.len(), 2);
+/// ```
+pub fn import_songs_from_file_with_options(file_path: &str, options: &ImportOptions) -> Result<Vec<ImportReport>, Box<dyn Error>> {
+    ImporterRegistry::default().import_songs_from_file_with_options(file_path, options)
+}
+
+/// The result of the import of a song in a directory: the path of its file and the song or the error of the import of the file
+pub type FileImportResult = (PathBuf, Result<Song, Box<dyn Error>>);
+
+/// Imports the songs of all files in a directory which have a supported extension, files with other extensions are skipped.
+/// If `recursive` is true, the files of all subdirectories are imported as well.
+/// Every file is imported on its own, so a file which can't be imported doesn't prevent the import of the others.
+/// Files with several songs (like a VideoPsalm songbook) add all of their songs.
+///
+/// A defaults file (see `songbook::DEFAULTS_FILE_NAME`) is not imported as song, its tags are inherited by the songs
+/// in its directory and its subdirectories which don't set them themselves.
+/// # Returns
+/// A result for every song with the path of its file (sorted by the paths) and a result with the error for every file
+/// which can't be imported, or an error if a directory can't be read.
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use cantara_songlib::importer::import_songs_from_directory;
+/// let results = import_songs_from_directory(Path::new("testfiles"), false).unwrap();
+/// let (_, song) = results.iter().find(|(path, _)| path.ends_with("Amazing Grace.song")).unwrap();
+/// assert_eq!(song.as_ref().unwrap().title, "Amazing Grace");
+/// ```
+pub fn import_songs_from_directory(dir: &Path, recursive: bool) -> Result<Vec<FileImportResult>, std::io::Error> {
+    ImporterRegistry::default().import_songs_from_directory(dir, recursive)
+}
+
+/// Completes an imported song: songs without a title get the fallback title and the tag templates are resolved.
+fn complete_import(report: ImportReport, fallback_title: &str, options: &ImportOptions) -> ImportReport {
+    let ImportReport { mut song, mut warnings } = report;
+    if song.title.is_empty() {
+        song.title = fallback_title.to_string();
+    }
+
+    if options.resolve_tag_templates {
+        warnings.extend(
+            resolve_tag_templates(&mut song)
+                .into_iter()
+                .map(ImportWarning::Metadata)
+        );
+    }
+
+    ImportReport { song, warnings }
+}
+
+/// Completes an imported song with the information of the file it comes from (the modification time).
+/// A song without a title is only valid if its title could be taken from the file name.
+fn finish_file_import(report: ImportReport, file_path: &str, options: &ImportOptions) -> Result<ImportReport, Box<dyn Error>> {
+    let ImportReport { mut song, warnings } = report;
+    if song.title.is_empty() {
+        return Err(Box::new(errors::CantaraImportInvalidFileNameError { file_path: file_path.to_string() }));
+    }
+
+    if options.modified_from_file_time && song.get_tag("modified").is_none() {
+        if let Ok(modified) = std::fs::metadata(file_path).and_then(|metadata| metadata.modified()) {
+            song.set_modified(modified);
+        }
+    }
+
+    Ok(ImportReport { song, warnings })
+}
+
+/// Creates a presentation chapter from a song file of any importable format.
+/// The song is imported with the importer for its format and the slides are generated from the song (see `slides_from_song`).
+/// Classic song files are presented directly from their text if `SlideSettings::legacy_classic_slides` is set.
+/// # Arguments
+/// * `file_path` - A string slice that holds the path to the file.
+/// * `slide_settings` - The settings for the creation of the slides
+/// # Returns
+/// A Result with the presentation chapter or an error if the file could not be imported
+/// # Example
+/// ```
+/// use cantara_songlib::importer::create_presentation_from_file;
+/// use cantara_songlib::slides::SlideSettings;
+/// let chapter = create_presentation_from_file("testfiles/Amazing Grace.song", &SlideSettings::default()).unwrap();
+/// assert!(!chapter.slides.is_empty());
+/// ```
+pub fn create_presentation_from_file(file_path: &str, slide_settings: &SlideSettings) -> Result<PresentationChapter, Box<dyn Error>> {
+    ImporterRegistry::default().create_presentation_from_file(file_path, slide_settings)
+}
+
+/// Returns the title which is used for a song file without a title tag: the file name without its extension.
+/// Dotfiles like `.song` keep their full file name.
+/// # Returns
+/// The title or None if the path has no (UTF-8) file name
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use cantara_songlib::importer::title_from_file_path;
+/// assert_eq!(title_from_file_path(Path::new("songs/Amazing Grace.song")), Some("Amazing Grace".to_string()));
+/// assert_eq!(title_from_file_path(Path::new("songs/.song")), Some(".song".to_string()));
+/// assert_eq!(title_from_file_path(Path::new("/")), None);
+/// ```
+pub fn title_from_file_path(file_path: &Path) -> Option<String> {
+    file_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .filter(|title| !title.is_empty())
+        .map(str::to_string)
+}
+
+/// Returns whether there is an importer for the given file type
+fn is_importable(file_type: FileType) -> bool {
+    matches!(file_type, FileType::ClassicSongFile | FileType::CSSF | FileType::CCLISongselectFile | FileType::ChordPro | FileType::SongBeamer | FileType::UltraStar | FileType::PlainText | FileType::VideoPsalm | FileType::ProPresenter | FileType::CantaraJson)
+}
+
+/// Imports the content with the importer of the given file type
+fn import_song_by_file_type(content: &str, file_type: FileType, options: &ImportOptions) -> Result<ImportReport, Box<dyn Error>> {
+    match file_type {
+        FileType::ClassicSongFile => classic_song::import_song_with_options(content, options),
+        FileType::CSSF => cssf::import_song_with_options(content, options)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        FileType::CCLISongselectFile => ccli_song::import_song(content)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        FileType::ChordPro => chordpro::import_song(content)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        FileType::SongBeamer => songbeamer::import_song(content)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        FileType::UltraStar => ultrastar::import_song(content)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        // The title is taken from the file name later if the text has no title line
+        FileType::PlainText => plaintext::import_song(content, "")
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        // Only the first song of a songbook is imported (see `import_songs_from_file_with_options`)
+        FileType::VideoPsalm => videopsalm::import_song(content)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        FileType::ProPresenter => propresenter::import_song(content)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+        FileType::CantaraJson => import_song_from_json(content)
+            .map(|song| ImportReport { song, warnings: Vec::new() }),
+    }
+}
+
+/// A cheap sanity check whether an imported song looks like it has been parsed with the wrong importer.
+/// This is the case if the lyrics contain structural markup (like `#verse.1` or XML tags)
+/// or if the content contains categorization lines, but no parts have been created.
+fn looks_misparsed(song: &Song, content: &str) -> bool {
+    let markup_regex = {
+        static MARKUP_REGEX: OnceLock<Regex> = OnceLock::new();
+        MARKUP_REGEX.get_or_init(|| Regex::new(r"^\s*(#\w+\.\d+\s*$|<[a-zA-Z?!/])").unwrap())
+    };
+
+    let lyrics_contain_markup = song.get_unpacked_parts().iter().any(|part| {
+        part.contents
+            .iter()
+            .filter(|content| matches!(content.voice_type, SongPartContentType::Lyrics { .. }))
+            .any(|content| content.content.lines().any(|line| markup_regex.is_match(line)))
+    });
+
+    lyrics_contain_markup
+        || (song.get_total_part_count() == 0 && content.lines().any(cssf::is_categorization_line))
 }
 
 
@@ -129,9 +520,30 @@ pub fn get_song_from_file_as_json(file_path: &str) -> Result<String, Box<dyn Err
     }
 }
 
+/// Loads a song from JSON which has been created by serializing a `Song` (e.g. with `get_song_from_file_as_json`).
+/// The structure of the song is validated: every part needs a valid and unique ID which ends with its number
+/// and the repetitions and part orders may only refer to parts of the song.
+/// # Returns
+/// A Result with the Song or an error if the JSON can't be deserialized or the song is invalid (`CantaraImportInvalidSongError`)
+/// # Example
+/// ```
+/// use cantara_songlib::importer::{get_song_from_file_as_json, import_song_from_json};
+/// let json = get_song_from_file_as_json("testfiles/Amazing Grace.song").unwrap();
+/// let song = import_song_from_json(&json).unwrap();
+/// assert_eq!(song.title, "Amazing Grace");
+/// ```
+pub fn import_song_from_json(json: &str) -> Result<Song, Box<dyn Error>> {
+    let mut song: Song = serde_json::from_str(json)?;
+    song.link_deserialized_parts()
+        .map_err(|message| errors::CantaraImportInvalidSongError { message })?;
+    Ok(song)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::importer::classic_song::slides_from_classic_song;
+    use crate::slides::LinkedEntity;
 
     #[test]
     /// This test tests a song import from a file with a title tag.
@@ -153,10 +565,226 @@ mod test {
     /// The function should return an error.
     /// The error should be of type CantaraImportUnknownFileExtensionError.
     fn test_import_song_with_unknown_file_extension_from_file() {
-        let result = import_song_from_file("testfiles/What a friend we have in Jesus.txt");
+        let file_path = std::env::temp_dir().join(format!("cantara-songlib-unknown-{}.pdf", std::process::id()));
+        std::fs::write(&file_path, "Just some lyrics").unwrap();
+        let result = import_song_from_file(file_path.to_str().unwrap());
+        std::fs::remove_file(&file_path).unwrap();
         assert!(result.is_err());
         let error: Box<dyn Error> = result.err().unwrap();
-        assert_eq!(error.to_string(), "Unknown file extension: txt");
+        assert_eq!(error.to_string(), "Unknown file extension: pdf");
+    }
+
+    #[test]
+    /// Text files are imported as plain lyrics, an empty text file has no content
+    fn test_import_plain_text_file() {
+        let song = import_song_from_file("testfiles/plaintext/Blessed Assurance.txt").unwrap();
+        assert_eq!(song.title, "Blessed Assurance");
+        assert!(song.get_total_part_count() > 0);
+
+        let result = import_song_from_file("testfiles/What a friend we have in Jesus.txt");
+        assert!(result.is_err_and(|error| error.is::<errors::CantaraImportNoContentError>()));
+    }
+
+    #[test]
+    /// Files with other extensions are skipped, a broken file doesn't abort the import of the directory
+    fn test_import_songs_from_directory() {
+        let directory = std::env::temp_dir().join(format!("cantara-songlib-directory-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("hymns")).unwrap();
+        std::fs::write(directory.join("B.song"), "#title: B\n\nSecond song").unwrap();
+        std::fs::write(directory.join("A.song"), "#title: A\n\nFirst song").unwrap();
+        std::fs::write(directory.join("Broken.song"), "").unwrap();
+        std::fs::write(directory.join("Notes.pdf"), "No song").unwrap();
+        std::fs::write(directory.join("hymns").join("C.song"), "#title: C\n\nThird song").unwrap();
+
+        let results = import_songs_from_directory(&directory, false).unwrap();
+        let recursive_results = import_songs_from_directory(&directory, true).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let file_names: Vec<&OsStr> = results.iter().filter_map(|(path, _)| path.file_name()).collect();
+        assert_eq!(file_names, vec!["A.song", "B.song", "Broken.song"]);
+        assert_eq!(results[0].1.as_ref().unwrap().title, "A");
+        assert!(results[2].1.is_err());
+
+        assert_eq!(recursive_results.len(), 4);
+        assert_eq!(recursive_results[3].0, directory.join("hymns").join("C.song"));
+        assert_eq!(recursive_results[3].1.as_ref().unwrap().title, "C");
+    }
+
+    #[test]
+    /// Every song of a file with several songs is imported, defaults files are inherited instead of being imported
+    fn test_import_songs_from_directory_with_songbooks_and_defaults() {
+        use crate::song::TagSource;
+        use crate::songbook::DEFAULTS_FILE_NAME;
+
+        let directory = std::env::temp_dir().join(format!("cantara-songlib-directory-defaults-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("german")).unwrap();
+        std::fs::copy("testfiles/videopsalm/Hymns.json", directory.join("Hymns.json")).unwrap();
+        std::fs::write(directory.join(DEFAULTS_FILE_NAME), "#language: en\n#ccli_license: 123456").unwrap();
+        std::fs::write(directory.join("german").join(DEFAULTS_FILE_NAME), "#language: de").unwrap();
+        std::fs::write(directory.join("german").join("Stille Nacht.song"), "#title: Stille Nacht\n\nStille Nacht").unwrap();
+
+        let results = import_songs_from_directory(&directory, true).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let songs: Vec<&Song> = results.iter().map(|(_, song)| song.as_ref().unwrap()).collect();
+        let titles: Vec<&str> = songs.iter().map(|song| song.title.as_str()).collect();
+        assert!(titles.contains(&"Blessed Assurance"));
+        assert!(titles.len() > 2);
+        assert!(!titles.iter().any(|title| title.starts_with('_')));
+
+        let hymn = songs.iter().find(|song| song.title == "Blessed Assurance").unwrap();
+        assert_eq!(hymn.get_tag("language").unwrap(), "en");
+        assert_eq!(hymn.tag_source("ccli_license"), Some(TagSource::Inherited));
+        let stille_nacht = songs.iter().find(|song| song.title == "Stille Nacht").unwrap();
+        assert_eq!(stille_nacht.get_tag("language").unwrap(), "de");
+        assert_eq!(stille_nacht.get_tag("ccli_license").unwrap(), "123456");
+    }
+
+    #[test]
+    /// Files without an extension are detected by their content, dotfiles get their file name as title
+    fn test_import_song_without_file_extension() {
+        let directory = std::env::temp_dir().join(format!("cantara-songlib-extensionless-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let extensionless = directory.join("What a friend we have in Jesus");
+        std::fs::copy("testfiles/What a friend we have in Jesus.song", &extensionless).unwrap();
+        let song = import_song_from_file(extensionless.to_str().unwrap()).unwrap();
+        assert_eq!(song.title, "What a friend we have in Jesus");
+        assert!(song.get_total_part_count() > 0);
+
+        let structured = directory.join("Structured");
+        std::fs::copy("testfiles/Misnamed Structured Song.song", &structured).unwrap();
+        let song = import_song_from_file(structured.to_str().unwrap()).unwrap();
+        assert_eq!(song.get_part_count(crate::song::SongPartType::Refrain), 1);
+
+        let dotfile = directory.join(".song");
+        std::fs::write(&dotfile, "Just some lyrics").unwrap();
+        assert_eq!(import_song_from_file(dotfile.to_str().unwrap()).unwrap().title, ".song");
+
+        let empty = directory.join("Empty");
+        std::fs::write(&empty, "").unwrap();
+        assert!(import_song_from_file(empty.to_str().unwrap()).is_err());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    /// Presentations can be created from every importable format
+    fn test_create_presentation_from_cssf_file() {
+        let settings = SlideSettings { title_slide: true, empty_last_slide: false, ..Default::default() };
+        let chapter = create_presentation_from_file("testfiles/cssf/parts.cssf", &settings).unwrap();
+        assert!(matches!(&chapter.linked_entity, LinkedEntity::Song(song) if song.title == "Amazing Grace"));
+        // The title slide and the parts in the guessed order (the refrain is repeated after the second verse)
+        assert_eq!(chapter.slides.len(), 5);
+        match &chapter.slides[2].slide_content {
+            crate::slides::SlideContent::SingleLanguageMainContent(content) => {
+                assert_eq!(content.clone().main_text(), "My chains are gone,\nI have been set free.");
+            },
+            _ => panic!("The third slide should be a content slide"),
+        }
+        let part_ids: Vec<Option<String>> = chapter.slides
+            .iter()
+            .map(|slide| slide.source().and_then(|source| source.part_id.clone()))
+            .collect();
+        assert_eq!(part_ids, vec![None, Some("verse.1".to_string()), Some("refrain.1".to_string()), Some("verse.2".to_string()), Some("refrain.1".to_string())]);
+
+        // Classic song files keep the output of the text-based slide generator
+        let chapter = create_presentation_from_file("testfiles/Amazing Grace.song", &settings).unwrap();
+        let content = std::fs::read_to_string("testfiles/Amazing Grace.song").unwrap();
+        assert_eq!(chapter.slides, slides_from_classic_song(&content, &settings, "Amazing Grace".to_string()));
+        assert!(matches!(chapter.linked_entity, LinkedEntity::SongFile(_)));
+
+        let settings = SlideSettings { legacy_classic_slides: false, ..settings };
+        let chapter = create_presentation_from_file("testfiles/Amazing Grace.song", &settings).unwrap();
+        assert!(matches!(chapter.linked_entity, LinkedEntity::Song(_)));
+    }
+
+    #[test]
+    /// A cssf file which has been renamed to .song should be detected and imported as cssf
+    fn test_import_misnamed_cssf_file() {
+        let report = import_song_from_file_with_options(
+            "testfiles/Misnamed Structured Song.song",
+            &ImportOptions::default()
+        ).unwrap();
+        assert_eq!(report.song.title, "Misnamed Structured Song");
+        assert_eq!(report.song.get_part_count(crate::song::SongPartType::Verse), 2);
+        assert_eq!(report.song.get_part_count(crate::song::SongPartType::Refrain), 1);
+        assert_eq!(report.warnings, vec![ImportWarning::FormatMismatch {
+            extension_type: FileType::ClassicSongFile,
+            detected_type: FileType::CSSF,
+        }]);
+    }
+
+    #[test]
+    /// Content in memory is imported like a file, the fallback title is used for songs without a title
+    fn test_import_song_from_string() {
+        let content = std::fs::read_to_string("testfiles/What a friend we have in Jesus.song").unwrap();
+        let song = import_song_from_string(&content, FileType::ClassicSongFile, "What a friend").unwrap();
+        assert_eq!(song, import_song_from_file("testfiles/What a friend we have in Jesus.song").map(|mut song| {
+            song.title = "What a friend".to_string();
+            song
+        }).unwrap());
+
+        let content = std::fs::read_to_string("testfiles/Misnamed Structured Song.song").unwrap();
+        let report = import_song_from_string_with_options(&content, FileType::ClassicSongFile, "", &ImportOptions::default()).unwrap();
+        assert_eq!(report.song.get_part_count(crate::song::SongPartType::Refrain), 1);
+        assert!(matches!(report.warnings[0], ImportWarning::FormatMismatch { detected_type: FileType::CSSF, .. }));
+    }
+
+    #[test]
+    fn test_import_latin_1_file() {
+        let song = import_song_from_file("testfiles/Näher, mein Gott, zu dir.song").unwrap();
+        assert_eq!(song.title, "Näher, mein Gott, zu dir");
+        let first_verse = song.get_part_by_id("Verse.1").unwrap().borrow().contents[0].content.clone();
+        assert!(first_verse.starts_with("Näher, mein Gott, zu dir,\nnäher zu dir!\nDrückt mich auch Kummer hier,"));
+    }
+
+    #[test]
+    fn test_decode_song_file() {
+        assert_eq!(decode_song_file(b"\xEF\xBB\xBFGr\xC3\xBC\xC3\x9Fe").unwrap(), "Grüße");
+        assert_eq!(decode_song_file(b"Gr\xFC\xDFe").unwrap(), "Grüße");
+        assert_eq!(decode_song_file(b"\xFF\xFEG\x00r\x00\xFC\x00").unwrap(), "Grü");
+        assert_eq!(decode_song_file(b"\xFE\xFF\x00G\x00r\x00\xFC").unwrap(), "Grü");
+
+        // Bytes which don't match the byte order mark can't be decoded
+        let error = decode_song_file(b"\xFF\xFEG\x00\x00\xD8").unwrap_err();
+        assert_eq!(error.encoding, "UTF-16LE");
+        assert!(decode_song_file(b"\xEF\xBB\xBFGr\xFCe").is_err());
+    }
+
+    #[test]
+    /// A forced format is used without any detection
+    fn test_import_with_format_override() {
+        let options = ImportOptions { format_override: Some(FileType::CSSF), ..Default::default() };
+        let report = import_song_from_file_with_options("testfiles/Misnamed Structured Song.song", &options).unwrap();
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.song.get_total_part_count(), 3);
+
+        let options = ImportOptions { format_override: Some(FileType::CSSF), ..Default::default() };
+        assert!(import_song_from_file_with_options("testfiles/Amazing Grace.song", &options).is_err());
+    }
+
+    #[test]
+    fn test_import_with_tag_templates() {
+        let song = import_song_from_file("testfiles/Lobe den Herren.song").unwrap();
+        assert_eq!(song.get_tag("source").unwrap(), "{{book}} Nr. {{number}}");
+
+        let options = ImportOptions { resolve_tag_templates: true, ..Default::default() };
+        let report = import_song_from_file_with_options("testfiles/Lobe den Herren.song", &options).unwrap();
+        assert_eq!(report.song.get_tag("source").unwrap(), "EG Nr. 317");
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_import_with_modified_from_file_time() {
+        assert_eq!(import_song_from_file("testfiles/Amazing Grace.song").unwrap().modified(), None);
+
+        let options = ImportOptions { modified_from_file_time: true, ..Default::default() };
+        let song = import_song_from_file_with_options("testfiles/Amazing Grace.song", &options).unwrap().song;
+        let file_time = std::fs::metadata("testfiles/Amazing Grace.song").unwrap().modified().unwrap();
+        let difference = file_time.duration_since(song.modified().unwrap()).unwrap();
+        // The tag has a precision of one second
+        assert!(difference.as_secs() < 1);
     }
 
     #[test]
@@ -164,4 +792,91 @@ mod test {
         let result = SongFile::new("testfiles/A Non Existing File.txt");
         assert_eq!(result.unwrap_err(), CantaraFileDoesNotExistError);
     }
+
+    #[test]
+    fn test_match_cantara_errors() {
+        let options = ImportOptions { duplicate_tag_policy: DuplicateTagPolicy::Error, ..Default::default() };
+        let error = classic_song::import_song_with_options("#title: A\n#title: B\n\nLa la la", &options).unwrap_err();
+        match errors::CantaraError::from_error(error.as_ref()) {
+            Some(errors::CantaraError::Parsing(parsing_error)) => assert_eq!(parsing_error.line, 2),
+            other => panic!("Unexpected error: {:?}", other),
+        }
+
+        let error = import_song_from_file("testfiles/A Non Existing File.song").unwrap_err();
+        assert_eq!(errors::CantaraError::from_error(error.as_ref()), None);
+    }
+
+    #[test]
+    fn test_parse_part_label() {
+        assert_eq!(parse_part_label("Chorus:", &[]), Some((SongPartType::Chorus, None)));
+        assert_eq!(parse_part_label(" [Strophe 2] ", &[]), Some((SongPartType::Verse, Some(2))));
+        assert_eq!(parse_part_label("Verse3", &[]), Some((SongPartType::Verse, Some(3))));
+        assert_eq!(parse_part_label("Amazing grace how sweet the sound", &[]), None);
+        // The labels of a format are only used if the label is not known to all formats
+        let format_labels = [("misc", SongPartType::Other), ("chorus", SongPartType::Other)];
+        assert_eq!(parse_part_label("Misc 1", &[]), None);
+        assert_eq!(parse_part_label("Misc 1", &format_labels), Some((SongPartType::Other, Some(1))));
+        assert_eq!(parse_part_label("Chorus", &format_labels), Some((SongPartType::Chorus, None)));
+    }
+
+    #[test]
+    /// A serialized song can be loaded again, its part order refers to the parts of the song
+    fn test_import_song_from_json() {
+        let song = import_song_from_file("testfiles/O What A Savior That He Died For Me.song").unwrap();
+        let json = serde_json::to_string(&song).unwrap();
+        let imported = import_song_from_json(&json).unwrap();
+        assert_eq!(imported.title, song.title);
+        let part_ids = |song: &Song| song.get_ordered_parts().iter().map(|part| part.borrow().id.get_id()).collect::<Vec<String>>();
+        assert_eq!(part_ids(&imported), part_ids(&song));
+        assert!(imported.get_unpacked_parts().iter().all(|part| part.id.get_checked_unique()));
+
+        let ordered_parts = imported.get_ordered_parts();
+        let refrain = imported.get_part_by_id(&ordered_parts[1].borrow().id.get_id()).unwrap();
+        assert!(std::rc::Rc::ptr_eq(&ordered_parts[1], &refrain));
+
+        // Exported songs can be imported from files
+        let file_path = std::env::temp_dir().join(format!("cantara-songlib-{}.cantara.json", std::process::id()));
+        std::fs::write(&file_path, &json).unwrap();
+        let result = import_song_from_file(file_path.to_str().unwrap());
+        std::fs::remove_file(&file_path).unwrap();
+        assert_eq!(result.unwrap().title, song.title);
+    }
+
+    #[test]
+    fn test_import_invalid_song_from_json() {
+        let mut song = Song::new("Invalid");
+        song.add_part_of_type(crate::song::SongPartType::Verse, None);
+        song.add_part_of_type(crate::song::SongPartType::Verse, None);
+        let json: serde_json::Value = serde_json::to_value(&song).unwrap();
+
+        let invalid_song = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut json = json.clone();
+            change(&mut json);
+            let error = import_song_from_json(&json.to_string()).unwrap_err();
+            match errors::CantaraError::from_error(error.as_ref()) {
+                Some(errors::CantaraError::InvalidSong(error)) => error.message,
+                other => panic!("Unexpected error: {:?}", other),
+            }
+        };
+        let duplicate_id = |json: &mut serde_json::Value| {
+            json["parts"][1]["id"]["id"] = "verse.1".into();
+            json["parts"][1]["number"] = 1.into();
+        };
+        assert_eq!(invalid_song(&duplicate_id), "the part ID verse.1 is used more than once");
+        assert_eq!(invalid_song(&|json| json["parts"][1]["number"] = 3.into()), "the part Verse.2 has the number 3");
+        assert_eq!(invalid_song(&|json| json["parts"][0]["id"]["id"] = "Verse".into()), "the part ID Verse is invalid");
+        let missing_part = |json: &mut serde_json::Value| {
+            let mut repeated_part = json["parts"][1].clone();
+            repeated_part["id"]["id"] = "Chorus.1".into();
+            json["parts"][0]["is_repetition_of"] = repeated_part;
+        };
+        assert_eq!(invalid_song(&missing_part), "the referenced part Chorus.1 does not exist");
+        let duplicate_part_order = |json: &mut serde_json::Value| {
+            let part_order = serde_json::json!({"name": {"Custom": "short"}, "partorderrule": "VerseRefrainBridgeRefrain"});
+            json["part_orders"] = serde_json::json!([part_order.clone(), part_order]);
+        };
+        assert_eq!(invalid_song(&duplicate_part_order), "the part order name short is used more than once");
+
+        assert!(import_song_from_json("{\"title\": \"No song\"}").is_err());
+    }
 }
@@ -0,0 +1,172 @@
+//! This module contains functions to import the songs of an OpenLP service file (`.osz`).
+//! A service file is a zip archive with a JSON manifest (`service_data.osj`), which contains all items of the service
+//! in their order. The song items contain the song in the OpenLyrics format (see `openlyrics` module).
+//! Items which are no songs (like bible passages, images or presentations) are skipped.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::importer::errors::CantaraImportInvalidServiceError;
+use crate::importer::openlyrics::{self, add_lyrics, apply_verse_order, part_for_verse_name};
+use crate::importer::ImportWarning;
+use crate::slides::{slides_from_song, LinkedEntity, PresentationChapter, SlideSettings};
+use crate::song::{LyricLanguage, Song};
+
+/// The songs of a service in their order together with the warnings about the skipped items
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ServiceImport {
+    pub songs: Vec<Song>,
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Reads the manifest of a service file
+/// # Returns
+/// The items of the service or an error if the file is no zip archive or contains no manifest
+fn read_manifest(path: &Path) -> Result<Vec<Value>, Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    let manifest_name: String = archive
+        .file_names()
+        .find(|name| name.ends_with(".osj"))
+        .map(str::to_string)
+        .ok_or_else(|| CantaraImportInvalidServiceError { message: "the service contains no manifest (.osj)".to_string() })?;
+
+    let mut manifest = String::new();
+    archive.by_name(&manifest_name)?.read_to_string(&mut manifest)?;
+    match serde_json::from_str(&manifest)? {
+        Value::Array(items) => Ok(items),
+        _ => Err(Box::new(CantaraImportInvalidServiceError { message: "the manifest contains no list of items".to_string() })),
+    }
+}
+
+/// Creates a song from the slides of a service item. This is used for items which don't contain the OpenLyrics XML of the song.
+/// The slides are assigned to the parts by their verse tag (like `V1` or `C1`).
+fn song_from_slides(title: &str, slides: &[Value]) -> Option<Song> {
+    let mut song = Song::new(title);
+    let mut parts = HashMap::new();
+    let mut file_order = Vec::new();
+    for slide in slides {
+        let Some(text) = slide.get("raw_slide").and_then(Value::as_str) else {
+            continue;
+        };
+        let verse_tag: &str = slide.get("verseTag").and_then(Value::as_str).unwrap_or("v");
+        let (part, is_new) = part_for_verse_name(&mut song, &mut parts, &verse_tag.to_lowercase())?;
+        // `[---]` marks an optional slide break
+        let lines: Vec<String> = text.lines().filter(|line| line.trim() != "[---]").map(str::to_string).collect();
+        add_lyrics(&part, LyricLanguage::Default, &lines);
+        if is_new {
+            file_order.push(part);
+        }
+    }
+    if song.get_total_part_count() == 0 {
+        return None;
+    }
+    apply_verse_order(&mut song, &parts, file_order, None);
+    Some(song)
+}
+
+/// Imports the song of a service item
+/// # Returns
+/// The song or the reason why the item has been skipped
+fn import_item(item: &Value) -> Result<Song, String> {
+    let header: &Value = &item["header"];
+    let plugin: &str = header.get("name").and_then(Value::as_str).unwrap_or_default();
+    if plugin != "songs" {
+        return Err(format!("the item is no song ({})", plugin));
+    }
+
+    match header.get("xml_version").and_then(Value::as_str).filter(|xml| !xml.trim().is_empty()) {
+        Some(xml) => openlyrics::import_song(xml).map_err(|error| error.to_string()),
+        None => {
+            let title: &str = header.get("title").and_then(Value::as_str).unwrap_or_default();
+            let slides: &[Value] = item.get("data").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+            song_from_slides(title, slides).ok_or_else(|| "the song has no lyrics".to_string())
+        },
+    }
+}
+
+/// Imports all songs of an OpenLP service file in the order of the service.
+/// Items which are no songs or can't be read are skipped, a warning is added for each of them.
+/// # Returns
+/// The songs and the warnings or an error if the file is no valid service file
+pub fn read_service(path: &Path) -> Result<ServiceImport, Box<dyn Error>> {
+    let mut songs: Vec<Song> = Vec::new();
+    let mut warnings: Vec<ImportWarning> = Vec::new();
+
+    // The first entry only contains information about the OpenLP installation
+    for item in read_manifest(path)?.iter().filter_map(|entry| entry.get("serviceitem")) {
+        match import_item(item) {
+            Ok(song) => songs.push(song),
+            Err(reason) => warnings.push(ImportWarning::SkippedServiceItem {
+                title: item["header"].get("title").and_then(Value::as_str).unwrap_or_default().to_string(),
+                reason,
+            }),
+        }
+    }
+    Ok(ServiceImport { songs, warnings })
+}
+
+/// Imports all songs of an OpenLP service file in the order of the service.
+/// Items which are no songs are skipped, use `read_service` to get warnings about them.
+/// # Returns
+/// The songs or an error if the file is no valid service file
+pub fn import_service(path: &Path) -> Result<Vec<Song>, Box<dyn Error>> {
+    Ok(read_service(path)?.songs)
+}
+
+/// Creates a presentation of all songs of an OpenLP service file, with one chapter per song in the order of the service.
+/// # Returns
+/// The chapters or an error if the file is no valid service file
+pub fn presentation_from_service(path: &Path, slide_settings: &SlideSettings) -> Result<Vec<PresentationChapter>, Box<dyn Error>> {
+    Ok(import_service(path)?
+        .into_iter()
+        .map(|song| PresentationChapter::new(slides_from_song(&song, slide_settings), LinkedEntity::Song(song)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::song::SongPartType;
+
+    const SERVICE_FILE: &str = "testfiles/openlp/Sunday Service.osz";
+
+    #[test]
+    fn test_import_service() {
+        let service = read_service(Path::new(SERVICE_FILE)).unwrap();
+        let titles: Vec<&str> = service.songs.iter().map(|song| song.title.as_str()).collect();
+        assert_eq!(titles, vec!["Amazing Grace", "Blessed Assurance"]);
+        assert_eq!(service.songs[0].get_tag("ccli").unwrap(), "22025");
+
+        // The second song has no OpenLyrics XML and is created from the slides
+        let second_song = &service.songs[1];
+        assert_eq!(second_song.get_part_count(SongPartType::Verse), 1);
+        assert_eq!(second_song.get_part_count(SongPartType::Chorus), 1);
+        let order: Vec<String> = second_song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(order, vec!["Verse.1", "Chorus.1"]);
+
+        assert_eq!(service.warnings, vec![
+            ImportWarning::SkippedServiceItem { title: "John 3:16".to_string(), reason: "the item is no song (bibles)".to_string() },
+            ImportWarning::SkippedServiceItem { title: "Announcements".to_string(), reason: "the item is no song (images)".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_presentation_from_service() {
+        let chapters = presentation_from_service(Path::new(SERVICE_FILE), &SlideSettings::default()).unwrap();
+        assert_eq!(chapters.len(), 2);
+        assert!(chapters.iter().all(|chapter| !chapter.slides.is_empty()));
+        assert!(matches!(&chapters[1].linked_entity, LinkedEntity::Song(song) if song.title == "Blessed Assurance"));
+    }
+
+    #[test]
+    fn test_invalid_service_file() {
+        assert!(import_service(Path::new("testfiles/Amazing Grace.song")).is_err());
+        assert!(import_service(Path::new("testfiles/openlp/Does not exist.osz")).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! This module contains functions to import songs from the OpenLyrics XML format, which is used by OpenLP and other programs.
+//! The properties of the song (titles, authors, copyright, ...) are mapped to tags, every `<verse>` becomes a part
+//! of the song. The part type is taken from the name of the verse (`v1` is the first verse, `c` the chorus, ...).
+//!
+//! # Example
+//! ```text
+//! <song xmlns="http://openlyrics.info/namespace/2009/song" version="0.8">
+//!   <properties>
+//!     <titles><title>Amazing Grace</title></titles>
+//!     <verseOrder>v1 c</verseOrder>
+//!   </properties>
+//!   <lyrics>
+//!     <verse name="v1"><lines>Amazing grace how sweet the sound<br/>that saved a wretch like me</lines></verse>
+//!   </lyrics>
+//! </song>
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+extern crate regex;
+use regex::Regex;
+use roxmltree::{Document, Node};
+
+use crate::importer::chordpro::split_chords;
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::song::{
+    LyricLanguage, PartOrder, PartOrderName, PartOrderRule, Song, SongPart, SongPartContent, SongPartContentType, SongPartType,
+};
+
+type PartReference = Rc<RefCell<SongPart>>;
+
+fn verse_name_regex() -> &'static Regex {
+    static VERSE_NAME_REGEX: OnceLock<Regex> = OnceLock::new();
+    VERSE_NAME_REGEX.get_or_init(|| Regex::new(r"^([a-zA-Z]+?)(\d*)([a-z]?)$").unwrap())
+}
+
+/// Returns the type and the number of a part from a verse name like `v1`, `c` or `p2`.
+/// Verse names with a letter at the end (like `v1a`) belong to the same part as the verse without it.
+/// The name of the part is the verse name without that letter and with the number 1 if no number is given.
+/// # Returns
+/// The type, the number and the name of the part or None if the name is invalid
+pub(crate) fn parse_verse_name(name: &str) -> Option<(SongPartType, Option<u32>, String)> {
+    let captures = verse_name_regex().captures(name.trim())?;
+    let part_type: SongPartType = match captures[1].to_lowercase().as_str() {
+        "v" => SongPartType::Verse,
+        "c" => SongPartType::Chorus,
+        "b" => SongPartType::Bridge,
+        "p" => SongPartType::PreChorus,
+        "i" => SongPartType::Intro,
+        "e" => SongPartType::Outro,
+        _ => SongPartType::Other,
+    };
+    let number: Option<u32> = captures[2].parse().ok();
+    // `c` and `c1` are the same part
+    Some((part_type, number, format!("{}{}", captures[1].to_lowercase(), number.unwrap_or(1))))
+}
+
+/// Returns the first child element with the given name (the namespace is ignored)
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+/// Returns all child elements with the given name (the namespace is ignored)
+fn children<'a, 'input: 'a>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+/// Returns the text of an element with collapsed whitespace
+fn element_text(node: Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Writes the content of a `<lines>` element with inline chords (like `[G]Amazing grace`).
+/// Line breaks are given by `<br/>`, the whitespace of the XML is written as spaces. Comments are left out.
+fn write_lines(node: Node, text: &mut String) {
+    for child in node.children() {
+        if child.is_text() {
+            text.extend(child.text().unwrap_or_default().chars().map(|character| match character.is_whitespace() {
+                true => ' ',
+                false => character,
+            }));
+            continue;
+        }
+        match child.tag_name().name() {
+            "br" => text.push('\n'),
+            "comment" => {},
+            "chord" => {
+                let chord: &str = child.attribute("name").or_else(|| child.attribute("root")).unwrap_or_default();
+                text.push_str(&format!("[{}]", chord));
+                // Since OpenLyrics 0.9, chords can contain the text they belong to
+                write_lines(child, text);
+            },
+            _ => write_lines(child, text),
+        }
+    }
+}
+
+/// Adds the tags of the `<properties>` element to the song
+fn apply_properties(song: &mut Song, properties: Node) {
+    if let Some(title) = child(properties, "titles").and_then(|titles| child(titles, "title")) {
+        song.title = element_text(title);
+        song.add_tag("title", &song.title.clone());
+    }
+    if let Some(authors) = child(properties, "authors") {
+        let authors: Vec<String> = children(authors, "author")
+            .map(|author| match author.attribute("type") {
+                Some(role) => format!("{} ({})", element_text(author), role),
+                None => element_text(author),
+            })
+            .collect();
+        if !authors.is_empty() {
+            song.add_tag("author", &authors.join(", "));
+        }
+    }
+    if let Some(themes) = child(properties, "themes") {
+        let themes: Vec<String> = children(themes, "theme").map(element_text).collect();
+        if !themes.is_empty() {
+            song.add_tag("themes", &themes.join(", "));
+        }
+    }
+    for (element, tag) in [
+        ("copyright", "copyright"),
+        ("ccliNo", "ccli"),
+        ("key", "key"),
+        ("tempo", "tempo"),
+        ("released", "year"),
+        ("publisher", "publisher"),
+    ] {
+        if let Some(value) = child(properties, element).map(element_text).filter(|value| !value.is_empty()) {
+            song.add_tag(tag, &value);
+        }
+    }
+}
+
+/// Adds lyrics (and chords) to a part. Further lyrics of the same language are appended to the existing lyrics.
+pub(crate) fn add_lyrics(part: &PartReference, language: LyricLanguage, lines: &[String]) {
+    let (lyrics, chords): (Vec<String>, Vec<String>) = lines.iter().map(|line| split_chords(line)).unzip();
+    let mut contents: Vec<SongPartContent> = vec![SongPartContent {
+        voice_type: SongPartContentType::Lyrics { language },
+        content: lyrics.join("\n"),
+    }];
+    if chords.iter().any(|chord_line| !chord_line.is_empty()) {
+        contents.push(SongPartContent { voice_type: SongPartContentType::Chords, content: chords.join("\n") });
+    }
+
+    let mut part = part.borrow_mut();
+    for content in contents {
+        match part.contents.iter_mut().find(|existing| existing.voice_type == content.voice_type) {
+            Some(existing) => {
+                existing.content.push('\n');
+                existing.content.push_str(&content.content);
+            },
+            None => part.add_content(content),
+        }
+    }
+}
+
+/// Adds a part with the given verse name to the song, or returns the existing part with that name
+pub(crate) fn part_for_verse_name(song: &mut Song, parts: &mut HashMap<String, PartReference>, name: &str) -> Option<(PartReference, bool)> {
+    let (part_type, number, base_name) = parse_verse_name(name)?;
+    if let Some(part) = parts.get(&base_name) {
+        return Some((part.clone(), false));
+    }
+    // A number which is already used by another part is replaced by the next free one
+    let number: Option<u32> = number.filter(|number| song.get_part_by_id(&format!("{}.{}", part_type, number)).is_none());
+    let part = song.add_part_of_type(part_type, number);
+    parts.insert(base_name, part.clone());
+    Some((part, true))
+}
+
+/// Sets the order of the parts from a list of verse names (like `v1 c v2 c`).
+/// Names without a matching part are skipped, the order of the file is kept if no name matches.
+pub(crate) fn apply_verse_order(song: &mut Song, parts: &HashMap<String, PartReference>, file_order: Vec<PartReference>, verse_order: Option<&str>) {
+    let ordered_parts: Vec<PartReference> = verse_order
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|name| parse_verse_name(name).and_then(|(_, _, base_name)| parts.get(&base_name).cloned()))
+        .collect();
+    let part_order: Vec<PartReference> = match ordered_parts.is_empty() {
+        true => file_order,
+        false => ordered_parts,
+    };
+    if !part_order.is_empty() {
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(part_order)));
+    }
+}
+
+/// Imports a song from a str which contains the song in the OpenLyrics XML format.
+/// Verses with the same name in another language (`lang` attribute) are added as lyrics in that language to the same part.
+/// Chords are stored as separate `Chords` content (see `chordpro` module), comments are left out.
+/// # Returns
+/// A Result with the Song or an error if the XML is invalid or there are no lyrics
+/// # Example
+/// ```
+/// use cantara_songlib::importer::openlyrics::import_song;
+/// use cantara_songlib::song::SongPartType;
+/// let xml = r#"<song><properties><titles><title>Test</title></titles></properties>
+///     <lyrics><verse name="v1"><lines>First line<br/>Second line</lines></verse></lyrics></song>"#;
+/// let song = import_song(xml).unwrap();
+/// assert_eq!(song.title, "Test");
+/// let verse = song.get_parts_by_type(SongPartType::Verse)[0].clone();
+/// assert_eq!(verse.borrow().contents[0].content, "First line\nSecond line");
+/// ```
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    let document = Document::parse(content)?;
+    let root: Node = document.root_element();
+    let lyrics: Node = child(root, "lyrics").ok_or(CantaraImportNoContentError {})?;
+
+    let mut song = Song::new("");
+    if let Some(properties) = child(root, "properties") {
+        apply_properties(&mut song, properties);
+    }
+
+    let mut parts: HashMap<String, PartReference> = HashMap::new();
+    let mut file_order: Vec<PartReference> = Vec::new();
+    // The language of the first verse is regarded as the default language of the song
+    let mut default_language: Option<Option<&str>> = None;
+    for verse in children(lyrics, "verse") {
+        let Some((part, is_new)) = part_for_verse_name(&mut song, &mut parts, verse.attribute("name").unwrap_or("v")) else {
+            continue;
+        };
+        let language: LyricLanguage = match (default_language.get_or_insert(verse.attribute("lang")), verse.attribute("lang")) {
+            (default, language) if *default == language => LyricLanguage::Default,
+            (_, language) => LyricLanguage::Specific(language.unwrap_or_default().to_string()),
+        };
+        let mut text = String::new();
+        for lines_element in children(verse, "lines") {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            write_lines(lines_element, &mut text);
+        }
+        let lines: Vec<String> = text
+            .split('\n')
+            .map(|line| line.split(' ').filter(|word| !word.is_empty()).collect::<Vec<&str>>().join(" "))
+            .collect();
+        add_lyrics(&part, language, &lines);
+        if is_new {
+            file_order.push(part);
+        }
+    }
+    if song.get_total_part_count() == 0 {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+
+    let verse_order: Option<String> = child(root, "properties").and_then(|properties| child(properties, "verseOrder")).map(element_text);
+    apply_verse_order(&mut song, &parts, file_order, verse_order.as_deref());
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMAZING_GRACE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<song xmlns="http://openlyrics.info/namespace/2009/song" version="0.8" createdIn="OpenLP 2.4">
+  <properties>
+    <titles><title>Amazing Grace</title></titles>
+    <authors>
+      <author type="words">John Newton</author>
+      <author>Edwin Othello Excell</author>
+    </authors>
+    <copyright>Public Domain</copyright>
+    <ccliNo>22025</ccliNo>
+    <verseOrder>v1 c v2 c</verseOrder>
+  </properties>
+  <lyrics>
+    <verse name="v1">
+      <lines><chord name="G"/>Amazing grace how <chord name="C"/>sweet the sound<br/>
+        That saved a wretch like me</lines>
+    </verse>
+    <verse name="c">
+      <lines><comment>All</comment>Praise the Lord<br/>Amazing grace</lines>
+    </verse>
+    <verse name="v2">
+      <lines>'Twas grace that taught my heart to fear<br/>And grace my fears relieved</lines>
+    </verse>
+    <verse name="v2" lang="de">
+      <lines>Die Gnade lehrte mich die Furcht</lines>
+    </verse>
+  </lyrics>
+</song>"#;
+
+    #[test]
+    fn test_import_openlyrics() {
+        let song = import_song(AMAZING_GRACE).unwrap();
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.get_tag("author").unwrap(), "John Newton (words), Edwin Othello Excell");
+        assert_eq!(song.get_tag("ccli").unwrap(), "22025");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+
+        let first_verse = song.get_part_by_id("Verse.1").unwrap();
+        let first_verse = first_verse.borrow();
+        assert_eq!(first_verse.contents[0].content, "Amazing grace how sweet the sound\nThat saved a wretch like me");
+        assert_eq!(first_verse.get_content(SongPartContentType::Chords).unwrap().content, "G                 C\n");
+
+        let chorus = song.get_part_by_id("Chorus.1").unwrap();
+        assert_eq!(chorus.borrow().contents[0].content, "Praise the Lord\nAmazing grace");
+
+        let second_verse = song.get_part_by_id("Verse.2").unwrap();
+        let german = SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) };
+        assert_eq!(second_verse.borrow().get_content(german).unwrap().content, "Die Gnade lehrte mich die Furcht");
+
+        let order: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(order, vec!["Verse.1", "Chorus.1", "Verse.2", "Chorus.1"]);
+    }
+
+    #[test]
+    fn test_invalid_openlyrics() {
+        assert!(import_song("<song><lyrics></lyrics></song>").is_err());
+        assert!(import_song("<song><lyrics>").is_err());
+        assert_eq!(parse_verse_name("v1a"), Some((SongPartType::Verse, Some(1), "v1".to_string())));
+        assert_eq!(parse_verse_name("c"), Some((SongPartType::Chorus, None, "c1".to_string())));
+    }
+}
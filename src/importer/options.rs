@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::filetypes::FileType;
+
+/// The prefixes of comment lines in classic song files which are used by default (see `ImportOptions::comment_prefixes`)
+pub const DEFAULT_COMMENT_PREFIXES: [&str; 2] = ["//", ";"];
+
+/// Returns the default comment prefixes as owned strings, as they are stored in the options
+pub fn default_comment_prefixes() -> Vec<String> {
+    DEFAULT_COMMENT_PREFIXES.iter().map(|prefix| prefix.to_string()).collect()
+}
+
+/// Options which control how a song file is imported.
+/// Use `ImportOptions::default()` to get the default behaviour.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportOptions {
+    /// Forces the import to use this format instead of determining it from the file extension
+    pub format_override: Option<FileType>,
+    /// Defines what happens if a tag is set more than once in a file
+    pub duplicate_tag_policy: DuplicateTagPolicy,
+    /// NFC-normalizes tag values (e.g. a combining accent copied from a PDF becomes a precomposed character)
+    pub normalize_unicode: bool,
+    /// Uses the `#language` tag for the language of the lyrics and the `#translation` tag for
+    /// the language of secondary (`---`) blocks instead of `LyricLanguage::Default`
+    pub language_from_tags: bool,
+    /// Resolves tag values with placeholders like `{{book}} Nr. {{number}}` against the other tags after the import
+    pub resolve_tag_templates: bool,
+    /// Sets the `modified` tag from the modification time of the file if the song doesn't contain it
+    pub modified_from_file_time: bool,
+    /// Defines how a repeated block in a classic song file is recognized as chorus
+    pub chorus_matching: ChorusMatching,
+    /// The minimum number of (non-empty) lines a block needs to be recognized as repetition of an earlier block (and thereby as chorus).
+    /// Shorter blocks (like a repeated "Hallelujah" response) stay independent verses.
+    pub min_repetition_lines: usize,
+    /// The minimum number of characters (without surrounding whitespace) a block needs to be recognized as repetition of an earlier block
+    pub min_repetition_chars: usize,
+    /// Recognizes a label-only first line of a block (like `Chorus:` or `Strophe 2`) in classic song files,
+    /// uses it as the type of the part and removes it from the lyrics.
+    /// This is disabled by default, because some songs legitimately start with a word like "Chorus".
+    /// Part markers in parentheses (like `(Chorus)` or `(Strophe 2)`) are recognized regardless of this setting.
+    pub recognize_part_labels: bool,
+    /// Recognizes the printed number of a stanza (like `3.` or `3)` at the beginning of its first line) in classic song files,
+    /// uses it as the number of the part and removes it from the lyrics
+    pub recognize_verse_numbers: bool,
+    /// Recognizes lines which only consist of chord symbols (like `G  C  D` or `[G C D]`) in classic song files.
+    /// They are removed from the lyrics and stored as `Chords` content of the part, with one line per lyric line.
+    /// This is disabled by default, because a lyric line like "A" can't be told apart from a chord.
+    pub detect_chord_lines: bool,
+    /// Lines of classic song files which start with one of these prefixes (after optional whitespace) are comments.
+    /// By default, these are `//` and `;`. Groups whose lyrics legitimately start with `;` can remove it from the list.
+    pub comment_prefixes: Vec<String>,
+    /// Keeps comment lines of classic song files in the `comments` of the part they belong to, so that they can be exported again.
+    /// Comments outside of a stanza are attached to the following stanza (or the last one at the end of the file).
+    pub preserve_comments: bool,
+    /// Removes whitespace at the end of each line before the content is parsed (see `normalize_input`)
+    pub strip_trailing_whitespace: bool,
+    /// Defines which blank lines separate the stanzas of a classic song file
+    pub stanza_separator: StanzaSeparator,
+    /// Defines how the songs of a classic song file which contains several songs are separated.
+    /// If None, the whole file is one song. The single song import only takes the first song, see `import_songs_from_file_with_options`.
+    pub song_separator: Option<SongSeparator>,
+    /// Renames the tags of classic song and cssf files while they are imported (e.g. `#autor` becomes `author`),
+    /// so that templates like `{{author}}` find the tag regardless of the name the file uses. If None, tags keep their names.
+    pub tag_aliases: Option<TagAliasMap>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            format_override: None,
+            duplicate_tag_policy: DuplicateTagPolicy::default(),
+            normalize_unicode: false,
+            language_from_tags: true,
+            resolve_tag_templates: false,
+            modified_from_file_time: false,
+            chorus_matching: ChorusMatching::default(),
+            min_repetition_lines: 2,
+            min_repetition_chars: 0,
+            recognize_part_labels: false,
+            recognize_verse_numbers: false,
+            detect_chord_lines: false,
+            comment_prefixes: default_comment_prefixes(),
+            preserve_comments: false,
+            strip_trailing_whitespace: false,
+            stanza_separator: StanzaSeparator::default(),
+            song_separator: None,
+            tag_aliases: None,
+        }
+    }
+}
+
+/// Defines how a tag which is set more than once in a file (e.g. two `#title:` lines) is handled.
+/// An `ImportWarning::DuplicateTag` is emitted for every duplicate unless the policy is `Error`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DuplicateTagPolicy {
+    /// The first value is kept, later values are discarded
+    KeepFirst,
+    /// The last value is kept, earlier values are discarded
+    #[default]
+    KeepLast,
+    /// All values are kept, separated by `; `
+    Append,
+    /// A duplicate tag results in a parsing error
+    Error,
+}
+
+/// Defines how a repeated block in a classic song file is recognized as repetition of an earlier block (and thereby as chorus)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChorusMatching {
+    /// The blocks have to be equal (ignoring case only)
+    Exact,
+    /// The blocks are compared ignoring case, line wrapping, repeated whitespace and punctuation at the end of lines
+    #[default]
+    Normalized,
+    /// Like `Normalized`, but blocks with a similarity of at least the given value (between 0.0 and 1.0) match as well
+    Similar(f32),
+}
+
+/// Defines which blank lines separate the stanzas of a classic song file
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StanzaSeparator {
+    /// Every blank line starts a new stanza
+    #[default]
+    SingleBlankLine,
+    /// Only two or more consecutive blank lines start a new stanza, single blank lines are kept within the stanza
+    MultipleBlankLines,
+}
+
+/// Defines how the songs of a file which contains several songs are separated
+#[derive(Clone, Debug, PartialEq)]
+pub enum SongSeparator {
+    /// A form feed character (page break) starts a new song
+    FormFeed,
+    /// A line which only consists of the given text (like `***`) starts a new song
+    Line(String),
+}
+
+/// Maps alternative names of tags (aliases, like the German `autor`) to the tag names which are used by Cantara (like `author`).
+/// `TagAliasMap::default()` contains the common German and English aliases, `TagAliasMap::new()` creates an empty map.
+/// # Example
+/// ```
+/// use cantara_songlib::importer::TagAliasMap;
+/// let aliases = TagAliasMap::default().with_alias("dichter", "author");
+/// assert_eq!(aliases.resolve("Autor"), "author");
+/// assert_eq!(aliases.resolve("dichter"), "author");
+/// assert_eq!(aliases.resolve("copyright"), "copyright");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagAliasMap {
+    aliases: HashMap<String, String>,
+}
+
+impl TagAliasMap {
+    /// Creates a map without any aliases
+    pub fn new() -> TagAliasMap {
+        TagAliasMap { aliases: HashMap::new() }
+    }
+
+    /// Adds an alias (the name is case-insensitive) for the tag `to`. An existing alias with that name is replaced.
+    pub fn with_alias(mut self, from: &str, to: &str) -> TagAliasMap {
+        self.aliases.insert(from.to_lowercase(), to.to_lowercase());
+        self
+    }
+
+    /// Returns the name of the tag which the given name stands for (in lowercase).
+    /// Names which are no alias are returned unchanged (in lowercase).
+    pub fn resolve(&self, key: &str) -> String {
+        let key: String = key.to_lowercase();
+        self.aliases.get(&key).cloned().unwrap_or(key)
+    }
+}
+
+impl Default for TagAliasMap {
+    fn default() -> Self {
+        [
+            ("autor", "author"),
+            ("composer", "author"),
+            ("writer", "author"),
+            ("text", "author"),
+            ("verfasser", "author"),
+            ("titel", "title"),
+            ("jahr", "year"),
+            ("melodie", "melody"),
+            ("tune", "melody"),
+            ("sprache", "language"),
+            ("tonart", "key"),
+            ("liederbuch", "songbook"),
+        ]
+        .into_iter()
+        .fold(TagAliasMap::new(), |aliases, (from, to)| aliases.with_alias(from, to))
+    }
+}
@@ -0,0 +1,183 @@
+//! This module contains functions to import lyrics from plain text files without any markup,
+//! e.g. lyrics which have been copied from a website.
+//! The blocks of the text (separated by blank lines) become the parts of the song. Labels like `Chorus:` or `[Verse 2]`
+//! and printed verse numbers like `1.` are used for the parts, a repeated block is recognized as chorus
+//! like in classic song files (see `classic_song`). Noise which is typical for pasted lyrics
+//! (repetition marks like `x2` and label-only blocks like `[Chorus]`) is removed.
+//!
+//! # Example
+//! ```text
+//! Amazing Grace
+//!
+//! 1. Amazing grace how sweet the sound
+//! that saved a wretch like me
+//!
+//! Chorus:
+//! ...
+//! ```
+
+use std::error::Error;
+use std::sync::OnceLock;
+
+extern crate regex;
+use regex::Regex;
+
+use crate::importer::classic_song;
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::importer::{normalize_input, parse_part_label, ImportOptions};
+use crate::song::{Song, SongPartType};
+
+/// Returns the regex which matches a line which only contains a repetition mark like `x2`, `(2x)` or `[x3]`
+fn repetition_line_regex() -> &'static Regex {
+    static REPETITION_LINE_REGEX: OnceLock<Regex> = OnceLock::new();
+    REPETITION_LINE_REGEX.get_or_init(|| Regex::new(r"(?i)^\s*[(\[]?\s*(?:x\s*\d+|\d+\s*x)\s*[)\]]?\s*$").unwrap())
+}
+
+/// Returns the regex which matches a repetition mark at the end of a lyric line like `Hallelujah (x2)`
+fn repetition_suffix_regex() -> &'static Regex {
+    static REPETITION_SUFFIX_REGEX: OnceLock<Regex> = OnceLock::new();
+    REPETITION_SUFFIX_REGEX.get_or_init(|| Regex::new(r"(?i)\s+[(\[]\s*(?:x\s*\d+|\d+\s*x)\s*[)\]]\s*$").unwrap())
+}
+
+/// Removes the repetition marks of a block
+/// # Returns
+/// The lines of the block without repetition marks (empty if the block only consists of repetition marks)
+fn strip_noise(block: &str) -> Vec<String> {
+    block
+        .lines()
+        .filter(|line| !repetition_line_regex().is_match(line))
+        .map(|line| repetition_suffix_regex().replace(line, "").to_string())
+        .collect()
+}
+
+/// A block which started with a label, used to repeat it for a later label-only block
+struct LabeledBlock {
+    label: (SongPartType, Option<u32>),
+    lines: Vec<String>,
+}
+
+/// Returns whether a line is the title of the song: the only line of the first block which is no label and no lyrics with a verse number
+fn is_title_block(lines: &[String]) -> bool {
+    let verse_number_regex = {
+        static VERSE_NUMBER_REGEX: OnceLock<Regex> = OnceLock::new();
+        VERSE_NUMBER_REGEX.get_or_init(|| Regex::new(r"^\s*\d{1,3}[.)]\s").unwrap())
+    };
+    lines.len() == 1 && parse_part_label(&lines[0], &[]).is_none() && !verse_number_regex.is_match(&lines[0])
+}
+
+/// Imports a song from a str which contains the lyrics as plain text.
+/// The first line is used as title if it stands alone (followed by a blank line), otherwise `fallback_title` is used
+/// (most likely the filename, an empty title is completed with the filename by `import_song_from_file`).
+/// A label-only block like `[Chorus]` repeats the last block with that label,
+/// a label-only block without an earlier block of that label is used as label of the following block.
+/// # Returns
+/// A Result with the Song or an error if there are no lyrics
+/// # Example
+/// ```
+/// use cantara_songlib::importer::plaintext::import_song;
+/// use cantara_songlib::song::SongPartType;
+/// let song = import_song("My Song\n\n1. First verse\nwith two lines\n\nChorus:\nThe chorus\nof the song\n\n2. Second verse\nwith two lines\n\n[Chorus]", "Fallback").unwrap();
+/// assert_eq!(song.title, "My Song");
+/// assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+/// assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+/// assert_eq!(song.get_ordered_parts().len(), 4);
+/// ```
+pub fn import_song(content: &str, fallback_title: &str) -> Result<Song, Box<dyn Error>> {
+    let content = normalize_input(content, true);
+    let mut blocks: Vec<Vec<String>> = content
+        .split("\n\n")
+        .map(strip_noise)
+        .filter(|lines| lines.iter().any(|line| !line.trim().is_empty()))
+        .map(|lines| lines.into_iter().filter(|line| !line.trim().is_empty()).collect())
+        .collect();
+
+    let title: String = match blocks.len() > 1 && is_title_block(&blocks[0]) {
+        true => blocks.remove(0).remove(0).trim().to_string(),
+        false => fallback_title.to_string(),
+    };
+
+    let mut labeled_blocks: Vec<LabeledBlock> = Vec::new();
+    let mut pending_label: Option<String> = None;
+    let mut stanzas: Vec<Vec<String>> = Vec::new();
+    for mut lines in blocks {
+        if let Some(label) = pending_label.take() {
+            lines.insert(0, label);
+        }
+        let label = parse_part_label(&lines[0], &[]);
+        match (label, lines.len()) {
+            (Some(label), 1) => {
+                let repeated_block = labeled_blocks.iter().rev().find(|block| {
+                    block.label.0 == label.0 && (label.1.is_none() || block.label.1 == label.1)
+                });
+                match repeated_block {
+                    Some(block) => stanzas.push(block.lines.clone()),
+                    None => pending_label = Some(lines.remove(0)),
+                }
+            },
+            (Some(label), _) => {
+                labeled_blocks.push(LabeledBlock { label, lines: lines.clone() });
+                stanzas.push(lines);
+            },
+            (None, _) => stanzas.push(lines),
+        }
+    }
+    if stanzas.is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+
+    let options = ImportOptions {
+        recognize_part_labels: true,
+        recognize_verse_numbers: true,
+        ..ImportOptions::default()
+    };
+    let stanzas: Vec<String> = stanzas.iter().map(|lines| lines.join("\n")).collect();
+    let mut song: Song = classic_song::import_song_with_options(&stanzas.join("\n\n"), &options)?.song;
+    song.title = title;
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::import_song_from_file;
+
+    #[test]
+    fn test_strip_noise() {
+        assert_eq!(strip_noise("Hallelujah (x2)\nx2\n(2x)\nPraise the Lord [3x]"), vec!["Hallelujah", "Praise the Lord"]);
+        assert_eq!(strip_noise("Just max 2 lines"), vec!["Just max 2 lines"]);
+    }
+
+    #[test]
+    fn test_import_pasted_song() {
+        let song = import_song_from_file("testfiles/plaintext/Blessed Assurance.txt").unwrap();
+        assert_eq!(song.title, "Blessed Assurance");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 3);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+
+        let order: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(order, vec!["Verse.1", "Chorus.1", "Verse.2", "Chorus.1", "Verse.3", "Chorus.1"]);
+
+        // Neither the verse numbers nor the labels and repetition marks end up in the lyrics
+        let first_verse = song.get_part_by_id("Verse.1").unwrap().borrow().contents[0].content.clone();
+        assert!(first_verse.starts_with("Blessed assurance, Jesus is mine!\n"));
+        let chorus = song.get_part_by_id("Chorus.1").unwrap().borrow().contents[0].content.clone();
+        assert!(chorus.starts_with("This is my story, this is my song,\n"));
+        assert_eq!(chorus.lines().count(), 4);
+    }
+
+    #[test]
+    fn test_title_and_repeated_block() {
+        // Without a title line the fallback title is used, an unlabeled repetition becomes the chorus
+        let song = import_song("First verse\nline two\n\nThe chorus\nline two\n\nSecond verse\nline two\n\nThe chorus\nline two", "Fallback").unwrap();
+        assert_eq!(song.title, "Fallback");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+
+        // A label-only block before the lyrics is their label
+        let song = import_song("Title\n\n[Bridge]\n\nThe bridge", "").unwrap();
+        assert_eq!(song.title, "Title");
+        assert_eq!(song.get_part_count(SongPartType::Bridge), 1);
+
+        assert!(import_song("x2\n\n\n", "Empty").is_err());
+    }
+}
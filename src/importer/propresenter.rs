@@ -0,0 +1,276 @@
+//! This module contains functions to import songs from ProPresenter 6 files (`.pro6`).
+//! A ProPresenter 6 file is an XML document. The slides are grouped by `RVSlideGrouping` elements
+//! (named like `Verse 1` or `Chorus`), the text of every slide is stored as base64-encoded RTF.
+//! The CCLI attributes of the document (`CCLISongTitle`, `CCLIAuthor`, ...) contain the metadata of the song.
+//!
+//! # Example
+//! ```text
+//! <RVPresentationDocument CCLISongTitle="Amazing Grace" CCLIAuthor="John Newton">
+//!   <array rvXMLIvarName="groups">
+//!     <RVSlideGrouping name="Verse 1">
+//!       <array rvXMLIvarName="slides">
+//!         <RVDisplaySlide>
+//!           <array rvXMLIvarName="displayElements">
+//!             <RVTextElement>
+//!               <NSString rvXMLIvarName="RTFData">e1xydGYxXGFuc2kgQW1hemluZyBncmFjZX0=</NSString>
+//! ...
+//! ```
+
+use std::error::Error;
+use std::sync::OnceLock;
+
+extern crate regex;
+use base64::Engine;
+use regex::Regex;
+use roxmltree::{Document, Node};
+
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::song::{LyricLanguage, Song, SongPartContent, SongPartContentType, SongPartType};
+use crate::text::decode_windows_1252;
+
+fn group_name_regex() -> &'static Regex {
+    static GROUP_NAME_REGEX: OnceLock<Regex> = OnceLock::new();
+    GROUP_NAME_REGEX.get_or_init(|| Regex::new(r"^\s*(.*?)\s*(\d+)?\s*$").unwrap())
+}
+
+/// RTF groups which contain no text of the document (like the font table)
+const RTF_DESTINATIONS: [&str; 9] =
+    ["fonttbl", "colortbl", "expandedcolortbl", "stylesheet", "info", "pict", "listtable", "listoverridetable", "generator"];
+
+/// Returns the tag for an attribute of the document or None if the attribute contains no metadata
+fn tag_name(attribute: &str) -> Option<&'static str> {
+    match attribute {
+        "CCLISongTitle" => Some("title"),
+        "CCLIAuthor" => Some("author"),
+        "CCLICopyrightInfo" => Some("copyright"),
+        "CCLISongNumber" => Some("ccli"),
+        _ => None,
+    }
+}
+
+/// Strips RTF down to its plain text. Paragraphs and line breaks become line breaks,
+/// escaped characters (`\'fc`, `\u252`) are decoded and groups without text (like the font table) are skipped.
+pub(crate) fn rtf_to_text(rtf: &str) -> String {
+    let mut text = String::new();
+    let mut characters = rtf.chars().peekable();
+    // Whether the characters of the current group are skipped, for every open group
+    let mut skipped_groups: Vec<bool> = Vec::new();
+    let mut skipping: bool = false;
+    let mut group_start: bool = false;
+    // The number of characters which follow `\u` as a replacement for readers without unicode support
+    let mut unicode_replacement_length: usize = 1;
+    let mut skipped_replacement: usize = 0;
+
+    while let Some(character) = characters.next() {
+        match character {
+            '{' => {
+                skipped_groups.push(skipping);
+                group_start = true;
+                continue;
+            },
+            '}' => skipping = skipped_groups.pop().unwrap_or_default(),
+            '\\' => match characters.next() {
+                Some(first) if first.is_ascii_alphabetic() => {
+                    let mut word = String::from(first);
+                    while let Some(letter) = characters.next_if(char::is_ascii_alphabetic) {
+                        word.push(letter);
+                    }
+                    let mut parameter = String::new();
+                    if let Some(sign) = characters.next_if_eq(&'-') {
+                        parameter.push(sign);
+                    }
+                    while let Some(digit) = characters.next_if(char::is_ascii_digit) {
+                        parameter.push(digit);
+                    }
+                    // A space after a control word only delimits it
+                    characters.next_if_eq(&' ');
+
+                    if group_start && RTF_DESTINATIONS.contains(&word.as_str()) {
+                        skipping = true;
+                    }
+                    let parameter: Option<i32> = parameter.parse().ok();
+                    match word.as_str() {
+                        _ if skipping => {},
+                        "par" | "line" => text.push('\n'),
+                        "tab" => text.push('\t'),
+                        "uc" => unicode_replacement_length = parameter.unwrap_or(1).max(0) as usize,
+                        "u" => {
+                            // Code points above 32767 are written as negative numbers
+                            let code_point = parameter.unwrap_or_default().rem_euclid(65536) as u32;
+                            text.extend(char::from_u32(code_point));
+                            skipped_replacement = unicode_replacement_length;
+                        },
+                        _ => {},
+                    }
+                },
+                Some('*') => skipping |= group_start,
+                Some('\'') => {
+                    let hex: String = characters.by_ref().take(2).collect();
+                    if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                        if skipped_replacement > 0 {
+                            skipped_replacement -= 1;
+                        } else if !skipping {
+                            text.push_str(&decode_windows_1252(&[byte]));
+                        }
+                    }
+                },
+                // A backslash at the end of a line is a paragraph (written by macOS)
+                Some('\n' | '\r') if !skipping => text.push('\n'),
+                Some('~') if !skipping => text.push('\u{00A0}'),
+                Some(symbol @ ('\\' | '{' | '}')) if !skipping => text.push(symbol),
+                _ => {},
+            },
+            '\n' | '\r' => {},
+            _ if skipped_replacement > 0 => skipped_replacement -= 1,
+            character if !skipping => text.push(character),
+            _ => {},
+        }
+        group_start = false;
+    }
+    text
+}
+
+/// Parses the name of a slide group like `Verse 1`, `Chorus` or `Ending`.
+/// # Returns
+/// The type and the number (if given) of the part. Names which are no known part type become `SongPartType::Other`.
+fn parse_group_name(name: &str) -> (SongPartType, Option<u32>) {
+    let Some(captures) = group_name_regex().captures(name) else {
+        return (SongPartType::Other, None);
+    };
+    let part_type: SongPartType = match SongPartType::from_label(&captures[1]) {
+        Some(part_type) => part_type,
+        None => match captures[1].to_lowercase().as_str() {
+            "ending" | "tag" => SongPartType::Outro,
+            _ => SongPartType::Other,
+        },
+    };
+    (part_type, captures.get(2).and_then(|number| number.as_str().parse().ok()))
+}
+
+/// Returns the child element of a node which has the given `rvXMLIvarName`
+fn find_ivar<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.attribute("rvXMLIvarName") == Some(name))
+}
+
+/// Returns the lyrics of a slide: the text of its text elements, decoded from the RTF (or the plain text if there is no RTF)
+fn slide_text(slide: Node) -> String {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let texts: Vec<String> = slide
+        .descendants()
+        .filter(|node| node.has_tag_name("RVTextElement"))
+        .filter_map(|element| {
+            let node = find_ivar(element, "RTFData").or_else(|| find_ivar(element, "PlainText"))?;
+            let bytes: Vec<u8> = engine.decode(node.text()?.trim()).ok()?;
+            let decoded: String = String::from_utf8(bytes).unwrap_or_else(|error| decode_windows_1252(error.as_bytes()));
+            Some(match node.attribute("rvXMLIvarName") {
+                Some("RTFData") => rtf_to_text(&decoded),
+                _ => decoded,
+            })
+        })
+        .collect();
+    texts
+        .join("\n")
+        .replace("\r\n", "\n")
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// Imports a song from a str which contains a ProPresenter 6 document.
+/// Every slide group becomes a part, the slides of a group are joined with blank lines.
+/// `CCLISongTitle`, `CCLIAuthor`, `CCLICopyrightInfo` and `CCLISongNumber` are stored as tags.
+/// # Returns
+/// A Result with the Song or an error if the document is no valid XML or contains no lyrics
+/// # Example
+/// ```
+/// use cantara_songlib::importer::propresenter::import_song;
+/// use cantara_songlib::song::SongPartType;
+/// let content = r#"<RVPresentationDocument CCLISongTitle="Test"><array rvXMLIvarName="groups">
+///   <RVSlideGrouping name="Chorus"><array rvXMLIvarName="slides"><RVDisplaySlide><array rvXMLIvarName="displayElements">
+///     <RVTextElement><NSString rvXMLIvarName="RTFData">e1xydGYxXGFuc2kgVGhlIGNob3J1c30=</NSString></RVTextElement>
+///   </array></RVDisplaySlide></array></RVSlideGrouping>
+/// </array></RVPresentationDocument>"#;
+/// let song = import_song(content).unwrap();
+/// assert_eq!(song.title, "Test");
+/// let chorus = song.get_parts_by_type(SongPartType::Chorus)[0].clone();
+/// assert_eq!(chorus.borrow().contents[0].content, "The chorus");
+/// ```
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    let document = Document::parse(content)?;
+    let root = document.root_element();
+
+    let mut song = Song::new("");
+    for attribute in root.attributes() {
+        let value: &str = attribute.value().trim();
+        if let Some(tag) = tag_name(attribute.name()).filter(|_| !value.is_empty()) {
+            if tag == "title" {
+                song.title = value.to_string();
+            }
+            song.add_tag(tag, value);
+        }
+    }
+
+    for group in root.descendants().filter(|node| node.has_tag_name("RVSlideGrouping")) {
+        let slides: Vec<String> = group
+            .descendants()
+            .filter(|node| node.has_tag_name("RVDisplaySlide"))
+            .map(slide_text)
+            .filter(|text| !text.is_empty())
+            .collect();
+        if slides.is_empty() {
+            continue;
+        }
+
+        let (part_type, number) = parse_group_name(group.attribute("name").unwrap_or_default());
+        // A number which is already used is replaced by the next free one
+        let number: Option<u32> = number.filter(|number| song.get_part_by_id(&format!("{}.{}", part_type, number)).is_none());
+        song.add_part_of_type(part_type, number).borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: slides.join("\n\n"),
+        });
+    }
+    if song.get_total_part_count() == 0 {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::import_song_from_file;
+
+    #[test]
+    fn test_rtf_to_text() {
+        let rtf = "{\\rtf1\\ansi\\ansicpg1252\\cocoartf1561\n{\\fonttbl\\f0\\fswiss\\fcharset0 Helvetica;}\n{\\colortbl;\\red255\\green255\\blue255;}\n\
+            {\\*\\expandedcolortbl;;}\n\\pard\\qc\\f0\\fs120 \\cf1 Gro\\'dfer Gott\\\nwir loben dich\\par Herr, wir \\u234?\\'3f preisen {\\b deine} St\\u228 \\'e4rke}";
+        assert_eq!(rtf_to_text(rtf), "Großer Gott\nwir loben dich\nHerr, wir ê? preisen deine Stärke");
+        assert_eq!(parse_group_name("Verse 2"), (SongPartType::Verse, Some(2)));
+        assert_eq!(parse_group_name("Ending"), (SongPartType::Outro, None));
+        assert_eq!(parse_group_name("Group"), (SongPartType::Other, None));
+    }
+
+    #[test]
+    fn test_import_propresenter_file() {
+        let song = import_song_from_file("testfiles/propresenter/Amazing Grace.pro6").unwrap();
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.get_tag("author").unwrap(), "John Newton");
+        assert_eq!(song.get_tag("copyright").unwrap(), "Public Domain");
+        assert_eq!(song.get_tag("ccli").unwrap(), "22025");
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+
+        // The slides of a group are joined with blank lines
+        let first_verse = song.get_part_by_id("Verse.1").unwrap().borrow().contents[0].content.clone();
+        assert_eq!(first_verse, "Amazing grace how sweet the sound\nThat saved a wretch like me\n\nI once was lost but now am found\nWas blind but now I see");
+    }
+
+    #[test]
+    fn test_invalid_document() {
+        assert!(import_song("<RVPresentationDocument/>").is_err());
+        assert!(import_song("no xml").is_err());
+    }
+}
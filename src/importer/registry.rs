@@ -0,0 +1,383 @@
+//! This module contains the `SongImporter` trait, which connects a file format with its importer,
+//! and the `ImporterRegistry`, which chooses the importer for a file.
+//! Applications can register their own importers to support further (e.g. proprietary) formats.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::filetypes::{get_file_type_by_content, FileType};
+use crate::importer::classic_song::{self, slides_from_classic_song};
+use crate::importer::errors::{CantaraImportInvalidFileNameError, CantaraImportUnknownFileExtensionError};
+use crate::importer::{
+    complete_import, finish_file_import, import_song_from_string, import_song_from_string_with_options, normalize_input,
+    read_song_file, title_from_file_path, videopsalm, FileImportResult, ImportOptions, ImportReport, SongFile,
+};
+use crate::slides::{slides_from_song, LinkedEntity, PresentationChapter, SlideSettings};
+use crate::song::Song;
+use crate::songbook::{load_defaults, DEFAULTS_FILE_NAME};
+
+/// An importer for a file format
+/// # Example
+/// ```
+/// use std::error::Error;
+/// use cantara_songlib::importer::{ImporterRegistry, SongImporter};
+/// use cantara_songlib::song::Song;
+///
+/// struct HymnalImporter;
+///
+/// impl SongImporter for HymnalImporter {
+///     fn extensions(&self) -> &[&str] {
+///         &["hymn"]
+///     }
+///
+///     fn can_parse(&self, content: &str) -> bool {
+///         content.starts_with("HYMN ")
+///     }
+///
+///     fn import(&self, content: &str, fallback_title: &str) -> Result<Song, Box<dyn Error>> {
+///         let title = content.lines().next().and_then(|line| line.strip_prefix("HYMN ")).unwrap_or(fallback_title);
+///         Ok(Song::new(title))
+///     }
+/// }
+///
+/// let mut registry = ImporterRegistry::new();
+/// registry.register(HymnalImporter);
+/// assert!(registry.find_importer("songs/Amazing Grace.hymn", "HYMN Amazing Grace").is_some());
+/// ```
+pub trait SongImporter {
+    /// Returns the file extensions (lowercase, without a leading dot) of the files which are imported with this importer
+    fn extensions(&self) -> &[&str];
+
+    /// Returns whether the content looks like it is in the format of this importer.
+    /// This is used for files without an extension and to choose between importers of the same extension.
+    fn can_parse(&self, content: &str) -> bool;
+
+    /// Imports a song from the content of a file.
+    /// `fallback_title` is used as title if the content contains no title (most likely the file name).
+    fn import(&self, content: &str, fallback_title: &str) -> Result<Song, Box<dyn Error>>;
+
+    /// Returns the built-in file type of the importer or None for importers of other formats.
+    /// Built-in file types are imported with all `ImportOptions` and warnings.
+    fn file_type(&self) -> Option<FileType> {
+        None
+    }
+}
+
+/// The importer of a built-in file type
+struct BuiltinImporter {
+    file_type: FileType,
+}
+
+impl SongImporter for BuiltinImporter {
+    fn extensions(&self) -> &[&str] {
+        match self.file_type {
+            FileType::ClassicSongFile => &["song"],
+            FileType::CSSF => &["cssf"],
+            // SongSelect exports are usually downloaded as text files
+            FileType::CCLISongselectFile => &["ccli", "txt"],
+            FileType::ChordPro => &["cho", "chopro"],
+            FileType::SongBeamer => &["sng"],
+            FileType::UltraStar => &["txt"],
+            FileType::PlainText => &["txt"],
+            FileType::VideoPsalm => &["json"],
+            FileType::ProPresenter => &["pro6"],
+            FileType::CantaraJson => &["json"],
+        }
+    }
+
+    fn can_parse(&self, content: &str) -> bool {
+        match self.file_type {
+            FileType::VideoPsalm => videopsalm::is_videopsalm_songbook(content),
+            file_type => get_file_type_by_content(content) == Some(file_type),
+        }
+    }
+
+    fn import(&self, content: &str, fallback_title: &str) -> Result<Song, Box<dyn Error>> {
+        import_song_from_string(content, self.file_type, fallback_title)
+    }
+
+    fn file_type(&self) -> Option<FileType> {
+        Some(self.file_type)
+    }
+}
+
+/// The importers which can be used to import files.
+/// The importer of a file is chosen by the extension of the file. If several importers handle the extension,
+/// the one which has been registered last and can parse the content is used, otherwise the one which has been registered first.
+/// Files without an extension are imported with the last registered importer which can parse the content (or as classic song files).
+/// So a registered importer is preferred to the built-in ones for the content it can parse.
+pub struct ImporterRegistry {
+    importers: Vec<Box<dyn SongImporter>>,
+}
+
+impl Default for ImporterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImporterRegistry {
+    /// Creates a registry with the importers of all built-in file types
+    pub fn new() -> ImporterRegistry {
+        let mut registry = ImporterRegistry { importers: Vec::new() };
+        // Text files are plain lyrics unless they can be parsed as SongSelect export or UltraStar file,
+        // JSON files are serialized songs unless they are VideoPsalm songbooks
+        for file_type in [
+            FileType::ClassicSongFile,
+            FileType::CSSF,
+            FileType::PlainText,
+            FileType::CCLISongselectFile,
+            FileType::ChordPro,
+            FileType::SongBeamer,
+            FileType::UltraStar,
+            FileType::ProPresenter,
+            FileType::CantaraJson,
+            FileType::VideoPsalm,
+        ] {
+            registry.register(BuiltinImporter { file_type });
+        }
+        registry
+    }
+
+    /// Registers an importer. It is preferred to all importers which have been registered before for the content it can parse.
+    pub fn register(&mut self, importer: impl SongImporter + 'static) {
+        self.importers.push(Box::new(importer));
+    }
+
+    /// Returns the importers in the order of their preference
+    fn preferred_importers(&self) -> impl Iterator<Item = &dyn SongImporter> {
+        self.importers.iter().rev().map(|importer| importer.as_ref())
+    }
+
+    /// Finds the importer for a file (see `ImporterRegistry`)
+    /// # Returns
+    /// The importer or None if no importer handles the extension of the file
+    pub fn find_importer(&self, file_path: &str, content: &str) -> Option<&dyn SongImporter> {
+        match Path::new(file_path).extension() {
+            Some(extension) => {
+                let extension: String = extension.to_string_lossy().to_lowercase();
+                let candidates: Vec<&dyn SongImporter> = self
+                    .preferred_importers()
+                    .filter(|importer| importer.extensions().contains(&extension.as_str()))
+                    .collect();
+                candidates.iter().find(|importer| importer.can_parse(content)).or(candidates.last()).copied()
+            },
+            None => self
+                .preferred_importers()
+                .find(|importer| importer.can_parse(content))
+                .or_else(|| self.find_importer("song.song", content)),
+        }
+    }
+
+    /// Returns whether an importer has been registered for the extension of a file
+    pub fn supports_extension(&self, file_path: &Path) -> bool {
+        file_path.extension().is_some_and(|extension| {
+            let extension: String = extension.to_string_lossy().to_lowercase();
+            self.importers.iter().any(|importer| importer.extensions().contains(&extension.as_str()))
+        })
+    }
+
+    /// Finds the importer for a file or returns a `CantaraImportUnknownFileExtensionError`
+    fn importer_for_file(&self, file_path: &str, content: &str) -> Result<&dyn SongImporter, Box<dyn Error>> {
+        self.find_importer(file_path, &normalize_input(content, false)).ok_or_else(|| {
+            let file_extension = Path::new(file_path).extension().unwrap_or_default().to_string_lossy().to_string();
+            Box::new(CantaraImportUnknownFileExtensionError { file_extension }).into()
+        })
+    }
+
+    /// Imports a song from a file with the importer for the file (see `import_song_from_file`)
+    pub fn import_song_from_file(&self, file_path: &str) -> Result<Song, Box<dyn Error>> {
+        self.import_song_from_file_with_options(file_path, &ImportOptions::default())
+            .map(|report| report.song)
+    }
+
+    /// Imports a song from a file with the importer for the file and the given import options (see `import_song_from_file_with_options`).
+    /// A format which is forced with `ImportOptions::format_override` is imported with its built-in importer.
+    pub fn import_song_from_file_with_options(&self, file_path: &str, options: &ImportOptions) -> Result<ImportReport, Box<dyn Error>> {
+        let content: String = read_song_file(file_path)?;
+        let fallback_title: String = title_from_file_path(Path::new(file_path)).unwrap_or_default();
+
+        let file_type: FileType = match options.format_override {
+            Some(file_type) => file_type,
+            None => {
+                let importer = self.importer_for_file(file_path, &content)?;
+                match importer.file_type() {
+                    Some(file_type) => file_type,
+                    None => {
+                        let report = ImportReport { song: importer.import(&content, &fallback_title)?, warnings: Vec::new() };
+                        return finish_file_import(complete_import(report, &fallback_title, options), file_path, options);
+                    },
+                }
+            },
+        };
+        let report = import_song_from_string_with_options(&content, file_type, &fallback_title, options)?;
+        finish_file_import(report, file_path, options)
+    }
+
+    /// Imports all songs of a file with the importer for the file (see `import_songs_from_file_with_options`)
+    pub fn import_songs_from_file_with_options(&self, file_path: &str, options: &ImportOptions) -> Result<Vec<ImportReport>, Box<dyn Error>> {
+        let content: String = read_song_file(file_path)?;
+        let content = normalize_input(&content, options.strip_trailing_whitespace);
+        let fallback_title: String = title_from_file_path(Path::new(file_path)).unwrap_or_default();
+
+        let file_type: Option<FileType> = match options.format_override {
+            Some(file_type) => Some(file_type),
+            None => self.importer_for_file(file_path, &content)?.file_type(),
+        };
+        let reports: Vec<ImportReport> = match file_type {
+            Some(FileType::ClassicSongFile) => classic_song::import_songs_with_options(&content, options)?,
+            Some(FileType::VideoPsalm) => videopsalm::import_songbook(&content)?
+                .into_iter()
+                .map(|song| ImportReport { song, warnings: Vec::new() })
+                .collect(),
+            _ => return Ok(vec![self.import_song_from_file_with_options(file_path, options)?]),
+        };
+        reports
+            .into_iter()
+            .map(|report| finish_file_import(complete_import(report, &fallback_title, options), file_path, options))
+            .collect()
+    }
+
+    /// Imports all songs of every file of a directory with a supported extension (see `import_songs_from_directory`)
+    pub fn import_songs_from_directory(&self, dir: &Path, recursive: bool) -> io::Result<Vec<FileImportResult>> {
+        let mut paths: Vec<PathBuf> = Vec::new();
+        self.collect_song_files(dir, recursive, &mut paths)?;
+        paths.sort();
+
+        let mut directory_defaults: HashMap<PathBuf, HashMap<String, String>> = HashMap::new();
+        let mut results: Vec<FileImportResult> = Vec::new();
+        for path in paths {
+            let defaults = directory_defaults_of(dir, path.parent().unwrap_or(dir), &mut directory_defaults)?;
+            let reports = match path.to_str() {
+                Some(file_path) => self.import_songs_from_file_with_options(file_path, &ImportOptions::default()),
+                None => Err(CantaraImportInvalidFileNameError { file_path: path.to_string_lossy().to_string() }.into()),
+            };
+            match reports {
+                Ok(reports) => results.extend(reports.into_iter().map(|report| {
+                    let mut song: Song = report.song;
+                    song.inherit_tags(defaults);
+                    (path.clone(), Ok(song))
+                })),
+                Err(error) => results.push((path, Err(error))),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Adds the files of a directory with a supported extension to `paths` (and the files of its subdirectories if `recursive` is true).
+    /// Defaults files are left out.
+    fn collect_song_files(&self, dir: &Path, recursive: bool, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path: PathBuf = entry?.path();
+            if path.is_dir() {
+                if recursive {
+                    self.collect_song_files(&path, recursive, paths)?;
+                }
+            } else if self.supports_extension(&path) && path.file_name().is_some_and(|name| name != DEFAULTS_FILE_NAME) {
+                paths.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a presentation chapter from a file with the importer for the file (see `create_presentation_from_file`)
+    pub fn create_presentation_from_file(&self, file_path: &str, slide_settings: &SlideSettings) -> Result<PresentationChapter, Box<dyn Error>> {
+        let content: String = read_song_file(file_path)?;
+        let content = normalize_input(&content, false);
+        let file_type: Option<FileType> = self.importer_for_file(file_path, &content)?.file_type();
+
+        if file_type == Some(FileType::ClassicSongFile) && slide_settings.legacy_classic_slides {
+            let title: String = title_from_file_path(Path::new(file_path))
+                .ok_or_else(|| CantaraImportInvalidFileNameError { file_path: file_path.to_string() })?;
+            let slides = slides_from_classic_song(&content, slide_settings, title);
+            return Ok(PresentationChapter::new(slides, LinkedEntity::SongFile(SongFile::new(file_path)?)));
+        }
+
+        let options = ImportOptions { detect_chord_lines: slide_settings.strip_chord_lines, ..Default::default() };
+        let song: Song = self.import_song_from_file_with_options(file_path, &options)?.song;
+        let slides = slides_from_song(&song, slide_settings);
+        Ok(PresentationChapter::new(slides, LinkedEntity::Song(song)))
+    }
+}
+
+/// Returns the tags which the songs of a directory inherit: the tags of its defaults file and of the defaults files of
+/// its parent directories up to the imported directory `root` (the defaults of a subdirectory take precedence).
+/// The defaults are loaded once per directory and kept in `cache`.
+fn directory_defaults_of<'a>(
+    root: &Path,
+    dir: &Path,
+    cache: &'a mut HashMap<PathBuf, HashMap<String, String>>,
+) -> io::Result<&'a HashMap<String, String>> {
+    if !cache.contains_key(dir) {
+        let mut defaults: HashMap<String, String> = match dir.parent() {
+            Some(parent) if dir != root && dir.starts_with(root) => directory_defaults_of(root, parent, cache)?.clone(),
+            _ => HashMap::new(),
+        };
+        defaults.extend(load_defaults(&dir.join(DEFAULTS_FILE_NAME))?);
+        cache.insert(dir.to_path_buf(), defaults);
+    }
+    Ok(&cache[dir])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An importer for a made-up format, which uses the first line as title
+    struct TitleImporter;
+
+    impl SongImporter for TitleImporter {
+        fn extensions(&self) -> &[&str] {
+            &["title", "txt"]
+        }
+
+        fn can_parse(&self, content: &str) -> bool {
+            content.starts_with("TITLE ")
+        }
+
+        fn import(&self, content: &str, _fallback_title: &str) -> Result<Song, Box<dyn Error>> {
+            Ok(Song::new(content.lines().next().unwrap_or_default().trim_start_matches("TITLE ")))
+        }
+    }
+
+    #[test]
+    fn test_find_builtin_importer() {
+        let registry = ImporterRegistry::new();
+        let file_type = |file_path: &str, content: &str| registry.find_importer(file_path, content).and_then(|importer| importer.file_type());
+        assert_eq!(file_type("Song.song", "La la la"), Some(FileType::ClassicSongFile));
+        assert_eq!(file_type("Song.CHO", "La la la"), Some(FileType::ChordPro));
+        assert_eq!(file_type("Song.txt", "La la la"), Some(FileType::PlainText));
+        assert_eq!(file_type("Song.txt", "Test\n\nVerse 1\nLa la la\n\nCCLI Song # 1234"), Some(FileType::CCLISongselectFile));
+        assert_eq!(file_type("Song.json", "{Songs:[{Verses:[]}]}"), Some(FileType::VideoPsalm));
+        assert_eq!(file_type("Song.json", "{\"title\": \"Test\"}"), Some(FileType::CantaraJson));
+        assert_eq!(file_type("Song", "#title: Test\n\n#verse.1\nLa la la"), Some(FileType::CSSF));
+        assert_eq!(file_type("Song", ""), Some(FileType::ClassicSongFile));
+        assert!(registry.find_importer("Song.docx", "La la la").is_none());
+    }
+
+    #[test]
+    fn test_register_importer() {
+        let mut registry = ImporterRegistry::new();
+        registry.register(TitleImporter);
+
+        let directory = std::env::temp_dir().join(format!("cantara-songlib-registry-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file_path = directory.join("Custom.title");
+        std::fs::write(&file_path, "TITLE My Song\nLa la la").unwrap();
+        let song = registry.import_song_from_file(file_path.to_str().unwrap()).unwrap();
+        assert_eq!(song.title, "My Song");
+        // The default registry doesn't know the format
+        assert!(crate::importer::import_song_from_file(file_path.to_str().unwrap()).is_err());
+
+        // The registered importer is preferred for text files which it can parse, other text files are still plain lyrics
+        let file_path = directory.join("Lyrics.txt");
+        std::fs::write(&file_path, "First verse\nwith two lines").unwrap();
+        assert_eq!(registry.find_importer(file_path.to_str().unwrap(), "TITLE Test").unwrap().file_type(), None);
+        let chapter = registry.create_presentation_from_file(file_path.to_str().unwrap(), &SlideSettings::default()).unwrap();
+        assert!(matches!(chapter.linked_entity, LinkedEntity::Song(ref song) if song.title == "Lyrics"));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::filetypes::FileType;
+use crate::metadata::MetadataWarning;
+use crate::song::Song;
+
+/// A non-fatal problem which was found while importing a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum ImportWarning {
+    /// The file extension indicated another format than the content of the file.
+    /// The file has been imported as the detected format.
+    FormatMismatch {
+        extension_type: FileType,
+        detected_type: FileType,
+    },
+    /// A tag has been set more than once in the file.
+    /// `discarded` is None if the values have been appended.
+    DuplicateTag {
+        key: String,
+        kept: String,
+        discarded: Option<String>,
+        /// The line (starting with 1) of the duplicate tag
+        line: usize,
+    },
+    /// Two parts of the same type have been given the same number in the file (e.g. two stanzas starting with `3.`)
+    DuplicatePartNumber {
+        /// The ID of the part (e.g. "Verse.3")
+        part: String,
+        /// The line (starting with 1) of the first line of the second part
+        line: usize,
+    },
+    /// A stanza has fewer secondary (`---`) sections than the other stanzas or the `#translations` tag.
+    /// The missing sections have been added as empty lyrics.
+    MissingSecondarySections {
+        /// The ID of the part (e.g. "Verse.2")
+        part: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A line of a metadata block starts with `#`, but is no valid tag (like `#title Amazing Grace` without a colon).
+    /// The line has been ignored.
+    MalformedTag {
+        text: String,
+        /// The line (starting with 1) of the malformed tag
+        line: usize,
+    },
+    /// A tag has no value (like `#author:`). The tag has been ignored.
+    EmptyTag {
+        key: String,
+        /// The line (starting with 1) of the empty tag
+        line: usize,
+    },
+    /// A stanza is nearly the same as an earlier part, e.g. it differs only in punctuation.
+    /// `repetition` is true if the stanza has been treated as repetition of the part (and the part thereby as chorus),
+    /// false if it has been added as a part of its own.
+    SimilarStanza {
+        /// The ID of the earlier part (e.g. "Chorus.1")
+        part: String,
+        /// The line (starting with 1) of the first line of the stanza
+        line: usize,
+        repetition: bool,
+    },
+    /// The `#order` tag refers to a part which does not exist, the order of the file has been used instead
+    UnknownPartInOrder {
+        /// The reference as it is written in the tag (e.g. "V5")
+        reference: String,
+        /// The line (starting with 1) of the tag
+        line: usize,
+    },
+    /// A problem with the metadata of the song (e.g. a tag template which could not be resolved)
+    Metadata(MetadataWarning),
+    /// An item of a service file has been skipped, because it is no song (e.g. a bible passage or an image) or can't be read
+    SkippedServiceItem {
+        title: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ImportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportWarning::FormatMismatch { extension_type, detected_type } => write!(
+                f,
+                "The file extension indicates {:?}, but the content looks like {:?}. The file has been imported as {:?}.",
+                extension_type, detected_type, detected_type
+            ),
+            ImportWarning::DuplicateTag { key, kept, discarded: Some(discarded), line } => write!(
+                f,
+                "Line {}: the tag #{} has already been set. Kept \"{}\", discarded \"{}\".",
+                line, key, kept, discarded
+            ),
+            ImportWarning::DuplicateTag { key, kept, discarded: None, line } => write!(
+                f,
+                "Line {}: the tag #{} has already been set. The values have been joined to \"{}\".",
+                line, key, kept
+            ),
+            ImportWarning::DuplicatePartNumber { part, line } => write!(
+                f,
+                "Line {}: the part {} occurs more than once.",
+                line, part
+            ),
+            ImportWarning::MissingSecondarySections { part, expected, found } => write!(
+                f,
+                "The part {} has {} of {} secondary (---) sections. The missing sections have been left empty.",
+                part, found, expected
+            ),
+            ImportWarning::MalformedTag { text, line } => write!(
+                f,
+                "Line {}: \"{}\" is no valid tag (#name: value). The line has been ignored.",
+                line, text
+            ),
+            ImportWarning::EmptyTag { key, line } => write!(
+                f,
+                "Line {}: the tag #{} has no value. The tag has been ignored.",
+                line, key
+            ),
+            ImportWarning::SimilarStanza { part, line, repetition: true } => write!(
+                f,
+                "Line {}: the stanza differs slightly from {}, but has been treated as the same part.",
+                line, part
+            ),
+            ImportWarning::SimilarStanza { part, line, repetition: false } => write!(
+                f,
+                "Line {}: the stanza is nearly the same as {}, but has been added as a part of its own.",
+                line, part
+            ),
+            ImportWarning::UnknownPartInOrder { reference, line } => write!(
+                f,
+                "Line {}: the order refers to {}, which is no part of the song, the order of the file has been used instead.",
+                line, reference
+            ),
+            ImportWarning::Metadata(warning) => write!(
+                f,
+                "The tag #{} (\"{}\"): {}",
+                warning.tag, warning.value, warning.message
+            ),
+            ImportWarning::SkippedServiceItem { title, reason } => write!(
+                f,
+                "The service item \"{}\" has been skipped: {}",
+                title, reason
+            ),
+        }
+    }
+}
+
+impl ImportWarning {
+    /// Returns the line (starting with 1) of the file which the warning refers to or None if it refers to no single line
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ImportWarning::DuplicateTag { line, .. }
+            | ImportWarning::DuplicatePartNumber { line, .. }
+            | ImportWarning::MalformedTag { line, .. }
+            | ImportWarning::EmptyTag { line, .. }
+            | ImportWarning::SimilarStanza { line, .. }
+            | ImportWarning::UnknownPartInOrder { line, .. } => Some(*line),
+            _ => None,
+        }
+    }
+}
+
+/// The result of an import: the imported song and all warnings which occured during the import
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ImportReport {
+    pub song: Song,
+    pub warnings: Vec<ImportWarning>,
+}
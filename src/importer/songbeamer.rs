@@ -0,0 +1,233 @@
+//! This module contains functions to import songs from SongBeamer files (`.sng`).
+//! A SongBeamer file starts with header lines like `#Title=Amazing Grace`, followed by the parts of the song,
+//! which are separated by `---` lines. The first line of a part may be a label like `Verse 1` or `Chorus`.
+//! Older files are often encoded with Windows-1252, which is handled when the file is read (see `import_song_from_file`).
+//!
+//! # Example
+//! ```text
+//! #Title=Amazing Grace
+//! #Author=John Newton
+//! ---
+//! Verse 1
+//! Amazing grace how sweet the sound
+//! that saved a wretch like me
+//! ```
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+extern crate regex;
+use regex::Regex;
+
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::importer::{normalize_input, parse_part_label};
+use crate::song::{
+    LyricLanguage, PartOrder, PartOrderName, PartOrderRule, Song, SongPart, SongPartContent, SongPartContentType, SongPartType,
+};
+
+type PartReference = Rc<RefCell<SongPart>>;
+
+fn header_regex() -> &'static Regex {
+    static HEADER_REGEX: OnceLock<Regex> = OnceLock::new();
+    HEADER_REGEX.get_or_init(|| Regex::new(r"^#([^=\s]+)=(.*)$").unwrap())
+}
+
+/// Labels which are only used by SongBeamer (see `importer::parse_part_label`)
+const SONGBEAMER_LABELS: [(&str, SongPartType); 7] = [
+    ("coda", SongPartType::Outro),
+    ("ending", SongPartType::Outro),
+    ("misc", SongPartType::Other),
+    ("part", SongPartType::Other),
+    ("teil", SongPartType::Other),
+    ("unknown", SongPartType::Other),
+    ("unbekannt", SongPartType::Other),
+];
+
+/// Returns whether a line is a header line which is typical for SongBeamer files (like `#Title=...` or `#LangCount=1`)
+pub(crate) fn is_header_line(line: &str) -> bool {
+    header_regex()
+        .captures(line)
+        .is_some_and(|captures| matches!(&captures[1], "Title" | "LangCount" | "Editor" | "Version"))
+}
+
+/// Returns the tag for a header key or None if the key only contains presentation settings
+fn tag_name(key: &str) -> Option<&'static str> {
+    match key.to_lowercase().as_str() {
+        "title" => Some("title"),
+        "author" => Some("author"),
+        "melody" => Some("melody"),
+        "(c)" => Some("copyright"),
+        "ccli" => Some("ccli"),
+        "key" => Some("key"),
+        "bible" => Some("scripture"),
+        "categories" => Some("categories"),
+        "songbook" => Some("songbook"),
+        _ => None,
+    }
+}
+
+/// A part which has been imported together with its label (empty for parts without a label)
+struct LabeledPart {
+    label: String,
+    part: PartReference,
+}
+
+/// Adds a block of the file as a part to the song. A block with the same label and the same text as an
+/// earlier block is regarded as a repetition of that part.
+/// # Returns
+/// The part which occurs at the position of the block or None if the block has no text
+fn add_block(song: &mut Song, lines: &[&str], parts: &mut Vec<LabeledPart>) -> Option<PartReference> {
+    let (label, part_type, number, lyrics) = match lines.first().and_then(|line| parse_part_label(line, &SONGBEAMER_LABELS)) {
+        Some((part_type, number)) => (lines[0].trim().to_lowercase(), part_type, number, &lines[1..]),
+        None => (String::new(), SongPartType::Verse, None, lines),
+    };
+    let text: String = lyrics.join("\n").trim_matches('\n').to_string();
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    let lyrics_type = SongPartContentType::Lyrics { language: LyricLanguage::Default };
+    let repeated_part = parts.iter().find(|labeled_part| {
+        !label.is_empty()
+            && labeled_part.label == label
+            && labeled_part.part.borrow().get_content(lyrics_type.clone()).is_some_and(|content| content.content == text)
+    });
+    if let Some(repeated_part) = repeated_part {
+        return Some(repeated_part.part.clone());
+    }
+
+    // A number which is already used (e.g. by a second `Chorus 1` with a different text) is replaced by the next free one
+    let number: Option<u32> = number.filter(|number| song.get_part_by_id(&format!("{}.{}", part_type, number)).is_none());
+    let part = song.add_part_of_type(part_type, number);
+    part.borrow_mut().add_content(SongPartContent { voice_type: lyrics_type, content: text });
+    parts.push(LabeledPart { label, part: part.clone() });
+    Some(part)
+}
+
+/// Imports a song from a str which contains the song in the SongBeamer format.
+/// The header keys are mapped to tags (`#(c)` to `copyright`, `#Bible` to `scripture`), settings like fonts are ignored.
+/// The order of the parts is taken from `#VerseOrder` if it is given, otherwise from the order of the blocks.
+/// # Returns
+/// A Result with the Song or an error if there is no content
+/// # Example
+/// ```
+/// use cantara_songlib::importer::songbeamer::import_song;
+/// use cantara_songlib::song::SongPartType;
+/// let song = import_song("#Title=Test\n---\nVerse 1\nFirst verse\n---\nChorus\nThe chorus").unwrap();
+/// assert_eq!(song.title, "Test");
+/// assert_eq!(song.get_part_count(SongPartType::Verse), 1);
+/// assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+/// ```
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    let content = normalize_input(content, true);
+    if content.trim().is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+
+    let mut song = Song::new("");
+    let mut verse_order: Option<String> = None;
+    let mut blocks: Vec<Vec<&str>> = vec![Vec::new()];
+    let mut in_header: bool = true;
+
+    for line in content.lines() {
+        if line == "---" {
+            in_header = false;
+            blocks.push(Vec::new());
+            continue;
+        }
+        if in_header {
+            if let Some(captures) = header_regex().captures(line) {
+                let value: &str = captures[2].trim();
+                if captures[1].eq_ignore_ascii_case("VerseOrder") {
+                    verse_order = Some(value.to_string());
+                } else if let Some(tag) = tag_name(&captures[1]).filter(|_| !value.is_empty()) {
+                    if tag == "title" {
+                        song.title = value.to_string();
+                    }
+                    song.add_tag(tag, value);
+                }
+                continue;
+            }
+            in_header = false;
+        }
+        // `--` separates two slides of the same part
+        if line != "--" {
+            blocks.last_mut().unwrap().push(line);
+        }
+    }
+
+    let mut parts: Vec<LabeledPart> = Vec::new();
+    let mut part_order: Vec<PartReference> = blocks
+        .iter()
+        .filter_map(|block| add_block(&mut song, block, &mut parts))
+        .collect();
+
+    if let Some(verse_order) = verse_order {
+        let ordered_parts: Vec<PartReference> = verse_order
+            .split(',')
+            .filter_map(|label| {
+                let label: String = label.trim().to_lowercase();
+                parts.iter().find(|labeled_part| labeled_part.label == label).map(|labeled_part| labeled_part.part.clone())
+            })
+            .collect();
+        if !ordered_parts.is_empty() {
+            part_order = ordered_parts;
+        }
+    }
+    if !part_order.is_empty() {
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(part_order)));
+    }
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::import_song_from_file;
+
+    #[test]
+    fn test_import_windows_1252_file() {
+        let song = import_song_from_file("testfiles/songbeamer/Großer Gott.sng").unwrap();
+        assert_eq!(song.title, "Großer Gott, wir loben dich");
+        assert_eq!(song.get_tag("author").unwrap(), "Ignaz Franz");
+        assert_eq!(song.get_tag("copyright").unwrap(), "Public Domain");
+        assert_eq!(song.get_tag("font"), None);
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+
+        let second_verse = song.get_part_by_id("Verse.2").unwrap();
+        let lyrics = second_verse.borrow().contents[0].content.clone();
+        assert!(lyrics.starts_with("Alles, was dich preisen kann,\n"));
+        assert!(lyrics.ends_with("„Heilig, heilig, heilig“ zu."));
+        // The slide break of the first verse is no lyrics
+        let first_verse = song.get_part_by_id("Verse.1").unwrap();
+        assert_eq!(first_verse.borrow().contents[0].content.lines().count(), 6);
+    }
+
+    #[test]
+    fn test_repeated_chorus() {
+        let content = "#Title=Test\n---\nVerse 1\nFirst verse\n---\nChorus\nThe chorus\n---\nVerse 2\nSecond verse\n---\nChorus\nThe chorus";
+        let song = import_song(content).unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 1);
+        let order: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(order, vec!["Verse.1", "Chorus.1", "Verse.2", "Chorus.1"]);
+
+        // A chorus with a different text is a new part
+        let song = import_song("---\nChorus\nThe chorus\n---\nChorus\nAnother chorus").unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 2);
+    }
+
+    #[test]
+    fn test_verse_order() {
+        let content = "#Title=Test\n#VerseOrder=Chorus,Verse 1,Chorus\n---\nVerse 1\nFirst verse\n---\nChorus\nThe chorus";
+        let song = import_song(content).unwrap();
+        let order: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(order, vec!["Chorus.1", "Verse.1", "Chorus.1"]);
+
+        // Blocks without a label are verses
+        let song = import_song("First verse\n---\nSecond verse").unwrap();
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+        assert!(import_song("\n").is_err());
+    }
+}
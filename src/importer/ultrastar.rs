@@ -0,0 +1,224 @@
+//! This module contains functions to extract the lyrics of UltraStar karaoke files (`.txt`).
+//! An UltraStar file starts with header lines like `#TITLE:` and `#ARTIST:`, followed by one line per sung note
+//! (`: 0 4 60 Amaz` is a note starting at beat 0 with a length of 4 beats, the pitch 60 and the syllable `Amaz`).
+//! Lines starting with `-` mark the end of a lyric line, `E` marks the end of the song.
+//! The timing is only used to find the verses of the song, it is not imported.
+//!
+//! # Example
+//! ```text
+//! #TITLE:Amazing Grace
+//! #ARTIST:John Newton
+//! #BPM:200
+//! : 0 4 60 A
+//! : 4 4 62 ma
+//! : 8 4 64 zing
+//! * 12 8 65  grace
+//! - 22
+//! : 24 4 64 how
+//! E
+//! ```
+
+use std::error::Error;
+use std::sync::OnceLock;
+
+extern crate regex;
+use regex::Regex;
+
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::importer::normalize_input;
+use crate::song::{LyricLanguage, Song, SongPartContent, SongPartContentType, SongPartType};
+
+fn note_regex() -> &'static Regex {
+    static NOTE_REGEX: OnceLock<Regex> = OnceLock::new();
+    // The syllable keeps its spaces, because they mark the boundaries of the words
+    NOTE_REGEX.get_or_init(|| Regex::new(r"^([:*FRG]) +(-?\d+) +(\d+) +(-?\d+) ?(.*)$").unwrap())
+}
+
+fn line_break_regex() -> &'static Regex {
+    static LINE_BREAK_REGEX: OnceLock<Regex> = OnceLock::new();
+    LINE_BREAK_REGEX.get_or_init(|| Regex::new(r"^- *(-?\d+)(?: +(-?\d+))?\s*$").unwrap())
+}
+
+/// Returns whether the content looks like an UltraStar file: it starts with a `#TITLE:` header and contains notes
+pub(crate) fn is_ultrastar_file(content: &str) -> bool {
+    content.trim_start().starts_with("#TITLE:") && content.lines().any(|line| note_regex().is_match(line))
+}
+
+/// Returns the tag for a header key or None if the key only belongs to the karaoke game (like `#MP3` or `#BPM`)
+fn tag_name(key: &str) -> Option<&'static str> {
+    match key.to_uppercase().as_str() {
+        "TITLE" => Some("title"),
+        "ARTIST" => Some("author"),
+        "YEAR" => Some("year"),
+        "LANGUAGE" => Some("language"),
+        "GENRE" => Some("genre"),
+        _ => None,
+    }
+}
+
+/// Appends a syllable to a lyric line. The words are separated by the spaces of the syllables,
+/// a syllable without spaces belongs to the same word as the previous one.
+/// `~` continues the previous syllable on another note and adds no text.
+fn append_syllable(line: &mut String, syllable: &str) {
+    let syllable: &str = syllable.strip_prefix('~').unwrap_or(syllable);
+    let text: &str = syllable.trim();
+    if syllable.starts_with(' ') && !line.is_empty() && !line.ends_with(' ') {
+        line.push(' ');
+    }
+    line.push_str(text);
+    if syllable.ends_with(' ') && !text.is_empty() {
+        line.push(' ');
+    }
+}
+
+/// A lyric line together with the pause (in beats) before it
+struct LyricLine {
+    text: String,
+    pause: i64,
+}
+
+/// Groups the lyric lines into verses. A verse ends before a line with a pause which is
+/// at least three times as long as the median pause between the lines.
+fn group_verses(lines: Vec<LyricLine>) -> Vec<Vec<String>> {
+    let mut pauses: Vec<i64> = lines.iter().skip(1).map(|line| line.pause).collect();
+    pauses.sort();
+    let median_pause: i64 = pauses.get(pauses.len() / 2).copied().unwrap_or_default().max(1);
+
+    let mut verses: Vec<Vec<String>> = Vec::new();
+    for (index, line) in lines.into_iter().enumerate() {
+        if index == 0 || line.pause >= 3 * median_pause {
+            verses.push(Vec::new());
+        }
+        verses.last_mut().unwrap().push(line.text);
+    }
+    verses
+}
+
+/// Imports the lyrics of a song from a str which contains an UltraStar karaoke file.
+/// The syllables of the notes are joined to lyric lines at the `-` line breaks, the lines are grouped into verses
+/// at long pauses. Only the first singer of duets is imported. `#TITLE` and `#ARTIST` are stored as `title` and `author`.
+/// # Returns
+/// A Result with the Song or an error if there are no lyrics
+/// # Example
+/// ```
+/// use cantara_songlib::importer::ultrastar::import_song;
+/// use cantara_songlib::song::SongPartType;
+/// let song = import_song("#TITLE:Test\n#ARTIST:Somebody\n: 0 2 60 He\n: 2 2 60 llo\n: 4 2 60  world\nE").unwrap();
+/// assert_eq!(song.title, "Test");
+/// let verse = song.get_parts_by_type(SongPartType::Verse)[0].clone();
+/// assert_eq!(verse.borrow().contents[0].content, "Hello world");
+/// ```
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    let content = normalize_input(content, false);
+    let mut song = Song::new("");
+    let mut relative: bool = false;
+
+    let mut lines: Vec<LyricLine> = Vec::new();
+    let mut current = LyricLine { text: String::new(), pause: 0 };
+    // The beat of the end of the last note and the beat which the notes are relative to (in relative mode)
+    let mut last_note_end: i64 = 0;
+    let mut offset: i64 = 0;
+    let mut current_start: Option<i64> = None;
+    let mut first_singer: bool = true;
+
+    for line in content.lines() {
+        if let Some((key, value)) = line.strip_prefix('#').and_then(|header| header.split_once(':')) {
+            let value: &str = value.trim();
+            if key.eq_ignore_ascii_case("RELATIVE") {
+                relative = value.eq_ignore_ascii_case("yes");
+            } else if let Some(tag) = tag_name(key).filter(|_| !value.is_empty()) {
+                if tag == "title" {
+                    song.title = value.to_string();
+                }
+                song.add_tag(tag, value);
+            }
+        } else if let Some(captures) = note_regex().captures(line) {
+            if !first_singer {
+                continue;
+            }
+            let start: i64 = offset + captures[2].parse::<i64>().unwrap_or_default();
+            let length: i64 = captures[3].parse().unwrap_or_default();
+            if current_start.is_none() {
+                current_start = Some(start);
+                current.pause = start - last_note_end;
+            }
+            append_syllable(&mut current.text, &captures[5]);
+            last_note_end = start + length;
+        } else if let Some(captures) = line_break_regex().captures(line) {
+            if !first_singer {
+                continue;
+            }
+            if relative {
+                // The second number (or the first one, if there is only one) is the beat the next notes are relative to
+                offset += captures.get(2).unwrap_or(captures.get(1).unwrap()).as_str().parse::<i64>().unwrap_or_default();
+            }
+            current_start = None;
+            let text = std::mem::take(&mut current.text);
+            lines.push(LyricLine { text: text.trim().to_string(), pause: current.pause });
+        } else if let Some(singer) = line.strip_prefix('P') {
+            // `P1` and `P2` are the singers of a duet, `P3` are both
+            first_singer = matches!(singer.trim(), "1" | "3");
+        } else if line.trim() == "E" {
+            break;
+        }
+    }
+    if !current.text.trim().is_empty() {
+        lines.push(LyricLine { text: current.text.trim().to_string(), pause: current.pause });
+    }
+    lines.retain(|line| !line.text.is_empty());
+    if lines.is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+
+    for verse in group_verses(lines) {
+        song.add_part_of_type(SongPartType::Verse, None).borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: verse.join("\n"),
+        });
+    }
+    Ok(song)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::import_song_from_file;
+
+    #[test]
+    fn test_append_syllable() {
+        let mut line = String::new();
+        for syllable in ["A", "ma", "zing", " grace", " how", "~", " sweet ", "the ", "sound"] {
+            append_syllable(&mut line, syllable);
+        }
+        assert_eq!(line, "Amazing grace how sweet the sound");
+
+        let mut line = String::new();
+        for syllable in ["Oh ", "~ ", "yes"] {
+            append_syllable(&mut line, syllable);
+        }
+        assert_eq!(line, "Oh yes");
+    }
+
+    #[test]
+    fn test_import_ultrastar_file() {
+        let song = import_song_from_file("testfiles/ultrastar/Amazing Grace.txt").unwrap();
+        assert_eq!(song.title, "Amazing Grace");
+        assert_eq!(song.get_tag("author").unwrap(), "John Newton");
+        assert_eq!(song.get_tag("mp3"), None);
+        assert_eq!(song.get_part_count(SongPartType::Verse), 2);
+
+        let first_verse = song.get_part_by_id("Verse.1").unwrap().borrow().contents[0].content.clone();
+        assert_eq!(first_verse, "Amazing grace how sweet the sound\nThat saved a wretch like me");
+        let second_verse = song.get_part_by_id("Verse.2").unwrap().borrow().contents[0].content.clone();
+        assert_eq!(second_verse, "I once was lost but now am found\nWas blind but now I see");
+    }
+
+    #[test]
+    fn test_relative_duet() {
+        let content = "#TITLE:Duet\n#RELATIVE:YES\nP1\n: 0 2 60 First\n- 4 6\n: 0 2 60 Se\n: 2 2 60 cond\nP2\n: 0 2 60 Other\nE";
+        let song = import_song(content).unwrap();
+        let verse = song.get_part_by_id("Verse.1").unwrap().borrow().contents[0].content.clone();
+        assert_eq!(verse, "First\nSecond");
+        assert!(import_song("#TITLE:Empty\nE").is_err());
+    }
+}
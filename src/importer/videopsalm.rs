@@ -0,0 +1,220 @@
+//! This module contains functions to import the songs of a VideoPsalm songbook (`.json`).
+//! A VideoPsalm songbook looks like JSON, but is actually a JavaScript object: the keys are not quoted
+//! and the texts contain raw line breaks. Every entry of `Songs` is a song with its `Verses`.
+//!
+//! # Example
+//! ```text
+//! {Guid:"...",Text:"My Songbook",Songs:[{ID:1,Text:"Amazing Grace",Author:"John Newton",Verses:[{ID:1,Text:"Amazing grace
+//! how sweet the sound"}]}]}
+//! ```
+
+use std::error::Error;
+
+use serde_json::Value;
+
+use crate::importer::errors::CantaraImportNoContentError;
+use crate::importer::normalize_input;
+use crate::song::{LyricLanguage, Song, SongPartContent, SongPartContentType, SongPartType};
+
+/// Turns the JavaScript object of a VideoPsalm songbook into valid JSON:
+/// unquoted keys are quoted, line breaks and control characters inside of strings are escaped
+/// and trailing commas are removed.
+fn to_json(content: &str) -> String {
+    let mut json = String::with_capacity(content.len());
+    let mut characters = content.chars().peekable();
+    let mut in_string: bool = false;
+
+    while let Some(character) = characters.next() {
+        if in_string {
+            match character {
+                '\\' => {
+                    json.push(character);
+                    if let Some(escaped) = characters.next() {
+                        json.push(escaped);
+                    }
+                },
+                '"' => {
+                    in_string = false;
+                    json.push(character);
+                },
+                '\n' => json.push_str("\\n"),
+                '\t' => json.push_str("\\t"),
+                character if character < ' ' => json.push(' '),
+                character => json.push(character),
+            }
+            continue;
+        }
+        match character {
+            '"' => {
+                in_string = true;
+                json.push(character);
+            },
+            ',' => {
+                // A trailing comma is no valid JSON
+                let next = characters.clone().find(|next| !next.is_whitespace());
+                if !matches!(next, Some('}' | ']')) {
+                    json.push(character);
+                }
+            },
+            character if character.is_alphabetic() || character == '_' => {
+                let mut word = String::from(character);
+                while let Some(&next) = characters.peek().filter(|next| next.is_alphanumeric() || **next == '_') {
+                    word.push(next);
+                    characters.next();
+                }
+                // Keys are followed by a colon, other words are values like `true` or `null`
+                match characters.clone().find(|next| !next.is_whitespace()) {
+                    Some(':') => json.push_str(&format!("\"{}\"", word)),
+                    _ => json.push_str(&word),
+                }
+            },
+            character => json.push(character),
+        }
+    }
+    json
+}
+
+/// Returns a string value of a VideoPsalm object (trimmed) or None if it is missing or empty
+fn get_text<'a>(object: &'a Value, key: &str) -> Option<&'a str> {
+    object.get(key).and_then(Value::as_str).map(str::trim).filter(|text| !text.is_empty())
+}
+
+/// Removes the chords (like `[G]`) from the text of a verse
+fn remove_chords(text: &str) -> String {
+    let mut lyrics = String::with_capacity(text.len());
+    let mut in_chord: bool = false;
+    for character in text.chars() {
+        match character {
+            '[' => in_chord = true,
+            ']' if in_chord => in_chord = false,
+            character if !in_chord => lyrics.push(character),
+            _ => {},
+        }
+    }
+    lyrics
+}
+
+/// Creates a song from an entry of the songbook
+/// # Returns
+/// The song or None if the entry has no lyrics
+fn import_entry(entry: &Value, songbook_name: Option<&str>) -> Option<Song> {
+    let mut song = Song::new(get_text(entry, "Text").unwrap_or_default());
+
+    let author: Option<&str> = get_text(entry, "Author");
+    let composer: Option<&str> = get_text(entry, "Composer").filter(|composer| Some(*composer) != author);
+    let authors: Vec<String> = author
+        .map(str::to_string)
+        .into_iter()
+        .chain(composer.map(|composer| format!("{} (music)", composer)))
+        .collect();
+    if !authors.is_empty() {
+        song.add_tag("author", &authors.join(", "));
+    }
+    if let Some(copyright) = get_text(entry, "Copyright") {
+        song.add_tag("copyright", &copyright.split_whitespace().collect::<Vec<&str>>().join(" "));
+    }
+    if let Some(ccli) = get_text(entry, "CCLI") {
+        song.add_tag("ccli", ccli);
+    }
+    if let Some(songbook_name) = songbook_name {
+        song.add_tag("songbook", songbook_name);
+    }
+
+    for verse in entry.get("Verses").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default() {
+        let Some(text) = verse.get("Text").and_then(Value::as_str) else {
+            continue;
+        };
+        let lyrics: String = remove_chords(text).lines().map(str::trim_end).collect::<Vec<&str>>().join("\n");
+        let lyrics: &str = lyrics.trim_matches('\n');
+        if lyrics.trim().is_empty() {
+            continue;
+        }
+        song.add_part_of_type(SongPartType::Verse, None).borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: lyrics.to_string(),
+        });
+    }
+    (song.get_total_part_count() > 0).then_some(song)
+}
+
+/// Returns whether the content looks like a VideoPsalm songbook: an object which contains songs with verses
+pub(crate) fn is_videopsalm_songbook(content: &str) -> bool {
+    content.trim_start().starts_with('{') && content.contains("Songs") && content.contains("Verses")
+}
+
+/// Imports all songs of a VideoPsalm songbook in their order.
+/// The verses of a song become its parts, `Author`, `Composer`, `Copyright` and `CCLI` are stored as tags
+/// and the name of the songbook is stored in the `songbook` tag. Songs without lyrics are skipped.
+/// # Returns
+/// A Result with the songs or an error if the content can't be parsed or contains no songs
+/// # Example
+/// ```
+/// use cantara_songlib::importer::videopsalm::import_songbook;
+/// let songs = import_songbook("{Text:\"Book\",Songs:[{Text:\"Test\",Author:\"Somebody\",Verses:[{ID:1,Text:\"First line\nSecond line\"}]}]}").unwrap();
+/// assert_eq!(songs[0].title, "Test");
+/// assert_eq!(songs[0].get_tag("author").unwrap(), "Somebody");
+/// assert_eq!(songs[0].get_tag("songbook").unwrap(), "Book");
+/// ```
+pub fn import_songbook(content: &str) -> Result<Vec<Song>, Box<dyn Error>> {
+    let content = normalize_input(content, false);
+    let songbook: Value = serde_json::from_str(to_json(&content).trim())?;
+    let songbook_name: Option<&str> = get_text(&songbook, "Text");
+
+    let songs: Vec<Song> = songbook
+        .get("Songs")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| import_entry(entry, songbook_name))
+        .collect();
+    if songs.is_empty() {
+        return Err(Box::new(CantaraImportNoContentError {}));
+    }
+    Ok(songs)
+}
+
+/// Imports the first song of a VideoPsalm songbook, use `import_songbook` to import all songs.
+/// # Returns
+/// A Result with the Song or an error if the content can't be parsed or contains no songs
+pub fn import_song(content: &str) -> Result<Song, Box<dyn Error>> {
+    Ok(import_songbook(content)?.remove(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::importer::{import_songs_from_file_with_options, ImportOptions};
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(to_json("{ID:1,Text:\"a\nb\",Empty:null,}"), "{\"ID\":1,\"Text\":\"a\\nb\",\"Empty\":null}");
+        // Colons and escaped quotes inside of strings are kept
+        assert_eq!(to_json("{Text:\"Note: \\\"x\\\"\"}"), "{\"Text\":\"Note: \\\"x\\\"\"}");
+        assert_eq!(remove_chords("[G]Amazing [C]grace"), "Amazing grace");
+    }
+
+    #[test]
+    fn test_import_songbook_file() {
+        let reports = import_songs_from_file_with_options("testfiles/videopsalm/Hymns.json", &ImportOptions::default()).unwrap();
+        let titles: Vec<&str> = reports.iter().map(|report| report.song.title.as_str()).collect();
+        assert_eq!(titles, vec!["Amazing Grace", "Blessed Assurance"]);
+
+        let amazing_grace = &reports[0].song;
+        assert_eq!(amazing_grace.get_tag("author").unwrap(), "John Newton");
+        assert_eq!(amazing_grace.get_tag("copyright").unwrap(), "Public Domain");
+        assert_eq!(amazing_grace.get_tag("songbook").unwrap(), "Hymns");
+        assert_eq!(amazing_grace.get_part_count(SongPartType::Verse), 2);
+        let first_verse = amazing_grace.get_part_by_id("Verse.1").unwrap().borrow().contents[0].content.clone();
+        assert_eq!(first_verse, "Amazing grace how sweet the sound\nThat saved a wretch like me");
+
+        let blessed_assurance = &reports[1].song;
+        assert_eq!(blessed_assurance.get_tag("author").unwrap(), "Fanny Crosby, Phoebe Knapp (music)");
+    }
+
+    #[test]
+    fn test_invalid_songbook() {
+        assert!(import_songbook("{Text:\"Empty\",Songs:[]}").is_err());
+        assert!(import_songbook("{Songs:[{Text:\"Broken\"").is_err());
+    }
+}
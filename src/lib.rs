@@ -13,22 +13,51 @@ Due to legacy reasons, the crate also supports the import of songs from other fo
 At the moment, the following import formats are supported:
 - The Cantara classic song format (lyrics only), see [`crate::importer::classic-song`] module.
 - The cssf song format (lyrics and scores), see cssf_song module. (under construction)
-- the CCLI song format (lyrics only), see ccli_song module. (under construction)
+- the plain text export of CCLI SongSelect (lyrics only), see ccli_song module.
+- ChordPro files (lyrics and chords), see chordpro module.
+- SongBeamer files (lyrics only), see songbeamer module.
+- OpenLyrics XML files and the songs of OpenLP service files, see openlyrics and openlp_service modules.
+- The lyrics of UltraStar karaoke files, see ultrastar module.
+- Lyrics pasted into plain text files without any markup, see plaintext module.
+- The songs of VideoPsalm songbooks, see videopsalm module.
+- ProPresenter 6 files, see propresenter module.
+
+The most common types and functions can be imported at once with `use cantara_songlib::prelude::*;`.
 */
 
 
 /// - The `song` module contains the data structures needed for songs and its methods for managing and interpreting song data.
 pub mod song;
 
+/// - The `metadata` module contains structured representations of song metadata (authors, key, tempo, ...).
+pub mod metadata;
+
 /// - The `importer` module contains functions for importing songs from different formats.
 pub mod importer;
 
+/// - The `exporter` module contains functions for exporting songs to different formats.
+pub mod exporter;
+
+/// - The `chords` module contains functions for working with chords (e.g. transposing chord lines).
+pub mod chords;
+
 /// The filetypes which are supported as input/output
 pub mod filetypes;
 
 pub mod slides;
 
-pub mod templating;
+/// - The `songbook` module contains a collection of songs which can be searched and filtered.
+pub mod songbook;
+
+/// - The `collection` module contains the songs of a repertoire together with their files, which can be searched and presented.
+pub mod collection;
+
+/// - The `prelude` module re-exports the types and functions which are needed most often.
+pub mod prelude;
+
+mod templating;
+
+mod text;
 
 #[cfg(test)]
 mod tests {
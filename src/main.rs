@@ -1,5 +1,10 @@
-use cantara_songlib::importer::classic_song::slides_from_classic_song;
-use cantara_songlib::slides::SlideSettings;
+use cantara_songlib::exporter::{export_song_to_string, lossiness};
+use cantara_songlib::filetypes::get_file_type_by_file_ending;
+use cantara_songlib::importer::{create_presentation_from_file, import_song_from_file};
+use cantara_songlib::metadata::Key;
+use cantara_songlib::song::NormalizeOptions;
+use cantara_songlib::slides::{audit, SlideBudget, SlideSettings};
+use cantara_songlib::collection::SongCollection;
 
 use std::path::PathBuf;
 use clap::{Parser, Subcommand};
@@ -18,7 +23,37 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Generates a presentation with presentation slides
-    Presentation
+    Presentation {
+        /// Prints the number of slides, the number of words and the estimated duration instead of the slides
+        #[arg(long)]
+        summary: bool,
+    },
+    /// Cleans up the lyrics of a song (whitespace, dashes) and lists the changes of every part
+    Fmt,
+    /// Checks a song and its slides and lists the problems, fails if the song has errors (not only warnings)
+    Validate,
+    /// Prints the numbers of parts, lines and words and the estimated duration of a song as JSON
+    Stats,
+    /// Converts a song to another format and prints it, everything which gets lost is listed as warning
+    Convert {
+        /// The file extension of the target format (song, cssf, txt or json)
+        target: String,
+        /// Doesn't print the warnings about lost information
+        #[arg(long)]
+        quiet: bool,
+        /// Transposes the chords and the key of the song to this key (e.g. `A` or `F#m`), the song needs a key tag
+        #[arg(long)]
+        to_key: Option<String>,
+    },
+    /// Searches the songs of a directory
+    Search {
+        /// Only list songs which contain all words of the query in their title, tags or lyrics
+        #[arg(long)]
+        query: Option<String>,
+        /// Only list songs with this theme (case- and diacritic-insensitive)
+        #[arg(long)]
+        theme: Option<String>,
+    },
 }
 
 fn main() -> Result<(), std::io::Error> {
@@ -30,25 +65,98 @@ fn main() -> Result<(), std::io::Error> {
 
     let file = cli.file.unwrap();
 
-    if !file.is_file() {
-        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Input file is not a file or does not exist."));
-    };
-
     match &cli.command {
-        Commands::Presentation => {
-            if file.extension() == Some(std::ffi::OsStr::new("song")) {
-                let settings = SlideSettings::default();
-
-                let file_content = std::fs::read_to_string(&file).unwrap();
-                let slides = slides_from_classic_song(
-                    &file_content,
-                    &settings,
-                    file.file_stem().unwrap().to_str().unwrap().to_string()
-                );
-                println!("{:#?}", slides);
-            } else {
-                return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "The file type is not supported."));
+        Commands::Presentation { summary } => {
+            if !file.is_file() {
+                return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Input file is not a file or does not exist."));
+            };
+
+            let settings = SlideSettings::default();
+            let chapter = create_presentation_from_file(&file.to_string_lossy(), &settings)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string()))?;
+            match summary {
+                true => println!("{}", chapter.summary()),
+                false => println!("{:#?}", chapter.slides),
+            }
+            for issue in audit(&chapter.slides, &SlideBudget::default()) {
+                eprintln!("Warning: {}", issue);
+            }
+        }
+        Commands::Fmt => {
+            let mut song = import_song_from_file(&file.to_string_lossy())
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string()))?;
+            for change in song.normalize(NormalizeOptions::default()).parts {
+                let rules: Vec<String> = change.rules.iter().map(|rule| rule.to_string()).collect();
+                println!("{}: {}", change.part, rules.join(", "));
+            }
+        }
+        Commands::Validate => {
+            let song = import_song_from_file(&file.to_string_lossy())
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string()))?;
+            let issues = song.validate();
+            for issue in &issues {
+                match issue.is_warning() {
+                    true => println!("Warning: {}", issue),
+                    false => println!("Error: {}", issue),
+                }
+            }
+            let chapter = create_presentation_from_file(&file.to_string_lossy(), &SlideSettings::default())
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string()))?;
+            let slide_issues = audit(&chapter.slides, &SlideBudget::default());
+            for issue in &slide_issues {
+                println!("Warning: {}", issue);
             }
+            if issues.is_empty() && slide_issues.is_empty() {
+                println!("No problems found.");
+            }
+            let error_count = issues.iter().filter(|issue| !issue.is_warning()).count();
+            if error_count > 0 {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Errors found in the song: {}", error_count)));
+            }
+        }
+        Commands::Stats => {
+            let song = import_song_from_file(&file.to_string_lossy())
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string()))?;
+            let statistics = serde_json::to_string_pretty(&song.statistics())
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string()))?;
+            println!("{}", statistics);
+        }
+        Commands::Convert { target, quiet, to_key } => {
+            let target_type = get_file_type_by_file_ending(&format!(".{}", target.trim_start_matches('.')))
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Unknown target format: {}", target)))?;
+            let mut song = import_song_from_file(&file.to_string_lossy())
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error.to_string()))?;
+            if let Some(to_key) = to_key {
+                let target_key = Key::parse(to_key)
+                    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Unknown key: {}", to_key)))?;
+                let key = song.key().ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "The song has no key tag, so it can't be transposed to another key.")
+                })?;
+                song.transpose(key.interval_to(&target_key) as i32);
+            }
+            let content = export_song_to_string(&song, target_type)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::Unsupported, error.to_string()))?;
+            if !quiet {
+                for warning in lossiness(&song, target_type) {
+                    eprintln!("Warning: {}", warning);
+                }
+            }
+            println!("{}", content);
+        }
+        Commands::Search { query, theme } => {
+            if !file.is_dir() {
+                return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Input directory is not a directory or does not exist."));
+            };
+
+            let collection = SongCollection::load_from_directory(&file)?;
+            let collection = match theme {
+                Some(theme) => SongCollection::new(collection.filter_by_theme(theme).into_iter().cloned().collect()),
+                None => collection,
+            };
+            collection
+                .search(query.as_deref().unwrap_or_default())
+                .iter()
+                .for_each(|entry| println!("{}", entry.song.title));
         }
     }
 
@@ -0,0 +1,1567 @@
+//! This module contains structured representations of the metadata of a song.
+//! Songs store their metadata as plain string tags (e.g. `#author: John Newton`).
+//! The types in this module parse these strings once, so that exporters and frontends don't need to re-parse them.
+
+use core::fmt;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::song::Song;
+use crate::templating::render_plain_template;
+use crate::text::fold_for_comparison;
+
+/// The role of an author of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum AuthorRole {
+    /// No specific role is given
+    Unspecified,
+    /// The author wrote the lyrics
+    Words,
+    /// The author wrote the music
+    Music,
+    /// The author translated the lyrics
+    Translation,
+    /// The author arranged the music
+    Arrangement,
+}
+
+impl AuthorRole {
+    /// Parses a role description like `words`, `Musik` or `arr.` (English and German)
+    pub fn parse(value: &str) -> Option<AuthorRole> {
+        let value = value.trim().trim_end_matches(':').trim().to_lowercase();
+        match value.as_str() {
+            "words" | "lyrics" | "text" | "worte" | "dichtung" => Some(AuthorRole::Words),
+            "music" | "melody" | "tune" | "musik" | "melodie" | "weise" => Some(AuthorRole::Music),
+            "translation" | "translated" | "transl." | "trans." | "tr." | "übersetzung" | "übers." | "übertragung" => Some(AuthorRole::Translation),
+            "arrangement" | "arranged" | "arr." | "arr" | "satz" | "bearbeitung" => Some(AuthorRole::Arrangement),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuthorRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthorRole::Unspecified => write!(f, ""),
+            AuthorRole::Words => write!(f, "words"),
+            AuthorRole::Music => write!(f, "music"),
+            AuthorRole::Translation => write!(f, "translation"),
+            AuthorRole::Arrangement => write!(f, "arrangement"),
+        }
+    }
+}
+
+/// An author of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Author {
+    pub name: String,
+    pub role: AuthorRole,
+}
+
+/// Splits a string on the given separators and on the words `and`/`und`, but never inside parentheses
+fn split_outside_parentheses(value: &str, separators: &[char]) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut depth: usize = 0;
+    let mut index = 0;
+
+    while index < value.len() {
+        let rest = &value[index..];
+        let character = rest.chars().next().unwrap();
+        if depth == 0 {
+            let lowercase_rest = rest.to_lowercase();
+            if let Some(word) = [" and ", " und "].iter().find(|word| lowercase_rest.starts_with(*word)) {
+                parts.push(std::mem::take(&mut current));
+                index += word.len();
+                continue;
+            }
+            if separators.contains(&character) {
+                parts.push(std::mem::take(&mut current));
+                index += character.len_utf8();
+                continue;
+            }
+        }
+        match character {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {},
+        }
+        current.push(character);
+        index += character.len_utf8();
+    }
+    parts.push(current);
+
+    parts
+        .into_iter()
+        .map(|part| part.trim().to_string())
+        .filter(|part| !part.is_empty())
+        .collect()
+}
+
+/// Parses a list of role descriptions like `words and music`. Returns None if any of them is no known role.
+fn parse_roles(value: &str) -> Option<Vec<AuthorRole>> {
+    let roles: Vec<Option<AuthorRole>> = split_outside_parentheses(value, &[',', '&', '/'])
+        .iter()
+        .map(|role| AuthorRole::parse(role))
+        .collect();
+    match roles.is_empty() {
+        true => None,
+        false => roles.into_iter().collect(),
+    }
+}
+
+/// Parses a single author entry like `John Newton (words)`, `John Newton | words`, `Music: Traditional` or `arr. John Rutter`
+fn parse_author_entry(entry: &str) -> Vec<Author> {
+    let authors_with_roles = |name: &str, roles: Vec<AuthorRole>| -> Vec<Author> {
+        roles
+            .into_iter()
+            .map(|role| Author { name: name.trim().to_string(), role })
+            .collect()
+    };
+
+    // Pipe-delimited role: "John Newton | words"
+    if let Some((name, role)) = entry.split_once('|') {
+        if let Some(roles) = parse_roles(role) {
+            return authors_with_roles(name, roles);
+        }
+    }
+
+    // Parenthesized role at the end: "John Newton (words)"
+    if entry.ends_with(')') {
+        if let Some(open_index) = entry.rfind('(') {
+            let role = &entry[open_index + 1..entry.len() - 1];
+            if let Some(roles) = parse_roles(role) {
+                return authors_with_roles(&entry[..open_index], roles);
+            }
+        }
+    }
+
+    // Prefixed role: "Music: Traditional", "Words by John Newton" or "arr. John Rutter"
+    if let Some((role, name)) = entry.split_once(':') {
+        if let Some(roles) = parse_roles(role) {
+            return authors_with_roles(name, roles);
+        }
+    }
+    let lowercase_entry = entry.to_lowercase();
+    if let Some(by_index) = lowercase_entry.find(" by ") {
+        if let Some(roles) = parse_roles(&entry[..by_index]) {
+            return authors_with_roles(&entry[by_index + 4..], roles);
+        }
+    }
+    if let Some((role, name)) = entry.split_once(' ') {
+        if role.ends_with('.') {
+            if let Some(role) = AuthorRole::parse(role) {
+                return authors_with_roles(name, vec![role]);
+            }
+        }
+    }
+
+    vec![Author { name: entry.trim().to_string(), role: AuthorRole::Unspecified }]
+}
+
+/// Parses an author tag value into a list of authors with their roles.
+/// Authors can be separated by `,`, `;`, `&` or the words `and`/`und`.
+/// Roles can be given in parentheses (`John Newton (words)`), after a pipe (`John Newton | words`)
+/// or as prefix (`Music: Traditional`, `arr. John Rutter`). English and German role names are understood.
+/// # Example
+/// ```
+/// use cantara_songlib::metadata::{parse_authors, AuthorRole};
+/// let authors = parse_authors("John Newton (words), Traditional (music)");
+/// assert_eq!(authors.len(), 2);
+/// assert_eq!(authors[0].name, "John Newton");
+/// assert_eq!(authors[0].role, AuthorRole::Words);
+/// assert_eq!(authors[1].role, AuthorRole::Music);
+/// ```
+pub fn parse_authors(value: &str) -> Vec<Author> {
+    split_outside_parentheses(value, &[',', ';', '&'])
+        .iter()
+        .flat_map(|entry| parse_author_entry(entry))
+        .filter(|author| !author.name.is_empty())
+        .collect()
+}
+
+/// Formats a list of authors so that it can be parsed again with `parse_authors`.
+/// Consecutive entries of the same author are merged (e.g. `Matt Redman (words and music)`).
+/// The classic song and the cssf exporter write the `author` tag with this function.
+pub fn format_authors(authors: &[Author]) -> String {
+    let mut entries: Vec<(String, Vec<String>)> = Vec::new();
+    for author in authors {
+        let role = author.role.to_string();
+        match entries.last_mut() {
+            Some((name, roles)) if *name == author.name && !role.is_empty() && !roles.is_empty() => roles.push(role),
+            _ => entries.push((
+                author.name.clone(),
+                if role.is_empty() { vec![] } else { vec![role] },
+            )),
+        }
+    }
+
+    entries
+        .iter()
+        .map(|(name, roles)| match roles.is_empty() {
+            true => name.clone(),
+            false => format!("{} ({})", name, roles.join(" and ")),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// The copyright information of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Copyright {
+    /// The year of the copyright (if given)
+    pub year: Option<u16>,
+    /// The holder of the copyright
+    pub holder: String,
+}
+
+impl Copyright {
+    /// Parses a copyright string like `© 1982 Thankyou Music` or `Public Domain`.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::Copyright;
+    /// let copyright = Copyright::parse("© 1982 Thankyou Music");
+    /// assert_eq!(copyright.year, Some(1982));
+    /// assert_eq!(copyright.holder, "Thankyou Music");
+    /// ```
+    pub fn parse(value: &str) -> Copyright {
+        let mut rest = value.trim();
+        for prefix in ["©", "(c)", "(C)", "Copyright", "copyright"] {
+            if let Some(stripped) = rest.strip_prefix(prefix) {
+                rest = stripped.trim_start();
+            }
+        }
+
+        let (year, holder) = match rest.split_once(char::is_whitespace) {
+            Some((first, holder)) if first.len() == 4 && first.parse::<u16>().is_ok() =>
+                (first.parse::<u16>().ok(), holder.trim()),
+            _ if rest.len() == 4 && rest.parse::<u16>().is_ok() => (rest.parse::<u16>().ok(), ""),
+            _ => (None, rest),
+        };
+
+        Copyright {
+            year,
+            holder: holder.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Copyright {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.year {
+            Some(year) if self.holder.is_empty() => write!(f, "{}", year),
+            Some(year) => write!(f, "{} {}", year, self.holder),
+            None => write!(f, "{}", self.holder),
+        }
+    }
+}
+
+/// The notation which is used for note names
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub enum NoteNotation {
+    /// English notation: `B` is B natural, `Bb` is B flat
+    #[default]
+    English,
+    /// German notation: `H` is B natural, `B` is B flat, accidentals can be written as suffixes (`Fis`, `Es`)
+    German,
+}
+
+/// An accidental of a note
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum Accidental {
+    Natural,
+    Sharp,
+    Flat,
+}
+
+/// A note name like `C`, `F#` or `Bb`
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Note {
+    /// The letter of the note (`A` to `G`)
+    pub letter: char,
+    pub accidental: Accidental,
+}
+
+impl Note {
+    /// Returns the pitch class of the note (0 = C, 1 = C#/Db, ..., 11 = B)
+    pub fn pitch_class(&self) -> u8 {
+        let natural: i32 = match self.letter {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            _ => 11,
+        };
+        let offset: i32 = match self.accidental {
+            Accidental::Natural => 0,
+            Accidental::Sharp => 1,
+            Accidental::Flat => -1,
+        };
+        (natural + offset).rem_euclid(12) as u8
+    }
+
+    /// Creates a note from a pitch class, using flats or sharps for the black keys
+    pub fn from_pitch_class(pitch_class: u8, prefer_flats: bool) -> Note {
+        let (letter, accidental) = match (pitch_class % 12, prefer_flats) {
+            (0, _) => ('C', Accidental::Natural),
+            (1, false) => ('C', Accidental::Sharp),
+            (1, true) => ('D', Accidental::Flat),
+            (2, _) => ('D', Accidental::Natural),
+            (3, false) => ('D', Accidental::Sharp),
+            (3, true) => ('E', Accidental::Flat),
+            (4, _) => ('E', Accidental::Natural),
+            (5, _) => ('F', Accidental::Natural),
+            (6, false) => ('F', Accidental::Sharp),
+            (6, true) => ('G', Accidental::Flat),
+            (7, _) => ('G', Accidental::Natural),
+            (8, false) => ('G', Accidental::Sharp),
+            (8, true) => ('A', Accidental::Flat),
+            (9, _) => ('A', Accidental::Natural),
+            (10, false) => ('A', Accidental::Sharp),
+            (10, true) => ('B', Accidental::Flat),
+            _ => ('B', Accidental::Natural),
+        };
+        Note { letter, accidental }
+    }
+
+    /// Transposes the note by the given number of semitones
+    pub fn transpose(&self, semitones: i32, prefer_flats: bool) -> Note {
+        let pitch_class = (self.pitch_class() as i32 + semitones).rem_euclid(12) as u8;
+        Note::from_pitch_class(pitch_class, prefer_flats)
+    }
+
+    /// Parses a note at the beginning of a string.
+    /// # Returns
+    /// The note and the rest of the string or None if the string does not start with a note
+    pub fn parse_prefix(value: &str, notation: NoteNotation) -> Option<(Note, &str)> {
+        let mut characters = value.chars();
+        let first = characters.next()?;
+        let rest = characters.as_str();
+        let uppercase = first.to_ascii_uppercase();
+
+        let (letter, mut accidental) = match (uppercase, notation) {
+            ('A'..='G', NoteNotation::English) => (uppercase, Accidental::Natural),
+            ('B', NoteNotation::German) => ('B', Accidental::Flat),
+            ('H', NoteNotation::German) => ('B', Accidental::Natural),
+            ('A'..='G', NoteNotation::German) => (uppercase, Accidental::Natural),
+            _ => return None,
+        };
+
+        let german_b = notation == NoteNotation::German && uppercase == 'B';
+        let german_h = notation == NoteNotation::German && uppercase == 'H';
+
+        if let Some(stripped) = rest.strip_prefix(['#', '♯']) {
+            if german_b || german_h {
+                return None;
+            }
+            accidental = Accidental::Sharp;
+            return Some((Note { letter, accidental }, stripped));
+        }
+        if let Some(stripped) = rest.strip_prefix(['b', '♭']) {
+            if german_b || german_h {
+                return None;
+            }
+            accidental = Accidental::Flat;
+            return Some((Note { letter, accidental }, stripped));
+        }
+
+        if notation == NoteNotation::German {
+            // German accidental suffixes: Cis, Des, Es, As, Hes
+            if let Some(stripped) = rest.strip_prefix("is") {
+                if german_b {
+                    return None;
+                }
+                accidental = Accidental::Sharp;
+                return Some((Note { letter, accidental }, stripped));
+            }
+            if let Some(stripped) = rest.strip_prefix("es") {
+                if german_b {
+                    return None;
+                }
+                accidental = Accidental::Flat;
+                return Some((Note { letter, accidental }, stripped));
+            }
+            if matches!(uppercase, 'A' | 'E') {
+                if let Some(stripped) = rest.strip_prefix('s') {
+                    accidental = Accidental::Flat;
+                    return Some((Note { letter, accidental }, stripped));
+                }
+            }
+        }
+
+        Some((Note { letter, accidental }, rest))
+    }
+
+    /// Formats the note in the given notation
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::{Note, NoteNotation};
+    /// let (note, _) = Note::parse_prefix("Bb", NoteNotation::English).unwrap();
+    /// assert_eq!(note.to_notation_string(NoteNotation::German), "B");
+    /// ```
+    pub fn to_notation_string(&self, notation: NoteNotation) -> String {
+        match notation {
+            NoteNotation::English => self.to_string(),
+            NoteNotation::German => match (self.letter, self.accidental) {
+                ('B', Accidental::Natural) => "H".to_string(),
+                ('B', Accidental::Flat) => "B".to_string(),
+                ('B', Accidental::Sharp) => "His".to_string(),
+                ('A', Accidental::Flat) => "As".to_string(),
+                ('E', Accidental::Flat) => "Es".to_string(),
+                (letter, Accidental::Natural) => letter.to_string(),
+                (letter, Accidental::Sharp) => format!("{}is", letter),
+                (letter, Accidental::Flat) => format!("{}es", letter),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.accidental {
+            Accidental::Natural => write!(f, "{}", self.letter),
+            Accidental::Sharp => write!(f, "{}#", self.letter),
+            Accidental::Flat => write!(f, "{}b", self.letter),
+        }
+    }
+}
+
+/// The mode of a musical key
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum KeyMode {
+    Major,
+    Minor,
+}
+
+/// The musical key of a song
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct Key {
+    /// The tonic note of the key (e.g. `Bb` or `F#`)
+    pub tonic: Note,
+    pub mode: KeyMode,
+}
+
+impl Key {
+    /// Parses a key in English notation like `G`, `Bb`, `f# minor` or `Em`.
+    /// # Returns
+    /// The Key or None if the string is no valid key
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::{Key, KeyMode};
+    /// let key = Key::parse("f# minor").unwrap();
+    /// assert_eq!(key.mode, KeyMode::Minor);
+    /// assert_eq!(key.to_string(), "F#m");
+    /// assert_eq!(Key::parse("H#"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<Key> {
+        Key::parse_with_notation(value, NoteNotation::English)
+    }
+
+    /// Parses a key in the given notation.
+    /// In German notation, `H` is B natural, `B` is B flat and a lowercase tonic without a mode (e.g. `e`) means minor.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::{Key, KeyMode, NoteNotation};
+    /// let key = Key::parse_with_notation("H-Dur", NoteNotation::German).unwrap();
+    /// assert_eq!(key.to_string(), "B");
+    /// let key = Key::parse_with_notation("fis", NoteNotation::German).unwrap();
+    /// assert_eq!(key.to_string(), "F#m");
+    /// ```
+    pub fn parse_with_notation(value: &str, notation: NoteNotation) -> Option<Key> {
+        let value = value.trim();
+        let (tonic, rest) = Note::parse_prefix(value, notation)?;
+        let lowercase_tonic = value.starts_with(|c: char| c.is_ascii_lowercase());
+
+        let mode = match rest.trim().trim_start_matches('-').trim().to_lowercase().as_str() {
+            "" if lowercase_tonic && notation == NoteNotation::German => KeyMode::Minor,
+            "" | "major" | "maj" | "dur" => KeyMode::Major,
+            "m" | "min" | "minor" | "moll" => KeyMode::Minor,
+            _ => return None,
+        };
+        Some(Key { tonic, mode })
+    }
+
+    /// Returns whether flats are conventionally used for this key (e.g. `F`, `Bb` or `Dm`)
+    pub(crate) fn uses_flats(&self) -> bool {
+        match self.tonic.accidental {
+            Accidental::Flat => true,
+            Accidental::Sharp => false,
+            Accidental::Natural => match self.mode {
+                KeyMode::Major => self.tonic.letter == 'F',
+                KeyMode::Minor => matches!(self.tonic.letter, 'D' | 'G' | 'C' | 'F'),
+            },
+        }
+    }
+
+    /// Transposes the key by the given number of semitones.
+    /// The spelling of the input is preserved when possible: a flat key stays a flat key and a sharp key stays a sharp key.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::Key;
+    /// assert_eq!(Key::parse("Bb").unwrap().transpose(1).to_string(), "B");
+    /// assert_eq!(Key::parse("Eb").unwrap().transpose(3).to_string(), "Gb");
+    /// assert_eq!(Key::parse("E").unwrap().transpose(2).to_string(), "F#");
+    /// ```
+    pub fn transpose(&self, semitones: i32) -> Key {
+        self.transpose_with_preference(semitones, self.uses_flats())
+    }
+
+    /// Transposes the key by the given number of semitones and uses flats or sharps for ambiguous keys (e.g. Gb vs F#)
+    pub fn transpose_with_preference(&self, semitones: i32, prefer_flats: bool) -> Key {
+        Key {
+            tonic: self.tonic.transpose(semitones, prefer_flats),
+            mode: self.mode,
+        }
+    }
+
+    /// Transposes the key by the given number of semitones and spells the new key as it is conventionally written
+    /// (e.g. `Bb` and `Eb`, but `C#m` and `G#m`). Keys which are written both ways (`F#`/`Gb`, `D#m`/`Ebm`) keep
+    /// the spelling of the input like in `transpose`.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::Key;
+    /// assert_eq!(Key::parse("C").unwrap().transpose_conventionally(-2).to_string(), "Bb");
+    /// assert_eq!(Key::parse("Am").unwrap().transpose_conventionally(-1).to_string(), "G#m");
+    /// assert_eq!(Key::parse("Gb").unwrap().transpose_conventionally(12).to_string(), "Gb");
+    /// ```
+    pub fn transpose_conventionally(&self, semitones: i32) -> Key {
+        let pitch_class = (self.tonic.pitch_class() as i32 + semitones).rem_euclid(12);
+        match (self.mode, pitch_class) {
+            (KeyMode::Major, 6) | (KeyMode::Minor, 3) => self.transpose(semitones),
+            (KeyMode::Major, _) | (KeyMode::Minor, 10) => self.transpose_with_preference(semitones, true),
+            (KeyMode::Minor, _) => self.transpose_with_preference(semitones, false),
+        }
+    }
+
+    /// Returns the smallest interval in semitones (between -5 and 6) from this key to another key
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::Key;
+    /// let g = Key::parse("G").unwrap();
+    /// assert_eq!(g.interval_to(&Key::parse("A").unwrap()), 2);
+    /// assert_eq!(g.interval_to(&Key::parse("E").unwrap()), -3);
+    /// ```
+    pub fn interval_to(&self, other: &Key) -> i8 {
+        let interval = (other.tonic.pitch_class() as i8 - self.tonic.pitch_class() as i8).rem_euclid(12);
+        if interval > 6 { interval - 12 } else { interval }
+    }
+
+    /// Formats the key in the given notation (e.g. `Fis-Moll` in German notation)
+    pub fn to_notation_string(&self, notation: NoteNotation) -> String {
+        match notation {
+            NoteNotation::English => self.to_string(),
+            NoteNotation::German => match self.mode {
+                KeyMode::Major => format!("{}-Dur", self.tonic.to_notation_string(notation)),
+                KeyMode::Minor => format!("{}-Moll", self.tonic.to_notation_string(notation)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            KeyMode::Major => write!(f, "{}", self.tonic),
+            KeyMode::Minor => write!(f, "{}m", self.tonic),
+        }
+    }
+}
+
+/// The tempo of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum Tempo {
+    /// Beats per minute
+    Bpm(u16),
+    /// A tempo marking like `Moderato`
+    Marking(String),
+}
+
+impl Tempo {
+    /// Parses a tempo given as number of beats per minute (`72`, `72 bpm`) or as tempo marking (`Moderato`).
+    /// # Returns
+    /// The tempo or None if the value is empty or malformed (e.g. `0`, `72 bmp` or `fast 72`)
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::Tempo;
+    /// assert_eq!(Tempo::parse("72 bpm"), Some(Tempo::Bpm(72)));
+    /// assert_eq!(Tempo::parse("Moderato"), Some(Tempo::Marking("Moderato".to_string())));
+    /// assert_eq!(Tempo::parse("72 bmp"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<Tempo> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        if value.starts_with(|c: char| c.is_ascii_digit()) {
+            let number_end = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+            let unit = value[number_end..].trim().to_lowercase();
+            if !(unit.is_empty() || unit == "bpm") {
+                return None;
+            }
+            return match value[..number_end].parse::<u16>() {
+                Ok(bpm) if (1..=MAX_BPM).contains(&bpm) => Some(Tempo::Bpm(bpm)),
+                _ => None,
+            };
+        }
+
+        // A tempo marking consists of words only (e.g. "Allegro con brio")
+        if value.chars().all(|c| c.is_alphabetic() || c.is_whitespace() || c == '-' || c == '.') {
+            return Some(Tempo::Marking(value.to_string()));
+        }
+        None
+    }
+}
+
+/// The maximum tempo which is accepted as beats per minute
+const MAX_BPM: u16 = 400;
+
+impl fmt::Display for Tempo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Tempo::Bpm(bpm) => write!(f, "{}", bpm),
+            Tempo::Marking(marking) => write!(f, "{}", marking),
+        }
+    }
+}
+
+/// The time signature of a song (e.g. 3/4)
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct TimeSignature {
+    pub numerator: u8,
+    pub denominator: u8,
+}
+
+impl TimeSignature {
+    /// Parses a time signature like `3/4` or `6/8`. The denominator has to be a power of two.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::TimeSignature;
+    /// assert_eq!(TimeSignature::parse("3/4"), Some(TimeSignature { numerator: 3, denominator: 4 }));
+    /// assert_eq!(TimeSignature::parse("3/5"), None);
+    /// ```
+    pub fn parse(value: &str) -> Option<TimeSignature> {
+        let (numerator, denominator) = value.split_once('/')?;
+        let numerator: u8 = numerator.trim().parse().ok()?;
+        let denominator: u8 = denominator.trim().parse().ok()?;
+        if numerator == 0 || !denominator.is_power_of_two() {
+            return None;
+        }
+        Some(TimeSignature { numerator, denominator })
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+/// The books of the bible with their English name and known abbreviations (English and German).
+/// The abbreviations are stored lowercase, without diacritics, spaces and dots.
+/// Numbered books (e.g. 1 and 2 Samuel) list their numbers, the abbreviations are then prefixed with the number.
+const BIBLE_BOOKS: &[(&str, &[u8], &[&str])] = &[
+    ("Genesis", &[], &["gen", "gn", "genesis", "1mose", "1mo"]),
+    ("Exodus", &[], &["ex", "exod", "exodus", "2mose", "2mo"]),
+    ("Leviticus", &[], &["lev", "leviticus", "3mose", "3mo"]),
+    ("Numbers", &[], &["num", "numbers", "4mose", "4mo"]),
+    ("Deuteronomy", &[], &["deut", "dtn", "dt", "deuteronomy", "5mose", "5mo"]),
+    ("Joshua", &[], &["josh", "jos", "joshua", "josua"]),
+    ("Judges", &[], &["judg", "jdg", "judges", "ri", "richter"]),
+    ("Ruth", &[], &["ruth", "rut", "rt"]),
+    ("Samuel", &[1, 2], &["sam", "sa", "samuel"]),
+    ("Kings", &[1, 2], &["kgs", "ki", "kings", "kon", "konige", "koenige"]),
+    ("Chronicles", &[1, 2], &["chr", "chron", "chronicles", "chronik"]),
+    ("Ezra", &[], &["ezra", "esr", "esra"]),
+    ("Nehemiah", &[], &["neh", "nehemiah", "nehemia"]),
+    ("Esther", &[], &["est", "esth", "esther", "ester"]),
+    ("Job", &[], &["job", "hi", "hiob"]),
+    ("Psalm", &[], &["ps", "psa", "psalm", "psalms", "psalmen"]),
+    ("Proverbs", &[], &["prov", "prv", "proverbs", "spr", "spruche", "sprueche"]),
+    ("Ecclesiastes", &[], &["eccl", "eccles", "ecclesiastes", "pred", "prediger", "koh", "kohelet"]),
+    ("Song of Songs", &[], &["song", "songofsongs", "songofsolomon", "sos", "hld", "hoheslied"]),
+    ("Isaiah", &[], &["isa", "isaiah", "jes", "jesaja"]),
+    ("Jeremiah", &[], &["jer", "jeremiah", "jeremia"]),
+    ("Lamentations", &[], &["lam", "lamentations", "klgl", "klagelieder"]),
+    ("Ezekiel", &[], &["ezek", "ez", "ezekiel", "hes", "hesekiel", "ezechiel"]),
+    ("Daniel", &[], &["dan", "dn", "daniel"]),
+    ("Hosea", &[], &["hos", "hosea"]),
+    ("Joel", &[], &["joel"]),
+    ("Amos", &[], &["am", "amos"]),
+    ("Obadiah", &[], &["obad", "obd", "obadiah", "obadja"]),
+    ("Jonah", &[], &["jon", "jonah", "jona"]),
+    ("Micah", &[], &["mic", "mi", "micah", "micha"]),
+    ("Nahum", &[], &["nah", "nahum"]),
+    ("Habakkuk", &[], &["hab", "habakkuk", "habakuk"]),
+    ("Zephaniah", &[], &["zeph", "zef", "zephaniah", "zefanja"]),
+    ("Haggai", &[], &["hag", "haggai"]),
+    ("Zechariah", &[], &["zech", "sach", "zechariah", "sacharja"]),
+    ("Malachi", &[], &["mal", "malachi", "maleachi"]),
+    ("Matthew", &[], &["matt", "mt", "mat", "matthew", "matthaus", "matthaeus"]),
+    ("Mark", &[], &["mark", "mk", "mrk", "markus"]),
+    ("Luke", &[], &["luke", "lk", "luk", "lukas"]),
+    ("John", &[], &["john", "jn", "joh", "johannes"]),
+    ("Acts", &[], &["acts", "act", "apg", "apostelgeschichte"]),
+    ("Romans", &[], &["rom", "romans", "romer", "roemer"]),
+    ("Corinthians", &[1, 2], &["cor", "co", "corinthians", "kor", "korinther"]),
+    ("Galatians", &[], &["gal", "galatians", "galater"]),
+    ("Ephesians", &[], &["eph", "ephesians", "epheser"]),
+    ("Philippians", &[], &["phil", "php", "philippians", "philipper"]),
+    ("Colossians", &[], &["col", "colossians", "kol", "kolosser"]),
+    ("Thessalonians", &[1, 2], &["thess", "thes", "th", "thessalonians", "thessalonicher"]),
+    ("Timothy", &[1, 2], &["tim", "ti", "timothy", "timotheus"]),
+    ("Titus", &[], &["titus", "tit"]),
+    ("Philemon", &[], &["phlm", "philem", "phm", "philemon"]),
+    ("Hebrews", &[], &["heb", "hebr", "hebrews", "hebraer"]),
+    ("James", &[], &["jas", "jam", "james", "jak", "jakobus"]),
+    ("Peter", &[1, 2], &["pet", "pt", "petr", "peter", "petrus"]),
+    ("John", &[1, 2, 3], &["john", "jn", "joh", "johannes"]),
+    ("Jude", &[], &["jude", "jud", "judas"]),
+    ("Revelation", &[], &["rev", "revelation", "offb", "offenbarung"]),
+];
+
+/// Looks up the English name of a bible book by its name or abbreviation (e.g. `1. Kor` -> `1 Corinthians`)
+fn find_bible_book(name: &str) -> Option<String> {
+    let lookup_key: String = fold_for_comparison(name)
+        .chars()
+        .filter(|character| !character.is_whitespace() && *character != '.')
+        .collect();
+    let (number, abbreviation) = match lookup_key.chars().next() {
+        Some(digit @ '1'..='3') => (digit.to_digit(10).map(|digit| digit as u8), &lookup_key[1..]),
+        _ => (None, lookup_key.as_str()),
+    };
+
+    BIBLE_BOOKS
+        .iter()
+        .find(|(_, numbers, abbreviations)| match number {
+            Some(number) => numbers.contains(&number),
+            None => numbers.is_empty(),
+        } && abbreviations.contains(&abbreviation))
+        .map(|(book, _, _)| match number {
+            Some(number) => format!("{} {}", number, book),
+            None => book.to_string(),
+        })
+}
+
+/// A range of verses within a chapter (a single verse has the same start and end)
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+pub struct VerseRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl VerseRange {
+    /// Parses a verse or verse range like `16` or `16-17`
+    fn parse(value: &str) -> Option<VerseRange> {
+        let mut bounds = value.split(['-', '–']).map(|bound| bound.trim().parse::<u16>());
+        let start = bounds.next()?.ok()?;
+        let end = match bounds.next() {
+            Some(end) => end.ok()?,
+            None => start,
+        };
+        match bounds.next().is_none() && start > 0 && end >= start {
+            true => Some(VerseRange { start, end }),
+            false => None,
+        }
+    }
+}
+
+impl fmt::Display for VerseRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.start == self.end {
+            true => write!(f, "{}", self.start),
+            false => write!(f, "{}-{}", self.start, self.end),
+        }
+    }
+}
+
+/// A reference to a bible passage (e.g. `John 3:16-17`)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct ScriptureRef {
+    /// The English name of the book (e.g. `1 Corinthians`)
+    pub book: String,
+    pub chapter: u16,
+    /// The referenced verses, empty if the whole chapter is referenced
+    pub verses: Vec<VerseRange>,
+}
+
+impl ScriptureRef {
+    /// Parses a single scripture reference like `John 3:16-17`, `Ps 23` or `Joh 3,16.18`.
+    /// Book names can be given in English or German, either written out or abbreviated.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::ScriptureRef;
+    /// let reference = ScriptureRef::parse("1. Kor 13,4-7").unwrap();
+    /// assert_eq!(reference.to_string(), "1 Corinthians 13:4-7");
+    /// ```
+    pub fn parse(value: &str) -> Option<ScriptureRef> {
+        Self::parse_with_previous_book(value, None)
+    }
+
+    /// Parses a reference, the book may be omitted if a previous book is given (e.g. `4:1` in `John 3:16; 4:1`)
+    fn parse_with_previous_book(value: &str, previous_book: Option<&str>) -> Option<ScriptureRef> {
+        let reference_regex = {
+            static REFERENCE_REGEX: OnceLock<Regex> = OnceLock::new();
+            REFERENCE_REGEX.get_or_init(|| Regex::new(
+                r"^\s*(?:(?P<book>(?:[1-3]\.?\s*)?[^\d\s:,;.][^\d:,;]*?)\.?\s*)?(?P<chapter>\d+)(?:\s*[:,]\s*(?P<verses>\d[\d\s,.\-–]*))?\s*$"
+            ).unwrap())
+        };
+        let captures = reference_regex.captures(value)?;
+
+        let book: String = match captures.name("book") {
+            Some(book) => find_bible_book(book.as_str())?,
+            None => previous_book?.to_string(),
+        };
+        let chapter: u16 = captures.name("chapter")?.as_str().parse().ok()?;
+        let verses: Vec<VerseRange> = match captures.name("verses") {
+            Some(verses) => verses
+                .as_str()
+                .split([',', '.'])
+                .map(VerseRange::parse)
+                .collect::<Option<Vec<VerseRange>>>()?,
+            None => Vec::new(),
+        };
+
+        match chapter > 0 {
+            true => Some(ScriptureRef { book, chapter, verses }),
+            false => None,
+        }
+    }
+}
+
+impl fmt::Display for ScriptureRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.book, self.chapter)?;
+        if !self.verses.is_empty() {
+            let verses: Vec<String> = self.verses.iter().map(|verses| verses.to_string()).collect();
+            write!(f, ":{}", verses.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a list of scripture references separated by semicolons.
+/// # Returns
+/// The parsed references and the fragments which could not be parsed
+fn parse_scripture_fragments(value: &str) -> (Vec<ScriptureRef>, Vec<String>) {
+    let mut references: Vec<ScriptureRef> = Vec::new();
+    let mut unparsed: Vec<String> = Vec::new();
+    for fragment in value.split(';').map(str::trim).filter(|fragment| !fragment.is_empty()) {
+        let previous_book = references.last().map(|reference: &ScriptureRef| reference.book.clone());
+        match ScriptureRef::parse_with_previous_book(fragment, previous_book.as_deref()) {
+            Some(reference) => references.push(reference),
+            None => unparsed.push(fragment.to_string()),
+        }
+    }
+    (references, unparsed)
+}
+
+/// Parses a list of scripture references separated by semicolons (e.g. `John 3:16-17; Ps 23`).
+/// A reference without a book refers to the book of the reference before (e.g. `John 3:16; 4:1`).
+/// Fragments which can't be parsed are skipped.
+/// # Example
+/// ```
+/// use cantara_songlib::metadata::parse_scripture_refs;
+/// let references = parse_scripture_refs("John 3:16-17; Ps 23");
+/// assert_eq!(references[0].book, "John");
+/// assert_eq!(references[1].to_string(), "Psalm 23");
+/// ```
+pub fn parse_scripture_refs(value: &str) -> Vec<ScriptureRef> {
+    parse_scripture_fragments(value).0
+}
+
+/// Converts a date to the number of days since 1970-01-01 (proleptic Gregorian calendar)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Converts the number of days since 1970-01-01 to a date (year, month, day)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Parses an ISO 8601 date or date and time like `2024-03-01`, `2024-03-01T12:30:00Z` or `2024-03-01T12:30:00+01:00`.
+/// Dates without a time are interpreted as midnight UTC, times without an offset as UTC.
+/// # Example
+/// ```
+/// use cantara_songlib::metadata::{format_iso8601, parse_iso8601};
+/// let time = parse_iso8601("2024-03-01T13:30:00+01:00").unwrap();
+/// assert_eq!(format_iso8601(time), "2024-03-01T12:30:00Z");
+/// ```
+pub fn parse_iso8601(value: &str) -> Option<SystemTime> {
+    let timestamp_regex = {
+        static TIMESTAMP_REGEX: OnceLock<Regex> = OnceLock::new();
+        TIMESTAMP_REGEX.get_or_init(|| Regex::new(
+            r"^(\d{4})-(\d{2})-(\d{2})(?:[T ](\d{2}):(\d{2})(?::(\d{2})(?:\.\d+)?)?(Z|[+-]\d{2}:?\d{2})?)?$"
+        ).unwrap())
+    };
+    let captures = timestamp_regex.captures(value.trim())?;
+    let number = |index: usize| -> Option<i64> {
+        match captures.get(index) {
+            Some(capture) => capture.as_str().parse().ok(),
+            None => Some(0),
+        }
+    };
+
+    let (year, month, day) = (number(1)?, number(2)? as u32, number(3)? as u32);
+    let (hour, minute, second) = (number(4)?, number(5)?, number(6)?);
+    if !(1..=12).contains(&month) || day == 0 || day > 31 || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    // Reject days which don't exist in the month (e.g. February 30th)
+    if civil_from_days(days_from_civil(year, month, day)) != (year, month, day) {
+        return None;
+    }
+
+    let offset_seconds: i64 = match captures.get(7).map(|offset| offset.as_str()) {
+        None | Some("Z") => 0,
+        Some(offset) => {
+            let digits: String = offset[1..].chars().filter(char::is_ascii_digit).collect();
+            let minutes = digits[..2].parse::<i64>().ok()? * 60 + digits[2..].parse::<i64>().ok()?;
+            match offset.starts_with('-') {
+                true => -minutes * 60,
+                false => minutes * 60,
+            }
+        },
+    };
+
+    let seconds = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset_seconds;
+    match seconds >= 0 {
+        true => Some(UNIX_EPOCH + Duration::from_secs(seconds as u64)),
+        false => UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs())),
+    }
+}
+
+/// Formats a point in time as ISO 8601 string in UTC (e.g. `2024-03-01T12:30:00Z`)
+pub fn format_iso8601(time: SystemTime) -> String {
+    let seconds: i64 = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(error) => -(error.duration().as_secs_f64().ceil() as i64),
+    };
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    let second_of_day = seconds.rem_euclid(86400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, second_of_day / 3600, second_of_day % 3600 / 60, second_of_day % 60
+    )
+}
+
+/// A problem which occured while reading the metadata of a song.
+/// Tags which could not be parsed are kept in `SongMetadata::extra`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct MetadataWarning {
+    pub tag: String,
+    pub value: String,
+    pub message: String,
+}
+
+/// The structured metadata of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct SongMetadata {
+    pub title: String,
+    pub authors: Vec<Author>,
+    pub copyright: Option<Copyright>,
+    pub ccli: Option<u32>,
+    pub key: Option<Key>,
+    pub tempo: Option<Tempo>,
+    pub time_signature: Option<TimeSignature>,
+    pub themes: Vec<String>,
+    pub language: Option<String>,
+    pub scripture: Vec<ScriptureRef>,
+    /// All tags which have no typed field (or could not be parsed), ordered by their name
+    pub extra: BTreeMap<String, String>,
+}
+
+/// The tags which are read into the typed fields of `SongMetadata`
+const AUTHOR_TAG: &str = "author";
+const COPYRIGHT_TAG: &str = "copyright";
+const CCLI_TAG: &str = "ccli";
+const KEY_TAG: &str = "key";
+const TEMPO_TAG: &str = "tempo";
+const TIME_SIGNATURE_TAG: &str = "time";
+const THEMES_TAG: &str = "themes";
+const LANGUAGE_TAG: &str = "language";
+const SCRIPTURE_TAG: &str = "scripture";
+/// The tags which can contain themes of a song (in the order they are read)
+pub const THEME_TAGS: [&str; 5] = [THEMES_TAG, "tags", "categories", "theme", "category"];
+
+/// Parses a list of themes separated by commas, semicolons or pipes.
+/// Empty entries and duplicates (compared case- and diacritic-insensitively) are removed, the order is kept.
+/// # Example
+/// ```
+/// use cantara_songlib::metadata::parse_themes;
+/// assert_eq!(parse_themes("Christmas; Advent, christmas | Candlelight"), vec!["Christmas", "Advent", "Candlelight"]);
+/// ```
+pub fn parse_themes(value: &str) -> Vec<String> {
+    let mut themes: Vec<String> = Vec::new();
+    for theme in value.split([',', ';', '|']) {
+        push_theme(&mut themes, theme);
+    }
+    themes
+}
+
+/// Adds a theme to a list of themes, unless it is empty or already contained in the list
+fn push_theme(themes: &mut Vec<String>, theme: &str) {
+    let theme = theme.trim();
+    let folded_theme = fold_for_comparison(theme);
+    if !theme.is_empty() && !themes.iter().any(|existing| fold_for_comparison(existing) == folded_theme) {
+        themes.push(theme.to_string());
+    }
+}
+
+impl SongMetadata {
+    /// Reads the structured metadata from the tags of a song.
+    /// Parsing is lenient: Values which can't be parsed are kept in the `extra` map and reported as warning.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::metadata::SongMetadata;
+    /// use cantara_songlib::song::Song;
+    /// let mut song = Song::new("Amazing Grace");
+    /// song.add_tag("ccli", "22025");
+    /// song.add_tag("year", "1779");
+    /// let (metadata, warnings) = SongMetadata::from_tags(&song);
+    /// assert_eq!(metadata.ccli, Some(22025));
+    /// assert_eq!(metadata.extra.get("year").unwrap(), "1779");
+    /// assert!(warnings.is_empty());
+    /// ```
+    pub fn from_tags(song: &Song) -> (SongMetadata, Vec<MetadataWarning>) {
+        let mut metadata = SongMetadata {
+            title: song.title.clone(),
+            ..Default::default()
+        };
+        let mut warnings: Vec<MetadataWarning> = Vec::new();
+
+        metadata.themes = song.themes();
+
+        let mut tags: Vec<(&String, &String)> = song.get_tags().iter().collect();
+        tags.sort();
+
+        for (tag, value) in tags {
+            let mut keep_as_extra = |message: &str| {
+                warnings.push(MetadataWarning {
+                    tag: tag.clone(),
+                    value: value.clone(),
+                    message: message.to_string(),
+                });
+                metadata.extra.insert(tag.clone(), value.clone());
+            };
+
+            match tag.as_str() {
+                "title" => {
+                    if metadata.title.is_empty() {
+                        metadata.title = value.clone();
+                    }
+                },
+                AUTHOR_TAG => metadata.authors = parse_authors(value),
+                COPYRIGHT_TAG => metadata.copyright = Some(Copyright::parse(value)),
+                CCLI_TAG => match value.trim().parse::<u32>() {
+                    Ok(number) => metadata.ccli = Some(number),
+                    Err(_) => keep_as_extra("The CCLI number is not a number"),
+                },
+                KEY_TAG => match Key::parse(value) {
+                    Some(key) => metadata.key = Some(key),
+                    None => keep_as_extra("The key could not be parsed"),
+                },
+                TEMPO_TAG => match Tempo::parse(value) {
+                    Some(tempo) => metadata.tempo = Some(tempo),
+                    None => keep_as_extra("The tempo could not be parsed"),
+                },
+                TIME_SIGNATURE_TAG => match TimeSignature::parse(value) {
+                    Some(time_signature) => metadata.time_signature = Some(time_signature),
+                    None => keep_as_extra("The time signature could not be parsed"),
+                },
+                LANGUAGE_TAG => metadata.language = Some(value.trim().to_string()),
+                SCRIPTURE_TAG => {
+                    let (references, unparsed) = parse_scripture_fragments(value);
+                    metadata.scripture = references;
+                    if !unparsed.is_empty() {
+                        keep_as_extra(&format!("The scripture references could not be parsed: {}", unparsed.join("; ")));
+                    }
+                },
+                _ if THEME_TAGS.contains(&tag.as_str()) => {},
+                _ => {
+                    metadata.extra.insert(tag.clone(), value.clone());
+                },
+            }
+        }
+
+        (metadata, warnings)
+    }
+
+    /// Writes the metadata back to the tags of a song. Typed fields which are empty remove the corresponding tags.
+    pub fn apply_to(&self, song: &mut Song) {
+        song.title = self.title.clone();
+        if self.title.is_empty() {
+            song.remove_tag("title");
+        } else {
+            song.add_tag("title", &self.title);
+        }
+
+        set_or_remove_tag(song, AUTHOR_TAG, match self.authors.is_empty() {
+            true => None,
+            false => Some(format_authors(&self.authors)),
+        });
+        set_or_remove_tag(song, COPYRIGHT_TAG, self.copyright.as_ref().map(|copyright| copyright.to_string()));
+        set_or_remove_tag(song, KEY_TAG, self.key.as_ref().map(|key| key.to_string()));
+        set_or_remove_tag(song, TEMPO_TAG, self.tempo.as_ref().map(|tempo| tempo.to_string()));
+        set_or_remove_tag(song, TIME_SIGNATURE_TAG, self.time_signature.map(|time_signature| time_signature.to_string()));
+        set_or_remove_tag(song, LANGUAGE_TAG, self.language.clone());
+        set_or_remove_tag(song, CCLI_TAG, self.ccli.map(|ccli| ccli.to_string()));
+        set_or_remove_tag(song, SCRIPTURE_TAG, match self.scripture.is_empty() {
+            true => None,
+            false => Some(self.scripture.iter().map(|reference| reference.to_string()).collect::<Vec<String>>().join("; ")),
+        });
+
+        for tag in THEME_TAGS {
+            song.remove_tag(tag);
+        }
+        if !self.themes.is_empty() {
+            song.add_tag(THEMES_TAG, &self.themes.join(", "));
+        }
+
+        // Unparsable values are kept in the extra map, so they are written last
+        for (tag, value) in &self.extra {
+            song.add_tag(tag, value);
+        }
+    }
+}
+
+/// The maximum depth of tag templates which reference other tag templates
+const MAX_TAG_TEMPLATE_DEPTH: usize = 8;
+
+/// Resolves tag values which contain handlebars placeholders against the other tags of the song
+/// (e.g. `#source: {{book}} Nr. {{number}}`). Templates may reference other templates.
+/// Cyclic references and too deeply nested templates are reported as warning, the raw value is kept in that case.
+/// Placeholders of missing tags are rendered empty and reported as warning.
+/// # Example
+/// ```
+/// use cantara_songlib::metadata::resolve_tag_templates;
+/// use cantara_songlib::song::Song;
+/// let mut song = Song::new("Lobe den Herren");
+/// song.add_tag("book", "EG");
+/// song.add_tag("number", "317");
+/// song.add_tag("source", "{{book}} Nr. {{number}}");
+/// assert!(resolve_tag_templates(&mut song).is_empty());
+/// assert_eq!(song.get_tag("source").unwrap(), "EG Nr. 317");
+/// ```
+pub fn resolve_tag_templates(song: &mut Song) -> Vec<MetadataWarning> {
+    let mut tags: HashMap<String, String> = song.get_tags().clone();
+    if !tags.contains_key("title") && !song.title.is_empty() {
+        tags.insert("title".to_string(), song.title.clone());
+    }
+
+    let mut resolver = TagTemplateResolver {
+        tags: &tags,
+        resolved: HashMap::new(),
+        failed: HashSet::new(),
+        warnings: Vec::new(),
+    };
+    let mut keys: Vec<&String> = tags.keys().collect();
+    keys.sort();
+    for key in keys {
+        resolver.resolve(key, &mut Vec::new());
+    }
+
+    let TagTemplateResolver { resolved, warnings, .. } = resolver;
+    for (key, value) in resolved {
+        if song.get_tag(&key).is_some_and(|raw_value| *raw_value != value) {
+            if key == "title" {
+                song.title = value.clone();
+            }
+            song.add_tag(&key, &value);
+        }
+    }
+    warnings
+}
+
+/// The state of resolving the tag templates of a song
+struct TagTemplateResolver<'a> {
+    /// The raw tags
+    tags: &'a HashMap<String, String>,
+    /// The resolved values of all tags which have been visited
+    resolved: HashMap<String, String>,
+    /// The tags which could not be resolved (they keep their raw value)
+    failed: HashSet<String>,
+    warnings: Vec<MetadataWarning>,
+}
+
+impl TagTemplateResolver<'_> {
+    /// Resolves a tag, `stack` contains the tags which are currently being resolved.
+    /// # Returns
+    /// The resolved value or None if the tag could not be resolved
+    fn resolve(&mut self, key: &str, stack: &mut Vec<String>) -> Option<String> {
+        if self.failed.contains(key) {
+            return None;
+        }
+        if let Some(value) = self.resolved.get(key) {
+            return Some(value.clone());
+        }
+        let raw_value: &str = self.tags.get(key)?;
+
+        let placeholder_regex = {
+            static PLACEHOLDER_REGEX: OnceLock<Regex> = OnceLock::new();
+            PLACEHOLDER_REGEX.get_or_init(|| Regex::new(r"\{\{\s*([\w-]+)\s*\}\}").unwrap())
+        };
+        let references: Vec<String> = placeholder_regex
+            .captures_iter(raw_value)
+            .map(|capture| capture.get(1).unwrap().as_str().to_lowercase())
+            .collect();
+        if references.is_empty() {
+            self.resolved.insert(key.to_string(), raw_value.to_string());
+            return Some(raw_value.to_string());
+        }
+
+        if stack.iter().any(|entry| entry == key) {
+            return None;
+        }
+        if stack.len() >= MAX_TAG_TEMPLATE_DEPTH {
+            self.fail(key, "The tag template is nested too deeply");
+            return None;
+        }
+
+        stack.push(key.to_string());
+        let mut values: HashMap<String, String> = HashMap::new();
+        for reference in references {
+            if values.contains_key(&reference) {
+                continue;
+            }
+            if !self.tags.contains_key(&reference) {
+                self.warn(key, &format!("The tag template references the missing tag '{}'", reference));
+                continue;
+            }
+            match self.resolve(&reference, stack) {
+                Some(value) => {
+                    values.insert(reference, value);
+                },
+                None => {
+                    stack.pop();
+                    self.fail(key, &format!("The tag template could not be resolved because of the tag '{}' (cyclic reference)", reference));
+                    return None;
+                },
+            }
+        }
+        stack.pop();
+
+        match render_plain_template(raw_value, &values) {
+            Ok(value) => {
+                self.resolved.insert(key.to_string(), value.clone());
+                Some(value)
+            },
+            Err(_) => {
+                self.fail(key, "The tag template could not be rendered");
+                None
+            },
+        }
+    }
+
+    fn warn(&mut self, key: &str, message: &str) {
+        self.warnings.push(MetadataWarning {
+            tag: key.to_string(),
+            value: self.tags.get(key).cloned().unwrap_or_default(),
+            message: message.to_string(),
+        });
+    }
+
+    /// Marks a tag as unresolvable, it keeps its raw value
+    fn fail(&mut self, key: &str, message: &str) {
+        if self.failed.insert(key.to_string()) {
+            self.warn(key, message);
+        }
+    }
+}
+
+fn set_or_remove_tag(song: &mut Song, tag: &str, value: Option<String>) {
+    match value {
+        Some(value) => song.add_tag(tag, &value),
+        None => {
+            song.remove_tag(tag);
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_fully_tagged_song() {
+        let mut song = Song::new("Amazing Grace");
+        song.add_tag("title", "Amazing Grace");
+        song.add_tag("author", "John Newton, John P. Rees");
+        song.add_tag("copyright", "© 1779 Public Domain");
+        song.add_tag("ccli", "22025");
+        song.add_tag("key", "G");
+        song.add_tag("tempo", "72 bpm");
+        song.add_tag("time", "3/4");
+        song.add_tag("themes", "Grace; Salvation");
+        song.add_tag("language", "en");
+        song.add_tag("year", "1779");
+
+        let (metadata, warnings) = SongMetadata::from_tags(&song);
+        assert!(warnings.is_empty());
+        assert_eq!(metadata.authors.len(), 2);
+        assert_eq!(metadata.authors[1].name, "John P. Rees");
+        assert_eq!(metadata.copyright, Some(Copyright { year: Some(1779), holder: "Public Domain".to_string() }));
+        assert_eq!(metadata.key, Some(Key { tonic: Note { letter: 'G', accidental: Accidental::Natural }, mode: KeyMode::Major }));
+        assert_eq!(metadata.tempo, Some(Tempo::Bpm(72)));
+        assert_eq!(metadata.time_signature, Some(TimeSignature { numerator: 3, denominator: 4 }));
+        assert_eq!(metadata.themes, vec!["Grace", "Salvation"]);
+        assert_eq!(metadata.language, Some("en".to_string()));
+        assert_eq!(metadata.extra.len(), 1);
+
+        let mut exported_song = Song::new("");
+        metadata.apply_to(&mut exported_song);
+        assert_eq!(exported_song.title, "Amazing Grace");
+        let (reparsed_metadata, _) = SongMetadata::from_tags(&exported_song);
+        assert_eq!(reparsed_metadata, metadata);
+    }
+
+    #[test]
+    fn test_malformed_numeric_tags() {
+        let mut song = Song::new("Test");
+        song.add_tag("ccli", "12a45");
+        song.add_tag("key", "X#");
+        song.add_tag("tempo", "Moderato");
+
+        let (metadata, warnings) = SongMetadata::from_tags(&song);
+        assert_eq!(metadata.ccli, None);
+        assert_eq!(metadata.key, None);
+        assert_eq!(metadata.tempo, Some(Tempo::Marking("Moderato".to_string())));
+        assert_eq!(metadata.extra.get("ccli").unwrap(), "12a45");
+        assert_eq!(metadata.extra.get("key").unwrap(), "X#");
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].tag, "ccli");
+
+        // Malformed values survive a round trip unchanged
+        let mut exported_song = Song::new("");
+        metadata.apply_to(&mut exported_song);
+        assert_eq!(exported_song.get_tag("ccli").unwrap(), "12a45");
+        assert_eq!(exported_song.get_tag("key").unwrap(), "X#");
+    }
+
+    #[test]
+    fn test_parse_authors() {
+        use AuthorRole::*;
+        let cases: Vec<(&str, Vec<(&str, AuthorRole)>)> = vec![
+            ("John Newton", vec![("John Newton", Unspecified)]),
+            ("John Newton (words), Traditional (music)", vec![("John Newton", Words), ("Traditional", Music)]),
+            ("Matt Redman & Beth Redman", vec![("Matt Redman", Unspecified), ("Beth Redman", Unspecified)]),
+            ("Stuart Townend and Keith Getty", vec![("Stuart Townend", Unspecified), ("Keith Getty", Unspecified)]),
+            ("Matt Redman (words and music)", vec![("Matt Redman", Words), ("Matt Redman", Music)]),
+            ("Joachim Neander | words; Stralsund 1665 | music", vec![("Joachim Neander", Words), ("Stralsund 1665", Music)]),
+            ("Text: Paul Gerhardt; Melodie: Johann Crüger", vec![("Paul Gerhardt", Words), ("Johann Crüger", Music)]),
+            ("Words by Fanny Crosby", vec![("Fanny Crosby", Words)]),
+            ("arr. John Rutter", vec![("John Rutter", Arrangement)]),
+            ("Jochen Klepper (Text) und Gerhard Schwarz (Musik)", vec![("Jochen Klepper", Words), ("Gerhard Schwarz", Music)]),
+            ("Isaac Watts (1674-1748)", vec![("Isaac Watts (1674-1748)", Unspecified)]),
+            ("Frances R. Havergal; tr. unknown, Lowell Mason (arr.)", vec![("Frances R. Havergal", Unspecified), ("unknown", Translation), ("Lowell Mason", Arrangement)]),
+            ("Catherine Winkworth (translation)", vec![("Catherine Winkworth", Translation)]),
+            (" , ; ", vec![]),
+        ];
+
+        for (input, expected) in cases {
+            let authors = parse_authors(input);
+            let actual: Vec<(&str, AuthorRole)> = authors.iter().map(|author| (author.name.as_str(), author.role.clone())).collect();
+            assert_eq!(actual, expected, "Parsing '{}'", input);
+        }
+    }
+
+    #[test]
+    fn test_format_authors() {
+        for input in ["John Newton (words), Traditional (music)", "Matt Redman (words and music)", "John Newton"] {
+            assert_eq!(format_authors(&parse_authors(input)), input);
+        }
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let cases = [
+            ("C", "C"), ("Bb", "Bb"), ("bb", "Bb"), ("F#", "F#"), ("f# minor", "F#m"), ("Em", "Em"),
+            ("Ab major", "Ab"), ("c min", "Cm"), ("  D  ", "D"), ("E♭", "Eb"), ("Gb", "Gb"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Key::parse(input).unwrap().to_string(), expected, "Parsing '{}'", input);
+        }
+        for input in ["H", "H#", "X", "C##", "Cx", "", "Dm7", "minor"] {
+            assert_eq!(Key::parse(input), None, "Parsing '{}'", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_german_keys() {
+        let cases = [
+            ("H", "B"), ("h", "Bm"), ("B", "Bb"), ("Hm", "Bm"), ("Fis", "F#"), ("fis", "F#m"),
+            ("Es-Dur", "Eb"), ("As", "Ab"), ("Cis-Moll", "C#m"), ("e", "Em"), ("D", "D"), ("Hes", "Bb"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(Key::parse_with_notation(input, NoteNotation::German).unwrap().to_string(), expected, "Parsing '{}'", input);
+        }
+        for input in ["H#", "B#", "Bis", "X"] {
+            assert_eq!(Key::parse_with_notation(input, NoteNotation::German), None, "Parsing '{}'", input);
+        }
+        assert_eq!(Key::parse("Bb").unwrap().to_notation_string(NoteNotation::German), "B-Dur");
+        assert_eq!(Key::parse("Bm").unwrap().to_notation_string(NoteNotation::German), "H-Moll");
+        assert_eq!(Key::parse("F#m").unwrap().to_notation_string(NoteNotation::German), "Fis-Moll");
+    }
+
+    #[test]
+    fn test_transpose_keys() {
+        let key = |value: &str| Key::parse(value).unwrap();
+        assert_eq!(key("C").transpose(0), key("C"));
+        assert_eq!(key("C").transpose(2).to_string(), "D");
+        assert_eq!(key("C").transpose(-1).to_string(), "B");
+        assert_eq!(key("F").transpose(1).to_string(), "Gb");
+        assert_eq!(key("G").transpose(-1).to_string(), "F#");
+        assert_eq!(key("Dm").transpose(1).to_string(), "Ebm");
+        assert_eq!(key("Am").transpose(1).to_string(), "A#m");
+        assert_eq!(key("F#").transpose(12).to_string(), "F#");
+        assert_eq!(key("Gb").transpose(24).to_string(), "Gb");
+        assert_eq!(key("C").transpose_with_preference(6, true).to_string(), "Gb");
+        assert_eq!(key("C").transpose_with_preference(6, false).to_string(), "F#");
+
+        assert_eq!(key("C").interval_to(&key("F#")), 6);
+        assert_eq!(key("C").interval_to(&key("Gb")), 6);
+        assert_eq!(key("D").interval_to(&key("C")), -2);
+        assert_eq!(key("B").interval_to(&key("C")), 1);
+        for semitones in -5..=6 {
+            assert_eq!(key("Eb").interval_to(&key("Eb").transpose(semitones)), semitones as i8);
+            assert_eq!(key("Eb").interval_to(&key("Eb").transpose(semitones + 12)), semitones as i8);
+        }
+    }
+
+    #[test]
+    fn test_parse_tempo() {
+        assert_eq!(Tempo::parse("72"), Some(Tempo::Bpm(72)));
+        assert_eq!(Tempo::parse(" 120 BPM "), Some(Tempo::Bpm(120)));
+        assert_eq!(Tempo::parse("72bpm"), Some(Tempo::Bpm(72)));
+        assert_eq!(Tempo::parse("Moderato"), Some(Tempo::Marking("Moderato".to_string())));
+        assert_eq!(Tempo::parse("Allegro con brio"), Some(Tempo::Marking("Allegro con brio".to_string())));
+        assert_eq!(Tempo::parse("Andante"), Some(Tempo::Marking("Andante".to_string())));
+
+        for malformed in ["", "0", "72 bmp", "999", "-72", "fast 72", "72.5", "1e3"] {
+            assert_eq!(Tempo::parse(malformed), None, "Parsing '{}'", malformed);
+        }
+    }
+
+    #[test]
+    fn test_parse_time_signature() {
+        assert_eq!(TimeSignature::parse("6/8").unwrap().to_string(), "6/8");
+        assert_eq!(TimeSignature::parse(" 4 / 4 ").unwrap().to_string(), "4/4");
+        for malformed in ["", "3", "0/4", "3/0", "3/6", "a/4"] {
+            assert_eq!(TimeSignature::parse(malformed), None, "Parsing '{}'", malformed);
+        }
+    }
+
+    #[test]
+    fn test_parse_copyright() {
+        assert_eq!(Copyright::parse("Public Domain"), Copyright { year: None, holder: "Public Domain".to_string() });
+        assert_eq!(Copyright::parse("(c) 2004 worshiptogether.com songs").year, Some(2004));
+        assert_eq!(Copyright::parse("1982").to_string(), "1982");
+    }
+
+    #[test]
+    fn test_parse_scripture_refs() {
+        let formatted = |value: &str| parse_scripture_refs(value)
+            .iter()
+            .map(|reference| reference.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(formatted("John 3:16-17; Ps 23"), vec!["John 3:16-17", "Psalm 23"]);
+        assert_eq!(formatted("Joh 3,16.18"), vec!["John 3:16,18"]);
+        assert_eq!(formatted("1. Kor 13,4-7; 2 Cor. 5:17"), vec!["1 Corinthians 13:4-7", "2 Corinthians 5:17"]);
+        assert_eq!(formatted("Römer 8,28; 12:1-2"), vec!["Romans 8:28", "Romans 12:1-2"]);
+        assert_eq!(formatted("Offb 21,1–5; 1 Joh 4,8"), vec!["Revelation 21:1-5", "1 John 4:8"]);
+        assert_eq!(formatted("Song of Songs 2:1"), vec!["Song of Songs 2:1"]);
+        assert_eq!(parse_scripture_refs("Ps 23")[0], ScriptureRef { book: "Psalm".to_string(), chapter: 23, verses: vec![] });
+
+        for malformed in ["", "John", "Unknown 3:16", "John 3:17-16", "4 John 1:1"] {
+            assert!(parse_scripture_refs(malformed).is_empty(), "Parsing '{}'", malformed);
+        }
+    }
+
+    #[test]
+    fn test_scripture_tag_with_unparsable_fragment() {
+        let mut song = Song::new("Amazing Grace");
+        song.add_tag("scripture", "Eph 2:8; somewhere in the prophets");
+        let (metadata, warnings) = SongMetadata::from_tags(&song);
+
+        assert_eq!(metadata.scripture.len(), 1);
+        assert_eq!(metadata.scripture[0].book, "Ephesians");
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(metadata.extra.get("scripture").unwrap(), "Eph 2:8; somewhere in the prophets");
+
+        // The raw value is preserved when writing the metadata back
+        let mut written_song = Song::new("Amazing Grace");
+        metadata.apply_to(&mut written_song);
+        assert_eq!(written_song.get_tag("scripture").unwrap(), "Eph 2:8; somewhere in the prophets");
+    }
+
+    #[test]
+    fn test_resolve_tag_templates() {
+        let mut song = Song::new("Lobe den Herren");
+        song.add_tag("book", "EG");
+        song.add_tag("number", "317");
+        song.add_tag("source", "{{book}} Nr. {{number}}");
+        song.add_tag("reference", "{{title}}, {{source}} & more");
+        assert!(resolve_tag_templates(&mut song).is_empty());
+        assert_eq!(song.get_tag("source").unwrap(), "EG Nr. 317");
+        assert_eq!(song.get_tag("reference").unwrap(), "Lobe den Herren, EG Nr. 317 & more");
+    }
+
+    #[test]
+    fn test_resolve_tag_templates_with_missing_tag() {
+        let mut song = Song::new("Lobe den Herren");
+        song.add_tag("book", "EG");
+        song.add_tag("source", "{{book}} Nr. {{number}}");
+        let warnings = resolve_tag_templates(&mut song);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].tag, "source");
+        assert_eq!(song.get_tag("source").unwrap(), "EG Nr. ");
+    }
+
+    #[test]
+    fn test_resolve_tag_templates_with_cycle() {
+        let mut song = Song::new("Lobe den Herren");
+        song.add_tag("first", "A {{second}}");
+        song.add_tag("second", "B {{first}}");
+        song.add_tag("book", "EG");
+        song.add_tag("source", "{{book}}");
+        let warnings = resolve_tag_templates(&mut song);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(song.get_tag("first").unwrap(), "A {{second}}");
+        assert_eq!(song.get_tag("second").unwrap(), "B {{first}}");
+        assert_eq!(song.get_tag("source").unwrap(), "EG");
+    }
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(parse_iso8601("1970-01-01"), Some(UNIX_EPOCH));
+        assert_eq!(format_iso8601(parse_iso8601("2024-02-29").unwrap()), "2024-02-29T00:00:00Z");
+        assert_eq!(format_iso8601(parse_iso8601("2023-12-31T23:30:00-01:00").unwrap()), "2024-01-01T00:30:00Z");
+        assert_eq!(format_iso8601(parse_iso8601("1899-05-17 08:15:30.25Z").unwrap()), "1899-05-17T08:15:30Z");
+        for malformed in ["", "2024-13-01", "2023-02-29", "2024-03-01T25:00", "01.03.2024", "2024-03-01T12:00:00+1"] {
+            assert_eq!(parse_iso8601(malformed), None, "Parsing '{}'", malformed);
+        }
+    }
+}
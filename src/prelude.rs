@@ -0,0 +1,35 @@
+//! The prelude contains the core types and the import and export entry points of the library,
+//! so that they don't need to be imported from their modules one by one.
+//!
+//! # Example
+//! ```
+//! use cantara_songlib::prelude::*;
+//!
+//! let settings = SlideSettings::default();
+//! let chapter: PresentationChapter = create_presentation_from_file("testfiles/Amazing Grace.song", &settings).unwrap();
+//! assert!(!chapter.slides.is_empty());
+//!
+//! let error = import_song_from_file("testfiles/does not exist.song").unwrap_err();
+//! match CantaraError::from_error(error.as_ref()) {
+//!     Some(CantaraError::Parsing(parsing_error)) => panic!("Invalid song in line {}", parsing_error.line),
+//!     Some(other_error) => panic!("{}", other_error),
+//!     // Errors which don't come from the library, e.g. because the file can't be read
+//!     None => assert!(error.downcast_ref::<std::io::Error>().is_some()),
+//! }
+//! ```
+
+pub use crate::collection::{SongCollection, SongEntry};
+pub use crate::exporter::{export_song_to_string, lossiness, suggest_filename, LossWarning};
+pub use crate::filetypes::FileType;
+pub use crate::importer::classic_song::slides_from_classic_song;
+pub use crate::importer::errors::CantaraError;
+pub use crate::importer::{
+    create_presentation_from_file, import_song_from_file, import_song_from_file_with_options, import_song_from_string,
+    import_song_with_report, import_songs_from_directory, import_songs_from_file_with_options, ImportOptions, ImportReport,
+    ImportWarning, ImporterRegistry, SongFile, SongImporter,
+};
+pub use crate::slides::{
+    slides_from_song, Presentation, PresentationChapter, Slide, SlideContent, SlideSettings,
+};
+pub use crate::song::{LyricLanguage, Song, SongBuilder, SongPart, SongPartContent, SongPartContentType, SongPartType};
+pub use crate::songbook::Songbook;
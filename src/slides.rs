@@ -1,10 +1,17 @@
 //! Here the logic for the slides is implemented
 
+use std::cell::RefCell;
 use std::cmp::{min};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::Duration;
+use regex::Regex;
 use serde::{Serialize, Deserialize};
 
-use crate::importer::SongFile;
-use crate::song::Song;
+use crate::importer::{default_comment_prefixes, SongFile};
+use crate::song::{PartOrderName, Song, SongPart, SongPartContent, SongPartContentType, SongPartType};
+use crate::templating::render_metadata;
 
 // A Presentation Chapter (mostly representing a song) which should be displayed
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
@@ -22,6 +29,120 @@ impl PresentationChapter {
             linked_entity
         }
     }
+
+    /// Returns the summary of the chapter with the default slide durations
+    pub fn summary(&self) -> ChapterSummary {
+        self.summary_with_durations(&SlideDurations::default())
+    }
+
+    /// Returns the summary of the chapter, the duration is estimated with the given slide durations (see `Slide::estimated_duration`)
+    pub fn summary_with_durations(&self, durations: &SlideDurations) -> ChapterSummary {
+        ChapterSummary {
+            slide_count: self.slides.len(),
+            content_slide_count: self.slides.iter().filter(|slide| slide.is_content_slide()).count(),
+            word_count: self.slides.iter().map(Slide::word_count).sum(),
+            estimated_duration: self.slides.iter().map(|slide| slide.estimated_duration(durations)).sum(),
+        }
+    }
+}
+
+/// A presentation which consists of several chapters (e.g. the songs of a service)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct Presentation {
+    pub chapters: Vec<PresentationChapter>,
+}
+
+impl Presentation {
+    pub fn new(chapters: Vec<PresentationChapter>) -> Self {
+        Presentation { chapters }
+    }
+
+    /// Applies the post processors to the slides of all chapters in the given order (see `PostProcessor`)
+    pub fn post_process(&mut self, processors: &[Box<dyn PostProcessor>]) {
+        let mut chapters: Vec<Vec<Slide>> = self.chapters
+            .iter_mut()
+            .map(|chapter| std::mem::take(&mut chapter.slides))
+            .collect();
+        for processor in processors {
+            processor.process(&mut chapters);
+        }
+        for (chapter, slides) in self.chapters.iter_mut().zip(chapters) {
+            chapter.slides = slides;
+        }
+    }
+
+    /// Returns the summary of the presentation with the default slide durations
+    pub fn summary(&self) -> PresentationSummary {
+        self.summary_with_durations(&SlideDurations::default())
+    }
+
+    /// Returns the summary of the presentation and of all of its chapters
+    pub fn summary_with_durations(&self, durations: &SlideDurations) -> PresentationSummary {
+        let chapters: Vec<ChapterSummary> = self.chapters
+            .iter()
+            .map(|chapter| chapter.summary_with_durations(durations))
+            .collect();
+        PresentationSummary {
+            total: ChapterSummary {
+                slide_count: chapters.iter().map(|chapter| chapter.slide_count).sum(),
+                content_slide_count: chapters.iter().map(|chapter| chapter.content_slide_count).sum(),
+                word_count: chapters.iter().map(|chapter| chapter.word_count).sum(),
+                estimated_duration: chapters.iter().map(|chapter| chapter.estimated_duration).sum(),
+            },
+            chapters,
+        }
+    }
+}
+
+/// Summary statistics of a presentation chapter (e.g. for planning a service)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct ChapterSummary {
+    /// The number of all slides (including title and empty slides)
+    pub slide_count: usize,
+    /// The number of slides with lyrics
+    pub content_slide_count: usize,
+    /// The number of words of the lyrics (translations are not counted)
+    pub word_count: usize,
+    /// The estimated time the slides are shown
+    pub estimated_duration: Duration,
+}
+
+impl std::fmt::Display for ChapterSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.estimated_duration.as_secs();
+        write!(f, "{} slides ({} with lyrics), {} words, about {}:{:02} min",
+            self.slide_count, self.content_slide_count, self.word_count, seconds / 60, seconds % 60)
+    }
+}
+
+/// Summary statistics of a presentation
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct PresentationSummary {
+    /// The summaries of the chapters in the order of the presentation
+    pub chapters: Vec<ChapterSummary>,
+    /// The sums of all chapters
+    pub total: ChapterSummary,
+}
+
+/// The durations which are used to estimate how long a slide is shown (see `Slide::estimated_duration`)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SlideDurations {
+    /// The duration of every line of lyrics on a slide (translations are not counted)
+    pub per_line: Duration,
+    /// The duration of a title slide
+    pub title_slide: Duration,
+    /// The duration of any other slide (e.g. an empty slide or a picture)
+    pub other_slide: Duration,
+}
+
+impl Default for SlideDurations {
+    fn default() -> Self {
+        SlideDurations {
+            per_line: Duration::from_secs(4),
+            title_slide: Duration::from_secs(5),
+            other_slide: Duration::ZERO,
+        }
+    }
 }
 
 /// Any source where slides can come from (now just a song, other sources might follow later)
@@ -49,7 +170,19 @@ pub enum SlideContent {
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
 pub struct Slide {
     pub slide_content: SlideContent,
-    pub linked_file: Option<SongFile>
+    pub linked_file: Option<SongFile>,
+    /// Where the content of the slide comes from (the part of the song and the lines of the file), if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SlideSource>,
+}
+
+/// The source of the content of a slide, used to get from a slide back to the part of the song or the lines of the file
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SlideSource {
+    /// The ID of the part (see `SongPartId`) if the slide has been created from a song
+    pub part_id: Option<String>,
+    /// The first and the last line (starting with 1) of the file which are shown on the slide
+    pub file_line_range: Option<(usize, usize)>,
 }
 
 impl Slide {
@@ -61,6 +194,7 @@ impl Slide {
                 }
             ),
             linked_file: None,
+            source: None,
         }
     }
 
@@ -74,21 +208,50 @@ impl Slide {
                 )                   
             ),
             linked_file: None,
+            source: None,
         }
     }
 
-    pub fn new_title_slide(title_text: String, meta_text: Option<String>) -> Self {
+    pub fn new_multi_language_content_slide(main_text_list: Vec<String>, spoiler_text_vector: Vec<String>, meta_text: Option<String>) -> Self {
+        Slide {
+            slide_content: SlideContent::MultiLanguageMainContent(
+                MultiLanguageMainContentSlide {
+                    main_text_list,
+                    spoiler_text_vector,
+                    // We don't allow empty strings in meta_text
+                    meta_text: meta_text.filter(|str| !str.trim().is_empty()),
+                }
+            ),
+            linked_file: None,
+            source: None,
+        }
+    }
+
+    pub fn new_title_slide(title_text: String, spoiler_text: Option<String>, meta_text: Option<String>) -> Self {
         Slide {
             slide_content: SlideContent::Title(
                 TitleSlide {
                     title_text,
+                    // We don't allow empty strings in spoiler_text
+                    spoiler_text: spoiler_text.filter(|str| !str.trim().is_empty()),
                     meta_text
                 }
             ),
             linked_file: None,
+            source: None,
         }
     }
 
+    /// Returns the source of the content of the slide (see `SlideSource`)
+    pub fn source(&self) -> Option<&SlideSource> {
+        self.source.as_ref()
+    }
+
+    pub fn with_source(mut self, source: Option<SlideSource>) -> Self {
+        self.source = source;
+        self
+    }
+
     pub fn with_song_file(self, linked_file: SongFile) -> Self {
         let mut cloned_self = self.clone();
         cloned_self.linked_file = Some(linked_file);
@@ -99,7 +262,7 @@ impl Slide {
     pub fn has_spoiler(&self) -> bool {
         match &self.slide_content {
             SlideContent::SingleLanguageMainContent(single_language_main_content_slide) => single_language_main_content_slide.spoiler_text.is_some(),
-            SlideContent::Title(_) => false,
+            SlideContent::Title(title_slide) => title_slide.spoiler_text.is_some(),
             SlideContent::MultiLanguageMainContent(multi_language_main_content_slide) => !multi_language_main_content_slide.spoiler_text_vector.is_empty(),
             SlideContent::SimplePicture(_) => false,
             SlideContent::Empty(_) => false,
@@ -107,6 +270,37 @@ impl Slide {
     }
 
 
+    /// Returns whether the slide shows lyrics
+    pub fn is_content_slide(&self) -> bool {
+        matches!(self.slide_content, SlideContent::SingleLanguageMainContent(_) | SlideContent::MultiLanguageMainContent(_))
+    }
+
+    /// Returns the main text of a content slide (the first language of a multi language slide)
+    fn primary_text(&self) -> Option<&str> {
+        match &self.slide_content {
+            SlideContent::SingleLanguageMainContent(content) => Some(&content.main_text),
+            SlideContent::MultiLanguageMainContent(content) => content.main_text_list.first().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of words of the main text (translations, spoilers and meta texts are not counted)
+    pub fn word_count(&self) -> usize {
+        self.primary_text().map_or(0, |text| text.split_whitespace().count())
+    }
+
+    /// Estimates how long the slide is shown: content slides by the number of lines of their main text, all other slides by a fixed duration
+    pub fn estimated_duration(&self, durations: &SlideDurations) -> Duration {
+        match (&self.slide_content, self.primary_text()) {
+            (_, Some(text)) => {
+                let lines = text.lines().filter(|line| !line.trim().is_empty()).count();
+                durations.per_line * lines as u32
+            },
+            (SlideContent::Title(_), None) => durations.title_slide,
+            _ => durations.other_slide,
+        }
+    }
+
     pub fn has_meta_text(&self) -> bool {
         match &self.slide_content {
             SlideContent::SingleLanguageMainContent(single_language_main_content_slide) => single_language_main_content_slide.meta_text.is_some(),
@@ -181,6 +375,9 @@ pub struct EmptySlide {
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
 pub struct TitleSlide {
     pub title_text: String,
+    /// The first line(s) of the song which are displayed below the title if present
+    #[serde(default)]
+    pub spoiler_text: Option<String>,
     pub meta_text: Option<String>,
 }
 
@@ -197,7 +394,8 @@ pub struct SimplePictureSlide {
 pub struct SlideSettings {
     /// Specifies whether a special title slide for the song should be generated
     pub title_slide: bool,
-    /// Specifies whether a spoiler should be shown as a secondary block
+    /// Specifies whether a spoiler should be shown as a secondary block.
+    /// If false, content slides show neither the next block nor a secondary section (like a translation) as spoiler.
     pub show_spoiler: bool,
     /// Specifies whether and how to display meta information
     pub show_meta_information: ShowMetaInformation,
@@ -207,10 +405,41 @@ pub struct SlideSettings {
     pub empty_last_slide: bool,
     /// Specifies the maximum amount of lines of each block. If the number is higher, the slides will be wrapped into several ones. In case of `None` this is ignored.
     pub max_lines: Option<usize>,
+    /// Specifies whether a label-only first line of a block (like `Chorus:` or `Strophe 2`) should be removed from the slide text
+    pub strip_part_labels: bool,
+    /// Specifies the prefixes of comment lines which are skipped (see `ImportOptions::comment_prefixes`), by default `//` and `;`
+    pub comment_prefixes: Vec<String>,
+    /// Specifies whether the printed number of a stanza (like `3.` at the beginning of its first line) should be removed from the slide text
+    pub strip_verse_numbers: bool,
+    /// Specifies whether lines which only consist of chord symbols (like `G  C  D` or `[G C D]`) should be removed from the slide text
+    /// (see `ImportOptions::detect_chord_lines`)
+    pub strip_chord_lines: bool,
+    /// Specifies whether the first line(s) of the first block should be shown as spoiler on the title slide
+    pub title_slide_spoiler: bool,
+    /// Specifies the maximum amount of lines of the spoiler on the title slide. In case of `None` the whole first block is shown.
+    pub spoiler_max_lines: Option<usize>,
+    /// Specifies whether presentations of classic song files are created directly from the text (see `slides_from_classic_song`)
+    /// instead of the imported song. This keeps the output of earlier versions.
+    pub legacy_classic_slides: bool,
+    /// Specifies how repeat markers in the lyrics (like `(2x)`, `x3` or `||: ... :||`) are displayed
+    pub repeat_markers: RepeatMarkers,
+    /// Specifies whether the parts are shown in the order in which they are sung (see `Song::get_ordered_parts`).
+    /// If false, every part is shown once in the order of the file.
+    pub follow_part_order: bool,
+    /// Specifies the part order which is followed (e.g. a short version of the song, see `Song::get_part_order`).
+    /// Songs without a part order of this name and `None` use the active part order of the song.
+    pub part_order_name: Option<PartOrderName>,
+    /// Specifies whether the chorus is shown after every verse (and at the end of the song) even if the song doesn't repeat it there
+    pub repeat_chorus: bool,
+    /// Specifies how a single secondary section of a block (after a `---` line) is displayed
+    pub secondary_block_role: SecondaryBlockRole,
+    /// Post processors which are applied to the slides after they have been generated, in the order of the list.
+    /// The processor for `empty_last_slide` is always applied before them.
+    pub post_processors: Vec<Box<dyn PostProcessor>>,
 }
 
-impl SlideSettings {
-    pub fn default() -> Self {
+impl Default for SlideSettings {
+    fn default() -> Self {
         SlideSettings { 
             title_slide: true, 
             meta_syntax: "".to_string(),
@@ -218,11 +447,64 @@ impl SlideSettings {
             empty_last_slide: true, 
             show_spoiler: true ,
             max_lines: None,
+            strip_part_labels: false,
+            comment_prefixes: default_comment_prefixes(),
+            strip_verse_numbers: false,
+            strip_chord_lines: false,
+            title_slide_spoiler: false,
+            spoiler_max_lines: None,
+            legacy_classic_slides: true,
+            repeat_markers: RepeatMarkers::Keep,
+            follow_part_order: true,
+            part_order_name: None,
+            repeat_chorus: false,
+            secondary_block_role: SecondaryBlockRole::default(),
+            post_processors: Vec::new(),
         }
     }
 }
 
+impl SlideSettings {
+    /// Applies the post processors of the settings to the slides of the chapters (see `PostProcessor`)
+    pub fn apply_post_processors(&self, chapters: &mut [Vec<Slide>]) {
+        if self.empty_last_slide {
+            AppendEmptySlide::default().process(chapters);
+        }
+        for processor in &self.post_processors {
+            processor.process(chapters);
+        }
+    }
+}
+
+/// Enum for specifying how repeat markers in the lyrics are displayed on the slides.
+/// A marker at the end of a line (`(2x)`, `x3`) repeats the line, a marker on a line of its own repeats the whole block
+/// and `||: ... :||` repeats the enclosed lines (twice or as often as given after `:||`).
+/// The markers are always kept in the content of the song.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RepeatMarkers {
+    /// The markers are shown as they are written
+    #[default]
+    Keep,
+    /// The markers are removed from the slides
+    Strip,
+    /// The markers are removed and the affected lines or slides are repeated
+    Expand,
+}
+
+/// Enum for specifying how a single secondary section of a block (after a `---` line) is displayed on the slides.
+/// Blocks with several secondary sections are always displayed as translations.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SecondaryBlockRole {
+    /// The secondary section is shown as spoiler below the main text
+    #[default]
+    Spoiler,
+    /// The secondary section is a translation, the slides contain the texts of both languages
+    /// and the next block in both languages as spoiler
+    Translation,
+}
+
 /// Enum for specifing the settings for the showing of meta information
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ShowMetaInformation {
     /// Don't show any meta information in the presentation
     None,
@@ -236,18 +518,435 @@ pub enum ShowMetaInformation {
 
 impl ShowMetaInformation {
     pub fn on_first_slide(&self) -> bool {
-        match self {
-            ShowMetaInformation::FirstSlide | ShowMetaInformation::FirstSlideAndLastSlide => true,
-            _ => false,
-        }
+        matches!(self, ShowMetaInformation::FirstSlide | ShowMetaInformation::FirstSlideAndLastSlide)
     }
     
     pub fn on_last_slide(&self) -> bool {
-        match self {
-            ShowMetaInformation::LastSlide | ShowMetaInformation::FirstSlideAndLastSlide => true,
-            _ => false,
+        matches!(self, ShowMetaInformation::LastSlide | ShowMetaInformation::FirstSlideAndLastSlide)
+    }
+}
+
+/// The source of a block which is passed to `slides_from_blocks`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct BlockSource {
+    /// The ID of the part which the block belongs to
+    pub part_id: Option<String>,
+    /// The file line of every line of the block (None for lines which are not in the file)
+    pub lines: Vec<Option<usize>>,
+    /// The file lines of the secondary sections of the block
+    pub sections: Vec<Vec<Option<usize>>>,
+}
+
+/// A line of a block together with its source (the index of the block and the line of the file)
+#[derive(Clone, Debug, Default, PartialEq)]
+struct SourceLine {
+    text: String,
+    block: usize,
+    file_line: Option<usize>,
+}
+
+/// Combines the lines of a block with their sources
+fn source_lines(lines: &[String], file_lines: &[Option<usize>], block: usize) -> Vec<SourceLine> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, text)| SourceLine { text: text.clone(), block, file_line: file_lines.get(index).copied().flatten() })
+        .collect()
+}
+
+/// Returns the texts of source lines
+fn texts(lines: &[SourceLine]) -> Vec<String> {
+    lines.iter().map(|line| line.text.clone()).collect()
+}
+
+/// Creates the slides of a song from its blocks. This is used by all slide generators.
+///
+/// # Arguments
+/// - `blocks`: The (main) blocks of the song, each one given as a Vec of lines
+/// - `secondary_sections`: The secondary sections (e.g. translations) of every block in the order of the song. There have to be as many entries as there are blocks.
+/// - `sources`: The sources of the blocks, which are used for the sources of the slides. Missing entries mean that the source is unknown.
+/// - `metadata`: The metadata which is used for the meta text
+/// - `slide_settings`: The settings for the creation of the slides
+/// - `backup_title`: The title which will be used if the metadata contain no title
+pub(crate) fn slides_from_blocks(
+    blocks: Vec<Vec<String>>,
+    secondary_sections: Vec<Vec<Vec<String>>>,
+    sources: Vec<BlockSource>,
+    mut metadata: HashMap<String, String>,
+    slide_settings: &SlideSettings,
+    backup_title: String) -> Vec<Slide> {
+    // Every line keeps track of its source while the blocks are repeated and wrapped
+    let no_source = BlockSource::default();
+    let source_blocks: Vec<Vec<SourceLine>> = blocks
+        .iter()
+        .enumerate()
+        .map(|(index, lines)| source_lines(lines, &sources.get(index).unwrap_or(&no_source).lines, index))
+        .collect();
+    let source_sections: Vec<Vec<Vec<SourceLine>>> = secondary_sections
+        .iter()
+        .enumerate()
+        .map(|(index, sections)| {
+            let section_sources: &[Vec<Option<usize>>] = &sources.get(index).unwrap_or(&no_source).sections;
+            sections
+                .iter()
+                .enumerate()
+                .map(|(section_index, lines)| source_lines(lines, section_sources.get(section_index).map_or(&[], |lines| lines), index))
+                .collect()
+        })
+        .collect();
+
+    let (mut source_blocks, source_sections) = match slide_settings.repeat_markers {
+        RepeatMarkers::Keep => (source_blocks, source_sections),
+        repeat_markers => apply_repeat_markers(&source_blocks, &source_sections, repeat_markers == RepeatMarkers::Expand),
+    };
+
+    // The secondary blocks for every section position. Stanzas with fewer sections get empty blocks for the missing ones.
+    let section_count: usize = source_sections.iter().map(|sections| sections.len()).max().unwrap_or(0).max(1);
+    let mut source_secondary_blocks: Vec<Vec<Vec<SourceLine>>> = (0..section_count)
+        .map(|section_index| source_sections
+            .iter()
+            .map(|sections| sections.get(section_index).cloned().unwrap_or_default())
+            .collect())
+        .collect();
+
+    if let Some(max_lines) = slide_settings.max_lines {
+        let mut all_blocks: Vec<Vec<Vec<SourceLine>>> = vec![source_blocks];
+        all_blocks.append(&mut source_secondary_blocks);
+        let mut wrapped_blocks_output: Vec<Vec<Vec<SourceLine>>> = wrap_blocks(&all_blocks, max_lines, true);
+        source_blocks = wrapped_blocks_output.remove(0);
+        source_secondary_blocks = wrapped_blocks_output;
+    }
+
+    // The source of every (wrapped) block: the part of its first line and the range of the file lines which are shown
+    let block_sources: Vec<Option<SlideSource>> = source_blocks
+        .iter()
+        .enumerate()
+        .map(|(index, lines)| {
+            let shown_lines = lines
+                .iter()
+                .chain(source_secondary_blocks.iter().flat_map(|section_blocks| section_blocks[index].iter()));
+            let file_lines: Vec<usize> = shown_lines.clone().filter_map(|line| line.file_line).collect();
+            let part_id: Option<String> = shown_lines
+                .map(|line| line.block)
+                .next()
+                .and_then(|block| sources.get(block))
+                .and_then(|source| source.part_id.clone());
+            let file_line_range = file_lines.iter().min().zip(file_lines.iter().max()).map(|(first, last)| (*first, *last));
+            match (&part_id, file_line_range) {
+                (None, None) => None,
+                _ => Some(SlideSource { part_id, file_line_range }),
+            }
+        })
+        .collect();
+    let blocks: Vec<Vec<String>> = source_blocks.iter().map(|lines| texts(lines)).collect();
+    let secondary_blocks: Vec<Vec<Vec<String>>> = source_secondary_blocks
+        .iter()
+        .map(|section_blocks| section_blocks.iter().map(|lines| texts(lines)).collect())
+        .collect();
+
+    // Make sure that the meta tag title is available before the meta text is rendered.
+    if !metadata.contains_key("title") {
+        metadata.insert("title".to_string(), backup_title);
+    }
+
+    // Create the Presentation
+    
+    let mut slides: Vec<Slide> = vec![];
+
+    let meta_text_rendering_result = render_metadata(
+        &slide_settings.meta_syntax,
+        &metadata
+    );
+    let mut meta_text: String = "".to_string();
+
+    let meta_text_showable: bool = match meta_text_rendering_result {
+        Ok(str) => {
+            meta_text = str.clone();
+            !str.is_empty()
+        },
+        Err(_) => false,
+    };
+
+    if slide_settings.title_slide {
+        let displayed_meta_text = match meta_text_showable {
+            true => Some(meta_text.clone()),
+            false => None,
+        };
+        
+        // The first line(s) of the first block are shown as spoiler while the title is displayed
+        let spoiler_text: Option<String> = match slide_settings.title_slide_spoiler {
+            true => blocks.first().map(|first_block| first_block
+                .iter()
+                .skip_while(|line| line.trim().is_empty())
+                .take(slide_settings.spoiler_max_lines.unwrap_or(usize::MAX))
+                .cloned()
+                .collect::<Vec<String>>()
+                .join("\n")),
+            false => None,
+        };
+
+        slides.push(
+            Slide::new_title_slide(
+                metadata.get("title").unwrap().into(),                                          
+                spoiler_text,
+                displayed_meta_text
+            )
+        )
+    }
+    
+    // A single secondary section is only shown side by side with the main text if it is a translation
+    let is_multi_language: bool = section_count > 1 || (
+        slide_settings.secondary_block_role == SecondaryBlockRole::Translation
+            && secondary_blocks.iter().flatten().any(|block| !block.is_empty())
+    );
+    let count = blocks.len();
+    // The first slide is the title slide if there is one (which always shows the meta text), otherwise the first slide with text.
+    // If the first block is wrapped, only its first slide shows the meta text.
+    let first_content_index: Option<usize> = match slide_settings.title_slide {
+        true => None,
+        false => blocks.iter().position(|block| block.iter().any(|line| !line.trim().is_empty())),
+    };
+    for (index, block) in blocks.iter().enumerate() {
+        let is_first_slide: bool = slide_settings.show_meta_information.on_first_slide() && first_content_index == Some(index);
+        let is_last_slide: bool = slide_settings.show_meta_information.on_last_slide() && index == count - 1;
+        let displayed_meta_text = match meta_text_showable && (is_first_slide || is_last_slide) {
+            true => Some(meta_text.clone()),
+            false => None,
+        };
+        
+        if is_multi_language {
+            // Several secondary sections (or a translation) are shown side by side in the order of the file, the next block is the spoiler
+            let main_text_list: Vec<String> = std::iter::once(block)
+                .chain(secondary_blocks.iter().map(|section_blocks| &section_blocks[index]))
+                .map(|lines| lines.join("\n"))
+                .collect();
+            let spoiler_text_vector: Vec<String> = match blocks.get(index+1).filter(|_| slide_settings.show_spoiler) {
+                Some(next_block) => std::iter::once(next_block)
+                    .chain(secondary_blocks.iter().map(|section_blocks| &section_blocks[index+1]))
+                    .map(|lines| lines.join("\n"))
+                    .collect(),
+                None => vec![],
+            };
+            slides.push(
+                Slide::new_multi_language_content_slide(main_text_list, spoiler_text_vector, displayed_meta_text)
+                    .with_source(block_sources[index].clone())
+            );
+            continue;
+        }
+
+        let secondary_block = secondary_blocks[0].get(index).unwrap();
+        if !slide_settings.show_spoiler {
+            slides.push(
+                Slide::new_content_slide(block.join("\n"), None, displayed_meta_text)
+                    .with_source(block_sources[index].clone())
+            );
+        } else if secondary_block.is_empty() {
+            match blocks.get(index+1) {
+                Some(next_block) => {
+                    slides.push(
+                        Slide::new_content_slide(block.join("\n"), Some(next_block.join("\n")), displayed_meta_text)
+                            .with_source(block_sources[index].clone())
+                    )
+                },
+                None => {
+                    slides.push(
+                        Slide::new_content_slide(
+                            block.join("\n"), None, 
+                            displayed_meta_text
+                        ).with_source(block_sources[index].clone())
+                    )
+                }
+            }
+        } else {
+            slides.push(
+                Slide::new_content_slide(block.join("\n"),
+                    Some(secondary_block.join("\n")), 
+                    displayed_meta_text
+                ).with_source(block_sources[index].clone())
+            );
+        }
+    }
+    
+    let mut chapters: Vec<Vec<Slide>> = vec![slides];
+    slide_settings.apply_post_processors(&mut chapters);
+    chapters.pop().unwrap_or_default()
+}
+
+/// Splits a trailing repeat count like `(2x)`, `[3x]`, `2x` or `x3` from a line
+/// # Returns
+/// The line without the marker and the count (or None if the line has no marker)
+fn split_repeat_count(line: &str) -> (&str, Option<usize>) {
+    let repeat_count_regex = {
+        static REPEAT_COUNT_REGEX: OnceLock<Regex> = OnceLock::new();
+        REPEAT_COUNT_REGEX.get_or_init(|| Regex::new(r"(?:^|\s)[(\[]?\s*(?:(\d+)\s*[x×]|[x×]\s*(\d+))\s*[)\]]?\s*$").unwrap())
+    };
+    match repeat_count_regex.captures(line) {
+        Some(captures) => {
+            let count = captures.get(1).or(captures.get(2)).and_then(|count| count.as_str().parse().ok());
+            (line[..captures.get(0).unwrap().start()].trim_end(), count)
+        },
+        None => (line, None),
+    }
+}
+
+/// Removes the repeat markers from the lines of a block and repeats the affected lines if `expand` is true
+/// # Returns
+/// The resulting lines and how often the whole block is to be shown
+fn apply_repeat_markers_to_lines(lines: &[SourceLine], expand: bool) -> (Vec<SourceLine>, usize) {
+    let times = |count: usize| if expand { count } else { 1 };
+    // The line with another text (the source is kept)
+    let with_text = |line: &SourceLine, text: &str| SourceLine { text: text.to_string(), ..line.clone() };
+    let mut result: Vec<SourceLine> = vec![];
+    let mut block_repetitions: usize = 1;
+    // The lines of an open `||: ... :||` section
+    let mut repeated_section: Option<Vec<SourceLine>> = None;
+
+    for line in lines {
+        let (text, count) = split_repeat_count(&line.text);
+        if text.trim().is_empty() {
+            match count {
+                // A marker on a line of its own repeats the whole block
+                Some(count) => block_repetitions = times(count),
+                None => result.push(line.clone()),
+            }
+            continue;
+        }
+
+        let mut text: &str = text;
+        if let Some(rest) = text.trim_start().strip_prefix("||:") {
+            text = rest.trim_start();
+            repeated_section.get_or_insert_with(Vec::new);
+        }
+        let closes_section = match text.trim_end().strip_suffix(":||") {
+            Some(rest) => {
+                text = rest.trim_end();
+                true
+            },
+            None => false,
+        };
+
+        match (&mut repeated_section, closes_section) {
+            (Some(section), true) => {
+                section.push(with_text(line, text));
+                for _ in 0..times(count.unwrap_or(2)) {
+                    result.extend(section.iter().cloned());
+                }
+                repeated_section = None;
+            },
+            (Some(section), false) => section.extend(std::iter::repeat_n(with_text(line, text), times(count.unwrap_or(1)))),
+            (None, _) => result.extend(std::iter::repeat_n(with_text(line, text), times(count.unwrap_or(1)))),
+        }
+    }
+    // An unclosed section is shown once
+    result.extend(repeated_section.unwrap_or_default());
+
+    (result, block_repetitions)
+}
+
+/// Removes the repeat markers from all blocks and their secondary sections and repeats the affected lines and blocks if `expand` is true.
+/// A block is repeated according to the markers of its main text.
+fn apply_repeat_markers(blocks: &[Vec<SourceLine>], secondary_sections: &[Vec<Vec<SourceLine>>], expand: bool) -> (Vec<Vec<SourceLine>>, Vec<Vec<Vec<SourceLine>>>) {
+    let mut result_blocks: Vec<Vec<SourceLine>> = vec![];
+    let mut result_sections: Vec<Vec<Vec<SourceLine>>> = vec![];
+    for (block, sections) in blocks.iter().zip(secondary_sections) {
+        let (lines, block_repetitions) = apply_repeat_markers_to_lines(block, expand);
+        let sections: Vec<Vec<SourceLine>> = sections
+            .iter()
+            .map(|section| apply_repeat_markers_to_lines(section, expand).0)
+            .collect();
+        for _ in 0..block_repetitions {
+            result_blocks.push(lines.clone());
+            result_sections.push(sections.clone());
         }
     }
+    (result_blocks, result_sections)
+}
+
+/// Returns the parts of the song in the order in which they are sung: the part order named in the settings
+/// if the song has it (see `SlideSettings::part_order_name`), otherwise the active part order of the song
+pub(crate) fn sung_parts(song: &Song, slide_settings: &SlideSettings) -> Vec<Rc<RefCell<SongPart>>> {
+    match slide_settings.part_order_name.as_ref().and_then(|name| song.get_part_order(name)) {
+        Some(part_order) => part_order.to_parts(song),
+        None => song.get_ordered_parts(),
+    }
+}
+
+/// Inserts the first chorus of the song after every verse which is not followed by a chorus
+/// and at the end if the parts don't end with a chorus (see `SlideSettings::repeat_chorus`)
+/// # Returns
+/// The parts with the repeated chorus or the given parts if the song has no chorus
+pub(crate) fn with_repeated_chorus(song: &Song, parts: Vec<Rc<RefCell<SongPart>>>) -> Vec<Rc<RefCell<SongPart>>> {
+    let Some(chorus) = song.get_parts_by_type(SongPartType::Chorus).into_iter().next() else {
+        return parts;
+    };
+    let is_chorus = |part: Option<&Rc<RefCell<SongPart>>>| part.is_some_and(|part| part.borrow().part_type == SongPartType::Chorus);
+
+    let mut repeated_parts: Vec<Rc<RefCell<SongPart>>> = Vec::new();
+    for (index, part) in parts.iter().enumerate() {
+        repeated_parts.push(part.clone());
+        if part.borrow().part_type == SongPartType::Verse && !is_chorus(parts.get(index + 1)) {
+            repeated_parts.push(chorus.clone());
+        }
+    }
+    if !is_chorus(repeated_parts.last()) {
+        repeated_parts.push(chorus);
+    }
+    repeated_parts
+}
+
+/// Generates slides from a song in the order of its parts (see `Song::get_ordered_parts` and `SlideSettings::follow_part_order`).
+/// The lyrics in the primary language of the song are the main text (see `SongPart::get_lyrics` for parts without lyrics in that language),
+/// further lyrics (e.g. translations) are shown as secondary blocks.
+///
+/// # Arguments
+/// - `song`: The song
+/// - `slide_settings`: The settings for the creation of the slides
+///
+/// # Returns
+/// A Vec<Slide> with the slides. This can be integrated into a PresentationChapter and a Presentation.
+///
+/// # Example
+/// ```
+/// use cantara_songlib::importer::cssf::import_song;
+/// use cantara_songlib::slides::{slides_from_song, SlideSettings};
+/// let song = import_song("#title: Test\n\n#verse.1\nFirst verse").unwrap();
+/// let slides = slides_from_song(&song, &SlideSettings::default());
+/// // The title slide, the verse and the empty last slide
+/// assert_eq!(slides.len(), 3);
+/// ```
+pub fn slides_from_song(song: &Song, slide_settings: &SlideSettings) -> Vec<Slide> {
+    let primary_language = song.primary_language();
+    let mut blocks: Vec<Vec<String>> = vec![];
+    let mut secondary_sections: Vec<Vec<Vec<String>>> = vec![];
+    let mut sources: Vec<BlockSource> = vec![];
+
+    let parts: Vec<Rc<RefCell<SongPart>>> = match slide_settings.follow_part_order {
+        true => sung_parts(song, slide_settings),
+        false => song.get_file_ordered_parts(),
+    };
+    let parts: Vec<Rc<RefCell<SongPart>>> = match slide_settings.repeat_chorus {
+        true => with_repeated_chorus(song, parts),
+        false => parts,
+    };
+    for part in parts {
+        let part = part.borrow();
+        // The lyrics in the primary language come first (see `SongPart::get_lyrics`), the other lyrics are secondary sections
+        let Some(primary_lyrics) = part.get_lyrics(&primary_language) else {
+            continue;
+        };
+        let lines = |content: &SongPartContent| content.content.lines().map(|line| line.to_string()).collect::<Vec<String>>();
+        blocks.push(lines(primary_lyrics));
+        secondary_sections.push(
+            part.contents
+                .iter()
+                .filter(|content| matches!(content.voice_type, SongPartContentType::Lyrics { .. }) && !std::ptr::eq(*content, primary_lyrics))
+                .map(lines)
+                .collect(),
+        );
+        sources.push(BlockSource { part_id: Some(part.id.to_string()), ..Default::default() });
+    }
+
+    slides_from_blocks(blocks, secondary_sections, sources, song.get_tags().clone(), slide_settings, song.title.clone())
 }
 
 /// This function wraps the blocks, so that the number of lines never exceeds maximum_lines.
@@ -258,24 +957,25 @@ impl ShowMetaInformation {
 /// - `blocks`: A `&mut Vec<Vec<Vec<String>>>` with all the blocks which should be wrapped
 /// - `maximum_lines`: The number of maximum lines which a block may have
 /// - `persistence`: Whether block brakes are to be preserved (recommended is true)
+///
 /// Panics if secondary_block is Some(s) but s.len() != primary_block.len()
 /// # Returns
 /// The modified blocks as `Vec<Vec<Vec<String>>>`
-pub fn wrap_blocks(blocks: &Vec<Vec<Vec<String>>>, maximum_lines: usize, persistence: bool) -> Vec<Vec<Vec<String>>>{
+pub(crate) fn wrap_blocks<T: Clone>(blocks: &[Vec<Vec<T>>], maximum_lines: usize, persistence: bool) -> Vec<Vec<Vec<T>>>{
     if blocks.is_empty() {
-        return blocks.clone();
+        return blocks.to_vec();
     }
 
     let first_block_length = blocks[0].len();
     if blocks.len() > 1 {
-        for i in 1..blocks.len() {
-            if blocks[i].len() != first_block_length {
+        for block in blocks.iter().skip(1) {
+            if block.len() != first_block_length {
                 panic!("The length of every block has to be equal.")
             }
         }
     }
 
-    let mut wrapped_blocks = blocks.clone();
+    let mut wrapped_blocks = blocks.to_vec();
 
     let mut block_index: usize = 0;
     while block_index < wrapped_blocks[0].len() {
@@ -309,10 +1009,245 @@ pub fn wrap_blocks(blocks: &Vec<Vec<Vec<String>>>, maximum_lines: usize, persist
     wrapped_blocks
 }
 
+/// The size budget of a text section of a slide (e.g. what fits a 1080p canvas at the configured font size), see `audit`
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SlideBudget {
+    /// The maximum number of lines of a section
+    pub max_lines: usize,
+    /// The maximum number of characters of a single line
+    pub max_line_length: usize,
+    /// The maximum number of characters of a section (without line breaks)
+    pub max_characters: usize,
+}
+
+impl Default for SlideBudget {
+    fn default() -> Self {
+        SlideBudget {
+            max_lines: 8,
+            max_line_length: 50,
+            max_characters: 300,
+        }
+    }
+}
+
+/// The measure of a slide section which exceeds the budget
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+pub enum SlideMeasure {
+    Lines,
+    LineLength,
+    Characters,
+}
+
+/// A section of a slide which exceeds the budget (see `audit`)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct SlideAuditIssue {
+    /// The index of the slide in the audited slides
+    pub slide_index: usize,
+    /// The label of the section of the slide (like `main text`, `translation 1`, `spoiler` or `meta text`)
+    pub section: String,
+    pub measure: SlideMeasure,
+    /// The measured value
+    pub value: usize,
+    /// The limit of the budget
+    pub limit: usize,
+}
+
+impl std::fmt::Display for SlideAuditIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let measure = match self.measure {
+            SlideMeasure::Lines => "lines",
+            SlideMeasure::LineLength => "characters in a line",
+            SlideMeasure::Characters => "characters",
+        };
+        write!(f, "slide {}: the {} has {} {} (at most {} fit)", self.slide_index + 1, self.section, self.value, measure, self.limit)
+    }
+}
+
+/// Returns the labeled text sections of a slide
+fn slide_sections(slide: &Slide) -> Vec<(String, &str)> {
+    let mut sections: Vec<(String, &str)> = Vec::new();
+    let (spoiler_text, meta_text) = match &slide.slide_content {
+        SlideContent::SingleLanguageMainContent(content) => {
+            sections.push(("main text".to_string(), &content.main_text));
+            (content.spoiler_text.as_deref(), content.meta_text.as_deref())
+        },
+        SlideContent::MultiLanguageMainContent(content) => {
+            sections.extend(content.main_text_list.iter().enumerate().map(|(index, text)| match index {
+                0 => ("main text".to_string(), text.as_str()),
+                _ => (format!("translation {}", index), text.as_str()),
+            }));
+            sections.extend(content.spoiler_text_vector.iter().map(|text| ("spoiler".to_string(), text.as_str())));
+            (None, content.meta_text.as_deref())
+        },
+        SlideContent::Title(content) => {
+            sections.push(("title".to_string(), &content.title_text));
+            (content.spoiler_text.as_deref(), content.meta_text.as_deref())
+        },
+        SlideContent::SimplePicture(_) | SlideContent::Empty(_) => (None, None),
+    };
+    sections.extend(spoiler_text.map(|text| ("spoiler".to_string(), text)));
+    sections.extend(meta_text.map(|text| ("meta text".to_string(), text)));
+    sections
+}
+
+/// Checks generated slides against a size budget, so that slides which won't fit the canvas can be reported or highlighted.
+/// Every text section of a slide (main text, translations, spoiler and meta text) is checked on its own.
+/// # Returns
+/// An issue for every section and measure which exceeds the budget
+/// # Example
+/// ```
+/// use cantara_songlib::slides::{audit, Slide, SlideBudget, SlideMeasure};
+/// let slides = vec![Slide::new_content_slide("A very long line which will never fit on a slide".to_string(), None, None)];
+/// let issues = audit(&slides, &SlideBudget { max_line_length: 20, ..Default::default() });
+/// assert_eq!(issues[0].measure, SlideMeasure::LineLength);
+/// assert_eq!(issues[0].section, "main text");
+/// ```
+pub fn audit(slides: &[Slide], budget: &SlideBudget) -> Vec<SlideAuditIssue> {
+    let mut issues: Vec<SlideAuditIssue> = Vec::new();
+    for (slide_index, slide) in slides.iter().enumerate() {
+        for (section, text) in slide_sections(slide) {
+            // Leading and trailing line breaks are not displayed
+            let lines: Vec<&str> = text.trim_matches('\n').lines().collect();
+            let measures = [
+                (SlideMeasure::Lines, lines.len(), budget.max_lines),
+                (SlideMeasure::LineLength, lines.iter().map(|line| line.trim().chars().count()).max().unwrap_or(0), budget.max_line_length),
+                (SlideMeasure::Characters, lines.iter().map(|line| line.trim().chars().count()).sum(), budget.max_characters),
+            ];
+            issues.extend(measures
+                .into_iter()
+                .filter(|(_, value, limit)| value > limit)
+                .map(|(measure, value, limit)| SlideAuditIssue { slide_index, section: section.clone(), measure, value, limit })
+            );
+        }
+    }
+    issues
+}
+
+/// A step which changes the slides after they have been generated (e.g. to add empty slides between songs).
+/// Post processors work on the slides of all chapters of a presentation, so that they can take the position of a song into account.
+/// The slides of a single song are processed as one chapter. They are configured as an ordered list in `SlideSettings::post_processors`
+/// or passed to `Presentation::post_process`.
+pub trait PostProcessor {
+    /// Processes the slides of the chapters in place
+    fn process(&self, chapters: &mut [Vec<Slide>]);
+}
+
+/// Appends an empty slide to every chapter or only to the last one (e.g. a black slide after the last song)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AppendEmptySlide {
+    pub black_background: bool,
+    pub only_last_chapter: bool,
+}
+
+impl PostProcessor for AppendEmptySlide {
+    fn process(&self, chapters: &mut [Vec<Slide>]) {
+        let skipped: usize = match self.only_last_chapter {
+            true => chapters.len().saturating_sub(1),
+            false => 0,
+        };
+        for slides in chapters.iter_mut().skip(skipped) {
+            slides.push(Slide::new_empty_slide(self.black_background));
+        }
+    }
+}
+
+/// Inserts an empty slide at the beginning of every chapter but the first one (and of the first one if `before_first` is true)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InsertSeparators {
+    pub black_background: bool,
+    pub before_first: bool,
+}
+
+impl PostProcessor for InsertSeparators {
+    fn process(&self, chapters: &mut [Vec<Slide>]) {
+        let skipped: usize = match self.before_first {
+            true => 0,
+            false => 1,
+        };
+        for slides in chapters.iter_mut().skip(skipped) {
+            slides.insert(0, Slide::new_empty_slide(self.black_background));
+        }
+    }
+}
+
+/// Removes a slide which is equal to the slide before it, also across chapters (e.g. two empty slides between two songs)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DedupeConsecutive;
+
+impl PostProcessor for DedupeConsecutive {
+    fn process(&self, chapters: &mut [Vec<Slide>]) {
+        let mut previous: Option<Slide> = None;
+        for slides in chapters.iter_mut() {
+            slides.retain(|slide| {
+                let is_duplicate = previous.as_ref() == Some(slide);
+                previous = Some(slide.clone());
+                !is_duplicate
+            });
+        }
+    }
+}
+
+/// Puts the number of the chapter in front of the title of its title slides (e.g. `2. Amazing Grace`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AttachNumbers;
+
+impl PostProcessor for AttachNumbers {
+    fn process(&self, chapters: &mut [Vec<Slide>]) {
+        for (index, slides) in chapters.iter_mut().enumerate() {
+            for slide in slides.iter_mut() {
+                if let SlideContent::Title(title_slide) = &mut slide.slide_content {
+                    title_slide.title_text = format!("{}. {}", index + 1, title_slide.title_text);
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_follow_part_order() {
+        let song: Song = crate::song::SongBuilder::new("Test").verse("First verse").chorus("The chorus").verse("Second verse").build();
+        let main_texts = |settings: &SlideSettings| {
+            slides_from_song(&song, settings).iter().filter_map(|slide| slide.primary_text().map(str::to_string)).collect::<Vec<String>>()
+        };
+        let settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+        assert_eq!(main_texts(&settings), vec!["First verse", "The chorus", "Second verse"]);
+
+        // The guessed order repeats the chorus
+        let mut song = song.clone();
+        song.part_orders.clear();
+        let main_texts = |settings: &SlideSettings| {
+            slides_from_song(&song, settings).iter().filter_map(|slide| slide.primary_text().map(str::to_string)).collect::<Vec<String>>()
+        };
+        assert_eq!(main_texts(&settings), vec!["First verse", "The chorus", "Second verse", "The chorus"]);
+        assert_eq!(
+            main_texts(&SlideSettings { follow_part_order: false, ..settings }),
+            vec!["First verse", "The chorus", "Second verse"]
+        );
+    }
+
+    #[test]
+    fn test_part_order_name() {
+        use crate::song::PartOrder;
+        let mut song: Song = crate::song::SongBuilder::new("Test").verse("First verse").chorus("The chorus").verse("Second verse").build();
+        let short_version = PartOrderName::Custom("short".to_string());
+        song.add_part_order(PartOrder::parse_shorthand("V2 C", &song).unwrap().with_name(short_version.clone())).unwrap();
+        let main_texts = |settings: &SlideSettings| {
+            slides_from_song(&song, settings).iter().filter_map(|slide| slide.primary_text().map(str::to_string)).collect::<Vec<String>>()
+        };
+        let settings = SlideSettings { title_slide: false, empty_last_slide: false, ..Default::default() };
+
+        assert_eq!(main_texts(&settings), vec!["First verse", "The chorus", "Second verse"]);
+        let short_settings = SlideSettings { part_order_name: Some(short_version), ..Default::default() };
+        assert_eq!(main_texts(&SlideSettings { title_slide: false, empty_last_slide: false, ..short_settings }), vec!["Second verse", "The chorus"]);
+        // Songs without the named part order use their active one
+        let missing_settings = SlideSettings { part_order_name: Some(PartOrderName::Custom("long".to_string())), ..Default::default() };
+        assert_eq!(main_texts(&SlideSettings { title_slide: false, empty_last_slide: false, ..missing_settings }), main_texts(&settings));
+    }
+
     #[test]
     fn create_empty_slide() {
         let slide = Slide::new_empty_slide(false);
@@ -328,6 +1263,69 @@ mod tests {
         assert!(!slide_2.has_spoiler());
     }
 
+    #[test]
+    fn check_title_slide_spoiler() {
+        let slide_1 = Slide::new_title_slide("Amazing Grace".to_string(), Some("Amazing grace".to_string()), None);
+        assert!(slide_1.has_spoiler());
+
+        let slide_2 = Slide::new_title_slide("Amazing Grace".to_string(), Some(" ".to_string()), None);
+        assert!(!slide_2.has_spoiler());
+
+        // Title slides which have been serialized without a spoiler can still be read
+        let json = r#"{"slide_content":{"Title":{"title_text":"Amazing Grace","meta_text":null}},"linked_file":null}"#;
+        let slide: Slide = serde_json::from_str(json).unwrap();
+        assert!(!slide.has_spoiler());
+    }
+
+    #[test]
+    fn test_repeat_markers() {
+        let apply_repeat_markers_to_lines = |lines: &[String], expand: bool| -> (Vec<String>, usize) {
+            let (lines, repetitions) = super::apply_repeat_markers_to_lines(&source_lines(lines, &[], 0), expand);
+            (texts(&lines), repetitions)
+        };
+        let block: Vec<String> = ["||: Hallelujah", "praise the Lord :||", "Sing to the Lord (2x)", "Amen x3"]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+
+        let (lines, repetitions) = apply_repeat_markers_to_lines(&block, false);
+        assert_eq!(lines, vec!["Hallelujah", "praise the Lord", "Sing to the Lord", "Amen"]);
+        assert_eq!(repetitions, 1);
+
+        let (lines, repetitions) = apply_repeat_markers_to_lines(&block, true);
+        assert_eq!(lines, vec![
+            "Hallelujah", "praise the Lord", "Hallelujah", "praise the Lord",
+            "Sing to the Lord", "Sing to the Lord",
+            "Amen", "Amen", "Amen",
+        ]);
+        assert_eq!(repetitions, 1);
+
+        // A marker on a line of its own repeats the block, a count after :|| the section
+        let block: Vec<String> = ["||: Kyrie :|| 3x", "(2x)"].iter().map(|line| line.to_string()).collect();
+        assert_eq!(apply_repeat_markers_to_lines(&block, true), (vec!["Kyrie".to_string(); 3], 2));
+        assert_eq!(apply_repeat_markers_to_lines(&block, false), (vec!["Kyrie".to_string()], 1));
+
+        // Words which only look like markers are kept
+        let block: Vec<String> = vec!["Phoenix3 rises".to_string(), "Box".to_string()];
+        assert_eq!(apply_repeat_markers_to_lines(&block, true), (block.clone(), 1));
+    }
+
+    #[test]
+    fn test_repeat_chorus() {
+        let song = crate::importer::cssf::import_song("#verse.1\nFirst verse\n\n#chorus.1\nThe chorus\n\n#verse.2\nSecond verse").unwrap();
+        let slide_settings = SlideSettings { title_slide: false, empty_last_slide: false, repeat_chorus: true, ..Default::default() };
+        let part_ids: Vec<Option<String>> = slides_from_song(&song, &slide_settings)
+            .iter()
+            .map(|slide| slide.source().and_then(|source| source.part_id.clone()))
+            .collect();
+        let expected: Vec<Option<String>> = ["verse.1", "chorus.1", "verse.2", "chorus.1"].iter().map(|id| Some(id.to_string())).collect();
+        assert_eq!(part_ids, expected);
+
+        // Songs without a chorus are not changed
+        let song = crate::importer::cssf::import_song("#verse.1\nFirst verse\n\n#verse.2\nSecond verse").unwrap();
+        assert_eq!(slides_from_song(&song, &slide_settings).len(), 2);
+    }
+
     #[test]
     fn test_wrap_blocks_function() {
         let example_blocks = vec![
@@ -344,4 +1342,124 @@ mod tests {
         let wrapped_blocks = wrap_blocks(&example_blocks, 3, true);
         dbg!(&wrapped_blocks);
     }
+
+    #[test]
+    fn test_audit() {
+        let stanza: String = (1..=10)
+            .map(|line| format!("\nThis is line {} of a stanza which has been written without any line breaks for the projector", line))
+            .collect();
+        let slides = vec![
+            Slide::new_title_slide("Overlong Song".to_string(), None, None),
+            Slide::new_content_slide(stanza.clone(), None, Some("Author".to_string())),
+            Slide::new_multi_language_content_slide(vec!["Short".to_string(), stanza], vec![], None),
+            Slide::new_empty_slide(false),
+        ];
+
+        let issues = audit(&slides, &SlideBudget::default());
+        let measures = |slide_index: usize| -> Vec<(String, SlideMeasure)> {
+            issues
+                .iter()
+                .filter(|issue| issue.slide_index == slide_index)
+                .map(|issue| (issue.section.clone(), issue.measure))
+                .collect()
+        };
+        assert!(measures(0).is_empty());
+        assert_eq!(measures(1), vec![
+            ("main text".to_string(), SlideMeasure::Lines),
+            ("main text".to_string(), SlideMeasure::LineLength),
+            ("main text".to_string(), SlideMeasure::Characters),
+        ]);
+        assert!(measures(2).iter().all(|(section, _)| section == "translation 1"));
+        assert!(measures(3).is_empty());
+        assert_eq!(issues[0].value, 10);
+        assert_eq!(issues[0].to_string(), "slide 2: the main text has 10 lines (at most 8 fit)");
+
+        // A generous budget doesn't report anything
+        let budget = SlideBudget { max_lines: 10, max_line_length: 100, max_characters: 1000 };
+        assert!(audit(&slides, &budget).is_empty());
+    }
+
+    #[test]
+    fn test_presentation_summary() {
+        let first_chapter = PresentationChapter::new(vec![
+            Slide::new_title_slide("First Song".to_string(), None, None),
+            Slide::new_content_slide("\nAmazing grace, how sweet the sound\nthat saved a wretch like me".to_string(), None, Some("John Newton".to_string())),
+            Slide::new_content_slide("\nI once was lost".to_string(), None, None),
+            Slide::new_empty_slide(false),
+        ], LinkedEntity::Title("First Song".to_string()));
+        let second_chapter = PresentationChapter::new(vec![
+            Slide::new_multi_language_content_slide(vec!["Lobt den Herrn\nalle Welt".to_string(), "Praise the Lord".to_string()], vec![], None),
+        ], LinkedEntity::Title("Second Song".to_string()));
+        let durations = SlideDurations {
+            per_line: Duration::from_secs(3),
+            title_slide: Duration::from_secs(10),
+            other_slide: Duration::from_secs(1),
+        };
+
+        let summary = first_chapter.summary_with_durations(&durations);
+        assert_eq!(summary, ChapterSummary {
+            slide_count: 4,
+            content_slide_count: 2,
+            word_count: 16,
+            estimated_duration: Duration::from_secs(10 + 3 * 3 + 1),
+        });
+        assert_eq!(summary.to_string(), "4 slides (2 with lyrics), 16 words, about 0:20 min");
+
+        let presentation = Presentation::new(vec![first_chapter, second_chapter]);
+        let summary = presentation.summary_with_durations(&durations);
+        assert_eq!(summary.chapters.len(), 2);
+        assert_eq!(summary.chapters[1].word_count, 5);
+        assert_eq!(summary.total.slide_count, 5);
+        assert_eq!(summary.total.content_slide_count, 3);
+        assert_eq!(summary.total.word_count, 21);
+        assert_eq!(summary.total.estimated_duration, Duration::from_secs(26));
+    }
+
+    #[test]
+    fn test_post_processors() {
+        let chapter = |title: &str| PresentationChapter::new(vec![
+            Slide::new_title_slide(title.to_string(), None, None),
+            Slide::new_content_slide(format!("Lyrics of {}", title), None, None),
+        ], LinkedEntity::Title(title.to_string()));
+        let empty = Slide::new_empty_slide(false);
+        let black = Slide::new_empty_slide(true);
+        let presentation = Presentation::new(vec![chapter("First"), chapter("Second")]);
+
+        // A separator before every song but the first, a black slide only after the last song
+        let processors: Vec<Box<dyn PostProcessor>> = vec![
+            Box::new(InsertSeparators::default()),
+            Box::new(AppendEmptySlide { black_background: true, only_last_chapter: true }),
+            Box::new(AttachNumbers),
+        ];
+        let mut processed = presentation.clone();
+        processed.post_process(&processors);
+        assert_eq!(processed.chapters[0].slides.len(), 2);
+        assert_eq!(processed.chapters[1].slides[0], empty);
+        assert_eq!(processed.chapters[1].slides[3], black);
+        assert!(matches!(&processed.chapters[1].slides[1].slide_content, SlideContent::Title(title) if title.title_text == "2. Second"));
+
+        // The order matters: duplicates are only removed if they exist when the processor runs
+        let mut deduped_last = presentation.clone();
+        deduped_last.post_process(&[Box::new(AppendEmptySlide::default()), Box::new(InsertSeparators::default()), Box::new(DedupeConsecutive)]);
+        assert_eq!(deduped_last.chapters[0].slides.last(), Some(&empty));
+        assert_eq!(deduped_last.chapters[1].slides.len(), 3);
+
+        let mut deduped_first = presentation.clone();
+        deduped_first.post_process(&[Box::new(DedupeConsecutive), Box::new(AppendEmptySlide::default()), Box::new(InsertSeparators::default())]);
+        assert_eq!(deduped_first.chapters[1].slides.len(), 4);
+        assert_eq!(deduped_first.chapters[1].slides[0], empty);
+    }
+
+    #[test]
+    fn test_post_processors_in_settings() {
+        let slide_settings = SlideSettings {
+            title_slide: false,
+            empty_last_slide: true,
+            post_processors: vec![Box::new(AppendEmptySlide { black_background: true, only_last_chapter: false })],
+            ..Default::default()
+        };
+        let mut chapters = vec![vec![Slide::new_content_slide("Lyrics".to_string(), None, None)]];
+        slide_settings.apply_post_processors(&mut chapters);
+        assert_eq!(chapters[0][1..], [Slide::new_empty_slide(false), Slide::new_empty_slide(true)]);
+    }
 }
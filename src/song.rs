@@ -1,26 +1,360 @@
 extern crate regex;
 use core::fmt;
 use regex::Regex;
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::{Ref, RefCell, RefMut}, collections::{BTreeMap, BTreeSet, HashMap, HashSet}, rc::Rc, time::{Duration, SystemTime}};
 
 extern crate serde;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::text::{
+    collapse_whitespace, curl_quotes, fold_for_comparison, normalize_dashes, normalize_ellipses, normalize_unicode, sentence_case, straighten_quotes,
+    trim_trailing_whitespace,
+};
+use crate::chords::{line_to_nashville, transpose_line_with_notation, ChordError, ChordLine};
+use crate::metadata::{
+    format_iso8601, parse_authors, parse_iso8601, parse_scripture_refs, parse_themes, Author, Key, NoteNotation, ScriptureRef, Tempo,
+    TimeSignature, THEME_TAGS,
+};
 
 /// Object which represents a song in Cantara
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+#[serde(into = "SerializedSong", from = "SerializedSong")]
 pub struct Song {
     pub title: String,
     tags: HashMap<String, String>,
+    /// The keys of all tags which have been inherited from defaults (e.g. of a songbook) instead of being set by the song itself
+    inherited_tags: HashSet<String>,
+    /// If true, the `modified` tag is updated whenever the song is changed
+    track_modifications: bool,
     parts: Vec<Rc<RefCell<SongPart>>>,
     pub part_orders: Vec<PartOrder>,
 }
 
+/// The serialized form of a song, which additionally contains the categories parsed from its tags (see `Song::get_categories`),
+/// so that consumers of the JSON don't have to split the tag values themselves.
+/// When a song is deserialized, the categories replace the categories of its tags if they differ (e.g. because a consumer
+/// has edited the array), see `Song::set_categories`.
+#[derive(Serialize, Deserialize)]
+struct SerializedSong {
+    title: String,
+    #[serde(serialize_with = "serialize_sorted_map")]
+    tags: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    categories: Option<Vec<String>>,
+    #[serde(default, serialize_with = "serialize_sorted_set")]
+    inherited_tags: HashSet<String>,
+    parts: Vec<Rc<RefCell<SongPart>>>,
+    part_orders: Vec<PartOrder>,
+}
+
+impl From<Song> for SerializedSong {
+    fn from(song: Song) -> Self {
+        let categories: Vec<String> = song.get_categories();
+        SerializedSong {
+            categories: (!categories.is_empty()).then_some(categories),
+            title: song.title,
+            tags: song.tags,
+            inherited_tags: song.inherited_tags,
+            parts: song.parts,
+            part_orders: song.part_orders,
+        }
+    }
+}
+
+impl From<SerializedSong> for Song {
+    fn from(serialized_song: SerializedSong) -> Self {
+        let mut song = Song {
+            title: serialized_song.title,
+            tags: serialized_song.tags,
+            inherited_tags: serialized_song.inherited_tags,
+            track_modifications: false,
+            parts: serialized_song.parts,
+            part_orders: serialized_song.part_orders,
+        };
+        if let Some(categories) = serialized_song.categories {
+            if categories != song.get_categories() {
+                song.set_categories(&categories);
+            }
+        }
+        song
+    }
+}
+
+/// Serializes a map with sorted keys, so that the serialization of a song is deterministic
+fn serialize_sorted_map<S: Serializer>(map: &HashMap<String, String>, serializer: S) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Serializes a set in sorted order, so that the serialization of a song is deterministic
+fn serialize_sorted_set<S: Serializer>(set: &HashSet<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    set.iter().collect::<BTreeSet<_>>().serialize(serializer)
+}
+
+/// The tag which contains the creation time of a song (ISO 8601)
+const CREATED_TAG: &str = "created";
+/// The tag which contains the time of the last modification of a song (ISO 8601)
+const MODIFIED_TAG: &str = "modified";
+
+/// An error which occured while editing a song
+#[derive(Clone, PartialEq, Debug)]
+pub enum SongEditError {
+    /// There is no part with the given ID
+    PartNotFound { id: String },
+    /// The index is not within the parts of the song
+    IndexOutOfRange { index: usize, part_count: usize },
+    /// The song already contains lyrics in the given language
+    LanguageExists { language: LyricLanguage },
+    /// A token of a shorthand part order is unknown (see `PartOrder::parse_shorthand`)
+    UnknownPartOrderToken { token: String },
+    /// A token of a shorthand part order refers to a part which the song doesn't have
+    PartOrderPartNotFound { token: String },
+    /// The song already has a part order with the given name
+    PartOrderExists { name: PartOrderName },
+    /// The song has no part order with the given name
+    PartOrderNotFound { name: PartOrderName },
+}
+
+impl fmt::Display for SongEditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SongEditError::PartNotFound { id } => write!(f, "The song has no part with the ID {}", id),
+            SongEditError::IndexOutOfRange { index, part_count } => {
+                write!(f, "The index {} is out of range, the song has {} parts", index, part_count)
+            },
+            SongEditError::LanguageExists { language: LyricLanguage::Default } => {
+                write!(f, "The song already contains lyrics without a specific language")
+            },
+            SongEditError::LanguageExists { language: LyricLanguage::Specific(language) } => {
+                write!(f, "The song already contains lyrics in the language {}", language)
+            },
+            SongEditError::UnknownPartOrderToken { token } => write!(f, "The part order contains the unknown part {}", token),
+            SongEditError::PartOrderPartNotFound { token } => {
+                write!(f, "The part order contains {}, but the song has no such part", token)
+            },
+            SongEditError::PartOrderExists { name } => write!(f, "The song already has a part order named {}", name),
+            SongEditError::PartOrderNotFound { name } => write!(f, "The song has no part order named {}", name),
+        }
+    }
+}
+
+impl std::error::Error for SongEditError {}
+
+/// The result of merging a translation into a song (see `Song::merge_translation`)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct MergeReport {
+    /// The IDs of the parts which got the lyrics of the translation
+    pub merged_parts: Vec<String>,
+    /// The IDs of the parts of the song which have no counterpart in the translation
+    pub unmatched_parts: Vec<String>,
+    /// The IDs of the parts of the translation which have no counterpart in the song and have been skipped
+    pub unmatched_translation_parts: Vec<String>,
+}
+
+/// The duration of a lyric line if the song has no tempo in beats per minute (see `Song::statistics`)
+const DEFAULT_LINE_DURATION: Duration = Duration::from_secs(4);
+
+/// Rough numbers about a song, e.g. for planning services (see `Song::statistics`)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct SongStatistics {
+    /// The number of parts of every type, keyed by the name of the type (e.g. `Verse`). Repetitions are not counted.
+    pub part_counts: BTreeMap<String, usize>,
+    /// The number of non-empty lyric lines
+    pub line_count: usize,
+    /// The number of words of the lyrics
+    pub word_count: usize,
+    /// The number of different words of the lyrics (case-insensitive, without punctuation)
+    pub unique_word_count: usize,
+    /// The number of characters of the longest lyric line
+    pub longest_line: usize,
+    /// The estimated duration of singing the song, including the repetitions of the part order
+    pub estimated_duration: Duration,
+}
+
+/// A line of a line-level diff of two texts (see `PartDiff`)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum LineDiff {
+    /// The line is contained in both texts
+    Kept(String),
+    /// The line is only contained in the new text
+    Added(String),
+    /// The line is only contained in the old text
+    Removed(String),
+}
+
+/// The changes of the lyrics in one language of a part which is contained in both versions of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct PartDiff {
+    /// The ID of the part (like `Verse.1`)
+    pub part: String,
+    pub language: LyricLanguage,
+    /// All lines of the old and the new lyrics in their order
+    pub lines: Vec<LineDiff>,
+}
+
+/// A part which has got another type (e.g. a verse which has become a chorus) with the same lyrics
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct RetypedPart {
+    /// The ID of the part in the old version of the song
+    pub old_part: String,
+    /// The ID of the part in the new version of the song
+    pub new_part: String,
+}
+
+/// A change of a tag between two versions of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum TagChange {
+    Added { key: String, value: String },
+    Removed { key: String, value: String },
+    Changed { key: String, old_value: String, new_value: String },
+}
+
+/// The differences between two versions of a song (see `Song::diff`)
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct SongDiff {
+    /// The IDs of the parts which are only contained in the new version
+    pub added_parts: Vec<String>,
+    /// The IDs of the parts which are only contained in the old version
+    pub removed_parts: Vec<String>,
+    pub retyped_parts: Vec<RetypedPart>,
+    /// The lyric changes of the parts which are contained in both versions
+    pub lyric_changes: Vec<PartDiff>,
+    /// The tag changes in the alphabetical order of the keys
+    pub tag_changes: Vec<TagChange>,
+}
+
+impl SongDiff {
+    /// Returns whether both versions of the song are the same
+    pub fn is_empty(&self) -> bool {
+        self.added_parts.is_empty()
+            && self.removed_parts.is_empty()
+            && self.retyped_parts.is_empty()
+            && self.lyric_changes.is_empty()
+            && self.tag_changes.is_empty()
+    }
+}
+
+/// Creates a line-level diff of two texts based on their longest common subsequence of lines
+fn diff_lines(old_text: &str, new_text: &str) -> Vec<LineDiff> {
+    let old_lines: Vec<&str> = old_text.lines().collect();
+    let new_lines: Vec<&str> = new_text.lines().collect();
+    // common_lengths[i][j] is the length of the longest common subsequence of old_lines[i..] and new_lines[j..]
+    let mut common_lengths: Vec<Vec<usize>> = vec![vec![0; new_lines.len() + 1]; old_lines.len() + 1];
+    for i in (0..old_lines.len()).rev() {
+        for j in (0..new_lines.len()).rev() {
+            common_lengths[i][j] = match old_lines[i] == new_lines[j] {
+                true => common_lengths[i + 1][j + 1] + 1,
+                false => common_lengths[i + 1][j].max(common_lengths[i][j + 1]),
+            };
+        }
+    }
+
+    let mut lines: Vec<LineDiff> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old_lines.len() || j < new_lines.len() {
+        if i < old_lines.len() && j < new_lines.len() && old_lines[i] == new_lines[j] {
+            lines.push(LineDiff::Kept(old_lines[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j < new_lines.len() && (i == old_lines.len() || common_lengths[i][j + 1] >= common_lengths[i + 1][j]) {
+            lines.push(LineDiff::Added(new_lines[j].to_string()));
+            j += 1;
+        } else {
+            lines.push(LineDiff::Removed(old_lines[i].to_string()));
+            i += 1;
+        }
+    }
+    lines
+}
+
+/// The tags which every song should have, a missing one is reported by `Song::validate`
+const RECOMMENDED_TAGS: [&str; 2] = ["author", "copyright"];
+
+/// A structural problem of a song which has been found by `Song::validate`
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub enum SongIssue {
+    /// More than one part has the same ID (case-insensitive)
+    DuplicatePartId { part: String },
+    /// The number of a part does not match the number at the end of its ID
+    PartNumberMismatch { part: String, number: u32 },
+    /// A part has no content at all (repetitions of other parts are not regarded as empty)
+    EmptyPart { part: String },
+    /// The lyrics of a part contain tabs
+    TabsInLyrics { part: String },
+    /// A line of the lyrics of a part ends with whitespace
+    TrailingWhitespace { part: String },
+    /// A part is a repetition of a part which is not contained in the song
+    UnknownRepetition { part: String, repeated_part: String },
+    /// The song has no title
+    EmptyTitle,
+    /// The song lacks a tag which every song should have (e.g. the author)
+    MissingTag { key: String },
+    /// The `tempo` tag is neither a number of beats per minute nor a tempo marking (see `Tempo::parse`)
+    InvalidTempo { value: String },
+    /// The `time` tag is no valid time signature (see `TimeSignature::parse`)
+    InvalidTimeSignature { value: String },
+}
+
+impl fmt::Display for SongIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SongIssue::DuplicatePartId { part } => write!(f, "The ID {} is used by more than one part.", part),
+            SongIssue::PartNumberMismatch { part, number } => write!(f, "The part {} has the number {}.", part, number),
+            SongIssue::EmptyPart { part } => write!(f, "The part {} has no content.", part),
+            SongIssue::TabsInLyrics { part } => write!(f, "The lyrics of the part {} contain tabs.", part),
+            SongIssue::TrailingWhitespace { part } => write!(f, "The lyrics of the part {} contain trailing whitespace.", part),
+            SongIssue::UnknownRepetition { part, repeated_part } => write!(
+                f,
+                "The part {} is a repetition of {}, which is no part of the song.",
+                part, repeated_part
+            ),
+            SongIssue::EmptyTitle => write!(f, "The song has no title."),
+            SongIssue::MissingTag { key } => write!(f, "The song has no #{} tag.", key),
+            SongIssue::InvalidTempo { value } => write!(f, "The tempo {} is invalid.", value),
+            SongIssue::InvalidTimeSignature { value } => write!(f, "The time signature {} is invalid.", value),
+        }
+    }
+}
+
+impl SongIssue {
+    /// Returns the ID of the part which the issue refers to or None if it refers to the whole song
+    pub fn part(&self) -> Option<&str> {
+        match self {
+            SongIssue::DuplicatePartId { part }
+            | SongIssue::PartNumberMismatch { part, .. }
+            | SongIssue::EmptyPart { part }
+            | SongIssue::TabsInLyrics { part }
+            | SongIssue::TrailingWhitespace { part }
+            | SongIssue::UnknownRepetition { part, .. } => Some(part),
+            SongIssue::EmptyTitle
+            | SongIssue::MissingTag { .. }
+            | SongIssue::InvalidTempo { .. }
+            | SongIssue::InvalidTimeSignature { .. } => None,
+        }
+    }
+
+    /// Returns whether the issue is only a warning (e.g. a missing tag or a malformed tempo), the song can be used anyway
+    pub fn is_warning(&self) -> bool {
+        matches!(self, SongIssue::MissingTag { .. } | SongIssue::InvalidTempo { .. } | SongIssue::InvalidTimeSignature { .. })
+    }
+}
+
+/// Where the value of a tag comes from
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+pub enum TagSource {
+    /// The tag has been set by the song itself
+    Own,
+    /// The tag has been inherited from defaults (e.g. the defaults file of a songbook)
+    Inherited,
+}
+
 impl Song {
     /// Create a new song with the given title
     pub fn new(title: &str) -> Song {
         Song {
             title: title.to_string(),
             tags: HashMap::new(),
+            inherited_tags: HashSet::new(),
+            track_modifications: false,
             parts: Vec::new(),
             part_orders: Vec::new(),
         }
@@ -28,7 +362,11 @@ impl Song {
 
     /// Add a tag to the song
     pub fn add_tag(&mut self, key: &str, value: &str) {
+        self.inherited_tags.remove(key);
         self.tags.insert(key.to_string(), value.to_string());
+        if key != MODIFIED_TAG {
+            self.touch();
+        }
     }
 
     /// Get the value of a tag
@@ -36,9 +374,413 @@ impl Song {
         self.tags.get(key)
     }
 
+    /// Remove a tag from the song
+    /// # Returns
+    /// The value of the removed tag or None if the tag did not exist
+    pub fn remove_tag(&mut self, key: &str) -> Option<String> {
+        self.inherited_tags.remove(key);
+        let removed_value = self.tags.remove(key);
+        if removed_value.is_some() && key != MODIFIED_TAG {
+            self.touch();
+        }
+        removed_value
+    }
+
+    /// Get the creation time of the song, parsed from the `created` tag (ISO 8601)
+    /// # Returns
+    /// The creation time or None if the tag is missing or malformed
+    pub fn created(&self) -> Option<SystemTime> {
+        self.get_tag(CREATED_TAG).and_then(|value| parse_iso8601(value))
+    }
+
+    /// Set the creation time of the song (stored as ISO 8601 in the `created` tag)
+    pub fn set_created(&mut self, time: SystemTime) {
+        self.add_tag(CREATED_TAG, &format_iso8601(time));
+    }
+
+    /// Get the time of the last modification of the song, parsed from the `modified` tag (ISO 8601)
+    /// # Returns
+    /// The modification time or None if the tag is missing or malformed
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.get_tag(MODIFIED_TAG).and_then(|value| parse_iso8601(value))
+    }
+
+    /// Set the time of the last modification of the song (stored as ISO 8601 in the `modified` tag)
+    pub fn set_modified(&mut self, time: SystemTime) {
+        self.add_tag(MODIFIED_TAG, &format_iso8601(time));
+    }
+
+    /// Enables or disables the tracking of modifications.
+    /// If enabled, adding parts and adding or removing tags set the `modified` tag to the current time.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::Song;
+    /// let mut song = Song::new("Amazing Grace");
+    /// song.set_track_modifications(true);
+    /// song.add_tag("author", "John Newton");
+    /// assert!(song.modified().is_some());
+    /// ```
+    pub fn set_track_modifications(&mut self, track_modifications: bool) {
+        self.track_modifications = track_modifications;
+    }
+
+    /// Sets the `modified` tag to the current time if modifications are tracked
+    fn touch(&mut self) {
+        if self.track_modifications {
+            self.tags.insert(MODIFIED_TAG.to_string(), format_iso8601(SystemTime::now()));
+            self.inherited_tags.remove(MODIFIED_TAG);
+        }
+    }
+
+    /// Merge default tags into the song. Tags which are already set by the song itself always win.
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use cantara_songlib::song::{Song, TagSource};
+    /// let mut song = Song::new("Amazing Grace");
+    /// song.add_tag("language", "en");
+    /// let defaults = HashMap::from([
+    ///     ("language".to_string(), "de".to_string()),
+    ///     ("ccli_license".to_string(), "12345".to_string()),
+    /// ]);
+    /// song.inherit_tags(&defaults);
+    /// assert_eq!(song.get_tag("language").unwrap(), "en");
+    /// assert_eq!(song.tag_source("ccli_license"), Some(TagSource::Inherited));
+    /// ```
+    pub fn inherit_tags(&mut self, defaults: &HashMap<String, String>) {
+        for (key, value) in defaults {
+            if !self.tags.contains_key(key) {
+                self.tags.insert(key.clone(), value.clone());
+                self.inherited_tags.insert(key.clone());
+            }
+        }
+    }
+
+    /// Get the source of a tag (set by the song itself or inherited from defaults)
+    /// # Returns
+    /// The source of the tag or None if the tag does not exist
+    pub fn tag_source(&self, key: &str) -> Option<TagSource> {
+        if !self.tags.contains_key(key) {
+            return None;
+        }
+        match self.inherited_tags.contains(key) {
+            true => Some(TagSource::Inherited),
+            false => Some(TagSource::Own),
+        }
+    }
+
+    /// Get the authors of the song with their roles, parsed from the `author` tag
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::Song;
+    /// use cantara_songlib::metadata::AuthorRole;
+    /// let mut song = Song::new("Amazing Grace");
+    /// song.add_tag("author", "John Newton (words)");
+    /// assert_eq!(song.authors()[0].role, AuthorRole::Words);
+    /// ```
+    pub fn authors(&self) -> Vec<Author> {
+        match self.get_tag("author") {
+            Some(value) => parse_authors(value),
+            None => Vec::new(),
+        }
+    }
+
+    /// Get the musical key of the song, parsed from the `key` tag (in English notation)
+    /// # Returns
+    /// The key or None if the tag is missing or can't be parsed
+    pub fn key(&self) -> Option<Key> {
+        self.get_tag("key").and_then(|value| Key::parse(value))
+    }
+
+    /// Transposes the chords and the `key` tag of the song by the given number of semitones (see `transpose_with_notation`)
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartContent, SongPartContentType, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_tag("key", "G");
+    /// let verse = song.add_part_of_type(SongPartType::Verse, None);
+    /// verse.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "G  D/F#  Em\n".to_string() });
+    /// song.transpose(-2);
+    /// assert_eq!(song.get_tag("key").unwrap(), "F");
+    /// assert_eq!(verse.borrow().get_content(SongPartContentType::Chords).unwrap().content, "F  C/E   Dm\n");
+    /// ```
+    pub fn transpose(&mut self, semitones: i32) {
+        self.transpose_with_notation(semitones, NoteNotation::English);
+    }
+
+    /// Transposes the chords and the `key` tag of the song by the given number of semitones.
+    /// The key is spelled as it is conventionally written (see `Key::transpose_conventionally`) and the chords are written
+    /// with flats if the transposed key is written with flats (e.g. `F` or `Bb`), otherwise with sharps. A `key` tag which can't be parsed is kept. Transposing by 0 semitones changes nothing.
+    /// # Arguments
+    /// * `semitones` - The number of semitones (negative to transpose down)
+    /// * `notation` - The notation of the note names of the chords and the key (e.g. German with `H` for B natural)
+    pub fn transpose_with_notation(&mut self, semitones: i32, notation: NoteNotation) {
+        if semitones.rem_euclid(12) == 0 {
+            return;
+        }
+        let key: Option<Key> = self.get_tag("key").and_then(|value| Key::parse_with_notation(value, notation));
+        let transposed_key: Option<Key> = key.map(|key| key.transpose_conventionally(semitones));
+        let prefer_flats: bool = transposed_key.is_some_and(|key| key.uses_flats());
+        self.transpose_chords(semitones, prefer_flats, notation);
+        if let Some(key) = transposed_key {
+            self.add_tag("key", &key.to_notation_string(notation));
+        }
+        self.touch();
+    }
+
+    /// Transposes the chord lines of all parts line by line (see `chords::transpose_line_with_notation`)
+    fn transpose_chords(&self, semitones: i32, prefer_flats: bool, notation: NoteNotation) {
+        for part_reference in &self.parts {
+            let mut part = part_reference.borrow_mut();
+            for content in part.contents.iter_mut().filter(|content| content.voice_type == SongPartContentType::Chords) {
+                content.content = content
+                    .content
+                    .split('\n')
+                    .map(|line| transpose_line_with_notation(line, semitones, prefer_flats, notation))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+            }
+        }
+    }
+
+    /// Creates a copy of the song for playing it with a capo: the chords are transposed down by the fret of the capo, so that
+    /// they show the shapes which are played, and the fret is stored in the `capo` tag. The `key` tag keeps the sounding key.
+    /// The chords are written with flats if the key of the shapes is written with flats, otherwise with sharps.
+    /// The song itself is not changed.
+    /// # Arguments
+    /// * `fret` - The fret of the capo, 0 returns an unchanged copy of the song
+    /// # Returns
+    /// The copy of the song or an error if the fret is greater than 11
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartContent, SongPartContentType, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_tag("key", "Eb");
+    /// let verse = song.add_part_of_type(SongPartType::Verse, None);
+    /// verse.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "Eb  Bb/D  Cm\n".to_string() });
+    /// let capo_song: Song = song.with_capo(1).unwrap();
+    /// assert_eq!(capo_song.get_part_by_id("Verse.1").unwrap().borrow().get_content(SongPartContentType::Chords).unwrap().content, "D   A/C#  Bm\n");
+    /// assert_eq!(capo_song.get_tag("capo").unwrap(), "1");
+    /// assert_eq!(capo_song.get_tag("key").unwrap(), "Eb");
+    /// ```
+    pub fn with_capo(&self, fret: u8) -> Result<Song, ChordError> {
+        if fret > 11 {
+            return Err(ChordError::InvalidCapo { fret });
+        }
+        let mut song: Song = self.detached_copy();
+        if fret == 0 {
+            return Ok(song);
+        }
+        let semitones: i32 = -(fret as i32);
+        let prefer_flats: bool = self.key().is_some_and(|key| key.transpose_conventionally(semitones).uses_flats());
+        song.transpose_chords(semitones, prefer_flats, NoteNotation::English);
+        song.add_tag("capo", &fret.to_string());
+        Ok(song)
+    }
+
+    /// Creates a copy of the song with its own parts, so that changing the parts of the copy doesn't change this song
+    /// (a clone shares its parts with the original song). The part orders and the references between the parts are
+    /// moved to the copied parts.
+    fn detached_copy(&self) -> Song {
+        let parts: Vec<Rc<RefCell<SongPart>>> =
+            self.parts.iter().map(|part| Rc::new(RefCell::new(part.borrow().clone()))).collect();
+        let copied_part = |reference: &Rc<RefCell<SongPart>>| -> Rc<RefCell<SongPart>> {
+            match self.parts.iter().position(|part| Rc::ptr_eq(part, reference)) {
+                Some(index) => parts[index].clone(),
+                None => reference.clone(),
+            }
+        };
+        for part_reference in &parts {
+            let mut part = part_reference.borrow_mut();
+            part.is_repetition_of = part.is_repetition_of.as_ref().map(copied_part);
+            part.occurs_after = part.occurs_after.as_ref().map(copied_part);
+        }
+        let mut part_orders: Vec<PartOrder> = self.part_orders.clone();
+        for part_order in &mut part_orders {
+            if let PartOrderRule::Custom(order_parts) = &mut part_order.partorderrule {
+                *order_parts = order_parts.iter().map(copied_part).collect();
+            }
+        }
+        Song { parts, part_orders, ..self.clone() }
+    }
+
+    /// Creates a copy of the song in which all chords are written in the Nashville number system relative to the `key` tag
+    /// (see `chords::to_nashville`), e.g. for musicians who play the song in another key. The song itself is not changed.
+    /// Tokens of the chord lines which are no chords are kept and the numbers stay at the columns of the chords.
+    /// # Returns
+    /// The copy of the song or an error if the song has no `key` tag or the key can't be parsed
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartContent, SongPartContentType, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_tag("key", "G");
+    /// let verse = song.add_part_of_type(SongPartType::Verse, None);
+    /// verse.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "G  D/F#  Em  F\n".to_string() });
+    /// let numbers: Song = song.render_chords_as_numbers().unwrap();
+    /// assert_eq!(numbers.get_part_by_id("Verse.1").unwrap().borrow().get_content(SongPartContentType::Chords).unwrap().content, "1  5/7   6m  b7\n");
+    /// assert_eq!(verse.borrow().get_content(SongPartContentType::Chords).unwrap().content, "G  D/F#  Em  F\n");
+    /// ```
+    pub fn render_chords_as_numbers(&self) -> Result<Song, ChordError> {
+        let key_tag: &String = self.get_tag("key").ok_or(ChordError::MissingKey)?;
+        let key: Key = Key::parse(key_tag).ok_or_else(|| ChordError::InvalidKey { key: key_tag.to_string() })?;
+        let song: Song = self.detached_copy();
+        for part_reference in &song.parts {
+            let mut part = part_reference.borrow_mut();
+            for content in part.contents.iter_mut().filter(|content| content.voice_type == SongPartContentType::Chords) {
+                content.content = content
+                    .content
+                    .split('\n')
+                    .map(|line| line_to_nashville(line, &key))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+            }
+        }
+        Ok(song)
+    }
+
+    /// Get the tempo of the song, parsed from the `tempo` tag (e.g. `72`, `72 bpm` or `Moderato`)
+    /// # Returns
+    /// The tempo or None if the tag is missing or malformed
+    pub fn tempo(&self) -> Option<Tempo> {
+        self.get_tag("tempo").and_then(|value| Tempo::parse(value))
+    }
+
+    /// Get the time signature of the song, parsed from the `time` tag (e.g. `3/4`)
+    /// # Returns
+    /// The time signature or None if the tag is missing or malformed
+    pub fn time_signature(&self) -> Option<TimeSignature> {
+        self.get_tag("time").and_then(|value| TimeSignature::parse(value))
+    }
+
+    /// Get the themes of the song (e.g. `Christmas`, `Advent`).
+    /// The themes are read from the `themes`, `tags`, `categories`, `theme` and `category` tags.
+    /// They are returned in the order they are given, duplicates are removed.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::Song;
+    /// let mut song = Song::new("O come, all ye faithful");
+    /// song.add_tag("themes", "Christmas; Advent");
+    /// song.add_tag("tags", "christmas, Candlelight");
+    /// assert_eq!(song.themes(), vec!["Christmas", "Advent", "Candlelight"]);
+    /// ```
+    pub fn themes(&self) -> Vec<String> {
+        let values: Vec<&str> = THEME_TAGS
+            .iter()
+            .filter_map(|tag| self.get_tag(tag))
+            .map(|value| value.as_str())
+            .collect();
+        parse_themes(&values.join(";"))
+    }
+
+    /// Returns whether the song has the given theme (see `themes`).
+    /// The comparison is case- and diacritic-insensitive.
+    pub fn has_theme(&self, theme: &str) -> bool {
+        let folded_theme = fold_for_comparison(theme);
+        self.themes().iter().any(|song_theme| fold_for_comparison(song_theme) == folded_theme)
+    }
+
+    /// Get the categories of the song, which are the same as its themes (see `themes`):
+    /// the values of the `tags`, `category` and `categories` tags (as well as `themes` and `theme`),
+    /// split on commas and semicolons, trimmed and without duplicates (compared case-insensitively).
+    /// The categories are also contained in the JSON serialization of the song as `categories` array,
+    /// a changed array replaces the categories when the song is deserialized (see `set_categories`).
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::Song;
+    /// let mut song = Song::new("Macht hoch die Tür");
+    /// song.add_tag("tags", "advent, christmas;  candlelight");
+    /// song.add_tag("category", "Advent");
+    /// assert_eq!(song.get_categories(), vec!["advent", "christmas", "candlelight"]);
+    /// ```
+    pub fn get_categories(&self) -> Vec<String> {
+        self.themes()
+    }
+
+    /// Replace the categories of the song: the tags which contain categories (see `get_categories`) are removed
+    /// and the categories are stored in the `categories` tag (which is left out if there are no categories).
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::Song;
+    /// let mut song = Song::new("Macht hoch die Tür");
+    /// song.add_tag("tags", "advent");
+    /// song.set_categories(&["Christmas".to_string(), "Candlelight".to_string()]);
+    /// assert_eq!(song.get_tag("tags"), None);
+    /// assert_eq!(song.get_tag("categories").unwrap(), "Christmas, Candlelight");
+    /// ```
+    pub fn set_categories(&mut self, categories: &[String]) {
+        for tag in THEME_TAGS {
+            self.remove_tag(tag);
+        }
+        let categories: Vec<String> = parse_themes(&categories.join(";"));
+        if !categories.is_empty() {
+            self.add_tag("categories", &categories.join(", "));
+        }
+    }
+
+    /// Add a category to the song, unless it already has it (compared case-insensitively).
+    /// The category is appended to the first tag which contains categories (see `get_categories`),
+    /// if there is none, the `categories` tag is created.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::Song;
+    /// let mut song = Song::new("Macht hoch die Tür");
+    /// song.add_tag("tags", "advent");
+    /// song.add_category("Christmas");
+    /// song.add_category("ADVENT");
+    /// assert_eq!(song.get_tag("tags").unwrap(), "advent, Christmas");
+    /// ```
+    pub fn add_category(&mut self, category: &str) {
+        let category = category.trim();
+        let categories: Vec<String> = self.get_categories();
+        if category.is_empty() || parse_themes(&format!("{};{}", categories.join(";"), category)).len() == categories.len() {
+            return;
+        }
+        match THEME_TAGS.iter().find(|tag| self.get_tag(tag).is_some_and(|value| !value.trim().is_empty())) {
+            Some(tag) => {
+                let value: String = format!("{}, {}", self.get_tag(tag).unwrap().trim(), category);
+                self.add_tag(tag, &value);
+            },
+            None => self.add_tag("categories", category),
+        }
+    }
+
+    /// Get the bible passages the song is based on, parsed from the `scripture` tag (e.g. `John 3:16-17; Ps 23`)
+    /// # Returns
+    /// All references which could be parsed
+    pub fn scripture_refs(&self) -> Vec<ScriptureRef> {
+        match self.get_tag("scripture") {
+            Some(value) => parse_scripture_refs(value),
+            None => Vec::new(),
+        }
+    }
+
+    /// Get the primary language of the song lyrics, taken from the `language` tag (e.g. `#language: de`)
+    /// # Returns
+    /// `LyricLanguage::Specific` with the language code or `LyricLanguage::Default` if no language is given
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{LyricLanguage, Song};
+    /// let mut song = Song::new("Stille Nacht");
+    /// assert_eq!(song.primary_language(), LyricLanguage::Default);
+    /// song.add_tag("language", "de");
+    /// assert_eq!(song.primary_language(), LyricLanguage::Specific("de".to_string()));
+    /// ```
+    pub fn primary_language(&self) -> LyricLanguage {
+        match self.get_tag("language").map(|language| language.trim()) {
+            Some(language) if !language.is_empty() => LyricLanguage::Specific(language.to_string()),
+            _ => LyricLanguage::Default,
+        }
+    }
+
+    /// Get all tags of the song
+    pub fn get_tags(&self) -> &HashMap<String, String> {
+        &self.tags
+    }
+
     /// Add a part to the song
     pub fn add_part(&mut self, part: SongPart) {
         self.parts.push(Rc::new(RefCell::new(part)));
+        self.touch();
     }
 
     /// Get the number of parts of a specific type
@@ -81,7 +823,7 @@ impl Song {
         };
         let id: String = format!(
             "{}.{}",
-            part_type.to_string(),
+            part_type,
             specific_number_option.unwrap_or(specific_number)
         );
         
@@ -174,6 +916,22 @@ impl Song {
         positions
     }
 
+    /// Find all parts which contain a content for which the given function returns true
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// assert!(song.find_matching_content_in_part(|content| content.content.is_empty()).is_empty());
+    /// ```
+    pub fn find_matching_content_in_part(&self, matches: impl Fn(&SongPartContent) -> bool) -> Vec<Rc<RefCell<SongPart>>> {
+        self.parts
+            .iter()
+            .filter(|part_refcell| part_refcell.borrow().contents.iter().any(&matches))
+            .cloned()
+            .collect()
+    }
+
     pub fn find_first_content_in_part(&self, content: &str) -> Option<Rc<RefCell<SongPart>>> {
         self.find_content_in_part(content).first().cloned()
     }
@@ -195,6 +953,432 @@ impl Song {
         None
     }
 
+    /// Change the type of a part. The part gets the next free number of the new type (e.g. `chorus.2` if there is already a `chorus.1`)
+    /// and its ID is updated accordingly. The remaining parts of the old type keep their numbers.
+    /// Part orders reference the parts directly and therefore stay valid.
+    /// This is the function to retype a part (e.g. a verse which is actually a chorus), there is no separate `retype_part`.
+    /// # Arguments
+    /// * `id` - The ID of the part (case-insensitive)
+    /// * `new_type` - The new type of the part
+    /// # Returns
+    /// The new ID of the part or an error if there is no part with the given ID
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_part_of_type(SongPartType::Chorus, None);
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// let id = song.change_part_type("verse.1", SongPartType::Chorus).unwrap();
+    /// assert_eq!(id.to_string(), "Chorus.2");
+    /// ```
+    pub fn change_part_type(&mut self, id: &str, new_type: SongPartType) -> Result<SongPartId, SongEditError> {
+        let part = self.parts
+            .iter()
+            .find(|part| part.borrow().id.get_id().eq_ignore_ascii_case(id))
+            .cloned()
+            .ok_or_else(|| SongEditError::PartNotFound { id: id.to_string() })?;
+        let new_id = self.change_type_of_part(&part, new_type);
+        self.touch();
+        Ok(new_id)
+    }
+
+    /// Change the type of the given part of the song (see `change_part_type`)
+    pub(crate) fn change_type_of_part(&self, part_reference: &Rc<RefCell<SongPart>>, new_type: SongPartType) -> SongPartId {
+        if part_reference.borrow().part_type == new_type {
+            return part_reference.borrow().id.clone();
+        }
+
+        let mut number: u32 = 1;
+        while self.parts.iter().any(|part| {
+            let part = part.borrow();
+            part.part_type == new_type && part.number == number
+        }) {
+            number += 1;
+        }
+
+        {
+            let mut part = part_reference.borrow_mut();
+            part.set_type(new_type);
+            part.number = number;
+            part.update_id();
+        }
+
+        let new_id = part_reference.borrow().id.get_id();
+        let is_unique = self.parts
+            .iter()
+            .filter(|part| part.borrow().id.get_id().eq_ignore_ascii_case(&new_id))
+            .count() == 1;
+        let mut part = part_reference.borrow_mut();
+        part.id.set_checked_unique(is_unique);
+        part.id.clone()
+    }
+
+    /// Remove a part from the song. Parts which are a repetition of the removed part lose this link, parts which occur
+    /// after the removed part are linked to the part after which the removed part occurred. The removed part is also
+    /// removed from all custom part orders.
+    /// # Arguments
+    /// * `id` - The ID of the part (case-insensitive)
+    /// # Returns
+    /// The removed part or None if there is no part with the given ID
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// assert_eq!(song.remove_part("verse.1").unwrap().id.to_string(), "Verse.1");
+    /// assert!(song.remove_part("verse.1").is_none());
+    /// assert_eq!(song.get_total_part_count(), 1);
+    /// ```
+    pub fn remove_part(&mut self, id: &str) -> Option<SongPart> {
+        let index: usize = self.part_index(id).ok()?;
+        let removed_part: Rc<RefCell<SongPart>> = self.parts.remove(index);
+        let is_removed_part = |part: &Option<Rc<RefCell<SongPart>>>| part.as_ref().is_some_and(|part| Rc::ptr_eq(part, &removed_part));
+
+        let removed_occurs_after: Option<Rc<RefCell<SongPart>>> = removed_part.borrow().occurs_after.clone();
+        for part_reference in &self.parts {
+            let mut part = part_reference.borrow_mut();
+            if is_removed_part(&part.is_repetition_of) {
+                part.is_repetition_of = None;
+            }
+            if is_removed_part(&part.occurs_after) {
+                part.occurs_after = removed_occurs_after.clone();
+            }
+        }
+        for part_order in &mut self.part_orders {
+            if let PartOrderRule::Custom(parts) = &mut part_order.partorderrule {
+                parts.retain(|part| !Rc::ptr_eq(part, &removed_part));
+            }
+        }
+        self.touch();
+
+        let part: SongPart = removed_part.borrow().clone();
+        Some(part)
+    }
+
+    /// Returns the index of the part with the given ID (case-insensitive) in the parts of the song
+    fn part_index(&self, id: &str) -> Result<usize, SongEditError> {
+        self.parts
+            .iter()
+            .position(|part| part.borrow().id.get_id().eq_ignore_ascii_case(id))
+            .ok_or_else(|| SongEditError::PartNotFound { id: id.to_string() })
+    }
+
+    /// Links every part which occurs after another part to the part before it, so that the links reflect the order of the parts
+    fn relink_occurs_after(&self) {
+        for (index, part_reference) in self.parts.iter().enumerate() {
+            let mut part = part_reference.borrow_mut();
+            if part.occurs_after.is_some() {
+                part.occurs_after = index.checked_sub(1).map(|previous_index| self.parts[previous_index].clone());
+            }
+        }
+    }
+
+    /// Move a part to another position in the parts of the song. The parts which occur after another part are linked
+    /// to the part before them afterwards. Part orders reference the parts directly and are not changed.
+    /// # Arguments
+    /// * `id` - The ID of the part (case-insensitive)
+    /// * `new_index` - The index of the part after moving it
+    /// # Returns
+    /// Ok or an error if there is no part with the given ID or the index is out of range
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// song.move_part("verse.3", 1).unwrap();
+    /// let ids: Vec<String> = song.get_unpacked_parts().iter().map(|part| part.id.to_string()).collect();
+    /// assert_eq!(ids, vec!["Verse.1", "Verse.3", "Verse.2"]);
+    /// assert!(song.move_part("verse.1", 3).is_err());
+    /// ```
+    pub fn move_part(&mut self, id: &str, new_index: usize) -> Result<(), SongEditError> {
+        let index: usize = self.part_index(id)?;
+        if new_index >= self.parts.len() {
+            return Err(SongEditError::IndexOutOfRange { index: new_index, part_count: self.parts.len() });
+        }
+        let part: Rc<RefCell<SongPart>> = self.parts.remove(index);
+        self.parts.insert(new_index, part);
+        self.relink_occurs_after();
+        self.touch();
+        Ok(())
+    }
+
+    /// Swap the positions of two parts of the song (see `move_part`)
+    /// # Arguments
+    /// * `a` - The ID of the first part (case-insensitive)
+    /// * `b` - The ID of the second part (case-insensitive)
+    /// # Returns
+    /// Ok or an error if one of the parts does not exist
+    pub fn swap_parts(&mut self, a: &str, b: &str) -> Result<(), SongEditError> {
+        let index_a: usize = self.part_index(a)?;
+        let index_b: usize = self.part_index(b)?;
+        self.parts.swap(index_a, index_b);
+        self.relink_occurs_after();
+        self.touch();
+        Ok(())
+    }
+
+    /// Merge the lyrics of a translation of the song (e.g. imported from another file) into the song.
+    /// The parts are matched by their position among the parts of the same type (the first verse with the first verse,
+    /// the first chorus with the first chorus, ...), repetitions of other parts are left out. The first lyrics of every
+    /// part of the translation are added to the matching part of the song in the given language.
+    /// # Arguments
+    /// * `other` - The translation of the song
+    /// * `language` - The language of the translation
+    /// # Returns
+    /// A `MergeReport` with the merged parts and the parts without counterpart, or an error if the song already contains
+    /// lyrics in the given language
+    /// # Example
+    /// ```
+    /// use cantara_songlib::importer::classic_song::import_song;
+    /// use cantara_songlib::song::{LyricLanguage, SongPartContentType};
+    /// let mut song = import_song("Amazing grace, how sweet the sound").unwrap();
+    /// let translation = import_song("O Gnade Gottes, wunderbar\n\nEine zweite Strophe").unwrap();
+    /// let report = song.merge_translation(&translation, LyricLanguage::Specific("de".to_string())).unwrap();
+    /// assert_eq!(report.merged_parts, vec!["Verse.1"]);
+    /// assert_eq!(report.unmatched_translation_parts, vec!["Verse.2"]);
+    /// let german = SongPartContentType::Lyrics { language: LyricLanguage::Specific("de".to_string()) };
+    /// let verse = song.get_part_by_id("Verse.1").unwrap();
+    /// assert_eq!(verse.borrow().get_content(german).unwrap().content, "O Gnade Gottes, wunderbar\n");
+    /// ```
+    pub fn merge_translation(&mut self, other: &Song, language: LyricLanguage) -> Result<MergeReport, SongEditError> {
+        let language_exists: bool = self.parts.iter().any(|part| {
+            part.borrow().contents.iter().any(|content| content.voice_type == SongPartContentType::Lyrics { language: language.clone() })
+        });
+        if language_exists {
+            return Err(SongEditError::LanguageExists { language });
+        }
+
+        let original_parts = |song: &Song| -> Vec<Rc<RefCell<SongPart>>> {
+            song.parts.iter().filter(|part| part.borrow().is_repetition_of.is_none()).cloned().collect()
+        };
+        let parts: Vec<Rc<RefCell<SongPart>>> = original_parts(self);
+        let translation_parts: Vec<Rc<RefCell<SongPart>>> = original_parts(other);
+        // The position of a part among the parts of the same type
+        let position = |parts: &[Rc<RefCell<SongPart>>], index: usize| -> usize {
+            let part_type: SongPartType = parts[index].borrow().part_type;
+            parts[..index].iter().filter(|part| part.borrow().part_type == part_type).count()
+        };
+        let find_counterpart = |part_index: usize, parts: &[Rc<RefCell<SongPart>>], other_parts: &[Rc<RefCell<SongPart>>]| {
+            let part_type: SongPartType = parts[part_index].borrow().part_type;
+            let part_position: usize = position(parts, part_index);
+            (0..other_parts.len())
+                .find(|other_index| other_parts[*other_index].borrow().part_type == part_type && position(other_parts, *other_index) == part_position)
+                .map(|other_index| other_parts[other_index].clone())
+        };
+
+        let mut report: MergeReport = MergeReport::default();
+        for (index, part) in parts.iter().enumerate() {
+            let Some(translation_part) = find_counterpart(index, &parts, &translation_parts) else {
+                report.unmatched_parts.push(part.borrow().id.to_string());
+                continue;
+            };
+            let lyrics: Option<String> = translation_part
+                .borrow()
+                .contents
+                .iter()
+                .find(|content| content.voice_type.is_lyrics())
+                .map(|content| content.content.clone());
+            if let Some(lyrics) = lyrics {
+                let mut part = part.borrow_mut();
+                part.add_content(SongPartContent {
+                    voice_type: SongPartContentType::Lyrics { language: language.clone() },
+                    content: lyrics,
+                });
+                report.merged_parts.push(part.id.to_string());
+            }
+        }
+        report.unmatched_translation_parts = (0..translation_parts.len())
+            .filter(|index| find_counterpart(*index, &translation_parts, &parts).is_none())
+            .map(|index| translation_parts[index].borrow().id.to_string())
+            .collect();
+
+        self.touch();
+        Ok(report)
+    }
+
+    /// Checks the structure of the song, e.g. before it is presented.
+    /// # Returns
+    /// All problems which have been found (see `SongIssue`), the problems of the parts in the order of the parts
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongIssue, SongPartType};
+    /// let mut song = Song::new("Amazing Grace");
+    /// song.add_tag("author", "John Newton");
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// assert_eq!(song.validate(), vec![
+    ///     SongIssue::EmptyPart { part: "Verse.1".to_string() },
+    ///     SongIssue::MissingTag { key: "copyright".to_string() },
+    /// ]);
+    /// ```
+    pub fn validate(&self) -> Vec<SongIssue> {
+        let mut issues: Vec<SongIssue> = Vec::new();
+        if self.title.trim().is_empty() {
+            issues.push(SongIssue::EmptyTitle);
+        }
+
+        let mut ids: HashSet<String> = HashSet::new();
+        for part_reference in &self.parts {
+            let part = part_reference.borrow();
+            let id: String = part.id.get_id();
+            if !ids.insert(id.to_lowercase()) {
+                issues.push(SongIssue::DuplicatePartId { part: id.clone() });
+            }
+            let id_number: Option<u32> = id.rsplit_once('.').and_then(|(_, number)| number.parse().ok());
+            if id_number != Some(part.number) {
+                issues.push(SongIssue::PartNumberMismatch { part: id.clone(), number: part.number });
+            }
+            match &part.is_repetition_of {
+                Some(repeated_part) if !self.parts.iter().any(|part| Rc::ptr_eq(part, repeated_part)) => {
+                    issues.push(SongIssue::UnknownRepetition { part: id.clone(), repeated_part: repeated_part.borrow().id.get_id() });
+                },
+                Some(_) => {},
+                None if part.contents.iter().all(|content| content.content.trim().is_empty()) => {
+                    issues.push(SongIssue::EmptyPart { part: id.clone() });
+                },
+                None => {},
+            }
+
+            let lyrics: Vec<&SongPartContent> = part.contents.iter().filter(|content| content.voice_type.is_lyrics()).collect();
+            if lyrics.iter().any(|content| content.content.contains('\t')) {
+                issues.push(SongIssue::TabsInLyrics { part: id.clone() });
+            }
+            if lyrics.iter().any(|content| content.content.lines().any(|line| line.trim_end() != line)) {
+                issues.push(SongIssue::TrailingWhitespace { part: id });
+            }
+        }
+
+        for key in RECOMMENDED_TAGS {
+            if self.get_tag(key).is_none_or(|value| value.trim().is_empty()) {
+                issues.push(SongIssue::MissingTag { key: key.to_string() });
+            }
+        }
+        // Empty values are ignored, like missing tags
+        if let Some(value) = self.get_tag("tempo").filter(|value| !value.trim().is_empty() && self.tempo().is_none()) {
+            issues.push(SongIssue::InvalidTempo { value: value.clone() });
+        }
+        if let Some(value) = self.get_tag("time").filter(|value| !value.trim().is_empty() && self.time_signature().is_none()) {
+            issues.push(SongIssue::InvalidTimeSignature { value: value.clone() });
+        }
+        issues
+    }
+
+    /// Compares the song with another version of it (e.g. after the song file has been edited).
+    /// The parts are compared by their IDs, a part which has got another type with the same lyrics is reported as retyped.
+    /// Only the values are compared, not the references between the parts.
+    /// # Arguments
+    /// * `other` - The new version of the song
+    /// # Returns
+    /// A `SongDiff` with the changed parts, lyrics and tags
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{LineDiff, SongBuilder};
+    /// let old_song = SongBuilder::new("Test").verse("Amazing grace\nhow sweet the sound").build();
+    /// let new_song = SongBuilder::new("Test").verse("Amazing grace\nhow sweet the sound!").build();
+    /// let diff = old_song.diff(&new_song);
+    /// assert_eq!(diff.lyric_changes[0].lines, vec![
+    ///     LineDiff::Kept("Amazing grace".to_string()),
+    ///     LineDiff::Added("how sweet the sound!".to_string()),
+    ///     LineDiff::Removed("how sweet the sound".to_string()),
+    /// ]);
+    /// assert!(old_song.diff(&old_song).is_empty());
+    /// ```
+    pub fn diff(&self, other: &Song) -> SongDiff {
+        let mut diff: SongDiff = SongDiff::default();
+        let old_parts: Vec<SongPart> = self.get_unpacked_parts();
+        let new_parts: Vec<SongPart> = other.get_unpacked_parts();
+        let same_id = |part: &SongPart, other_part: &SongPart| part.id.get_id().eq_ignore_ascii_case(&other_part.id.get_id());
+        let lyrics = |part: &SongPart| -> Vec<SongPartContent> {
+            part.contents.iter().filter(|content| content.voice_type.is_lyrics()).cloned().collect()
+        };
+        // Whether a part has a counterpart with the same ID and the same lyrics
+        let is_unchanged = |part: &SongPart, parts: &[SongPart]| parts.iter().any(|other_part| same_id(part, other_part) && lyrics(part) == lyrics(other_part));
+
+        let mut matched_old: Vec<bool> = vec![false; old_parts.len()];
+        let mut matched_new: Vec<bool> = vec![false; new_parts.len()];
+        for (old_index, old_part) in old_parts.iter().enumerate() {
+            if is_unchanged(old_part, &new_parts) || lyrics(old_part).is_empty() {
+                continue;
+            }
+            let retyped_part = new_parts.iter().enumerate().find(|(new_index, new_part)| {
+                !matched_new[*new_index]
+                    && new_part.part_type != old_part.part_type
+                    && lyrics(new_part) == lyrics(old_part)
+                    && !is_unchanged(new_part, &old_parts)
+            });
+            if let Some((new_index, new_part)) = retyped_part {
+                matched_old[old_index] = true;
+                matched_new[new_index] = true;
+                diff.retyped_parts.push(RetypedPart { old_part: old_part.id.get_id(), new_part: new_part.id.get_id() });
+            }
+        }
+
+        for (old_index, old_part) in old_parts.iter().enumerate() {
+            if matched_old[old_index] {
+                continue;
+            }
+            let counterpart = new_parts.iter().enumerate().find(|(new_index, new_part)| !matched_new[*new_index] && same_id(old_part, new_part));
+            let Some((new_index, new_part)) = counterpart else {
+                continue;
+            };
+            matched_old[old_index] = true;
+            matched_new[new_index] = true;
+
+            let mut languages: Vec<LyricLanguage> = Vec::new();
+            for content in lyrics(old_part).into_iter().chain(lyrics(new_part)) {
+                if let SongPartContentType::Lyrics { language } = content.voice_type {
+                    if !languages.contains(&language) {
+                        languages.push(language);
+                    }
+                }
+            }
+            for language in languages {
+                let content_type = SongPartContentType::Lyrics { language: language.clone() };
+                let old_text: String = old_part.get_content(content_type.clone()).map(|content| content.content.clone()).unwrap_or_default();
+                let new_text: String = new_part.get_content(content_type).map(|content| content.content.clone()).unwrap_or_default();
+                if old_text != new_text {
+                    diff.lyric_changes.push(PartDiff { part: old_part.id.get_id(), language, lines: diff_lines(&old_text, &new_text) });
+                }
+            }
+        }
+        diff.removed_parts = old_parts.iter().zip(matched_old).filter(|(_, matched)| !matched).map(|(part, _)| part.id.get_id()).collect();
+        diff.added_parts = new_parts.iter().zip(matched_new).filter(|(_, matched)| !matched).map(|(part, _)| part.id.get_id()).collect();
+
+        let mut keys: Vec<&String> = self.tags.keys().chain(other.tags.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let change: Option<TagChange> = match (self.get_tag(key), other.get_tag(key)) {
+                (Some(old_value), Some(new_value)) if old_value != new_value => Some(TagChange::Changed {
+                    key: key.clone(),
+                    old_value: old_value.clone(),
+                    new_value: new_value.clone(),
+                }),
+                (Some(value), None) => Some(TagChange::Removed { key: key.clone(), value: value.clone() }),
+                (None, Some(value)) => Some(TagChange::Added { key: key.clone(), value: value.clone() }),
+                _ => None,
+            };
+            diff.tag_changes.extend(change);
+        }
+        diff
+    }
+
+    /// Remove all parts of a specific type from the song (see `remove_part`)
+    /// # Arguments
+    /// * `part_type` - The type of the parts which are to be removed
+    /// # Returns
+    /// The number of removed parts
+    pub fn remove_parts_by_type(&mut self, part_type: SongPartType) -> usize {
+        let ids: Vec<String> = self
+            .get_parts_by_type(part_type)
+            .iter()
+            .map(|part| part.borrow().id.get_id())
+            .collect();
+        ids.iter().filter(|id| self.remove_part(id).is_some()).count()
+    }
+
     /// Gets the part by the index (the order)
     /// # Arguments
     /// * `index` - the index of the part
@@ -212,7 +1396,8 @@ impl Song {
     /// Gets a vector of all parts of a specific SongPartType
     /// # Arguments
     /// * `part_type`: A SongPartType
-    /// Returns
+    ///
+    /// # Returns
     /// A vector of all SongParts with the SongPartType given.
     pub fn get_parts_by_type(&self, part_type: SongPartType) -> Vec<Rc<RefCell<SongPart>>> {
         let mut parts: Vec<Rc<RefCell<SongPart>>> = Vec::new();
@@ -257,6 +1442,259 @@ impl Song {
         parts
     }
 
+    /// Iterates over the parts of the song in the order in which they have been added, without cloning them
+    /// (see `get_unpacked_parts` for detached copies)
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{Song, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// song.add_part_of_type(SongPartType::Chorus, None);
+    /// let ids: Vec<String> = song.parts().map(|part| part.id.to_string()).collect();
+    /// assert_eq!(ids, vec!["Verse.1", "Chorus.1"]);
+    /// ```
+    pub fn parts(&self) -> impl Iterator<Item = Ref<'_, SongPart>> {
+        self.parts.iter().map(|part| part.borrow())
+    }
+
+    /// Iterates mutably over the parts of the song in the order in which they have been added, e.g. to edit their contents.
+    /// The song is regarded as modified when the iterator is created.
+    pub fn parts_mut(&mut self) -> impl Iterator<Item = RefMut<'_, SongPart>> {
+        self.touch();
+        self.parts.iter().map(|part| part.borrow_mut())
+    }
+
+    /// Iterates over the lyrics of the parts in the given language in the order in which the parts have been added.
+    /// Parts without lyrics in the language are left out.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::importer::classic_song::import_song;
+    /// use cantara_songlib::song::LyricLanguage;
+    /// let song = import_song("Amazing grace\n\nHow sweet the sound").unwrap();
+    /// let lyrics: Vec<String> = song.lyrics_blocks(&LyricLanguage::Default).collect();
+    /// assert_eq!(lyrics, vec!["Amazing grace\n", "How sweet the sound\n"]);
+    /// ```
+    pub fn lyrics_blocks<'a>(&'a self, language: &'a LyricLanguage) -> impl Iterator<Item = String> + 'a {
+        self.parts().filter_map(move |part| {
+            part.contents
+                .iter()
+                .find(|content| {
+                    matches!(&content.voice_type, SongPartContentType::Lyrics { language: content_language } if content_language == language)
+                })
+                .map(|content| content.content.clone())
+        })
+    }
+
+    /// Get all languages of the lyrics of the song in the order in which they first occur in the parts
+    /// # Example
+    /// ```
+    /// use cantara_songlib::importer::classic_song::import_song;
+    /// use cantara_songlib::song::LyricLanguage;
+    /// let song = import_song("#language: de\n\nLobt den Herrn\n---\nPraise the Lord").unwrap();
+    /// // The lyrics after `---` lines are stored as secondary language
+    /// assert_eq!(
+    ///     song.available_languages(),
+    ///     vec![LyricLanguage::Specific("de".to_string()), LyricLanguage::Specific("secondary".to_string())]
+    /// );
+    /// ```
+    pub fn available_languages(&self) -> Vec<LyricLanguage> {
+        let mut languages: Vec<LyricLanguage> = Vec::new();
+        for part in self.parts() {
+            for content in &part.contents {
+                if let SongPartContentType::Lyrics { language } = &content.voice_type {
+                    if !languages.contains(language) {
+                        languages.push(language.clone());
+                    }
+                }
+            }
+        }
+        languages
+    }
+
+    /// Get the lyrics of the song in the given language: the lyrics of the parts in the order in which they are sung
+    /// (see `get_ordered_parts`), separated by blank lines. Parts without lyrics in the language fall back to other
+    /// lyrics (see `SongPart::get_lyrics`), parts without any lyrics are left out.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{LyricLanguage, SongBuilder};
+    /// let song = SongBuilder::new("Test").verse("First verse").chorus("The chorus").build();
+    /// assert_eq!(song.lyrics_text(&LyricLanguage::Specific("de".to_string())), "First verse\n\nThe chorus");
+    /// ```
+    pub fn lyrics_text(&self, language: &LyricLanguage) -> String {
+        self.get_ordered_parts()
+            .iter()
+            .filter_map(|part| part.borrow().get_lyrics(language).map(|content| content.content.trim_end().to_string()))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    /// Get the plain text of the song in its primary language (see `primary_language`), e.g. for search indexes or reports.
+    /// The lyrics of the parts are separated by blank lines, chords and other contents are left out.
+    /// # Arguments
+    /// * `follow_order` - If true, the parts are written in the order in which they are sung, so a repeated chorus is written
+    ///   every time it is sung (see `lyrics_text`). If false, every part is written once in the order in which it has been added.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::SongBuilder;
+    /// let song = SongBuilder::new("Test").verse("First verse").chorus("The chorus").verse("Second verse").chorus("The chorus").build();
+    /// assert_eq!(song.plain_lyrics(false), "First verse\n\nThe chorus\n\nSecond verse");
+    /// assert_eq!(song.plain_lyrics(true), "First verse\n\nThe chorus\n\nSecond verse\n\nThe chorus");
+    /// ```
+    pub fn plain_lyrics(&self, follow_order: bool) -> String {
+        let language: LyricLanguage = self.primary_language();
+        if follow_order {
+            return self.lyrics_text(&language);
+        }
+        self.parts()
+            .filter(|part| part.is_repetition_of.is_none())
+            .filter_map(|part| part.get_lyrics(&language).map(|content| content.content.trim_end().to_string()))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    /// Get the first line of the lyrics as it is sung (e.g. as index key of a songbook), leading whitespace is removed
+    /// # Returns
+    /// The first non-empty line of the lyrics in the primary language or None if the song has no lyrics
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::SongBuilder;
+    /// let song = SongBuilder::new("Test").verse("\n  Amazing grace, how sweet the sound\nthat saved a wretch like me").build();
+    /// assert_eq!(song.first_line().unwrap(), "Amazing grace, how sweet the sound");
+    /// ```
+    pub fn first_line(&self) -> Option<String> {
+        let language: LyricLanguage = self.primary_language();
+        self.get_ordered_parts().iter().find_map(|part| {
+            part.borrow()
+                .get_lyrics(&language)
+                .and_then(|content| content.content.lines().map(str::trim).find(|line| !line.is_empty()).map(str::to_string))
+        })
+    }
+
+    /// Get rough numbers about the song: the number of parts per type and the numbers of lines and words of the lyrics in
+    /// the primary language, every part counted once. The duration is estimated from the lines which are sung (following the
+    /// part order, so a repeated chorus is counted every time) with two bars per line, if the `tempo` tag contains the beats
+    /// per minute (the number of beats per bar is taken from the `time` tag, 4 by default), otherwise with 4 seconds per line.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use cantara_songlib::song::SongBuilder;
+    /// let song = SongBuilder::new("Test")
+    ///     .tag("tempo", "120")
+    ///     .verse("Amazing grace, how sweet the sound")
+    ///     .chorus("Grace, grace")
+    ///     .verse("It was grace")
+    ///     .chorus("Grace, grace")
+    ///     .build();
+    /// let statistics = song.statistics();
+    /// assert_eq!(statistics.part_counts["Verse"], 2);
+    /// assert_eq!(statistics.line_count, 3);
+    /// assert_eq!(statistics.word_count, 11);
+    /// assert_eq!(statistics.unique_word_count, 8);
+    /// assert_eq!(statistics.longest_line, 34);
+    /// // 4 sung lines of 8 beats at 120 bpm
+    /// assert_eq!(statistics.estimated_duration, Duration::from_secs(16));
+    /// ```
+    pub fn statistics(&self) -> SongStatistics {
+        let mut statistics = SongStatistics::default();
+        for part in self.parts().filter(|part| part.is_repetition_of.is_none()) {
+            *statistics.part_counts.entry(part.part_type.to_string()).or_default() += 1;
+        }
+
+        let lyrics: String = self.plain_lyrics(false);
+        let lines: Vec<&str> = lyrics.lines().filter(|line| !line.trim().is_empty()).collect();
+        statistics.line_count = lines.len();
+        statistics.longest_line = lines.iter().map(|line| line.trim_end().chars().count()).max().unwrap_or(0);
+        let words: Vec<&str> = lyrics.split_whitespace().collect();
+        statistics.word_count = words.len();
+        statistics.unique_word_count = words
+            .iter()
+            .map(|word| word.trim_matches(|character: char| !character.is_alphanumeric()).to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect::<HashSet<String>>()
+            .len();
+
+        let line_duration: Duration = match self.tempo() {
+            Some(Tempo::Bpm(bpm)) => {
+                let beats_per_line: u32 = 2 * self.time_signature().map_or(4, |time_signature| time_signature.numerator as u32);
+                Duration::from_secs(60) * beats_per_line / bpm as u32
+            },
+            _ => DEFAULT_LINE_DURATION,
+        };
+        let sung_lines: usize = self.plain_lyrics(true).lines().filter(|line| !line.trim().is_empty()).count();
+        statistics.estimated_duration = line_duration * sung_lines as u32;
+        statistics
+    }
+
+    /// Cleans up the lyrics, the title and the tag values of the song (e.g. after an import) as configured by the options:
+    /// the text is NFC-normalized, quotes are unified, whitespace is trimmed and collapsed, dashes and ellipses are normalized
+    /// and shouted stanzas are converted to sentence case.
+    /// Chords and voices are never changed, because their spacing is meaningful. Tag values are never converted to sentence case
+    /// and only human-readable tags are changed, tags which are read by programs (like `order` or `ccli`) are kept as they are.
+    /// # Returns
+    /// The changes for every part whose lyrics have been changed and the number of changed lines (see `NormalizationReport`)
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{NormalizationRule, NormalizeOptions, QuoteStyle};
+    /// use cantara_songlib::importer::classic_song::import_song;
+    /// let mut song = import_song("#title: \"Amazing\"   grace\n\nAmazing grace...\nhow sweet the sound").unwrap();
+    /// let report = song.normalize(NormalizeOptions { quotes: QuoteStyle::Typographic, ..Default::default() });
+    /// assert_eq!(report.changed_lines, 2);
+    /// assert_eq!(report.parts[0].rules, vec![NormalizationRule::Ellipses]);
+    /// assert_eq!(song.title, "“Amazing” grace");
+    /// assert_eq!(song.get_part_by_index(0).unwrap().borrow().contents[0].content, "Amazing grace…\nhow sweet the sound\n");
+    /// ```
+    pub fn normalize(&mut self, options: NormalizeOptions) -> NormalizationReport {
+        let steps: Vec<(NormalizationRule, NormalizationStep)> = normalization_steps(&options);
+        // Applies the steps to a text and adds the rules which have changed it
+        let apply_steps = |text: &str, is_lyrics: bool, rules: &mut Vec<NormalizationRule>| -> String {
+            let mut normalized: String = text.to_string();
+            for (rule, step) in &steps {
+                if *rule == NormalizationRule::SentenceCase && !is_lyrics {
+                    continue;
+                }
+                if let Some(text) = step(&normalized).filter(|text| *text != normalized) {
+                    normalized = text;
+                    if !rules.contains(rule) {
+                        rules.push(*rule);
+                    }
+                }
+            }
+            normalized
+        };
+        // None of the cleanups adds or removes lines, so the lines can be compared one by one
+        let changed_lines = |old: &str, new: &str| old.split('\n').zip(new.split('\n')).filter(|(old, new)| old != new).count();
+
+        let mut report = NormalizationReport::default();
+        for part_reference in &self.parts {
+            let mut part = part_reference.borrow_mut();
+            let mut rules: Vec<NormalizationRule> = Vec::new();
+            for content in part.contents.iter_mut().filter(|content| content.voice_type.is_lyrics()) {
+                let normalized: String = apply_steps(&content.content, true, &mut rules);
+                report.changed_lines += changed_lines(&content.content, &normalized);
+                content.content = normalized;
+            }
+            if !rules.is_empty() {
+                report.parts.push(PartNormalization { part: part.id.to_string(), rules });
+            }
+        }
+        let title: String = apply_steps(&self.title, false, &mut Vec::new());
+        // The title is usually stored as tag as well, so it is only counted once
+        if !self.tags.contains_key("title") {
+            report.changed_lines += changed_lines(&self.title, &title);
+        }
+        self.title = title;
+        for (_, value) in self.tags.iter_mut().filter(|(key, _)| !MACHINE_READABLE_TAGS.contains(&key.as_str())) {
+            let normalized: String = apply_steps(value, false, &mut Vec::new());
+            report.changed_lines += changed_lines(value, &normalized);
+            *value = normalized;
+        }
+
+        if report.changed_lines > 0 {
+            self.touch();
+        }
+        report
+    }
+
     /// Get the number of parts
     /// # Returns
     /// The number of parts in the song
@@ -265,8 +1703,97 @@ impl Song {
     }
     
     
-    /// Add a part order which is guessed based of the song parts
-    /// 
+    /// Get the active part order of the song (its first part order), e.g. the order which has been given in the song file.
+    /// # Returns
+    /// The part order or None if the song has no part order (the order is guessed by `get_ordered_parts` then)
+    pub fn get_active_part_order(&self) -> Option<&PartOrder> {
+        self.part_orders.first()
+    }
+
+    /// Get the part order with the given name (e.g. a short version of the song)
+    /// # Returns
+    /// The part order or None if the song has no part order with this name
+    pub fn get_part_order(&self, name: &PartOrderName) -> Option<&PartOrder> {
+        self.part_orders.iter().find(|part_order| &part_order.name == name)
+    }
+
+    /// Adds a part order to the song. The first part order of a song is the active one (see `Song::set_active_part_order`).
+    /// # Returns
+    /// Ok or an error if the song already has a part order with the same name
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{PartOrder, PartOrderName, Song, SongEditError, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// let short_version = PartOrderName::Custom("short".to_string());
+    /// song.add_part_order(PartOrder::parse_shorthand("V1 V2", &song).unwrap()).unwrap();
+    /// song.add_part_order(PartOrder::parse_shorthand("V1", &song).unwrap().with_name(short_version.clone())).unwrap();
+    ///
+    /// assert!(matches!(
+    ///     song.add_part_order(PartOrder::parse_shorthand("V2", &song).unwrap()),
+    ///     Err(SongEditError::PartOrderExists { .. })
+    /// ));
+    /// assert_eq!(song.get_ordered_parts().len(), 2);
+    /// song.set_active_part_order(&short_version).unwrap();
+    /// assert_eq!(song.get_ordered_parts().len(), 1);
+    /// ```
+    pub fn add_part_order(&mut self, part_order: PartOrder) -> Result<(), SongEditError> {
+        if self.get_part_order(&part_order.name).is_some() {
+            return Err(SongEditError::PartOrderExists { name: part_order.name });
+        }
+        self.part_orders.push(part_order);
+        self.touch();
+        Ok(())
+    }
+
+    /// Sets a part order of the song: an existing part order with the same name is replaced at its position
+    /// (so that an active part order stays active), otherwise the part order is added like with `Song::add_part_order`
+    pub fn set_part_order(&mut self, part_order: PartOrder) {
+        match self.part_orders.iter_mut().find(|existing| existing.name == part_order.name) {
+            Some(existing) => *existing = part_order,
+            None => self.part_orders.push(part_order),
+        }
+        self.touch();
+    }
+
+    /// Makes the part order with the given name the active one, which is used by `Song::get_ordered_parts`.
+    /// The part order is moved to the front, the order of the other part orders is kept.
+    /// # Returns
+    /// Ok or an error if the song has no part order with this name
+    pub fn set_active_part_order(&mut self, name: &PartOrderName) -> Result<(), SongEditError> {
+        let index: usize = self
+            .part_orders
+            .iter()
+            .position(|part_order| &part_order.name == name)
+            .ok_or_else(|| SongEditError::PartOrderNotFound { name: name.clone() })?;
+        if index > 0 {
+            let part_order: PartOrder = self.part_orders.remove(index);
+            self.part_orders.insert(0, part_order);
+            self.touch();
+        }
+        Ok(())
+    }
+
+    /// Get every part once in the order in which the parts have been added (e.g. the order of the file),
+    /// parts which are repetitions of other parts are left out
+    pub fn get_file_ordered_parts(&self) -> Vec<Rc<RefCell<SongPart>>> {
+        self.parts.iter().filter(|part| part.borrow().is_repetition_of.is_none()).cloned().collect()
+    }
+
+    /// Get the parts in the order in which they are sung, taken from the active part order of the song.
+    /// If the song has no part order, the order is guessed (see `PartOrder::from_guess`).
+    /// Repeated parts (like a chorus) occur several times in the result.
+    pub fn get_ordered_parts(&self) -> Vec<Rc<RefCell<SongPart>>> {
+        match self.get_active_part_order() {
+            Some(part_order) => part_order.to_parts(self),
+            None => PartOrder::from_guess(self).to_parts(self),
+        }
+    }
+
+    /// Add a part order which is guessed based of the song parts (see `PartOrder::from_guess`) as `PartOrderName::Default`
+    /// # Returns
+    /// Ok or an error if the song already has a default part order (e.g. the order of its file)
     /// # Example
     /// ```
     /// use cantara_songlib::song::{Song, SongPart, SongPartId};
@@ -277,14 +1804,178 @@ impl Song {
     /// song.add_part(part);
     /// let part = SongPart::new(SongPartId::parse("refrain.1").unwrap(), 1);
     ///  song.add_part(part);
-    /// song.add_guessed_part_order();
+    /// song.add_guessed_part_order().unwrap();
     /// 
     /// assert!(song.part_orders.len() == 1);
+    /// assert!(song.add_guessed_part_order().is_err());
     /// ```
-    pub fn add_guessed_part_order(&mut self) {
-        self.part_orders.push(
-            PartOrder::from_guess(self)
-        );
+    pub fn add_guessed_part_order(&mut self) -> Result<(), SongEditError> {
+        self.add_part_order(PartOrder::from_guess(self))
+    }
+
+    /// Checks the structure of a song which has been deserialized (e.g. from JSON) and links its parts.
+    /// Every part needs a valid and unique ID which ends with its number. The references between the parts
+    /// (repetitions and custom part orders) are deserialized as copies, they are replaced by the parts of the song with the same ID.
+    /// # Returns
+    /// Ok or a message which describes the first problem which has been found
+    pub(crate) fn link_deserialized_parts(&mut self) -> Result<(), String> {
+        let mut ids: HashSet<String> = HashSet::new();
+        for part_reference in &self.parts {
+            let mut part = part_reference.borrow_mut();
+            let id: String = part.id.get_id();
+            if SongPartId::parse(&id).is_none() {
+                return Err(format!("the part ID {} is invalid", id));
+            }
+            let id_number: Option<u32> = id.rsplit_once('.').and_then(|(_, number)| number.parse().ok());
+            if id_number != Some(part.number) {
+                return Err(format!("the part {} has the number {}", id, part.number));
+            }
+            if !ids.insert(id.to_lowercase()) {
+                return Err(format!("the part ID {} is used more than once", id));
+            }
+            part.id.set_checked_unique(true);
+        }
+
+        let resolve = |reference: &Option<Rc<RefCell<SongPart>>>| -> Result<Option<Rc<RefCell<SongPart>>>, String> {
+            match reference {
+                Some(reference) => {
+                    let id: String = reference.borrow().id.get_id();
+                    self.get_part_by_id(&id).map(Some).ok_or_else(|| format!("the referenced part {} does not exist", id))
+                },
+                None => Ok(None),
+            }
+        };
+        // The references are resolved before they are set, so that no part is borrowed twice
+        let mut links = Vec::new();
+        for part_reference in &self.parts {
+            let part = part_reference.borrow();
+            links.push((resolve(&part.is_repetition_of)?, resolve(&part.occurs_after)?));
+        }
+        for (part_reference, (is_repetition_of, occurs_after)) in self.parts.iter().zip(links) {
+            let mut part = part_reference.borrow_mut();
+            part.is_repetition_of = is_repetition_of;
+            part.occurs_after = occurs_after;
+        }
+
+        let mut part_orders: Vec<PartOrder> = self.part_orders.clone();
+        for (index, part_order) in part_orders.iter_mut().enumerate() {
+            if self.part_orders[..index].iter().any(|previous| previous.name == part_order.name) {
+                return Err(format!("the part order name {} is used more than once", part_order.name));
+            }
+            if let PartOrderRule::Custom(parts) = &mut part_order.partorderrule {
+                for part in parts.iter_mut() {
+                    *part = resolve(&Some(part.clone()))?.unwrap();
+                }
+            }
+        }
+        self.part_orders = part_orders;
+        Ok(())
+    }
+}
+
+/// A builder for creating songs in code without dealing with part IDs and references.
+/// The parts get their IDs in the order in which they are added and the song gets a part order with all parts.
+/// A repeatable part (like a chorus) which is added again with the same text becomes a repetition of the first one.
+/// # Example
+/// ```
+/// use cantara_songlib::song::{LyricLanguage, SongBuilder};
+/// let song = SongBuilder::new("Amazing Grace")
+///     .tag("author", "John Newton")
+///     .verse("Amazing grace, how sweet the sound")
+///     .chorus("My chains are gone")
+///     .verse("'Twas grace that taught my heart to fear")
+///     .chorus("My chains are gone")
+///     .lyrics_language(LyricLanguage::Specific("en".into()))
+///     .build();
+/// assert_eq!(song.get_tag("author").unwrap(), "John Newton");
+/// let ids: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+/// assert_eq!(ids, vec!["Verse.1", "Chorus.1", "Verse.2", "Chorus.2"]);
+/// assert!(song.get_part_by_id("Chorus.2").unwrap().borrow().is_repetition_of.is_some());
+/// ```
+#[derive(Clone, Debug)]
+pub struct SongBuilder {
+    title: String,
+    tags: Vec<(String, String)>,
+    parts: Vec<(SongPartType, String)>,
+    lyrics_language: LyricLanguage,
+}
+
+impl SongBuilder {
+    /// Starts a song with the given title
+    pub fn new(title: &str) -> SongBuilder {
+        SongBuilder {
+            title: title.to_string(),
+            tags: Vec::new(),
+            parts: Vec::new(),
+            lyrics_language: LyricLanguage::Default,
+        }
+    }
+
+    /// Adds a tag (see `Song::add_tag`)
+    pub fn tag(mut self, key: &str, value: &str) -> SongBuilder {
+        self.tags.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a part of the given type with the given lyrics
+    pub fn part(mut self, part_type: SongPartType, lyrics: &str) -> SongBuilder {
+        self.parts.push((part_type, lyrics.to_string()));
+        self
+    }
+
+    /// Adds a verse with the given lyrics
+    pub fn verse(self, lyrics: &str) -> SongBuilder {
+        self.part(SongPartType::Verse, lyrics)
+    }
+
+    /// Adds a chorus with the given lyrics
+    pub fn chorus(self, lyrics: &str) -> SongBuilder {
+        self.part(SongPartType::Chorus, lyrics)
+    }
+
+    /// Adds a bridge with the given lyrics
+    pub fn bridge(self, lyrics: &str) -> SongBuilder {
+        self.part(SongPartType::Bridge, lyrics)
+    }
+
+    /// Sets the language of all lyrics of the song (`LyricLanguage::Default` if not set)
+    pub fn lyrics_language(mut self, language: LyricLanguage) -> SongBuilder {
+        self.lyrics_language = language;
+        self
+    }
+
+    /// Creates the song. Like imported lyrics, the lyrics of every part end with a line break.
+    pub fn build(self) -> Song {
+        let mut song: Song = Song::new(&self.title);
+        for (key, value) in &self.tags {
+            song.add_tag(key, value);
+        }
+
+        let mut part_order: Vec<Rc<RefCell<SongPart>>> = Vec::new();
+        for (part_type, lyrics) in self.parts {
+            let lyrics: String = format!("{}\n", lyrics.trim_end());
+            let repeated_part: Option<Rc<RefCell<SongPart>>> = match part_type.is_repeatable() {
+                true => song.get_parts_by_type(part_type).into_iter().find(|part| {
+                    let part = part.borrow();
+                    part.is_repetition_of.is_none() && part.contents.first().is_some_and(|content| content.content == lyrics)
+                }),
+                false => None,
+            };
+            let part: Rc<RefCell<SongPart>> = song.add_part_of_type(part_type, None);
+            {
+                let mut part = part.borrow_mut();
+                part.add_content(SongPartContent {
+                    voice_type: SongPartContentType::Lyrics { language: self.lyrics_language.clone() },
+                    content: lyrics,
+                });
+                part.set_repition(repeated_part);
+            }
+            part_order.push(part);
+        }
+        if !part_order.is_empty() {
+            song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(part_order)));
+        }
+        song
     }
 }
 
@@ -305,24 +1996,27 @@ pub enum SongPartType {
     Other,
 }
 
-impl SongPartType {
-    pub fn to_string(&self) -> String {
-        match self {
-            SongPartType::Verse => "Verse".to_string(),
-            SongPartType::Chorus => "Chorus".to_string(),
-            SongPartType::Bridge => "Bridge".to_string(),
-            SongPartType::Intro => "Intro".to_string(),
-            SongPartType::Outro => "Outro".to_string(),
-            SongPartType::Interlude => "Interlude".to_string(),
-            SongPartType::Instrumental => "Instrumental".to_string(),
-            SongPartType::Solo => "Solo".to_string(),
-            SongPartType::PreChorus => "PreChorus".to_string(),
-            SongPartType::PostChorus => "PostChorus".to_string(),
-            SongPartType::Refrain => "Refrain".to_string(),
-            SongPartType::Other => "Other".to_string(),
-        }
+impl fmt::Display for SongPartType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            SongPartType::Verse => "Verse",
+            SongPartType::Chorus => "Chorus",
+            SongPartType::Bridge => "Bridge",
+            SongPartType::Intro => "Intro",
+            SongPartType::Outro => "Outro",
+            SongPartType::Interlude => "Interlude",
+            SongPartType::Instrumental => "Instrumental",
+            SongPartType::Solo => "Solo",
+            SongPartType::PreChorus => "PreChorus",
+            SongPartType::PostChorus => "PostChorus",
+            SongPartType::Refrain => "Refrain",
+            SongPartType::Other => "Other",
+        };
+        write!(f, "{}", name)
     }
+}
 
+impl SongPartType {
     /// Create a SongPartType from a string (case-insensitive)
     pub fn from_string(s: &str) -> SongPartType {
         // Make the string lowercase
@@ -336,13 +2030,46 @@ impl SongPartType {
             "interlude" => SongPartType::Interlude,
             "instrumental" => SongPartType::Instrumental,
             "solo" => SongPartType::Solo,
-            "preChorus" => SongPartType::PreChorus,
-            "postChorus" => SongPartType::PostChorus,
+            "prechorus" => SongPartType::PreChorus,
+            "postchorus" => SongPartType::PostChorus,
             "refrain" => SongPartType::Refrain,
             _ => SongPartType::Other,
         }
     }
 
+    /// Parses a part label like it is written in hand-written song files (e.g. `Chorus`, `Strophe` or `Bridge`).
+    /// English and German names are understood.
+    /// # Returns
+    /// The type of the part or None if the label is unknown
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::SongPartType;
+    /// assert_eq!(SongPartType::from_label("Strophe"), Some(SongPartType::Verse));
+    /// assert_eq!(SongPartType::from_label("Pre-Chorus"), Some(SongPartType::PreChorus));
+    /// assert_eq!(SongPartType::from_label("Amazing"), None);
+    /// ```
+    pub fn from_label(label: &str) -> Option<SongPartType> {
+        let label: String = label
+            .to_lowercase()
+            .chars()
+            .filter(|character| !character.is_whitespace() && *character != '-')
+            .collect();
+        match label.as_str() {
+            "verse" | "stanza" | "strophe" | "vers" => Some(SongPartType::Verse),
+            "chorus" | "kehrvers" => Some(SongPartType::Chorus),
+            "refrain" => Some(SongPartType::Refrain),
+            "bridge" | "brücke" | "bruecke" => Some(SongPartType::Bridge),
+            "intro" | "einleitung" | "vorspiel" => Some(SongPartType::Intro),
+            "outro" | "schluss" | "nachspiel" => Some(SongPartType::Outro),
+            "interlude" | "zwischenspiel" => Some(SongPartType::Interlude),
+            "instrumental" => Some(SongPartType::Instrumental),
+            "solo" => Some(SongPartType::Solo),
+            "prechorus" | "prerefrain" => Some(SongPartType::PreChorus),
+            "postchorus" | "postrefrain" => Some(SongPartType::PostChorus),
+            _ => None,
+        }
+    }
+
     /// Returns whether a song part type is repeatable.
     /// A song part is *repeatable* if it can be used multiple times in a song with all of its contents (e.g. lyrics, chords, etc.).
     pub fn is_repeatable(&self) -> bool {
@@ -389,20 +2116,22 @@ impl SongPartContentType {
     pub fn is_lyrics(&self) -> bool {
         matches!(self, SongPartContentType::Lyrics { .. })
     }
+}
 
-    pub fn to_string(&self) -> String {
+impl fmt::Display for SongPartContentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            SongPartContentType::LeadVoice => "LeadVoice".to_string(),
-            SongPartContentType::SupranoVoice => "SupranoVoice".to_string(),
-            SongPartContentType::AltoVoice => "AltoVoice".to_string(),
-            SongPartContentType::TenorVoice => "TenorVoice".to_string(),
-            SongPartContentType::BassVoice => "BassVoice".to_string(),
-            SongPartContentType::Instrumental => "Instrumental".to_string(),
-            SongPartContentType::Solo => "Solo".to_string(),
-            SongPartContentType::Chords => "Chords".to_string(),
+            SongPartContentType::LeadVoice => write!(f, "LeadVoice"),
+            SongPartContentType::SupranoVoice => write!(f, "SupranoVoice"),
+            SongPartContentType::AltoVoice => write!(f, "AltoVoice"),
+            SongPartContentType::TenorVoice => write!(f, "TenorVoice"),
+            SongPartContentType::BassVoice => write!(f, "BassVoice"),
+            SongPartContentType::Instrumental => write!(f, "Instrumental"),
+            SongPartContentType::Solo => write!(f, "Solo"),
+            SongPartContentType::Chords => write!(f, "Chords"),
             SongPartContentType::Lyrics { language } => match language {
-                LyricLanguage::Default => "Lyrics".to_string(),
-                LyricLanguage::Specific(lang) => format!("Lyrics ({})", lang),
+                LyricLanguage::Default => write!(f, "Lyrics"),
+                LyricLanguage::Specific(lang) => write!(f, "Lyrics ({})", lang),
             },
         }
     }
@@ -504,6 +2233,9 @@ pub struct SongPart {
     pub contents: Vec<SongPartContent>,
     /// defines whether this part is a repetition of a previous part
     pub is_repetition_of: Option<Rc<RefCell<SongPart>>>,
+    /// Comments from the source file which belong to this part (e.g. `// sing twice` in a classic song file)
+    #[serde(default)]
+    pub comments: Vec<String>,
     occurs_after: Option<Rc<RefCell<SongPart>>>,
 }
 
@@ -521,20 +2253,118 @@ impl SongPart {
             number: specific_number,
             contents: Vec::new(),
             is_repetition_of: is_repetition,
+            comments: Vec::new(),
             occurs_after: None,
         }
     }
 
+    /// Appends content to the part. Content of a type which the part already has is appended as well
+    /// (e.g. a second lyrics entry in the same language), use `SongPart::set_content` to replace it instead.
     pub fn add_content(&mut self, content: SongPartContent) {
         self.contents.push(content);
     }
 
+    /// Sets the content of a type: an existing entry with the same `voice_type` (for lyrics: in the same language) is replaced
+    /// at its position, otherwise the content is appended. Further entries of the same type are removed,
+    /// so that the part contains the content of this type exactly once.
+    /// # Returns
+    /// The content which has been replaced or None if the part had no content of this type
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{LyricLanguage, SongPart, SongPartContent, SongPartContentType, SongPartId};
+    /// let mut part = SongPart::new(SongPartId::parse("verse.1").unwrap(), 1);
+    /// let lyrics = |content: &str| SongPartContent {
+    ///     voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+    ///     content: content.to_string(),
+    /// };
+    /// assert_eq!(part.set_content(lyrics("Amazing grace, how sweat the sound")), None);
+    /// let replaced = part.set_content(lyrics("Amazing grace, how sweet the sound"));
+    /// assert_eq!(replaced.unwrap().content, "Amazing grace, how sweat the sound");
+    /// assert_eq!(part.contents, vec![lyrics("Amazing grace, how sweet the sound")]);
+    /// ```
+    pub fn set_content(&mut self, content: SongPartContent) -> Option<SongPartContent> {
+        let Some(index) = self.contents.iter().position(|existing| existing.voice_type == content.voice_type) else {
+            self.contents.push(content);
+            return None;
+        };
+        let voice_type: SongPartContentType = content.voice_type.clone();
+        let replaced: SongPartContent = std::mem::replace(&mut self.contents[index], content);
+        let mut position: usize = 0;
+        self.contents.retain(|existing| {
+            let keep: bool = position <= index || existing.voice_type != voice_type;
+            position += 1;
+            keep
+        });
+        Some(replaced)
+    }
+
+    /// Removes the content of a type (for lyrics: in the given language), including further entries of the same type
+    /// # Returns
+    /// The first removed content or None if the part has no content of this type
+    pub fn remove_content(&mut self, voice_type: SongPartContentType) -> Option<SongPartContent> {
+        let index: usize = self.contents.iter().position(|content| content.voice_type == voice_type)?;
+        let removed: SongPartContent = self.contents.remove(index);
+        self.contents.retain(|content| content.voice_type != voice_type);
+        Some(removed)
+    }
+
+    /// Get all contents whose type matches the predicate, in the order of the part
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{LyricLanguage, SongPart, SongPartContent, SongPartContentType, SongPartId};
+    /// let mut part = SongPart::new(SongPartId::parse("verse.1").unwrap(), 1);
+    /// part.add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "G  C".to_string() });
+    /// part.add_content(SongPartContent {
+    ///     voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Specific("en".to_string()) },
+    ///     content: "Amazing grace".to_string(),
+    /// });
+    /// let lyrics = part.contents_of_type(SongPartContentType::is_lyrics);
+    /// assert_eq!(lyrics.len(), 1);
+    /// assert_eq!(lyrics[0].content, "Amazing grace");
+    /// ```
+    pub fn contents_of_type<P: Fn(&SongPartContentType) -> bool>(&self, predicate: P) -> Vec<&SongPartContent> {
+        self.contents.iter().filter(|content| predicate(&content.voice_type)).collect()
+    }
+
     pub fn get_content(&self, voice_type: SongPartContentType) -> Option<&SongPartContent> {
         self.contents
             .iter()
             .find(|voice| voice.voice_type == voice_type)
     }
 
+    /// Get the lyrics of the part in the given language.
+    /// If the part has no lyrics in the language, the lyrics without a specific language are returned,
+    /// if there are none either, the first lyrics of the part.
+    /// # Returns
+    /// The lyrics or None if the part has no lyrics at all
+    pub fn get_lyrics(&self, language: &LyricLanguage) -> Option<&SongPartContent> {
+        let lyrics = |wanted_language: Option<&LyricLanguage>| {
+            self.contents.iter().find(|content| match &content.voice_type {
+                SongPartContentType::Lyrics { language } => wanted_language.is_none_or(|wanted_language| language == wanted_language),
+                _ => false,
+            })
+        };
+        lyrics(Some(language)).or_else(|| lyrics(Some(&LyricLanguage::Default))).or_else(|| lyrics(None))
+    }
+
+    /// Parses the chord lines of the part (see `chords::ChordLine`)
+    /// # Returns
+    /// One chord line for every line of the `Chords` content or None if the part has no chords
+    /// # Example
+    /// ```
+    /// use cantara_songlib::importer::chordpro::import_song;
+    /// use cantara_songlib::song::SongPartType;
+    /// let song = import_song("{soc}\n[G]This is the [C]chorus\n{eoc}").unwrap();
+    /// let chorus = song.get_parts_by_type(SongPartType::Chorus)[0].clone();
+    /// let chord_lines = chorus.borrow().parsed_chords().unwrap();
+    /// assert_eq!(chord_lines[0].tokens[1].0, 12);
+    /// assert_eq!(chord_lines[0].chords().map(|chord| chord.to_string()).collect::<Vec<String>>(), vec!["G", "C"]);
+    /// ```
+    pub fn parsed_chords(&self) -> Option<Vec<ChordLine>> {
+        self.get_content(SongPartContentType::Chords)
+            .map(|content| content.content.lines().map(ChordLine::parse).collect())
+    }
+
     pub fn has_lyrics(&self) -> bool {
         self.contents.iter().any(|voice| 
             matches!(voice.voice_type,SongPartContentType::Lyrics { .. })
@@ -570,16 +2400,26 @@ impl SongPart {
     }
 
     pub fn update_id(&mut self) {
-        self.id = SongPartId::parse(&format!("{}.{}", self.part_type.to_string(), self.number)).unwrap();
+        self.id = SongPartId::parse(&format!("{}.{}", self.part_type, self.number)).unwrap();
     }
 }
 
+/// The name of a part order, a song can have several part orders (e.g. a short and a full version)
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
 pub enum PartOrderName {
     Default,
     Custom(String),
 }
 
+impl fmt::Display for PartOrderName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartOrderName::Default => write!(f, "Default"),
+            PartOrderName::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
 pub struct PartOrder {
     pub name: PartOrderName,
@@ -593,92 +2433,182 @@ impl PartOrder {
             partorderrule,
         }
     }
-    /// Create a PartOrder which is guessed by the song structure (the parts in the song)
+
+    /// Returns the part order with another name, e.g. to add a parsed order as a further version of a song
+    pub fn with_name(self, name: PartOrderName) -> PartOrder {
+        PartOrder { name, ..self }
+    }
+    /// Create a PartOrder which is guessed by the song structure (the parts in the song):
+    /// a song which begins with a verse and has exactly one chorus (or refrain) gets `VerseRefrainBridgeRefrain`,
+    /// a song which begins with its only chorus gets `RefrainVerseBridgeRefrain`. Parts which are repetitions of another part
+    /// (see `SongPart::is_repetition_of`) don't count as further choruses. All other songs (e.g. with several different choruses
+    /// or without a chorus) get a `Custom` order with the parts in the order in which they have been added.
     /// # Arguments
     /// * `song` - The song for which the PartOrder should be guessed
     /// # Returns
     /// A PartOrder which is guessed by the song structure
     pub fn from_guess(song: &Song) -> PartOrder {
-        let song_part_count = song.get_total_part_count();
-
-        // If the song has less then two parts, return Custom with the parts in the order they were added
-        if song_part_count < 2 {
-            return PartOrder::new(
-                PartOrderName::Default,
-                PartOrderRule::Custom(song.parts.clone()),
-            );
+        let custom_order = || PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(song.parts.clone()));
+        let parts: Vec<Rc<RefCell<SongPart>>> = song.get_file_ordered_parts();
+        // Songs with less than two parts are just sung in the order they were added
+        if parts.len() < 2 {
+            return custom_order();
         }
 
-        // If the song begins with a verse, it is likely that the song has the structure VerseRefrainBridgeRefrain
-        let first_part_type: SongPartType = song.get_part_by_index(0).unwrap().borrow().get_type();
-
-        if first_part_type == SongPartType::Verse {
-            return PartOrder::new(
-                PartOrderName::Default,
-                PartOrderRule::VerseRefrainBridgeRefrain,
-            );
+        let chorus_count: usize = parts
+            .iter()
+            .filter(|part| matches!(part.borrow().part_type, SongPartType::Chorus | SongPartType::Refrain))
+            .count();
+        if chorus_count != 1 {
+            return custom_order();
         }
-        
-        // If the song begins with a refrain, it is likely that the song has the structure RefrainVerseBridgeRefrain
-        else if first_part_type == SongPartType::Refrain {
-            return PartOrder::new(
-                PartOrderName::Default,
-                PartOrderRule::RefrainVerseBridgeRefrain,
-            );
+        let first_part_type: SongPartType = parts[0].borrow().part_type;
+        match first_part_type {
+            SongPartType::Verse => PartOrder::new(PartOrderName::Default, PartOrderRule::VerseRefrainBridgeRefrain),
+            SongPartType::Chorus | SongPartType::Refrain => PartOrder::new(PartOrderName::Default, PartOrderRule::RefrainVerseBridgeRefrain),
+            _ => custom_order(),
         }
+    }
+
+    /// Parses a part order which is written in shorthand (e.g. `V1 C V2 C B C`), like worship planners write it.
+    /// The tokens are separated by whitespace or commas and are case-insensitive:
+    /// `V<n>` (verse), `C`/`C<n>` (chorus or refrain), `B` (bridge), `PC` (pre-chorus), `I` (intro) and `O` (outro).
+    /// A token without a number refers to the first part of its type, all tokens may have a number (e.g. `B2`).
+    /// `C` refers to a chorus and only to a refrain if the song has no matching chorus.
+    /// Parts without a shorthand can be referred to by their ID (e.g. `Interlude.1`).
+    /// # Arguments
+    /// * `shorthand` - The part order in shorthand
+    /// * `song` - The song whose parts are referred to
+    /// # Returns
+    /// A custom part order with the parts of the song or an error which names the first token that can't be resolved
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::{PartOrder, Song, SongEditError, SongPartType};
+    /// let mut song = Song::new("Test Song");
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    /// song.add_part_of_type(SongPartType::Chorus, None);
+    /// song.add_part_of_type(SongPartType::Verse, None);
+    ///
+    /// let part_order = PartOrder::parse_shorthand("V1 C V2 C", &song).unwrap();
+    /// assert_eq!(part_order.to_shorthand().unwrap(), "V1 C V2 C");
+    /// assert_eq!(
+    ///     PartOrder::parse_shorthand("V1 C V3", &song),
+    ///     Err(SongEditError::PartOrderPartNotFound { token: "V3".to_string() })
+    /// );
+    /// ```
+    pub fn parse_shorthand(shorthand: &str, song: &Song) -> Result<PartOrder, SongEditError> {
+        let file_ordered_parts: Vec<Rc<RefCell<SongPart>>> = song.get_file_ordered_parts();
+        let mut parts: Vec<Rc<RefCell<SongPart>>> = Vec::new();
+        for token in shorthand.split(|c: char| c.is_whitespace() || c == ',').filter(|token| !token.is_empty()) {
+            let not_found = || SongEditError::PartOrderPartNotFound { token: token.to_string() };
+            // Parts without a shorthand are referred to by their ID
+            if SongPartId::parse(token).is_some() {
+                let part: &Rc<RefCell<SongPart>> = file_ordered_parts
+                    .iter()
+                    .find(|part| part.borrow().id.get_id().eq_ignore_ascii_case(token))
+                    .ok_or_else(not_found)?;
+                parts.push(part.clone());
+                continue;
+            }
 
-        // If the song has no refrain or bridge, it is likely that the song has the structure VerseRefrainBridgeRefrain
-        if (song.get_part_count(SongPartType::Refrain) == 0) || (song.get_part_count(SongPartType::Bridge) == 0) {
-            return PartOrder::new(
-                PartOrderName::Default,
-                PartOrderRule::VerseRefrainBridgeRefrain,
-            );
+            let unknown_token = || SongEditError::UnknownPartOrderToken { token: token.to_string() };
+            let uppercase_token: String = token.to_uppercase();
+            let (prefix, number) = uppercase_token.split_at(uppercase_token.find(|c: char| c.is_ascii_digit()).unwrap_or(uppercase_token.len()));
+            let part_types: &[SongPartType] = match prefix {
+                "V" => &[SongPartType::Verse],
+                "C" => &[SongPartType::Chorus, SongPartType::Refrain],
+                "B" => &[SongPartType::Bridge],
+                "PC" => &[SongPartType::PreChorus],
+                "I" => &[SongPartType::Intro],
+                "O" => &[SongPartType::Outro],
+                _ => return Err(unknown_token()),
+            };
+            let number: Option<u32> = match number {
+                "" => None,
+                number => Some(number.parse().map_err(|_| unknown_token())?),
+            };
+            // The types are searched in the order of their priority
+            let part: &Rc<RefCell<SongPart>> = part_types
+                .iter()
+                .find_map(|part_type| {
+                    file_ordered_parts.iter().find(|part| {
+                        let part = part.borrow();
+                        part.part_type == *part_type && number.is_none_or(|number| part.number == number)
+                    })
+                })
+                .ok_or_else(not_found)?;
+            parts.push(part.clone());
         }
+        Ok(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(parts)))
+    }
 
-        // In every other case, we have a custom song structure
-        PartOrder::new(
-            PartOrderName::Default,
-            PartOrderRule::Custom(song.parts.clone()),
-        )
+    /// Writes the part order in shorthand (see `PartOrder::parse_shorthand`), e.g. to display it in an editable text field.
+    /// Verses always get their number, other parts only if it is not 1. Repetitions are written like the part they repeat.
+    /// Refrains are written by their ID (e.g. `Refrain.1`), because a `C` would refer to a chorus of the same song,
+    /// so that parsing the shorthand always results in the same order.
+    /// # Returns
+    /// The shorthand or None if the order is defined by a rule instead of a list of parts
+    /// (use `PartOrder::to_parts` and a custom part order for those)
+    pub fn to_shorthand(&self) -> Option<String> {
+        let PartOrderRule::Custom(parts) = &self.partorderrule else {
+            return None;
+        };
+        let tokens: Vec<String> = parts
+            .iter()
+            .map(|part| {
+                let part: Rc<RefCell<SongPart>> = part.borrow().is_repetition_of.clone().unwrap_or_else(|| part.clone());
+                let part = part.borrow();
+                let prefix: &str = match part.part_type {
+                    SongPartType::Verse => return format!("V{}", part.number),
+                    SongPartType::Chorus => "C",
+                    SongPartType::Bridge => "B",
+                    SongPartType::PreChorus => "PC",
+                    SongPartType::Intro => "I",
+                    SongPartType::Outro => "O",
+                    _ => return part.id.get_id(),
+                };
+                match part.number {
+                    1 => prefix.to_string(),
+                    number => format!("{}{}", prefix, number),
+                }
+            })
+            .collect();
+        Some(tokens.join(" "))
     }
 
-    /// This function applies an algorithm which creates a song structure as normally it is sang.
+    /// Creates the song structure as it is normally sung (see `PartOrderRule::VerseRefrainBridgeRefrain`):
+    /// the latest chorus (together with its pre-chorus) is repeated after every verse which is not directly followed by a chorus
+    /// and after a bridge. Parts which are repetitions of other parts are left out, because the choruses are repeated anyway.
+    /// Songs without a chorus are sung in the order in which their parts have been added.
     fn apply_versechorusbridgechorus_algorithm(&self, song: &Song) -> Vec<Rc<RefCell<SongPart>>> {
-        let mut parts: Vec<Rc<RefCell<SongPart>>> = Vec::new();
-        let stanza_parts: Vec<Rc<RefCell<SongPart>>> = song.get_parts_by_type(SongPartType::Verse);
-        let chorus_parts: Vec<Rc<RefCell<SongPart>>> = song.get_parts_by_type(SongPartType::Chorus);
-
-        if chorus_parts.is_empty() {
-            return stanza_parts;
+        // Refrains (e.g. from cssf files) are handled like choruses
+        let is_chorus = |part: &Rc<RefCell<SongPart>>| matches!(part.borrow().part_type, SongPartType::Chorus | SongPartType::Refrain);
+        let part_type = |part: Option<&Rc<RefCell<SongPart>>>| part.map(|part| part.borrow().part_type);
+        let song_parts: Vec<Rc<RefCell<SongPart>>> = song.get_file_ordered_parts();
+        if !song_parts.iter().any(is_chorus) {
+            return song_parts;
         }
 
+        let mut parts: Vec<Rc<RefCell<SongPart>>> = Vec::new();
         let mut current_choruses: Vec<Rc<RefCell<SongPart>>> = Vec::new();
-
-        for part_refcell in song.parts.clone() {
-            let part = part_refcell.borrow().clone();
-            if part.part_type == SongPartType::Verse {
-                parts.push(Rc::new(RefCell::new(part)));
-                parts.append(&mut current_choruses.clone());
-            } else if part.part_type == SongPartType::Chorus {
-                if current_choruses.len() > 1 && current_choruses.first().unwrap().borrow().get_type() == SongPartType::PreChorus {
-                    // Remove only Chorus not PreChorus
-                    let first_element = current_choruses.first().unwrap().borrow().clone();
-                    current_choruses.clear();
-                    current_choruses.push(Rc::new(RefCell::new(first_element.clone())));
-                } else {
-                    current_choruses.clear();
-                }
-
-                current_choruses.push(Rc::new(RefCell::new(part)));
-                parts.append(&mut current_choruses.clone());
-            }
-            else if part.part_type == SongPartType::PreChorus {
-                current_choruses.push(Rc::new(RefCell::new(part)));
-                parts.append(&mut current_choruses.clone());
-            }
-            else if part.part_type == SongPartType::Bridge {
-                current_choruses.push(Rc::new(RefCell::new(part)));
-                parts.append(&mut current_choruses.clone());
+        for (index, part) in song_parts.iter().enumerate() {
+            parts.push(part.clone());
+            match part_type(Some(part)) {
+                Some(SongPartType::Chorus | SongPartType::Refrain) => {
+                    if part_type(index.checked_sub(1).and_then(|previous| song_parts.get(previous))) != Some(SongPartType::PreChorus) {
+                        current_choruses.clear();
+                    }
+                    current_choruses.push(part.clone());
+                },
+                Some(SongPartType::PreChorus) => current_choruses = vec![part.clone()],
+                Some(SongPartType::Verse) => {
+                    let next_type: Option<SongPartType> = part_type(song_parts.get(index + 1));
+                    if !matches!(next_type, Some(SongPartType::Chorus | SongPartType::Refrain | SongPartType::PreChorus)) {
+                        parts.extend(current_choruses.iter().cloned());
+                    }
+                },
+                Some(SongPartType::Bridge) => parts.extend(current_choruses.iter().cloned()),
+                _ => {},
             }
         }
         parts
@@ -689,17 +2619,23 @@ impl PartOrder {
             PartOrderRule::Custom(parts) => parts.clone(),
             PartOrderRule::VerseRefrainBridgeRefrain => { self.apply_versechorusbridgechorus_algorithm(song) },
             PartOrderRule::RefrainVerseBridgeRefrain => {
-                match song.get_parts_by_type(SongPartType::Refrain).first() {
-                    None => self.apply_versechorusbridgechorus_algorithm(song),
-                    Some(first_part) => {
-                        let mut parts = vec![first_part.clone()];
-                        parts.append(&mut self.apply_versechorusbridgechorus_algorithm(song));
+                let mut parts: Vec<Rc<RefCell<SongPart>>> = self.apply_versechorusbridgechorus_algorithm(song);
+                // The song begins with the first chorus, even if it has been added after the first verse
+                let first_chorus: Option<Rc<RefCell<SongPart>>> = song
+                    .get_parts_by_type(SongPartType::Refrain)
+                    .into_iter()
+                    .chain(song.get_parts_by_type(SongPartType::Chorus))
+                    .find(|part| part.borrow().is_repetition_of.is_none());
+                match (first_chorus, parts.first()) {
+                    (Some(first_chorus), Some(first_part)) if !Rc::ptr_eq(&first_chorus, first_part) => {
+                        parts.insert(0, first_chorus);
                         parts
-                    }
+                    },
+                    _ => parts,
                 }
             }
         }
-    } 
+    }
 }
 
 /// A rule which defines the order of the parts in a song
@@ -725,6 +2661,130 @@ pub enum PartOrderRule {
     Custom(Vec<Rc<RefCell<SongPart>>>),
 }
 
+/// The style which `Song::normalize` unifies the quotes to
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub enum QuoteStyle {
+    /// The quotes are not changed
+    #[default]
+    Keep,
+    /// All quotes become straight quotes (`"` and `'`)
+    Straight,
+    /// All quotes become typographic quotes (`“`, `”`, `‘` and `’`)
+    Typographic,
+}
+
+/// The cleanups which are performed by `Song::normalize`
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+pub struct NormalizeOptions {
+    /// Unifies the quotes
+    pub quotes: QuoteStyle,
+    /// Removes trailing whitespace at the end of the lines
+    pub trim_trailing_whitespace: bool,
+    /// Collapses repeated spaces and tabs within lines and removes trailing whitespace (the indentation is kept)
+    pub collapse_whitespace: bool,
+    /// Replaces dashes which are typed as `--` or as a spaced hyphen with an en dash
+    pub normalize_dashes: bool,
+    /// Replaces three dots with an ellipsis (`…`)
+    pub normalize_ellipses: bool,
+    /// Normalizes the text to the Unicode normalization form C, so that letters with accents are always stored the same way
+    pub unicode_nfc: bool,
+    /// Converts lyrics which are written in capital letters only to sentence case (tag values are never converted)
+    pub sentence_case: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            quotes: QuoteStyle::Keep,
+            trim_trailing_whitespace: true,
+            collapse_whitespace: true,
+            normalize_dashes: true,
+            normalize_ellipses: true,
+            unicode_nfc: true,
+            sentence_case: false,
+        }
+    }
+}
+
+/// A cleanup which has changed the lyrics (see `Song::normalize`)
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+pub enum NormalizationRule {
+    Unicode,
+    Quotes,
+    Whitespace,
+    Dashes,
+    Ellipses,
+    SentenceCase,
+}
+
+impl fmt::Display for NormalizationRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NormalizationRule::Unicode => write!(f, "unicode"),
+            NormalizationRule::Quotes => write!(f, "quotes"),
+            NormalizationRule::Whitespace => write!(f, "whitespace"),
+            NormalizationRule::Dashes => write!(f, "dashes"),
+            NormalizationRule::Ellipses => write!(f, "ellipses"),
+            NormalizationRule::SentenceCase => write!(f, "sentence case"),
+        }
+    }
+}
+
+/// The changes of `Song::normalize` to a part of a song
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+pub struct PartNormalization {
+    /// The ID of the part (like `Verse.1`)
+    pub part: String,
+    /// The cleanups which have changed the lyrics of the part
+    pub rules: Vec<NormalizationRule>,
+}
+
+/// The changes of `Song::normalize`
+#[derive(Clone, Serialize, Deserialize, PartialEq, Debug, Default)]
+pub struct NormalizationReport {
+    /// The changes for every part whose lyrics have been changed
+    pub parts: Vec<PartNormalization>,
+    /// The number of lines which have been changed in the lyrics, the title and the tag values
+    /// (a tag value counts as one line per line of the value)
+    pub changed_lines: usize,
+}
+
+/// The tags whose values are read by programs, they are never changed by `Song::normalize`
+const MACHINE_READABLE_TAGS: [&str; 10] =
+    ["order", "scripture", "ccli", "ccli_license", "created", "modified", "key", "tempo", "time", "language"];
+
+/// A cleanup of a text, returns None if the cleanup is not applicable
+type NormalizationStep = fn(&str) -> Option<String>;
+
+/// Returns the enabled cleanups of the options in the order in which they are applied
+fn normalization_steps(options: &NormalizeOptions) -> Vec<(NormalizationRule, NormalizationStep)> {
+    let mut steps: Vec<(NormalizationRule, NormalizationStep)> = Vec::new();
+    if options.unicode_nfc {
+        steps.push((NormalizationRule::Unicode, |text| Some(normalize_unicode(text))));
+    }
+    match options.quotes {
+        QuoteStyle::Keep => {},
+        QuoteStyle::Straight => steps.push((NormalizationRule::Quotes, |text| Some(straighten_quotes(text)))),
+        QuoteStyle::Typographic => steps.push((NormalizationRule::Quotes, |text| Some(curl_quotes(text)))),
+    }
+    if options.trim_trailing_whitespace {
+        steps.push((NormalizationRule::Whitespace, |text| Some(trim_trailing_whitespace(text))));
+    }
+    if options.collapse_whitespace {
+        steps.push((NormalizationRule::Whitespace, |text| Some(collapse_whitespace(text))));
+    }
+    if options.normalize_dashes {
+        steps.push((NormalizationRule::Dashes, |text| Some(normalize_dashes(text))));
+    }
+    if options.normalize_ellipses {
+        steps.push((NormalizationRule::Ellipses, |text| Some(normalize_ellipses(text))));
+    }
+    if options.sentence_case {
+        steps.push((NormalizationRule::SentenceCase, sentence_case));
+    }
+    steps
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -747,6 +2807,667 @@ mod tests {
         assert_eq!(song.get_tag("key3").unwrap(), "value3");
     }
 
+    #[test]
+    fn test_categories() {
+        let mut song: Song = Song::new("Test Song");
+        assert!(song.get_categories().is_empty());
+        song.add_category("Lob & Anbetung");
+        assert_eq!(song.get_tag("categories").unwrap(), "Lob & Anbetung");
+        song.add_category(" lob & anbetung ");
+        song.add_category("Advent");
+        assert_eq!(song.get_categories(), vec!["Lob & Anbetung", "Advent"]);
+
+        // The categories are serialized as array, the tags stay as they are if the array has not been changed
+        let mut json: serde_json::Value = serde_json::to_value(&song).unwrap();
+        assert_eq!(json["categories"], serde_json::json!(["Lob & Anbetung", "Advent"]));
+        assert_eq!(crate::importer::import_song_from_json(&json.to_string()).unwrap(), song);
+        assert!(serde_json::to_value(Song::new("Test Song")).unwrap().get("categories").is_none());
+
+        // A changed array replaces the categories of the tags
+        json["categories"] = serde_json::json!(["Advent", "Weihnachten"]);
+        let imported: Song = crate::importer::import_song_from_json(&json.to_string()).unwrap();
+        assert_eq!(imported.get_categories(), vec!["Advent", "Weihnachten"]);
+        assert_eq!(imported.get_tag("categories").unwrap(), "Advent, Weihnachten");
+        json["categories"] = serde_json::json!([]);
+        assert!(crate::importer::import_song_from_json(&json.to_string()).unwrap().get_categories().is_empty());
+    }
+
+    #[test]
+    fn test_change_part_type() {
+        let mut song = Song::new("Test Song");
+        song.add_part_of_type(SongPartType::Verse, None);
+        song.add_part_of_type(SongPartType::Verse, None);
+        let part_order_part = song.get_part_by_index(1).unwrap();
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(vec![part_order_part])));
+
+        assert_eq!(song.change_part_type("verse.1", SongPartType::Chorus).unwrap().to_string(), "Chorus.1");
+        let id = song.change_part_type("Verse.2", SongPartType::Chorus).unwrap();
+        assert_eq!(id.to_string(), "Chorus.2");
+        assert!(id.get_checked_unique());
+        // The part order follows the changed part
+        assert_eq!(song.get_ordered_parts()[0].borrow().id.to_string(), "Chorus.2");
+
+        assert_eq!(
+            song.change_part_type("verse.3", SongPartType::Chorus),
+            Err(SongEditError::PartNotFound { id: "verse.3".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_retype_part() {
+        // The second of three verses becomes the second chorus, the other verses keep their numbers
+        let mut song = Song::new("Test Song");
+        song.add_part_of_type(SongPartType::Chorus, None);
+        let verses: Vec<Rc<RefCell<SongPart>>> = (0..3).map(|_| song.add_part_of_type(SongPartType::Verse, None)).collect();
+        song.set_part_order(PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(verses.clone())));
+        let id: SongPartId = song.change_part_type("verse.2", SongPartType::Chorus).unwrap();
+        assert_eq!(id.to_string(), "Chorus.2");
+        let verse = verses[1].borrow();
+        assert_eq!((verse.part_type, verse.number, verse.id.to_string()), (SongPartType::Chorus, 2, "Chorus.2".to_string()));
+        let ordered_ids: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(ordered_ids, vec!["Verse.1", "Chorus.2", "Verse.3"]);
+    }
+
+    #[test]
+    fn test_remove_part() {
+        let mut song = Song::new("Test Song");
+        let verse = song.add_part_of_type(SongPartType::Verse, None);
+        let chorus = song.add_part_of_type(SongPartType::Chorus, None);
+        let repetition = song.add_part_of_type(SongPartType::Chorus, None);
+        repetition.borrow_mut().set_repition(Some(chorus.clone()));
+        chorus.borrow_mut().set_occurs_after(Some(verse.clone()));
+        let bridge = song.add_part_of_type(SongPartType::Bridge, None);
+        bridge.borrow_mut().set_occurs_after(Some(chorus.clone()));
+        song.set_part_order(PartOrder::new(
+            PartOrderName::Default,
+            PartOrderRule::Custom(vec![verse.clone(), chorus.clone(), bridge.clone(), chorus.clone()]),
+        ));
+
+        let removed_part: SongPart = song.remove_part("chorus.1").unwrap();
+        assert_eq!(removed_part.id.to_string(), "Chorus.1");
+        assert_eq!(song.get_total_part_count(), 3);
+        // The parts which referenced the chorus are fixed up
+        assert!(repetition.borrow().is_repetition_of.is_none());
+        assert!(Rc::ptr_eq(&bridge.borrow().get_occurs_after().unwrap(), &verse));
+        let ordered_ids: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(ordered_ids, vec!["Verse.1", "Bridge.1"]);
+        assert!(song.remove_part("chorus.1").is_none());
+
+        song.add_part_of_type(SongPartType::Verse, None);
+        assert_eq!(song.remove_parts_by_type(SongPartType::Verse), 2);
+        assert_eq!(song.remove_parts_by_type(SongPartType::Verse), 0);
+        assert_eq!(song.get_ordered_parts().len(), 1);
+        assert_eq!(song.get_total_part_count(), 2);
+    }
+
+    #[test]
+    fn test_move_part() {
+        let mut song = Song::new("Test Song");
+        let verse_1 = song.add_part_of_type(SongPartType::Verse, None);
+        let verse_2 = song.add_part_of_type(SongPartType::Verse, None);
+        let verse_3 = song.add_part_of_type(SongPartType::Verse, None);
+        verse_2.borrow_mut().set_occurs_after(Some(verse_1.clone()));
+        verse_3.borrow_mut().set_occurs_after(Some(verse_2.clone()));
+
+        song.move_part("verse.3", 1).unwrap();
+        let ids = |song: &Song| song.get_unpacked_parts().iter().map(|part| part.id.to_string()).collect::<Vec<String>>();
+        assert_eq!(ids(&song), vec!["Verse.1", "Verse.3", "Verse.2"]);
+        assert!(Rc::ptr_eq(&verse_3.borrow().get_occurs_after().unwrap(), &verse_1));
+        assert!(Rc::ptr_eq(&verse_2.borrow().get_occurs_after().unwrap(), &verse_3));
+        assert!(verse_1.borrow().get_occurs_after().is_none());
+        let json: serde_json::Value = serde_json::to_value(&song).unwrap();
+        assert_eq!(json["parts"][1]["id"]["id"], "Verse.3");
+
+        song.swap_parts("verse.1", "Verse.2").unwrap();
+        assert_eq!(ids(&song), vec!["Verse.2", "Verse.3", "Verse.1"]);
+        assert!(verse_2.borrow().get_occurs_after().is_none());
+        assert!(Rc::ptr_eq(&verse_3.borrow().get_occurs_after().unwrap(), &verse_2));
+
+        assert_eq!(song.move_part("verse.1", 3), Err(SongEditError::IndexOutOfRange { index: 3, part_count: 3 }));
+        assert_eq!(song.move_part("verse.4", 0), Err(SongEditError::PartNotFound { id: "verse.4".to_string() }));
+        assert_eq!(song.swap_parts("verse.1", "chorus.1"), Err(SongEditError::PartNotFound { id: "chorus.1".to_string() }));
+        assert_eq!(ids(&song), vec!["Verse.2", "Verse.3", "Verse.1"]);
+    }
+
+    #[test]
+    fn test_merge_translation() {
+        let mut song: Song = crate::importer::classic_song::import_song(
+            "First verse\nwith two lines\n\nThe chorus\nwith two lines\n\nSecond verse\nwith two lines\n\nThe chorus\nwith two lines\n\nThird verse\nwith two lines"
+        ).unwrap();
+        let translation: Song = crate::importer::classic_song::import_song(
+            "Erste Strophe\nmit zwei Zeilen\n\nDer Refrain\nmit zwei Zeilen\n\nZweite Strophe\nmit zwei Zeilen\n\nDer Refrain\nmit zwei Zeilen\n\n(Bridge)\nEine Bridge\nmit zwei Zeilen"
+        ).unwrap();
+        let german = LyricLanguage::Specific("de".to_string());
+
+        let report: MergeReport = song.merge_translation(&translation, german.clone()).unwrap();
+        assert_eq!(report.merged_parts, vec!["Verse.1", "Chorus.1", "Verse.2"]);
+        assert_eq!(report.unmatched_parts, vec!["Verse.3"]);
+        assert_eq!(report.unmatched_translation_parts, vec!["Bridge.1"]);
+        let chorus = song.get_part_by_id("Chorus.1").unwrap();
+        let german_lyrics: String = chorus.borrow().get_content(SongPartContentType::Lyrics { language: german.clone() }).unwrap().content.clone();
+        assert_eq!(german_lyrics, "Der Refrain\nmit zwei Zeilen\n");
+        assert!(song.get_part_by_id("Verse.3").unwrap().borrow().contents.len() == 1);
+
+        // A language can only be merged once
+        assert_eq!(song.merge_translation(&translation, german.clone()), Err(SongEditError::LanguageExists { language: german }));
+        assert!(song.merge_translation(&translation, LyricLanguage::Default).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut song = Song::new(" ");
+        song.add_tag("copyright", "Public Domain");
+        let verse = song.add_part_of_type(SongPartType::Verse, None);
+        verse.borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "Amazing grace \nhow\tsweet the sound\n".to_string(),
+        });
+        let chorus = song.add_part_of_type(SongPartType::Chorus, None);
+        chorus.borrow_mut().number = 2;
+        let removed_part = Rc::new(RefCell::new(SongPart::new(SongPartId::parse("verse.9").unwrap(), 9)));
+        let repetition = song.add_part_of_type(SongPartType::Verse, None);
+        repetition.borrow_mut().set_repition(Some(removed_part));
+        song.add_part(SongPart::new(SongPartId::parse("verse.2").unwrap(), 2));
+
+        let issues: Vec<SongIssue> = song.validate();
+        assert_eq!(issues, vec![
+            SongIssue::EmptyTitle,
+            SongIssue::TabsInLyrics { part: "Verse.1".to_string() },
+            SongIssue::TrailingWhitespace { part: "Verse.1".to_string() },
+            SongIssue::PartNumberMismatch { part: "Chorus.1".to_string(), number: 2 },
+            SongIssue::EmptyPart { part: "Chorus.1".to_string() },
+            SongIssue::UnknownRepetition { part: "Verse.2".to_string(), repeated_part: "verse.9".to_string() },
+            SongIssue::DuplicatePartId { part: "verse.2".to_string() },
+            SongIssue::EmptyPart { part: "verse.2".to_string() },
+            SongIssue::MissingTag { key: "author".to_string() },
+        ]);
+        assert_eq!(issues[1].part(), Some("Verse.1"));
+        assert!(issues.iter().filter(|issue| issue.is_warning()).eq([&issues[8]]));
+        assert_eq!(issues[5].to_string(), "The part Verse.2 is a repetition of verse.9, which is no part of the song.");
+
+        let song = crate::importer::import_song_from_file("testfiles/Amazing Grace.song").unwrap();
+        assert!(song.validate().iter().all(|issue| issue.is_warning()));
+    }
+
+    #[test]
+    fn test_validate_tempo_and_time_signature() {
+        let mut song = Song::new("Amazing Grace");
+        song.add_tag("author", "John Newton");
+        song.add_tag("copyright", "Public Domain");
+        song.add_tag("tempo", "72 bpm");
+        song.add_tag("time", "3/4");
+        assert!(song.validate().is_empty());
+
+        song.add_tag("tempo", "72 bmp");
+        song.add_tag("time", "3/5");
+        let issues: Vec<SongIssue> = song.validate();
+        assert_eq!(issues, vec![
+            SongIssue::InvalidTempo { value: "72 bmp".to_string() },
+            SongIssue::InvalidTimeSignature { value: "3/5".to_string() },
+        ]);
+        assert!(issues.iter().all(|issue| issue.is_warning() && issue.part().is_none()));
+        assert_eq!(issues[1].to_string(), "The time signature 3/5 is invalid.");
+
+        song.add_tag("tempo", "Moderato");
+        song.add_tag("time", " ");
+        assert!(song.validate().is_empty());
+    }
+
+    #[test]
+    fn test_lyrics_languages() {
+        let english = LyricLanguage::Specific("en".to_string());
+        let german = LyricLanguage::Specific("de".to_string());
+        let mut song = Song::new("Test Song");
+        let verse = song.add_part_of_type(SongPartType::Verse, None);
+        verse.borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "Lobt den Herrn\n".to_string(),
+        });
+        verse.borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: english.clone() },
+            content: "Praise the Lord\n".to_string(),
+        });
+        let chorus = song.add_part_of_type(SongPartType::Chorus, None);
+        chorus.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "G  C\n".to_string() });
+        chorus.borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: english.clone() },
+            content: "Hallelujah\n".to_string(),
+        });
+        song.add_part_of_type(SongPartType::Bridge, None);
+        song.add_guessed_part_order().unwrap();
+
+        // The requested language, then the lyrics without a language, then any lyrics
+        let verse_lyrics = |language: &LyricLanguage| verse.borrow().get_lyrics(language).unwrap().content.clone();
+        assert_eq!(verse_lyrics(&english), "Praise the Lord\n");
+        assert_eq!(verse_lyrics(&german), "Lobt den Herrn\n");
+        assert_eq!(verse_lyrics(&LyricLanguage::Default), "Lobt den Herrn\n");
+        assert_eq!(chorus.borrow().get_lyrics(&german).unwrap().content, "Hallelujah\n");
+        assert_eq!(song.get_part_by_id("Bridge.1").unwrap().borrow().get_lyrics(&german), None);
+
+        assert_eq!(song.available_languages(), vec![LyricLanguage::Default, english.clone()]);
+        // The guessed order repeats the chorus after the bridge, which has no lyrics
+        assert_eq!(song.lyrics_text(&english), "Praise the Lord\n\nHallelujah\n\nHallelujah");
+        assert_eq!(song.lyrics_text(&german), "Lobt den Herrn\n\nHallelujah\n\nHallelujah");
+        assert_eq!(Song::new("Empty").lyrics_text(&german), "");
+    }
+
+    #[test]
+    fn test_plain_lyrics() {
+        let mut song = crate::importer::import_song_from_file("testfiles/Amazing Grace.song").unwrap();
+        for mut part in song.parts_mut() {
+            part.add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "Gsus4  D/F#\n".to_string() });
+        }
+        assert_eq!(song.first_line().unwrap(), "Amazing grace");
+        for follow_order in [false, true] {
+            let lyrics: String = song.plain_lyrics(follow_order);
+            assert!(lyrics.starts_with("Amazing grace\nhow sweet the sound\n"));
+            assert!(lyrics.contains("\n\nIt was grace that tought\n"));
+            assert!(!lyrics.contains("Gsus4"), "Chords are in the lyrics: {}", lyrics);
+            assert!(!lyrics.ends_with('\n'));
+        }
+        assert_eq!(Song::new("Empty").first_line(), None);
+        assert_eq!(Song::new("Empty").plain_lyrics(true), "");
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut song = crate::importer::import_song_from_file("testfiles/Amazing Grace.song").unwrap();
+        let statistics: SongStatistics = song.statistics();
+        assert_eq!(statistics.part_counts, BTreeMap::from([("Verse".to_string(), 3)]));
+        assert_eq!(statistics.line_count, 18);
+        assert_eq!(statistics.word_count, 74);
+        assert_eq!(statistics.unique_word_count, 51);
+        assert_eq!(statistics.longest_line, 28);
+        assert_eq!(statistics.estimated_duration, DEFAULT_LINE_DURATION * 18);
+
+        // Two bars of 3 beats per line at 60 bpm
+        song.add_tag("tempo", "60");
+        song.add_tag("time", "3/4");
+        assert_eq!(song.statistics().estimated_duration, Duration::from_secs(6) * 18);
+        song.add_tag("tempo", "Andante");
+        assert_eq!(song.statistics().estimated_duration, DEFAULT_LINE_DURATION * 18);
+        assert!(serde_json::to_string(&song.statistics()).unwrap().contains("\"part_counts\":{\"Verse\":3}"));
+
+        // The chorus is sung three times in the part order, but only counted once
+        let song = crate::importer::import_song_from_file("testfiles/Ordered Song.song").unwrap();
+        let statistics: SongStatistics = song.statistics();
+        assert_eq!(statistics.part_counts, BTreeMap::from([("Chorus".to_string(), 1), ("Verse".to_string(), 2)]));
+        assert_eq!(statistics.line_count, 6);
+        assert_eq!(statistics.estimated_duration, DEFAULT_LINE_DURATION * 10);
+        assert_eq!(Song::new("Empty").statistics(), SongStatistics::default());
+    }
+
+    #[test]
+    fn test_part_order_shorthand() {
+        let mut song = Song::new("Test Song");
+        let verse_1 = song.add_part_of_type(SongPartType::Verse, None);
+        let chorus = song.add_part_of_type(SongPartType::Chorus, None);
+        let verse_2 = song.add_part_of_type(SongPartType::Verse, None);
+
+        let part_order: PartOrder = PartOrder::parse_shorthand("V1 C V2 C", &song).unwrap();
+        assert_eq!(part_order.partorderrule, PartOrderRule::Custom(vec![verse_1.clone(), chorus.clone(), verse_2.clone(), chorus.clone()]));
+        assert_eq!(part_order.to_shorthand().unwrap(), "V1 C V2 C");
+        // Case, commas and explicit numbers are accepted, the shorthand is written canonically
+        let part_order: PartOrder = PartOrder::parse_shorthand(" v1, c1,V2  C ", &song).unwrap();
+        assert_eq!(part_order.to_shorthand().unwrap(), "V1 C V2 C");
+        assert_eq!(PartOrder::parse_shorthand(&part_order.to_shorthand().unwrap(), &song).unwrap(), part_order);
+
+        // Repetitions are written like the part they repeat, parts without a shorthand by their ID
+        let repeated_chorus = song.add_part_of_type(SongPartType::Chorus, None);
+        repeated_chorus.borrow_mut().is_repetition_of = Some(chorus.clone());
+        let interlude = song.add_part_of_type(SongPartType::Interlude, None);
+        let bridge = song.add_part_of_type(SongPartType::Bridge, None);
+        let part_order = PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(vec![verse_1, repeated_chorus, interlude, bridge.clone(), chorus]));
+        assert_eq!(part_order.to_shorthand().unwrap(), "V1 C Interlude.1 B C");
+        assert_eq!(PartOrder::parse_shorthand("Interlude.1 B", &song).unwrap().to_shorthand().unwrap(), "Interlude.1 B");
+        assert_eq!(PartOrder::new(PartOrderName::Default, PartOrderRule::VerseRefrainBridgeRefrain).to_shorthand(), None);
+        assert_eq!(PartOrder::parse_shorthand("", &song).unwrap().to_shorthand().unwrap(), "");
+
+        // A refrain is written by its ID if the song has a chorus as well, C is the chorus
+        let mut song_with_refrain: Song = song.detached_copy();
+        let refrain = song_with_refrain.add_part_of_type(SongPartType::Refrain, None);
+        let chorus = song_with_refrain.get_part_by_id("Chorus.1").unwrap();
+        let refrain_order = PartOrder::new(PartOrderName::Default, PartOrderRule::Custom(vec![refrain, chorus]));
+        assert_eq!(refrain_order.to_shorthand().unwrap(), "Refrain.1 C");
+        assert_eq!(PartOrder::parse_shorthand("Refrain.1 C", &song_with_refrain).unwrap(), refrain_order);
+        // Without a chorus, C refers to the refrain
+        let mut refrain_song = Song::new("Refrain Song");
+        let verse = refrain_song.add_part_of_type(SongPartType::Verse, None);
+        let refrain = refrain_song.add_part_of_type(SongPartType::Refrain, None);
+        let refrain_order: PartOrder = PartOrder::parse_shorthand("V1 C", &refrain_song).unwrap();
+        assert_eq!(refrain_order.partorderrule, PartOrderRule::Custom(vec![verse, refrain]));
+        assert_eq!(PartOrder::parse_shorthand(&refrain_order.to_shorthand().unwrap(), &refrain_song).unwrap(), refrain_order);
+
+        let error = |shorthand: &str| PartOrder::parse_shorthand(shorthand, &song).unwrap_err();
+        assert_eq!(error("V1 X C"), SongEditError::UnknownPartOrderToken { token: "X".to_string() });
+        assert_eq!(error("V1a"), SongEditError::UnknownPartOrderToken { token: "V1a".to_string() });
+        assert_eq!(error("V1 C V3"), SongEditError::PartOrderPartNotFound { token: "V3".to_string() });
+        assert_eq!(error("PC"), SongEditError::PartOrderPartNotFound { token: "PC".to_string() });
+        assert_eq!(error("Solo.1").to_string(), "The part order contains Solo.1, but the song has no such part");
+    }
+
+    #[test]
+    fn test_named_part_orders() {
+        let mut song: Song = SongBuilder::new("Test Song").verse("First verse").chorus("The chorus").verse("Second verse").build();
+        let full_version = PartOrderName::Default;
+        let short_version = PartOrderName::Custom("short".to_string());
+        let part_ids = |song: &Song| song.get_ordered_parts().iter().map(|part| part.borrow().id.get_id()).collect::<Vec<String>>();
+
+        song.add_part_order(PartOrder::parse_shorthand("V1 C", &song).unwrap().with_name(short_version.clone())).unwrap();
+        assert_eq!(
+            song.add_part_order(PartOrder::parse_shorthand("V2", &song).unwrap().with_name(short_version.clone())),
+            Err(SongEditError::PartOrderExists { name: short_version.clone() })
+        );
+        assert_eq!(song.part_orders.len(), 2);
+        assert_eq!(song.get_part_order(&short_version).unwrap().to_shorthand().unwrap(), "V1 C");
+        assert_eq!(song.get_part_order(&full_version).unwrap().to_shorthand().unwrap(), "V1 C V2");
+        assert_eq!(song.get_part_order(&PartOrderName::Custom("long".to_string())), None);
+        assert_eq!(part_ids(&song), vec!["Verse.1", "Chorus.1", "Verse.2"]);
+
+        song.set_active_part_order(&short_version).unwrap();
+        assert_eq!(part_ids(&song), vec!["Verse.1", "Chorus.1"]);
+        assert_eq!(
+            song.set_active_part_order(&PartOrderName::Custom("long".to_string())).unwrap_err().to_string(),
+            "The song has no part order named long"
+        );
+
+        // The active part order stays active after serialization
+        let json: String = serde_json::to_string(&song).unwrap();
+        let imported: Song = crate::importer::import_song_from_json(&json).unwrap();
+        assert_eq!(part_ids(&imported), vec!["Verse.1", "Chorus.1"]);
+        assert_eq!(imported.part_orders.iter().map(|part_order| part_order.name.clone()).collect::<Vec<_>>(), vec![short_version, full_version]);
+        let json: String = serde_json::to_string(&imported).unwrap();
+        assert_eq!(serde_json::to_string(&crate::importer::import_song_from_json(&json).unwrap()).unwrap(), json);
+
+        // A song with the order of its file doesn't get a second default part order
+        let mut song: Song = crate::importer::classic_song::import_song("Verse\none\n\nThe\nchorus\n\nVerse\ntwo\n\nThe\nchorus").unwrap();
+        assert_eq!(song.add_guessed_part_order(), Err(SongEditError::PartOrderExists { name: PartOrderName::Default }));
+        song.set_part_order(PartOrder::from_guess(&song));
+        assert_eq!(song.part_orders.len(), 1);
+        assert!(crate::importer::import_song_from_json(&serde_json::to_string(&song).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_part_order_rules() {
+        let create_song = |part_types: &[SongPartType]| {
+            let mut song = Song::new("Test Song");
+            for part_type in part_types {
+                song.add_part_of_type(*part_type, None);
+            }
+            song
+        };
+        let ordered_ids = |song: &Song, rule: PartOrderRule| {
+            PartOrder::new(PartOrderName::Default, rule)
+                .to_parts(song)
+                .iter()
+                .map(|part| part.borrow().id.to_string())
+                .collect::<Vec<String>>()
+        };
+        use SongPartType::{Bridge, Chorus, PreChorus, Refrain, Verse};
+
+        // The chorus is repeated after every verse, the bridge is sung before the last chorus
+        let song: Song = create_song(&[Verse, Chorus, Verse, Verse, Bridge]);
+        assert_eq!(
+            ordered_ids(&song, PartOrderRule::VerseRefrainBridgeRefrain),
+            vec!["Verse.1", "Chorus.1", "Verse.2", "Chorus.1", "Verse.3", "Chorus.1", "Bridge.1", "Chorus.1"]
+        );
+        // The ordered parts are the parts of the song
+        let ordered_parts = PartOrder::new(PartOrderName::Default, PartOrderRule::VerseRefrainBridgeRefrain).to_parts(&song);
+        assert!(Rc::ptr_eq(&ordered_parts[1], &song.get_part_by_id("Chorus.1").unwrap()));
+        assert!(Rc::ptr_eq(&ordered_parts[1], &ordered_parts[3]));
+
+        let song: Song = create_song(&[Verse, PreChorus, Chorus, Verse]);
+        assert_eq!(
+            ordered_ids(&song, PartOrderRule::VerseRefrainBridgeRefrain),
+            vec!["Verse.1", "PreChorus.1", "Chorus.1", "Verse.2", "PreChorus.1", "Chorus.1"]
+        );
+
+        // Songs without a chorus are sung in the order of their parts, including the bridge
+        let song: Song = create_song(&[Verse, Verse, Bridge]);
+        assert_eq!(ordered_ids(&song, PartOrderRule::VerseRefrainBridgeRefrain), vec!["Verse.1", "Verse.2", "Bridge.1"]);
+        assert_eq!(ordered_ids(&song, PartOrderRule::RefrainVerseBridgeRefrain), vec!["Verse.1", "Verse.2", "Bridge.1"]);
+
+        // The refrain is sung first, even if it has been added after the first verse
+        let song: Song = create_song(&[Verse, Refrain, Verse]);
+        assert_eq!(
+            ordered_ids(&song, PartOrderRule::RefrainVerseBridgeRefrain),
+            vec!["Refrain.1", "Verse.1", "Refrain.1", "Verse.2", "Refrain.1"]
+        );
+        let song: Song = create_song(&[Chorus, Verse, Verse]);
+        assert_eq!(
+            ordered_ids(&song, PartOrderRule::RefrainVerseBridgeRefrain),
+            vec!["Chorus.1", "Verse.1", "Chorus.1", "Verse.2", "Chorus.1"]
+        );
+
+        // Custom orders are returned as they are
+        let verse = song.get_part_by_id("Verse.2").unwrap();
+        assert_eq!(ordered_ids(&song, PartOrderRule::Custom(vec![verse.clone(), verse])), vec!["Verse.2", "Verse.2"]);
+        assert_eq!(ordered_ids(&Song::new("Empty"), PartOrderRule::VerseRefrainBridgeRefrain), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_guess_part_order() {
+        let create_song = |part_types: &[SongPartType]| {
+            let mut song = Song::new("Test Song");
+            for part_type in part_types {
+                song.add_part_of_type(*part_type, None);
+            }
+            song
+        };
+        let guessed_rule = |song: &Song| PartOrder::from_guess(song).partorderrule;
+        use SongPartType::{Bridge, Chorus, Intro, Refrain, Verse};
+
+        assert_eq!(guessed_rule(&create_song(&[Verse, Chorus, Verse, Bridge])), PartOrderRule::VerseRefrainBridgeRefrain);
+        assert_eq!(guessed_rule(&create_song(&[Verse, Verse, Refrain])), PartOrderRule::VerseRefrainBridgeRefrain);
+        assert_eq!(guessed_rule(&create_song(&[Chorus, Verse, Verse])), PartOrderRule::RefrainVerseBridgeRefrain);
+        assert_eq!(guessed_rule(&create_song(&[Refrain, Verse])), PartOrderRule::RefrainVerseBridgeRefrain);
+
+        // Several different choruses, no chorus, unusual beginnings and single parts keep the order of the parts
+        for part_types in [vec![Verse, Chorus, Verse, Chorus], vec![Verse, Verse], vec![Intro, Verse, Chorus], vec![Verse]] {
+            let song: Song = create_song(&part_types);
+            assert_eq!(guessed_rule(&song), PartOrderRule::Custom(song.parts.clone()), "Guessed a rule for {:?}", part_types);
+        }
+
+        // Repetitions of the chorus are no further choruses
+        let mut song: Song = create_song(&[Verse, Chorus, Verse]);
+        let chorus = song.get_part_by_id("Chorus.1").unwrap();
+        song.add_part_of_type(Chorus, None).borrow_mut().is_repetition_of = Some(chorus);
+        assert_eq!(guessed_rule(&song), PartOrderRule::VerseRefrainBridgeRefrain);
+        let ordered_ids: Vec<String> = song.get_ordered_parts().iter().map(|part| part.borrow().id.to_string()).collect();
+        assert_eq!(ordered_ids, vec!["Verse.1", "Chorus.1", "Verse.2", "Chorus.1"]);
+    }
+
+    #[test]
+    fn test_part_iterators() {
+        let mut song: Song = crate::importer::classic_song::import_song("Amazing grace\n\nHow sweet the sound").unwrap();
+        assert_eq!(song.parts().count(), 2);
+        assert!(song.parts().all(|part| part.part_type == SongPartType::Verse));
+
+        for mut part in song.parts_mut() {
+            let content: String = part.contents[0].content.to_uppercase();
+            part.contents[0].content = content;
+        }
+        assert_eq!(song.get_part_by_id("Verse.2").unwrap().borrow().contents[0].content, "HOW SWEET THE SOUND\n");
+        let lyrics: Vec<String> = song.lyrics_blocks(&LyricLanguage::Default).collect();
+        assert_eq!(lyrics, vec!["AMAZING GRACE\n", "HOW SWEET THE SOUND\n"]);
+        assert_eq!(song.lyrics_blocks(&LyricLanguage::Specific("de".to_string())).count(), 0);
+    }
+
+    #[test]
+    fn test_song_builder() {
+        let song: Song = SongBuilder::new("Test Song")
+            .tag("author", "Somebody")
+            .chorus("The chorus")
+            .verse("First verse")
+            .chorus("The chorus\n")
+            .verse("Second verse")
+            .chorus("Another chorus")
+            .bridge("The bridge")
+            .chorus("The chorus")
+            .build();
+        assert_eq!(song.title, "Test Song");
+        assert_eq!(song.get_part_count(SongPartType::Chorus), 4);
+        let chorus = song.get_part_by_id("Chorus.1").unwrap();
+        for id in ["Chorus.2", "Chorus.4"] {
+            assert!(Rc::ptr_eq(song.get_part_by_id(id).unwrap().borrow().is_repetition_of.as_ref().unwrap(), &chorus));
+        }
+        assert!(song.get_part_by_id("Chorus.3").unwrap().borrow().is_repetition_of.is_none());
+        assert_eq!(song.get_ordered_parts().len(), 7);
+        assert_eq!(chorus.borrow().contents[0].content, "The chorus\n");
+        assert!(song.validate().iter().all(|issue| issue.is_warning()));
+
+        // Verses are never repetitions, a song without parts has no part order
+        let song: Song = SongBuilder::new("Test Song").verse("Amen").verse("Amen").build();
+        assert!(song.get_part_by_id("Verse.2").unwrap().borrow().is_repetition_of.is_none());
+        assert!(SongBuilder::new("").build().part_orders.is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let old_song: Song = crate::importer::import_song_from_file("testfiles/Amazing Grace.song").unwrap();
+        let new_song: Song = crate::importer::import_song_from_file("testfiles/diff/Amazing Grace.song").unwrap();
+        let diff: SongDiff = old_song.diff(&new_song);
+
+        assert_eq!(diff.retyped_parts, vec![RetypedPart { old_part: "Verse.3".to_string(), new_part: "Chorus.1".to_string() }]);
+        assert_eq!(diff.added_parts, vec!["Verse.3"]);
+        assert!(diff.removed_parts.is_empty());
+        assert_eq!(diff.lyric_changes.len(), 1);
+        assert_eq!(diff.lyric_changes[0].part, "Verse.2");
+        assert_eq!(diff.lyric_changes[0].lines[..3], [
+            LineDiff::Added("It was grace that taught".to_string()),
+            LineDiff::Removed("It was grace that tought".to_string()),
+            LineDiff::Kept("my heart to fear,".to_string()),
+        ]);
+        assert_eq!(diff.tag_changes, vec![
+            TagChange::Changed { key: "author".to_string(), old_value: "John Newton".to_string(), new_value: "John Newton, 1779".to_string() },
+            TagChange::Added { key: "copyright".to_string(), value: "Public Domain".to_string() },
+        ]);
+
+        let reverse_diff: SongDiff = new_song.diff(&old_song);
+        assert_eq!(reverse_diff.removed_parts, vec!["Verse.3"]);
+        assert!(matches!(&reverse_diff.tag_changes[1], TagChange::Removed { key, .. } if key == "copyright"));
+
+        // Copies of the song are the same, even if the IDs have not been checked for uniqueness
+        let json: String = serde_json::to_string(&old_song).unwrap();
+        assert!(old_song.diff(&serde_json::from_str(&json).unwrap()).is_empty());
+        assert!(serde_json::to_value(&diff).is_ok());
+    }
+
+    #[test]
+    fn test_transpose() {
+        let mut song = Song::new("Test Song");
+        song.add_tag("key", "D");
+        let verse = song.add_part_of_type(SongPartType::Verse, None);
+        verse.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "D  A/C#\n\nG\n".to_string() });
+        verse.borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "A lyric line\n".to_string(),
+        });
+        let original: Song = song.clone();
+        song.transpose(0);
+        assert_eq!(song, original);
+
+        song.transpose(-2);
+        assert_eq!(song.get_tag("key").unwrap(), "C");
+        assert_eq!(verse.borrow().contents[0].content, "C  G/B\n\nF\n");
+        // The chords are written like the transposed key
+        song.transpose(-2);
+        assert_eq!(song.get_tag("key").unwrap(), "Bb");
+        assert_eq!(verse.borrow().contents[0].content, "Bb F/A\n\nEb\n");
+        assert_eq!(verse.borrow().contents[1].content, "A lyric line\n");
+
+        let mut song = Song::new("Test Song");
+        song.add_tag("key", "H");
+        let verse = song.add_part_of_type(SongPartType::Verse, None);
+        verse.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "H  e  B".to_string() });
+        song.transpose_with_notation(1, NoteNotation::German);
+        assert_eq!(song.get_tag("key").unwrap(), "C-Dur");
+        assert_eq!(verse.borrow().contents[0].content, "C  f  H");
+    }
+
+    #[test]
+    fn test_render_chords_as_numbers() {
+        let mut song: Song = SongBuilder::new("Test Song")
+            .verse("First verse")
+            .chorus("The chorus")
+            .verse("Second verse")
+            .chorus("The chorus")
+            .build();
+        assert_eq!(song.render_chords_as_numbers(), Err(ChordError::MissingKey));
+        song.add_tag("key", "Hm");
+        assert_eq!(song.render_chords_as_numbers(), Err(ChordError::InvalidKey { key: "Hm".to_string() }));
+
+        song.add_tag("key", "Am");
+        let chorus = song.get_part_by_id("Chorus.1").unwrap();
+        chorus.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "Am  F  C  G/B  E7\n".to_string() });
+        let numbers: Song = song.render_chords_as_numbers().unwrap();
+        let numbered_chorus = numbers.get_part_by_id("Chorus.1").unwrap();
+        assert_eq!(numbered_chorus.borrow().get_content(SongPartContentType::Chords).unwrap().content, "1m  6  3  7/2  57\n");
+        assert_eq!(chorus.borrow().get_content(SongPartContentType::Chords).unwrap().content, "Am  F  C  G/B  E7\n");
+        assert_eq!(numbers.get_tag("key").unwrap(), "Am");
+
+        // The copy has its own parts, but the same structure
+        assert!(!Rc::ptr_eq(&numbered_chorus, &chorus));
+        let ordered_parts: Vec<Rc<RefCell<SongPart>>> = numbers.get_ordered_parts();
+        assert_eq!(ordered_parts.len(), 4);
+        assert!(Rc::ptr_eq(&ordered_parts[1], &numbered_chorus));
+        let repetition = numbers.get_part_by_id("Chorus.2").unwrap();
+        assert!(Rc::ptr_eq(repetition.borrow().is_repetition_of.as_ref().unwrap(), &numbered_chorus));
+        assert_eq!(crate::exporter::classic_song::export_song(&numbers), crate::exporter::classic_song::export_song(&song));
+    }
+
+    #[test]
+    fn test_with_capo() {
+        let mut song = Song::new("Test Song");
+        song.add_tag("key", "F");
+        let verse = song.add_part_of_type(SongPartType::Verse, None);
+        verse.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "F  C/E  Dm  Bb\n".to_string() });
+        verse.borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "A lyric line\n".to_string(),
+        });
+
+        assert_eq!(song.with_capo(0).unwrap(), song);
+        assert_eq!(song.with_capo(12), Err(ChordError::InvalidCapo { fret: 12 }));
+
+        let capo_song: Song = song.with_capo(3).unwrap();
+        let capo_verse = capo_song.get_part_by_id("Verse.1").unwrap();
+        assert_eq!(capo_verse.borrow().contents[0].content, "D  A/C# Bm  G\n");
+        assert_eq!(capo_verse.borrow().contents[1].content, "A lyric line\n");
+        assert_eq!(capo_song.get_tag("capo").unwrap(), "3");
+        assert_eq!(capo_song.get_tag("key").unwrap(), "F");
+        // The song itself is not changed
+        assert_eq!(verse.borrow().contents[0].content, "F  C/E  Dm  Bb\n");
+        assert_eq!(song.get_tag("capo"), None);
+
+        // The shapes are written with flats if their key is written with flats
+        song.add_tag("key", "G");
+        let capo_verse = song.with_capo(2).unwrap().get_part_by_id("Verse.1").unwrap();
+        assert_eq!(capo_verse.borrow().contents[0].content, "Eb Bb/D Cm  Ab\n");
+    }
+
+    #[test]
+    fn test_track_modifications() {
+        let mut song: Song = Song::new("Amazing Grace");
+        song.add_tag("author", "John Newton");
+        assert_eq!(song.modified(), None);
+
+        song.set_track_modifications(true);
+        song.set_modified(std::time::UNIX_EPOCH);
+        assert_eq!(song.get_tag("modified").unwrap(), "1970-01-01T00:00:00Z");
+
+        song.add_part_of_type(SongPartType::Verse, None);
+        let bumped = song.modified().unwrap();
+        assert!(bumped > std::time::UNIX_EPOCH);
+
+        song.set_modified(std::time::UNIX_EPOCH);
+        song.remove_tag("author");
+        assert!(song.modified().unwrap() > std::time::UNIX_EPOCH);
+
+        song.set_created(std::time::UNIX_EPOCH);
+        assert_eq!(song.created(), Some(std::time::UNIX_EPOCH));
+    }
+
     #[test]
     fn test_new_song_part() {
         let part = SongPart::new(SongPartId::parse("verse.1").unwrap(), 1);
@@ -763,6 +3484,50 @@ mod tests {
         dbg!(song.parts);
     }
 
+    #[test]
+    fn test_set_and_remove_content() {
+        let english = LyricLanguage::Specific("en".to_string());
+        let german = LyricLanguage::Specific("de".to_string());
+        let content = |voice_type: SongPartContentType, content: &str| SongPartContent { voice_type, content: content.to_string() };
+        let lyrics = |language: &LyricLanguage, text: &str| content(SongPartContentType::Lyrics { language: language.clone() }, text);
+
+        let mut part = SongPart::new(SongPartId::parse("verse.1").unwrap(), 1);
+        part.add_content(lyrics(&english, "Amazing grace"));
+        part.add_content(content(SongPartContentType::Chords, "G  C"));
+        part.add_content(lyrics(&english, "Amazing grace (duplicate)"));
+        // Lyrics are replaced per language, the duplicate is removed and the position is kept
+        assert_eq!(part.set_content(lyrics(&german, "O Gnade Gottes")), None);
+        assert_eq!(part.set_content(lyrics(&english, "Amazing grace, how sweet")), Some(lyrics(&english, "Amazing grace")));
+        assert_eq!(
+            part.contents,
+            vec![
+                lyrics(&english, "Amazing grace, how sweet"),
+                content(SongPartContentType::Chords, "G  C"),
+                lyrics(&german, "O Gnade Gottes"),
+            ]
+        );
+        assert_eq!(part.contents_of_type(SongPartContentType::is_lyrics).len(), 2);
+        assert_eq!(part.contents_of_type(|voice_type| voice_type == &SongPartContentType::Chords), vec![&content(SongPartContentType::Chords, "G  C")]);
+
+        assert_eq!(part.remove_content(SongPartContentType::Lyrics { language: german.clone() }), Some(lyrics(&german, "O Gnade Gottes")));
+        assert_eq!(part.remove_content(SongPartContentType::Lyrics { language: german }), None);
+        assert_eq!(part.remove_content(SongPartContentType::BassVoice), None);
+        assert_eq!(part.contents.len(), 2);
+
+        // Importing the lyrics of a song again into its parts doesn't duplicate them
+        let song: Song = crate::importer::classic_song::import_song(&std::fs::read_to_string("testfiles/Amazing Grace.song").unwrap()).unwrap();
+        let reimported: Song = crate::importer::classic_song::import_song(&std::fs::read_to_string("testfiles/Amazing Grace.song").unwrap()).unwrap();
+        let lyrics_count = |song: &Song| song.get_unpacked_parts().iter().map(|part| part.contents_of_type(SongPartContentType::is_lyrics).len()).sum::<usize>();
+        let original_lyrics_count: usize = lyrics_count(&song);
+        for (part, reimported_part) in song.parts.iter().zip(reimported.parts.iter()) {
+            for content in reimported_part.borrow().contents_of_type(SongPartContentType::is_lyrics) {
+                assert!(part.borrow_mut().set_content(content.clone()).is_some());
+            }
+        }
+        assert_eq!(lyrics_count(&song), original_lyrics_count);
+        assert_eq!(song.lyrics_text(&song.primary_language()), reimported.lyrics_text(&reimported.primary_language()));
+    }
+
     #[test]
     fn test_add_content_with_multiple_parts() {
         let mut song: Song = Song::new("Amazing Grace");
@@ -772,5 +3537,106 @@ mod tests {
         song.add_part(part);
         assert_eq!(song.parts.len(), 2);
     }
-    
+
+    #[test]
+    fn test_normalize_lyrics() {
+        let create_song = || {
+            let mut song = Song::new("Test Song");
+            let verse = song.add_part_of_type(SongPartType::Verse, None);
+            verse.borrow_mut().add_content(SongPartContent {
+                voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+                content: "“Praise  him” --\tall   ye people\n".to_string(),
+            });
+            verse.borrow_mut().add_content(SongPartContent {
+                voice_type: SongPartContentType::Chords,
+                content: "G  --  D   \"C\"".to_string(),
+            });
+            let chorus = song.add_part_of_type(SongPartType::Chorus, None);
+            chorus.borrow_mut().add_content(SongPartContent {
+                voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+                content: "HALLELUJAH, I SING\n".to_string(),
+            });
+            song
+        };
+        let lyrics = |song: &Song, index: usize| song.get_part_by_index(index).unwrap().borrow().contents[0].content.clone();
+        let nothing = NormalizeOptions {
+            quotes: QuoteStyle::Keep,
+            trim_trailing_whitespace: false,
+            collapse_whitespace: false,
+            normalize_dashes: false,
+            normalize_ellipses: false,
+            unicode_nfc: false,
+            sentence_case: false,
+        };
+
+        // Every option on its own
+        let mut song = create_song();
+        assert!(song.normalize(nothing).parts.is_empty());
+        assert_eq!(song, create_song());
+
+        let mut song = create_song();
+        let changes = song.normalize(NormalizeOptions { quotes: QuoteStyle::Straight, ..nothing }).parts;
+        assert_eq!(changes, vec![PartNormalization { part: "Verse.1".to_string(), rules: vec![NormalizationRule::Quotes] }]);
+        assert_eq!(lyrics(&song, 0), "\"Praise  him\" --\tall   ye people\n");
+
+        let mut song = create_song();
+        song.normalize(NormalizeOptions { collapse_whitespace: true, ..nothing });
+        assert_eq!(lyrics(&song, 0), "“Praise him” -- all ye people\n");
+
+        let mut song = create_song();
+        song.normalize(NormalizeOptions { normalize_dashes: true, ..nothing });
+        assert_eq!(lyrics(&song, 0), "“Praise  him” –\tall   ye people\n");
+
+        let mut song = create_song();
+        let changes = song.normalize(NormalizeOptions { sentence_case: true, ..nothing }).parts;
+        assert_eq!(changes, vec![PartNormalization { part: "Chorus.1".to_string(), rules: vec![NormalizationRule::SentenceCase] }]);
+        assert_eq!(lyrics(&song, 1), "Hallelujah, I sing\n");
+
+        // All options combined, the chords stay untouched
+        let mut song = create_song();
+        let changes = song.normalize(NormalizeOptions { quotes: QuoteStyle::Straight, sentence_case: true, ..Default::default() }).parts;
+        assert_eq!(changes[0].rules, vec![NormalizationRule::Quotes, NormalizationRule::Whitespace, NormalizationRule::Dashes]);
+        assert_eq!(lyrics(&song, 0), "\"Praise him\" – all ye people\n");
+        assert_eq!(lyrics(&song, 1), "Hallelujah, I sing\n");
+        assert_eq!(song.get_part_by_index(0).unwrap().borrow().contents[1].content, "G  --  D   \"C\"");
+
+        // Trailing whitespace is removed without collapsing the spaces within the lines
+        let mut song = create_song();
+        song.get_part_by_index(1).unwrap().borrow_mut().contents[0].content = "HALLELUJAH  I SING   \n".to_string();
+        song.normalize(NormalizeOptions { trim_trailing_whitespace: true, ..nothing });
+        assert_eq!(lyrics(&song, 1), "HALLELUJAH  I SING\n");
+    }
+
+    #[test]
+    fn test_normalize_song() {
+        let mut song = Song::new("Gna\u{0308}dig. . .");
+        song.add_tag("title", "Gna\u{0308}dig. . .");
+        song.add_tag("copyright", "HILLSONG  MUSIC -- 2001");
+        song.add_tag("key", "G");
+        let verse = song.add_part_of_type(SongPartType::Verse, None);
+        verse.borrow_mut().add_content(SongPartContent {
+            voice_type: SongPartContentType::Lyrics { language: LyricLanguage::Default },
+            content: "Amazing grace...\nhow sweet the sound \nthat saved a wretch\n".to_string(),
+        });
+        verse.borrow_mut().add_content(SongPartContent { voice_type: SongPartContentType::Chords, content: "G...  C   \n".to_string() });
+
+        song.add_tag("scripture", "Psalm 23:1 -- 3");
+        song.add_tag("order", "Verse 1  Chorus");
+        let report = song.normalize(NormalizeOptions { sentence_case: true, ..Default::default() });
+        assert_eq!(report.changed_lines, 4);
+        assert_eq!(report.parts, vec![PartNormalization { part: "Verse.1".to_string(), rules: vec![NormalizationRule::Whitespace, NormalizationRule::Ellipses] }]);
+        assert_eq!(song.title, "Gnädig…");
+        assert_eq!(song.get_tag("title").unwrap(), "Gnädig…");
+        // Tag values are not converted to sentence case
+        assert_eq!(song.get_tag("copyright").unwrap(), "HILLSONG MUSIC – 2001");
+        assert_eq!(song.get_tag("key").unwrap(), "G");
+        // Tags which are read by programs are kept as they are
+        assert_eq!(song.get_tag("scripture").unwrap(), "Psalm 23:1 -- 3");
+        assert_eq!(song.get_tag("order").unwrap(), "Verse 1  Chorus");
+        assert_eq!(verse.borrow().contents[0].content, "Amazing grace…\nhow sweet the sound\nthat saved a wretch\n");
+        assert_eq!(verse.borrow().contents[1].content, "G...  C   \n");
+
+        // Normalizing again changes nothing
+        assert_eq!(song.normalize(NormalizeOptions { sentence_case: true, ..Default::default() }), NormalizationReport::default());
+    }
 }
@@ -0,0 +1,127 @@
+//! A songbook is a list of songs (e.g. of a directory of song files) which can be filtered.
+//! To keep the files of the songs (e.g. for a repertoire of a frontend), use a `SongCollection`.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+use crate::collection::SongCollection;
+use crate::importer::classic_song;
+use crate::song::Song;
+
+/// The name of the file in a songbook directory which declares tags shared by all songs (e.g. `#language: de`)
+pub const DEFAULTS_FILE_NAME: &str = "_defaults.song";
+
+/// A collection of songs
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Songbook {
+    pub songs: Vec<Song>,
+}
+
+impl Songbook {
+    /// Creates a songbook from a list of songs
+    pub fn new(songs: Vec<Song>) -> Songbook {
+        Songbook { songs }
+    }
+
+    /// Loads all songs from a directory (not recursively), see `SongCollection::load_from_directory`.
+    /// Files which can't be imported (e.g. because of an unsupported extension) are skipped,
+    /// files with several songs (like a VideoPsalm songbook) add all of their songs.
+    /// The songs are sorted by their file name.
+    ///
+    /// If the directory contains a defaults file (see [DEFAULTS_FILE_NAME]), its tags are inherited by every song
+    /// which doesn't set them itself. The title of the defaults file is never inherited.
+    pub fn from_directory(dir: &Path) -> io::Result<Songbook> {
+        SongCollection::load_from_directory(dir).map(Songbook::from)
+    }
+
+    /// Returns all songs which have the given theme (see `Song::has_theme`).
+    /// The comparison is case- and diacritic-insensitive.
+    /// # Example
+    /// ```
+    /// use cantara_songlib::song::Song;
+    /// use cantara_songlib::songbook::Songbook;
+    /// let mut song = Song::new("Tochter Zion");
+    /// song.add_tag("themes", "Advent; Weihnachten");
+    /// let songbook = Songbook::new(vec![song, Song::new("Amazing Grace")]);
+    /// assert_eq!(songbook.filter_by_theme("advent").len(), 1);
+    /// ```
+    pub fn filter_by_theme(&self, theme: &str) -> Vec<&Song> {
+        self.songs.iter().filter(|song| song.has_theme(theme)).collect()
+    }
+}
+
+/// A songbook contains the songs of a collection without their files
+impl From<SongCollection> for Songbook {
+    fn from(collection: SongCollection) -> Songbook {
+        Songbook { songs: collection.songs.into_iter().map(|entry| entry.song).collect() }
+    }
+}
+
+/// Reads the tags of a defaults file. A missing file results in no defaults.
+pub(crate) fn load_defaults(path: &Path) -> io::Result<HashMap<String, String>> {
+    if !path.is_file() {
+        return Ok(HashMap::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    let mut defaults = match classic_song::import_song(&content) {
+        Ok(song) => song.get_tags().clone(),
+        Err(_) => HashMap::new(),
+    };
+    defaults.remove("title");
+    Ok(defaults)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_by_theme() {
+        let mut christmas_song = Song::new("Stille Nacht");
+        christmas_song.add_tag("tags", "Weihnachten, Heiligabend");
+        let mut easter_song = Song::new("Christ ist erstanden");
+        easter_song.add_tag("categories", "Ostern; Österliche Freudenzeit");
+        let mut advent_song = Song::new("Macht hoch die Tür");
+        advent_song.add_tag("themes", "Advent | Weihnachten | weihnachten");
+
+        let songbook = Songbook::new(vec![christmas_song, easter_song, advent_song]);
+        let titles = |songs: Vec<&Song>| songs.iter().map(|song| song.title.clone()).collect::<Vec<String>>();
+
+        assert_eq!(titles(songbook.filter_by_theme("WEIHNACHTEN")), vec!["Stille Nacht", "Macht hoch die Tür"]);
+        assert_eq!(titles(songbook.filter_by_theme("osterliche freudenzeit")), vec!["Christ ist erstanden"]);
+        assert!(songbook.filter_by_theme("Pfingsten").is_empty());
+        assert_eq!(songbook.songs[2].themes(), vec!["Advent", "Weihnachten"]);
+    }
+
+    #[test]
+    fn test_songbook_defaults_file() {
+        use crate::song::TagSource;
+
+        let dir = std::env::temp_dir().join(format!("cantara-songlib-songbook-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(DEFAULTS_FILE_NAME), "#language: de\n#ccli_license: 123456").unwrap();
+        std::fs::write(dir.join("Amazing Grace.song"), "#title: Amazing Grace\n#language: en\n\nAmazing grace").unwrap();
+        std::fs::write(dir.join("Stille Nacht.song"), "#title: Stille Nacht\n\nStille Nacht, heilige Nacht").unwrap();
+
+        let songbook = Songbook::from_directory(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(songbook.songs.len(), 2);
+        let amazing_grace = &songbook.songs[0];
+        let stille_nacht = &songbook.songs[1];
+        assert_eq!(amazing_grace.get_tag("language").unwrap(), "en");
+        assert_eq!(amazing_grace.tag_source("language"), Some(TagSource::Own));
+        assert_eq!(amazing_grace.tag_source("ccli_license"), Some(TagSource::Inherited));
+        assert_eq!(stille_nacht.get_tag("language").unwrap(), "de");
+        assert_eq!(stille_nacht.tag_source("language"), Some(TagSource::Inherited));
+        assert_eq!(stille_nacht.get_tag("ccli_license").unwrap(), "123456");
+        assert_eq!(stille_nacht.tag_source("author"), None);
+    }
+
+    #[test]
+    fn test_songbook_from_directory() {
+        let songbook = Songbook::from_directory(Path::new("testfiles")).unwrap();
+        assert!(songbook.songs.iter().any(|song| song.title == "Amazing Grace"));
+    }
+}
@@ -7,13 +7,22 @@ use handlebars::Handlebars;
 use handlebars::RenderError;
 
 /// This function parses metadata of a song file against a Handlebar template string and returns a string
-pub fn render_metadata(
+pub(crate) fn render_metadata(
     template_string: &str, 
     metadata: &HashMap<String, String>) -> Result<String, RenderError> {
     let reg = Handlebars::new();
     reg.render_template(template_string, metadata)
 }
 
+/// Renders a template against the given values without escaping HTML characters (e.g. for tag values which reference other tags)
+pub(crate) fn render_plain_template(
+    template_string: &str,
+    values: &HashMap<String, String>) -> Result<String, RenderError> {
+    let mut reg = Handlebars::new();
+    reg.register_escape_fn(handlebars::no_escape);
+    reg.render_template(template_string, values)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,4 +47,12 @@ mod tests {
             "Amazing Grace ()"
         );
     }
+
+    #[test]
+    fn test_render_plain_template() {
+        let mut values: HashMap<String, String> = HashMap::new();
+        values.insert("publisher".to_string(), "Hänssler & Co".to_string());
+
+        assert_eq!(render_plain_template("© {{publisher}}", &values).unwrap(), "© Hänssler & Co");
+    }
 }
\ No newline at end of file
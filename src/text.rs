@@ -0,0 +1,309 @@
+//! Small text helpers which are shared by several modules of the crate
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Returns whether a character is invisible and should never be part of a value (zero-width characters and the byte order mark)
+fn is_invisible(character: char) -> bool {
+    matches!(character, '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// Normalizes a value which has been captured from a file (e.g. a tag value):
+/// carriage returns and zero-width characters are removed, non-breaking spaces are replaced with
+/// regular spaces and the value is trimmed. If `nfc` is true, the value is NFC-normalized as well.
+pub(crate) fn normalize_value(value: &str, nfc: bool) -> String {
+    let cleaned: String = value
+        .chars()
+        .filter(|character| *character != '\r' && !is_invisible(*character))
+        .map(|character| match character {
+            '\u{00A0}' | '\u{202F}' => ' ',
+            _ => character,
+        })
+        .collect();
+    match nfc {
+        true => cleaned.trim().nfc().collect(),
+        false => cleaned.trim().to_string(),
+    }
+}
+
+/// Returns the base letter of a character with a diacritic (e.g. `ä` -> `a`)
+fn strip_diacritic(character: char) -> Option<&'static str> {
+    let base = match character {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'ç' | 'ć' | 'č' => "c",
+        'ď' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' => "i",
+        'ł' => "l",
+        'ñ' | 'ń' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+        'ř' => "r",
+        'ś' | 'š' => "s",
+        'ß' => "ss",
+        'ť' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+        'ý' | 'ÿ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'æ' => "ae",
+        'œ' => "oe",
+        _ => return None,
+    };
+    Some(base)
+}
+
+/// Folds a string for case- and diacritic-insensitive comparisons (e.g. `Ostern` and `ÖSTERN` are equal)
+pub(crate) fn fold_for_comparison(value: &str) -> String {
+    let mut folded = String::with_capacity(value.len());
+    for character in value.trim().chars().flat_map(char::to_lowercase) {
+        match strip_diacritic(character) {
+            Some(base) => folded.push_str(base),
+            None => folded.push(character),
+        }
+    }
+    folded
+}
+
+/// Normalizes a block of lyrics for comparisons: the comparison ignores case, line wrapping,
+/// repeated whitespace and punctuation after words (e.g. `Amazing grace,\nhow sweet` equals `amazing grace how sweet`)
+pub(crate) fn normalize_for_matching(value: &str) -> String {
+    value
+        .split_whitespace()
+        .map(|word| word.trim_end_matches(|character: char| character.is_ascii_punctuation() || matches!(character, '–' | '—' | '…')))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Returns the similarity of two strings between 0.0 (completely different) and 1.0 (equal),
+/// based on the Levenshtein distance of their characters
+pub(crate) fn similarity(first: &str, second: &str) -> f32 {
+    let first: Vec<char> = first.chars().collect();
+    let second: Vec<char> = second.chars().collect();
+    let maximum_length = first.len().max(second.len());
+    if maximum_length == 0 {
+        return 1.0;
+    }
+
+    let mut previous_row: Vec<usize> = (0..=second.len()).collect();
+    for (first_index, first_character) in first.iter().enumerate() {
+        let mut current_row: Vec<usize> = vec![first_index + 1];
+        for (second_index, second_character) in second.iter().enumerate() {
+            let substitution_cost = if first_character == second_character { 0 } else { 1 };
+            current_row.push(
+                (previous_row[second_index] + substitution_cost)
+                    .min(previous_row[second_index + 1] + 1)
+                    .min(current_row[second_index] + 1)
+            );
+        }
+        previous_row = current_row;
+    }
+
+    1.0 - previous_row[second.len()] as f32 / maximum_length as f32
+}
+
+/// Replaces all typographic quotes with straight ones (`“Hello”` -> `"Hello"`, `don’t` -> `don't`)
+pub(crate) fn straighten_quotes(text: &str) -> String {
+    text.chars()
+        .map(|character| match character {
+            '“' | '”' | '„' | '‟' | '″' => '"',
+            '‘' | '’' | '‚' | '‛' | '′' => '\'',
+            _ => character,
+        })
+        .collect()
+}
+
+/// Replaces all quotes with typographic ones. A quote at the beginning of a word opens, every other quote closes
+/// (a single quote within a word becomes an apostrophe).
+pub(crate) fn curl_quotes(text: &str) -> String {
+    let mut curled = String::with_capacity(text.len());
+    let mut previous: Option<char> = None;
+    for character in straighten_quotes(text).chars() {
+        let is_opening = previous.is_none_or(|previous| previous.is_whitespace() || matches!(previous, '(' | '[' | '{'));
+        curled.push(match (character, is_opening) {
+            ('"', true) => '“',
+            ('"', false) => '”',
+            ('\'', true) => '‘',
+            ('\'', false) => '’',
+            _ => character,
+        });
+        previous = Some(character);
+    }
+    curled
+}
+
+/// Collapses runs of spaces and tabs within the lines to a single space and removes trailing whitespace.
+/// The indentation at the beginning of a line is kept (e.g. for echoes).
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let content = line.trim_start();
+            let indentation = &line[..line.len() - content.len()];
+            let words: Vec<&str> = content.split([' ', '\t']).filter(|word| !word.is_empty()).collect();
+            format!("{}{}", indentation, words.join(" "))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Removes the whitespace at the end of every line
+pub(crate) fn trim_trailing_whitespace(text: &str) -> String {
+    text.split('\n').map(str::trim_end).collect::<Vec<&str>>().join("\n")
+}
+
+/// Replaces three dots (`...`, also spaced like `. . .`) with an ellipsis (`…`). Longer runs of dots are kept.
+pub(crate) fn normalize_ellipses(text: &str) -> String {
+    let characters: Vec<char> = text.chars().collect();
+    let mut normalized = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < characters.len() {
+        if characters[index] != '.' {
+            normalized.push(characters[index]);
+            index += 1;
+            continue;
+        }
+        // The dots may be separated by single spaces
+        let mut end = index + 1;
+        let mut dot_count = 1;
+        let spaced = characters.get(end) == Some(&' ') && characters.get(end + 1) == Some(&'.');
+        while characters.get(end) == Some(&'.') || (spaced && characters.get(end) == Some(&' ') && characters.get(end + 1) == Some(&'.')) {
+            if characters[end] == '.' {
+                dot_count += 1;
+            }
+            end += 1;
+        }
+        match dot_count {
+            3 => normalized.push('…'),
+            _ => normalized.extend(&characters[index..end]),
+        }
+        index = end;
+    }
+    normalized
+}
+
+/// Normalizes a text to the Unicode normalization form C (e.g. an `e` followed by a combining accent becomes `é`)
+pub(crate) fn normalize_unicode(text: &str) -> String {
+    text.nfc().collect()
+}
+
+/// Replaces dashes which are typed as `--` or as a single hyphen between spaces with an en dash
+/// (`Lord -- hear` and `Lord - hear` become `Lord – hear`). Hyphenated words and longer runs of hyphens are kept.
+pub(crate) fn normalize_dashes(text: &str) -> String {
+    let characters: Vec<char> = text.chars().collect();
+    let mut normalized = String::with_capacity(text.len());
+    let mut index = 0;
+    while index < characters.len() {
+        if characters[index] != '-' {
+            normalized.push(characters[index]);
+            index += 1;
+            continue;
+        }
+        let run_length = characters[index..].iter().take_while(|character| **character == '-').count();
+        let is_spaced = index > 0 && characters[index - 1] == ' ' && characters.get(index + run_length) == Some(&' ');
+        match run_length {
+            2 => normalized.push('–'),
+            1 if is_spaced => normalized.push('–'),
+            _ => normalized.extend(std::iter::repeat_n('-', run_length)),
+        }
+        index += run_length;
+    }
+    normalized
+}
+
+/// Converts a text which is written in capital letters only (like `AMAZING GRACE`) to sentence case:
+/// every line starts with a capital letter, the rest is lowercase (apart from the word `I`).
+/// # Returns
+/// The converted text or None if the text contains lowercase letters or too few letters to be regarded as shouting
+pub(crate) fn sentence_case(text: &str) -> Option<String> {
+    let letter_count = text.chars().filter(|character| character.is_alphabetic()).count();
+    if letter_count < 4 || text.chars().any(char::is_lowercase) {
+        return None;
+    }
+
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let lowercase = line.to_lowercase();
+            let words: Vec<String> = lowercase
+                .split(' ')
+                .map(|word| match word {
+                    "i" => "I".to_string(),
+                    _ if word.starts_with("i'") || word.starts_with("i’") => format!("I{}", &word[1..]),
+                    _ => word.to_string(),
+                })
+                .collect();
+            let line = words.join(" ");
+            match line.char_indices().find(|(_, character)| character.is_alphabetic()) {
+                Some((position, character)) => format!(
+                    "{}{}{}", &line[..position], character.to_uppercase(), &line[position + character.len_utf8()..]
+                ),
+                None => line,
+            }
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Decodes text which has been written with the Windows-1252 encoding (e.g. by older Windows programs).
+/// Latin-1 is a subset of Windows-1252, so Latin-1 files are decoded correctly as well.
+pub(crate) fn decode_windows_1252(bytes: &[u8]) -> String {
+    encoding_rs::WINDOWS_1252.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_for_comparison() {
+        assert_eq!(fold_for_comparison("Österlich"), "osterlich");
+        assert_eq!(fold_for_comparison(" Noël "), "noel");
+        assert_eq!(fold_for_comparison("Straße"), "strasse");
+        assert_eq!(fold_for_comparison("CHRISTMAS"), "christmas");
+    }
+
+    #[test]
+    fn test_normalize_for_matching() {
+        assert_eq!(
+            normalize_for_matching("Amazing grace, how sweet the sound,\nThat saved a wretch like me!"),
+            normalize_for_matching("amazing grace,\nhow  sweet the sound\nthat saved a wretch like me")
+        );
+        assert_ne!(normalize_for_matching("Amazing grace"), normalize_for_matching("Amazing race"));
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert_eq!(similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+        assert_eq!(similarity("abc", ""), 0.0);
+    }
+
+    #[test]
+    fn test_normalize_value() {
+        assert_eq!(normalize_value("Amazing Grace\r", false), "Amazing Grace");
+        assert_eq!(normalize_value("\u{00A0}John\u{00A0}Newton\u{200B}\u{FEFF}", false), "John Newton");
+        // "e" followed by a combining diaeresis
+        assert_eq!(normalize_value("Noe\u{0308}l", true), "Noël");
+        assert_eq!(normalize_value("Noe\u{0308}l", false), "Noe\u{0308}l");
+    }
+
+    #[test]
+    fn test_lyrics_cleanup_helpers() {
+        assert_eq!(straighten_quotes("„Hallo“, she said, “don’t”"), "\"Hallo\", she said, \"don't\"");
+        assert_eq!(curl_quotes("\"Don't\" ('so')"), "“Don’t” (‘so’)");
+        assert_eq!(collapse_whitespace("  Echo\t  line  \nA\t\tB "), "  Echo line\nA B");
+        assert_eq!(normalize_dashes("Lord -- hear - my well-known ---"), "Lord – hear – my well-known ---");
+        assert_eq!(trim_trailing_whitespace("  Echo \t\nA  B \n"), "  Echo\nA  B\n");
+        assert_eq!(normalize_ellipses("And then... . . . Amen. .... 1.5"), "And then… … Amen. .... 1.5");
+        assert_eq!(normalize_unicode("Gnade, Ja\u{0308}sus"), "Gnade, Jäsus");
+        assert_eq!(sentence_case("AMAZING GRACE\nI'M FOUND, I SEE"), Some("Amazing grace\nI'm found, I see".to_string()));
+        assert_eq!(sentence_case("Amazing GRACE"), None);
+        assert_eq!(sentence_case("O!"), None);
+    }
+
+    #[test]
+    fn test_decode_windows_1252() {
+        assert_eq!(decode_windows_1252(b"Gr\xFC\xDFe \x93Gott\x94 \x96 5 \x80"), "Grüße “Gott” – 5 €");
+        assert_eq!(decode_windows_1252(b"Plain"), "Plain");
+    }
+}
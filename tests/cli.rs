@@ -0,0 +1,66 @@
+//! Tests of the command line interface, which run the binary of the crate
+
+use std::process::{Command, Output};
+
+/// Runs the binary with the given arguments
+fn run(arguments: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cantara-songlib")).args(arguments).output().unwrap()
+}
+
+#[test]
+fn test_convert() {
+    let output = run(&["convert", "txt", "testfiles/Amazing Grace.song"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stdout.starts_with("Amazing grace\nhow sweet the sound"));
+    assert!(stderr.lines().any(|line| line == "Warning: The tags will be lost: author, title"));
+
+    // The warnings are left out with --quiet, the exported content stays the same
+    let quiet_output = run(&["convert", "txt", "--quiet", "testfiles/Amazing Grace.song"]);
+    assert!(quiet_output.status.success());
+    assert!(quiet_output.stderr.is_empty());
+    assert_eq!(String::from_utf8(quiet_output.stdout).unwrap(), stdout);
+
+    // A lossless export has no warnings
+    let output = run(&["convert", "cssf", "testfiles/Amazing Grace.song"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("#title: Amazing Grace"));
+    assert!(output.stderr.is_empty());
+
+    assert!(!run(&["convert", "pdf", "testfiles/Amazing Grace.song"]).status.success());
+    assert!(!run(&["convert", "sng", "testfiles/Amazing Grace.song"]).status.success());
+}
+
+#[test]
+fn test_convert_to_key() {
+    let output = run(&["convert", "cssf", "--to-key", "A", "testfiles/chordpro/Amazing Grace.cho"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("#key: A\n"));
+    assert!(stdout.contains("\nD                A\nE       A\n"));
+
+    assert!(!run(&["convert", "cssf", "--to-key", "X", "testfiles/chordpro/Amazing Grace.cho"]).status.success());
+    // A song without a key can't be transposed to another key
+    let output = run(&["convert", "cssf", "--to-key", "A", "testfiles/Amazing Grace.song"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("no key tag"));
+}
+
+#[test]
+fn test_validate() {
+    let output = run(&["validate", "testfiles/Amazing Grace.song"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Warning: The song has no #copyright tag.\n");
+
+    // The problems of the song and of its slides are listed, errors let the command fail
+    let file = std::env::temp_dir().join(format!("cantara-songlib-cli-validate-{}.song", std::process::id()));
+    std::fs::write(&file, format!("#title: Test\n#tempo: fast 72\n\nLa la\tla\n\n{}\n", "la ".repeat(60))).unwrap();
+    let output = run(&["validate", file.to_str().unwrap()]);
+    std::fs::remove_file(&file).unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Error: The lyrics of the part Verse.1 contain tabs.\n"));
+    assert!(stdout.contains("Warning: The tempo fast 72 is invalid.\n"));
+    assert!(stdout.lines().any(|line| line.starts_with("Warning: slide 3: the main text has")));
+}